@@ -0,0 +1,47 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Benchmarks of the basic `Vector3D` operations. Run them with and without
+//! the `simd` feature to compare the SIMD and scalar implementations.
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use rand::Rng;
+
+use lumol::Vector3D;
+mod utils;
+
+fn random_vectors(n: usize) -> Vec<Vector3D> {
+    let mut rng = utils::get_rng();
+    (0..n).map(|_| Vector3D::new(rng.gen(), rng.gen(), rng.gen())).collect()
+}
+
+fn vectors(c: &mut Criterion) {
+    let vectors = random_vectors(1000);
+    c.bench_function("vectors::dot", move |b| b.iter(|| {
+        let mut sum = 0.0;
+        for pair in vectors.windows(2) {
+            sum += pair[0] * pair[1];
+        }
+        black_box(sum)
+    }));
+
+    let vectors = random_vectors(1000);
+    c.bench_function("vectors::norm", move |b| b.iter(|| {
+        let mut sum = 0.0;
+        for vector in &vectors {
+            sum += vector.norm();
+        }
+        black_box(sum)
+    }));
+
+    let vectors = random_vectors(1000);
+    c.bench_function("vectors::add_scaled", move |b| b.iter(|| {
+        let mut total = Vector3D::zero();
+        for vector in &vectors {
+            total.add_scaled(vector[0], vector);
+        }
+        black_box(total)
+    }));
+}
+
+criterion_group!(benches, vectors);
+criterion_main!(benches);
@@ -84,6 +84,11 @@ impl Potential for LennardJones {
         let s6 = f64::powi(self.sigma / r, 6);
         -24.0 * self.epsilon * (s6 - 2.0 * f64::powi(s6, 2)) / r
     }
+
+    fn force_derivative(&self, r: f64) -> f64 {
+        let s6 = f64::powi(self.sigma / r, 6);
+        24.0 * self.epsilon * (7.0 * s6 - 26.0 * f64::powi(s6, 2)) / (r * r)
+    }
 }
 
 impl PairPotential for LennardJones {
@@ -104,6 +109,101 @@ impl PairPotential for LennardJones {
     }
 }
 
+/// Soft-core Lennard-Jones potential, used to smoothly couple and decouple
+/// particles in free energy computations.
+///
+/// $$ V(r) = 4 \lambda \epsilon \left[ \frac{1}{s(r)^2} - \frac{1}{s(r)} \right]
+///    \text{ with } s(r) = \alpha (1 - \lambda) + \left(\frac r \sigma \right)^6 $$
+///
+/// where $\sigma$ is the Lennard-Jones distance constant, $\epsilon$ the
+/// energetic constant, $\lambda$ the coupling parameter between 0 (no
+/// interaction) and 1 (full Lennard-Jones interaction), and $\alpha$ the
+/// soft-core parameter. For $0 < \lambda < 1$, the energy stays finite when
+/// $r$ goes to 0 [1].
+///
+/// The derivative of the energy with respect to $\lambda$ is given by
+/// `PairPotential::lambda_derivative`, and can be used for thermodynamic
+/// integration.
+///
+/// [1] Beutler et al. Chem. Phys. Lett. 222, 529 (1994); doi:
+/// 10.1016/0009-2614(94)00397-1
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::{LennardJones, SoftCoreLJ};
+/// let lj = LennardJones { sigma: 2.0, epsilon: 10.0 };
+/// let potential = SoftCoreLJ { sigma: 2.0, epsilon: 10.0, lambda: 1.0, alpha: 0.5 };
+/// assert!(f64::abs(potential.energy(3.0) - lj.energy(3.0)) < 1e-12);
+///
+/// let potential = SoftCoreLJ { sigma: 2.0, epsilon: 10.0, lambda: 0.5, alpha: 0.5 };
+/// assert!(potential.energy(0.0).is_finite());
+/// ```
+#[derive(Clone, Copy)]
+pub struct SoftCoreLJ {
+    /// Distance constant of the Lennard-Jones potential
+    pub sigma: f64,
+    /// Energy constant of the Lennard-Jones potential
+    pub epsilon: f64,
+    /// Coupling parameter, between 0 and 1
+    pub lambda: f64,
+    /// Soft-core parameter
+    pub alpha: f64,
+}
+
+impl SoftCoreLJ {
+    /// Get the soft-core distance $s(r)$
+    fn soft_distance(&self, r: f64) -> f64 {
+        self.alpha * (1.0 - self.lambda) + f64::powi(r / self.sigma, 6)
+    }
+}
+
+impl Potential for SoftCoreLJ {
+    fn energy(&self, r: f64) -> f64 {
+        let s = self.soft_distance(r);
+        4.0 * self.lambda * self.epsilon * (1.0 / (s * s) - 1.0 / s)
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        let s = self.soft_distance(r);
+        let ds_dr = 6.0 * f64::powi(r, 5) / f64::powi(self.sigma, 6);
+        4.0 * self.lambda * self.epsilon * (2.0 / (s * s * s) - 1.0 / (s * s)) * ds_dr
+    }
+}
+
+impl PairPotential for SoftCoreLJ {
+    fn tail_energy(&self, cutoff: f64) -> f64 {
+        // The soft-core shift is negligible at the cutoff distance
+        let lj = LennardJones { sigma: self.sigma, epsilon: self.epsilon };
+        self.lambda * lj.tail_energy(cutoff)
+    }
+
+    fn tail_virial(&self, cutoff: f64) -> f64 {
+        let lj = LennardJones { sigma: self.sigma, epsilon: self.epsilon };
+        self.lambda * lj.tail_virial(cutoff)
+    }
+
+    fn lambda_derivative(&self, r: f64) -> f64 {
+        let s = self.soft_distance(r);
+        let direct = 4.0 * self.epsilon * (1.0 / (s * s) - 1.0 / s);
+        // Contribution from the dependency of s(r) on lambda
+        let ds_dlambda = -self.alpha;
+        let de_ds = 4.0 * self.lambda * self.epsilon * (1.0 / (s * s) - 2.0 / (s * s * s));
+        direct + de_ds * ds_dlambda
+    }
+
+    fn lambda_force_derivative(&self, r: f64) -> f64 {
+        let s = self.soft_distance(r);
+        let ds_dr = 6.0 * f64::powi(r, 5) / f64::powi(self.sigma, 6);
+        let direct = 4.0 * self.epsilon * (2.0 / (s * s * s) - 1.0 / (s * s)) * ds_dr;
+        // Contribution from the dependency of s(r) on lambda
+        let ds_dlambda = -self.alpha;
+        let df_ds = 4.0 * self.lambda * self.epsilon * (2.0 / (s * s * s) - 6.0 / (s * s * s * s));
+        direct + df_ds * ds_dr * ds_dlambda
+    }
+}
+
 /// Harmonic potential.
 ///
 /// $$ V(x) = \frac{1}{2} k (x - x_0)^2 $$
@@ -139,6 +239,10 @@ impl Potential for Harmonic {
     fn force(&self, x: f64) -> f64 {
         self.k * (self.x0 - x)
     }
+
+    fn force_derivative(&self, _: f64) -> f64 {
+        -self.k
+    }
 }
 
 impl PairPotential for Harmonic {
@@ -255,41 +359,149 @@ impl Potential for Torsion {
 
 impl DihedralPotential for Torsion {}
 
+/// Cosine series dihedral potential.
+///
+/// This is the periodic dihedral potential used by the CHARMM, AMBER and OPLS
+/// force fields, written as a sum of [`Torsion`](struct.Torsion.html) terms:
+///
+/// $$ V(x) = \sum_i k_i (1 + \cos(n_i x - \delta_i))$$
+///
+/// where $k_i$ are the force constants, $n_i$ the periodicities of each term,
+/// and $\delta_i$ the phase shifts.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::{CosineDihedral, Torsion};
+/// # use std::f64::consts::PI;
+/// let potential = CosineDihedral::new(vec![
+///     Torsion { k: 10.0, n: 1, delta: 0.0 },
+///     Torsion { k: 5.0, n: 3, delta: PI },
+/// ]);
+/// assert_eq!(potential.energy(PI), 0.0);
+/// assert!(potential.force(PI).abs() < 1e-12);
+/// ```
+#[derive(Clone)]
+pub struct CosineDihedral {
+    /// Terms of the cosine series
+    pub terms: Vec<Torsion>,
+}
+
+impl CosineDihedral {
+    /// Create a new `CosineDihedral` potential from the given `terms`
+    pub fn new(terms: Vec<Torsion>) -> CosineDihedral {
+        CosineDihedral {
+            terms: terms,
+        }
+    }
+}
+
+impl Potential for CosineDihedral {
+    fn energy(&self, phi: f64) -> f64 {
+        self.terms.iter().map(|term| term.energy(phi)).sum()
+    }
+
+    fn force(&self, phi: f64) -> f64 {
+        self.terms.iter().map(|term| term.force(phi)).sum()
+    }
+}
+
+impl DihedralPotential for CosineDihedral {}
+
 /// Buckingham potential.
 ///
-/// $$ V(x) = A \exp \left(\frac{\sigma - r}{\rho} \right) - \frac{C}{r^6} $$
+/// $$ V(x) = A \exp \left(\frac{- r}{\rho} \right) - \frac{C}{r^6} $$
 ///
-/// where $A$ and $C$ are energetic constants, and $\rho$ and $\sigma$ are
-/// length parameters.
+/// where $A$ and $C$ are energetic constants, and $\rho$ is a length
+/// parameter.
+///
+/// This potential goes to $-\infty$ when $r \to 0$, which can lead to
+/// particles collapsing on top of one another at high temperature. To prevent
+/// this, [`Buckingham::with_r_min`] creates a potential continued linearly
+/// below `r_min`:
+///
+/// $$ V(r < r_{min}) = V(r_{min}) + F(r_{min}) (r_{min} - r) $$
+///
+/// where $F = - dV/dr$ is the force. The energy and the force are continuous
+/// at `r_min`, and the force is constant and repulsive below `r_min`, creating
+/// a repulsive wall. The tail corrections do not depend on `r_min`, which
+/// should be smaller than the cutoff distance.
+///
+/// [`Buckingham::with_r_min`]: struct.Buckingham.html#method.with_r_min
 ///
 /// # Examples
 ///
 /// ```
 /// # use lumol_core::energy::Potential;
 /// # use lumol_core::energy::Buckingham;
-/// let potential = Buckingham { a: 2.0, c: 1.0, rho: 5.3 };
+/// let potential = Buckingham::new(2.0, 1.0, 5.3);
 /// assert_eq!(potential.energy(2.2), 1.3117360696239022);
 /// assert_eq!(potential.force(2.2), 0.2251072178835946);
+///
+/// let potential = Buckingham::with_r_min(2.0, 1.0, 5.3, 1.8);
+/// assert_eq!(potential.energy(2.2), 1.3117360696239022);
+/// assert_eq!(potential.force(0.5), potential.force(1.8));
 /// ```
 #[derive(Clone, Copy)]
 pub struct Buckingham {
     /// Exponential term energetic constant
-    pub a: f64,
+    a: f64,
     /// `1/r^6` term energetic constant
-    pub c: f64,
+    c: f64,
     /// Width of the exponential term length constant
-    pub rho: f64,
+    rho: f64,
+    /// Distance under which the potential is continued linearly, or 0 to use
+    /// the bare potential at all distances
+    r_min: f64,
 }
 
-impl Potential for Buckingham {
-    fn energy(&self, r: f64) -> f64 {
+impl Buckingham {
+    /// Create a new `Buckingham` potential with the energetic constants `a`
+    /// and `c`, and the length constant `rho`.
+    pub fn new(a: f64, c: f64, rho: f64) -> Buckingham {
+        Buckingham {
+            a: a,
+            c: c,
+            rho: rho,
+            r_min: 0.0,
+        }
+    }
+
+    /// Create a new `Buckingham` potential with the energetic constants `a`
+    /// and `c`, and the length constant `rho`, continued linearly below
+    /// `r_min`.
+    ///
+    /// # Panics
+    ///
+    /// If `r_min` is not positive, or if the force at `r_min` is not
+    /// repulsive, *i.e.* if `r_min` is smaller than the position of the
+    /// maximum of the potential.
+    pub fn with_r_min(a: f64, c: f64, rho: f64, r_min: f64) -> Buckingham {
+        assert!(r_min > 0.0, "'r_min' must be positive in Buckingham potential");
+        let potential = Buckingham {
+            a: a,
+            c: c,
+            rho: rho,
+            r_min: r_min,
+        };
+        assert!(
+            potential.bare_force(r_min) > 0.0,
+            "'r_min' must be after the maximum of the Buckingham potential"
+        );
+        return potential;
+    }
+
+    /// Get the energy at distance `r`, without taking `r_min` into account
+    fn bare_energy(&self, r: f64) -> f64 {
         let r3 = r * r * r;
         let r6 = r3 * r3;
         let exp = exp(-r / self.rho);
         self.a * exp - self.c / r6
     }
 
-    fn force(&self, r: f64) -> f64 {
+    /// Get the force at distance `r`, without taking `r_min` into account
+    fn bare_force(&self, r: f64) -> f64 {
         let r3 = r * r * r;
         let r7 = r3 * r3 * r;
         let exp = exp(-r / self.rho);
@@ -297,12 +509,31 @@ impl Potential for Buckingham {
     }
 }
 
+impl Potential for Buckingham {
+    fn energy(&self, r: f64) -> f64 {
+        if r < self.r_min {
+            let force = self.bare_force(self.r_min);
+            self.bare_energy(self.r_min) + force * (self.r_min - r)
+        } else {
+            self.bare_energy(r)
+        }
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        if r < self.r_min {
+            self.bare_force(self.r_min)
+        } else {
+            self.bare_force(r)
+        }
+    }
+}
+
 impl PairPotential for Buckingham {
     fn tail_energy(&self, rc: f64) -> f64 {
         let rc2 = rc * rc;
         let rc3 = rc2 * rc;
         let exp = exp(-rc / self.rho);
-        let factor = rc2 - 2.0 * rc * self.rho + 2.0 * self.rho * self.rho;
+        let factor = rc2 + 2.0 * rc * self.rho + 2.0 * self.rho * self.rho;
         self.a * self.rho * exp * factor - self.c / (3.0 * rc3)
     }
 
@@ -312,7 +543,7 @@ impl PairPotential for Buckingham {
         let exp = exp(-rc / self.rho);
         let factor = rc3 + 3.0 * rc2 * self.rho + 6.0 * rc * self.rho * self.rho +
                      6.0 * self.rho * self.rho * self.rho;
-        self.a * exp * factor - 20.0 * self.c / rc3 + 8.0
+        self.a * exp * factor - 2.0 * self.c / rc3
     }
 }
 
@@ -369,7 +600,7 @@ impl PairPotential for BornMayerHuggins {
         let rc2 = rc * rc;
         let rc3 = rc2 * rc;
         let exp = exp((self.sigma - rc) / self.rho);
-        let factor = rc2 - 2.0 * rc * self.rho + 2.0 * self.rho * self.rho;
+        let factor = rc2 + 2.0 * rc * self.rho + 2.0 * self.rho * self.rho;
         self.a * self.rho * exp * factor - self.c / (3.0 * rc3) + self.d / (5.0 * rc2 * rc3)
     }
 
@@ -379,7 +610,7 @@ impl PairPotential for BornMayerHuggins {
         let exp = exp((self.sigma - rc) / self.rho);
         let factor = rc3 + 3.0 * rc2 * self.rho + 6.0 * rc * self.rho * self.rho +
                      6.0 * self.rho * self.rho * self.rho;
-        self.a * exp * factor - 20.0 * self.c / rc3 + 8.0 * self.d / (5.0 * rc2 * rc3)
+        self.a * exp * factor - 2.0 * self.c / rc3 + 8.0 * self.d / (5.0 * rc2 * rc3)
     }
 }
 
@@ -397,7 +628,7 @@ impl PairPotential for BornMayerHuggins {
 /// # use lumol_core::energy::Morse;
 /// let potential = Morse { a: 2.0, x0: 1.3, depth: 4.0 };
 /// assert_eq!(potential.energy(1.0), 2.703517287822119);
-/// assert_eq!(potential.force(1.0), -37.12187076378477);
+/// assert_eq!(potential.force(1.0), 23.96796995753662);
 /// ```
 #[derive(Clone, Copy)]
 pub struct Morse {
@@ -417,7 +648,7 @@ impl Potential for Morse {
 
     fn force(&self, r: f64) -> f64 {
         let exp = exp((self.x0 - r) * self.a);
-        2.0 * self.depth * (1.0 - exp * exp) * self.a
+        -2.0 * self.depth * self.a * exp * (1.0 - exp)
     }
 }
 
@@ -602,6 +833,18 @@ mod tests {
 
     const EPS: f64 = 1e-9;
 
+    /// Integrate `f` from `start` to (almost) infinity, using Simpson's rule
+    fn integrate<F: Fn(f64) -> f64>(f: F, start: f64) -> f64 {
+        let n = 1_000_000;
+        let h = 1000.0 / n as f64;
+        let mut sum = f(start) + f(start + n as f64 * h);
+        for i in 1..n {
+            let weight = if i % 2 == 0 { 2.0 } else { 4.0 };
+            sum += weight * f(start + i as f64 * h);
+        }
+        return sum * h / 3.0;
+    }
+
     #[test]
     fn null() {
         let null = NullPotential;
@@ -636,6 +879,17 @@ mod tests {
         assert_eq!(lj.tail_virial(2.0), -17.06666666666667);
         assert_eq!(lj.tail_virial(14.42), -0.1366035877536718);
 
+        assert_relative_eq!(
+            lj.tail_energy(5.0),
+            integrate(|r| r * r * lj.energy(r), 5.0),
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(
+            lj.tail_virial(5.0),
+            integrate(|r| r * r * r * lj.force(r), 5.0),
+            max_relative = 1e-6
+        );
+
         assert!(lj.force(f64::powf(2.0, 1.0 / 6.0) * 2.0).abs() < 1e-15);
         assert_ulps_eq!(lj.force(2.5), -0.95773475733504);
 
@@ -644,6 +898,73 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, lj.force(4.0), epsilon = 1e-6);
     }
 
+    #[test]
+    fn soft_core_lj() {
+        let lj = LennardJones {
+            epsilon: 0.8,
+            sigma: 2.0,
+        };
+        let mut soft = SoftCoreLJ {
+            epsilon: 0.8,
+            sigma: 2.0,
+            lambda: 1.0,
+            alpha: 0.5,
+        };
+
+        // lambda = 1 is the usual Lennard-Jones potential
+        for &r in &[1.5, 2.0, 2.5, 4.0, 10.0] {
+            assert_relative_eq!(soft.energy(r), lj.energy(r), max_relative = 1e-12);
+            assert_relative_eq!(soft.force(r), lj.force(r), max_relative = 1e-12);
+        }
+        assert_ulps_eq!(soft.tail_energy(5.0), lj.tail_energy(5.0));
+        assert_ulps_eq!(soft.tail_virial(5.0), lj.tail_virial(5.0));
+
+        // lambda = 0 removes the interaction, including at r = 0
+        soft.lambda = 0.0;
+        for &r in &[0.0, 1.0, 2.0, 2.5, 10.0] {
+            assert_eq!(soft.energy(r), 0.0);
+            assert_eq!(soft.force(r), 0.0);
+        }
+        assert_eq!(soft.tail_energy(5.0), 0.0);
+        assert_eq!(soft.tail_virial(5.0), 0.0);
+
+        // Intermediate lambda values are not singular at r = 0
+        soft.lambda = 0.5;
+        assert!(soft.energy(0.0).is_finite());
+        assert_eq!(soft.force(0.0), 0.0);
+
+        let e0 = soft.energy(2.1);
+        let e1 = soft.energy(2.1 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, soft.force(2.1), epsilon = 1e-6);
+
+        // Derivative with respect to lambda
+        let h = 1e-6;
+        let at_lambda = |lambda| SoftCoreLJ { lambda: lambda, ..soft };
+        for &lambda in &[0.0, 0.3, 0.5, 0.9] {
+            for &r in &[0.0, 1.0, 2.0, 3.0] {
+                let forward = at_lambda(lambda + h).energy(r);
+                let backward = at_lambda(lambda - h).energy(r);
+                let expected = (forward - backward) / (2.0 * h);
+                assert_relative_eq!(
+                    at_lambda(lambda).lambda_derivative(r),
+                    expected,
+                    epsilon = 1e-6,
+                    max_relative = 1e-6
+                );
+
+                let forward = at_lambda(lambda + h).force(r);
+                let backward = at_lambda(lambda - h).force(r);
+                let expected = (forward - backward) / (2.0 * h);
+                assert_relative_eq!(
+                    at_lambda(lambda).lambda_force_derivative(r),
+                    expected,
+                    epsilon = 1e-6,
+                    max_relative = 1e-6
+                );
+            }
+        }
+    }
+
     #[test]
     fn harmonic() {
         let harmonic = Harmonic { k: 50.0, x0: 2.0 };
@@ -661,6 +982,43 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, harmonic.force(2.1), epsilon = 1e-6);
     }
 
+    /// Get the derivative of the force of `potential` at `x` using centered
+    /// finite differences
+    fn finite_differences<P: Potential>(potential: &P, x: f64) -> f64 {
+        let h = 1e-6;
+        return (potential.force(x + h) - potential.force(x - h)) / (2.0 * h);
+    }
+
+    #[test]
+    fn force_derivative() {
+        let lj = LennardJones {
+            epsilon: 0.8,
+            sigma: 2.0,
+        };
+        for &r in &[1.9, 2.0, 2.245, 2.5, 4.0, 8.0] {
+            assert_relative_eq!(lj.force_derivative(r), finite_differences(&lj, r), max_relative = 1e-6);
+        }
+        // The force goes through a minimum at r = (26/7)^(1/6) sigma
+        let r_min = f64::powf(26.0 / 7.0, 1.0 / 6.0) * 2.0;
+        assert!(lj.force_derivative(r_min).abs() < 1e-12);
+
+        let harmonic = Harmonic { k: 50.0, x0: 2.0 };
+        for &x in &[0.5, 2.0, 2.1, 10.0] {
+            assert_eq!(harmonic.force_derivative(x), -50.0);
+            assert_relative_eq!(harmonic.force_derivative(x), finite_differences(&harmonic, x), max_relative = 1e-6);
+        }
+
+        // Default implementation, using finite differences
+        let gaussian = Gaussian::new(8.0, 0.5);
+        for &r in &[0.5, 1.0, 2.5] {
+            let expected = -2.0 * 8.0 * 0.5 * (1.0 - 2.0 * 0.5 * r * r) * f64::exp(-0.5 * r * r);
+            // The derivative vanishes at r = 1, use an absolute tolerance there
+            assert_relative_eq!(
+                gaussian.force_derivative(r), expected, epsilon = 1e-8, max_relative = 1e-6
+            );
+        }
+    }
+
     #[test]
     fn cosine_harmonic() {
         let harmonic = CosineHarmonic::new(50.0, 2.0);
@@ -695,24 +1053,113 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, torsion.force(4.0), epsilon = 1e-6);
     }
 
+    #[test]
+    fn cosine_dihedral() {
+        let dihedral = CosineDihedral::new(vec![Torsion {
+            k: 5.0,
+            n: 3,
+            delta: 0.0,
+        }]);
+
+        // Three minima, at -PI/3, PI/3 and PI
+        for &phi in &[-PI / 3.0, PI / 3.0, PI] {
+            assert_ulps_eq!(dihedral.energy(phi), 0.0, epsilon = 1e-12);
+            assert_ulps_eq!(dihedral.force(phi), 0.0, epsilon = 1e-12);
+
+            // The torque brings the angle back to the minimum
+            assert!(dihedral.force(phi + 0.1) < 0.0);
+            assert!(dihedral.force(phi - 0.1) > 0.0);
+        }
+
+        // Maxima in between
+        assert_eq!(dihedral.energy(0.0), 10.0);
+        assert_ulps_eq!(dihedral.energy(2.0 * PI / 3.0), 10.0);
+
+        for &phi in &[-2.0, 0.3, 1.0, 2.5] {
+            let h = 1e-6;
+            let e0 = dihedral.energy(phi - h);
+            let e1 = dihedral.energy(phi + h);
+            assert_relative_eq!((e0 - e1) / (2.0 * h), dihedral.force(phi), epsilon = 1e-6);
+        }
+
+        let dihedral = CosineDihedral::new(vec![
+            Torsion { k: 2.0, n: 1, delta: 0.5 },
+            Torsion { k: 1.0, n: 2, delta: PI },
+            Torsion { k: 0.5, n: 3, delta: 0.0 },
+        ]);
+        let energy = 2.0 * (1.0 + cos(1.1 - 0.5)) + 1.0 * (1.0 + cos(2.2 - PI)) +
+                     0.5 * (1.0 + cos(3.3));
+        assert_relative_eq!(dihedral.energy(1.1), energy, epsilon = 1e-12);
+
+        for &phi in &[-2.0, 0.3, 1.0, 2.5] {
+            let h = 1e-6;
+            let e0 = dihedral.energy(phi - h);
+            let e1 = dihedral.energy(phi + h);
+            assert_relative_eq!((e0 - e1) / (2.0 * h), dihedral.force(phi), epsilon = 1e-6);
+        }
+    }
+
     #[test]
     fn buckingham() {
-        let buckingham = Buckingham {
-            a: 2.0,
-            c: 1.0,
-            rho: 2.0,
-        };
+        let buckingham = Buckingham::new(2.0, 1.0, 2.0);
 
         // Comparing to externally computed values
         assert_eq!(buckingham.energy(2.0), 0.7201338823428847);
         assert_eq!(buckingham.force(2.0), 0.32100444117144233);
 
-        assert_eq!(buckingham.tail_energy(10.0), 1.8323882504179136);
-        assert_eq!(buckingham.tail_virial(10.0), 33.422487868546725);
+        assert_eq!(buckingham.tail_energy(10.0), 3.988531290125263);
+        assert_eq!(buckingham.tail_virial(10.0), 25.440487868546725);
+
+        assert_relative_eq!(
+            buckingham.tail_energy(10.0),
+            integrate(|r| r * r * buckingham.energy(r), 10.0),
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(
+            buckingham.tail_virial(10.0),
+            integrate(|r| r * r * r * buckingham.force(r), 10.0),
+            max_relative = 1e-6
+        );
+
+        for &r in &[1.0, 2.0, 4.0] {
+            let e0 = buckingham.energy(r);
+            let e1 = buckingham.energy(r + EPS);
+            assert_relative_eq!((e0 - e1) / EPS, buckingham.force(r), epsilon = 1e-6);
+        }
+
+        // Without r_min, the energy goes to -inf at short distances
+        assert!(buckingham.energy(0.1) < -1e5);
+    }
+
+    #[test]
+    fn buckingham_r_min() {
+        let bare = Buckingham::new(2.0, 1.0, 2.0);
+        // The maximum of this potential is around r = 1.45
+        let buckingham = Buckingham::with_r_min(2.0, 1.0, 2.0, 1.5);
+
+        // Nothing changes above r_min
+        assert_eq!(buckingham.energy(2.0), bare.energy(2.0));
+        assert_eq!(buckingham.force(2.0), bare.force(2.0));
+        assert_eq!(buckingham.energy(1.5), bare.energy(1.5));
+
+        // Constant repulsive force and increasing energy below r_min
+        assert!(buckingham.force(1.5) > 0.0);
+        assert_eq!(buckingham.force(0.1), buckingham.force(1.5));
+        assert!(buckingham.energy(0.1) > buckingham.energy(0.5));
+        assert!(buckingham.energy(0.5) > buckingham.energy(1.5));
+
+        for &r in &[0.1, 0.5, 1.0, 2.0] {
+            let e0 = buckingham.energy(r);
+            let e1 = buckingham.energy(r + EPS);
+            assert_relative_eq!((e0 - e1) / EPS, buckingham.force(r), epsilon = 1e-6);
+        }
+    }
 
-        let e0 = buckingham.energy(4.0);
-        let e1 = buckingham.energy(4.0 + EPS);
-        assert_relative_eq!((e0 - e1) / EPS, buckingham.force(4.0), epsilon = 1e-6);
+    #[test]
+    #[should_panic(expected = "'r_min' must be after the maximum of the Buckingham potential")]
+    fn buckingham_wrong_r_min() {
+        // The maximum of this potential is around r = 1.45
+        let _ = Buckingham::with_r_min(2.0, 1.0, 2.0, 1.0);
     }
 
     #[test]
@@ -729,8 +1176,19 @@ mod tests {
         assert_eq!(born.energy(2.0), 1.986328125);
         assert_eq!(born.force(2.0), 0.9609375);
 
-        assert_eq!(born.tail_energy(10.0), 4.981521444402363);
-        assert_eq!(born.tail_virial(10.0), 69.13986044386026);
+        assert_eq!(born.tail_energy(10.0), 10.842525888797299);
+        assert_eq!(born.tail_virial(10.0), 69.15786044386026);
+
+        assert_relative_eq!(
+            born.tail_energy(10.0),
+            integrate(|r| r * r * born.energy(r), 10.0),
+            max_relative = 1e-6
+        );
+        assert_relative_eq!(
+            born.tail_virial(10.0),
+            integrate(|r| r * r * r * born.force(r), 10.0),
+            max_relative = 1e-6
+        );
 
         let e0 = born.energy(4.0);
         let e1 = born.energy(4.0 + EPS);
@@ -747,14 +1205,23 @@ mod tests {
 
         // Comparing to externally computed values
         assert_eq!(morse.energy(1.0), 2.703517287822119);
-        assert_eq!(morse.force(1.0), -37.12187076378477);
+        assert_eq!(morse.force(1.0), 23.96796995753662);
 
         assert_eq!(morse.tail_energy(1.0), 0.0);
         assert_eq!(morse.tail_virial(1.0), 0.0);
 
-        let e0 = morse.energy(1.3);
-        let e1 = morse.energy(1.3 + EPS);
-        assert_relative_eq!((e0 - e1) / EPS, morse.force(1.3), epsilon = 1e-6);
+        // No force at the equilibrium distance
+        assert_eq!(morse.force(1.3), 0.0);
+
+        for &r in &[1.1, 1.3, 1.6, 3.0] {
+            let e0 = morse.energy(r);
+            let e1 = morse.energy(r + EPS);
+            assert_relative_eq!((e0 - e1) / EPS, morse.force(r), epsilon = 1e-6);
+        }
+
+        // Repulsive below the equilibrium distance, attractive above
+        assert!(morse.force(1.0) > 0.0);
+        assert!(morse.force(1.6) < 0.0);
     }
 
     #[test]
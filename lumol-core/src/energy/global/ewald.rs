@@ -497,6 +497,37 @@ impl Ewald {
         return energies.sum();
     }
 
+    /// Real space contribution to the energy of each particle, including
+    /// the corrections for excluded pairs. The energy of each pair is split
+    /// evenly between the two particles.
+    fn real_space_atomic_energies(&self, configuration: &Configuration, energies: &mut [f64]) {
+        assert_eq!(energies.len(), configuration.size());
+        let natoms = configuration.size();
+        let charges = configuration.particles().charge;
+
+        for i in 0..natoms {
+            let qi = charges[i];
+            if qi == 0.0 {
+                continue;
+            }
+
+            for j in i + 1..natoms {
+                let qj = charges[j];
+                if qj == 0.0 {
+                    continue;
+                }
+
+                let path = configuration.bond_path(i, j);
+                let info = self.restriction.information(path);
+
+                let r = configuration.distance(i, j);
+                let energy = 0.5 * self.real_space_energy_pair(info, qi * qj, r);
+                energies[i] += energy;
+                energies[j] += energy;
+            }
+        }
+    }
+
     /// Real space contribution to the forces
     fn real_space_forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
         assert_eq!(forces.len(), configuration.size());
@@ -664,6 +695,15 @@ impl Ewald {
                               .sum::<f64>();
         return -self.alpha / sqrt(PI) * q2 / FOUR_PI_EPSILON_0;
     }
+
+    /// Self-interaction contribution to the energy of each particle
+    fn self_atomic_energies(&self, configuration: &Configuration, energies: &mut [f64]) {
+        assert_eq!(energies.len(), configuration.size());
+        let charges = configuration.particles().charge;
+        for (energy, &charge) in energies.iter_mut().zip(charges) {
+            *energy -= self.alpha / sqrt(PI) * charge * charge / FOUR_PI_EPSILON_0;
+        }
+    }
 }
 
 
@@ -726,6 +766,32 @@ impl Ewald {
         return energy / FOUR_PI_EPSILON_0;
     }
 
+    /// k-space contribution to the energy of each particle. The energy of
+    /// the particle `i` is `q_i Re[e^{i k r_i} rho^*(k)]` summed over all the
+    /// k-vectors with the energy factors, which sums to the total k-space
+    /// energy `|rho(k)|^2`.
+    fn kspace_atomic_energies(&mut self, configuration: &Configuration, energies: &mut [f64]) {
+        assert_eq!(energies.len(), configuration.size());
+        self.eik_dot_r(configuration);
+
+        let charges = configuration.particles().charge;
+        for (i, energy) in energies.iter_mut().enumerate() {
+            if charges[i] == 0.0 {
+                continue;
+            }
+
+            let mut potential = 0.0;
+            let kvecs = zip!(&self.factors.kvecs, &self.factors.energy, &self.rho);
+            for (&(ikx, iky, ikz), factor, rho) in kvecs {
+                let eikr = self.eikr[(ikx, 0, i)] *
+                           self.eikr[(iky, 1, i)] *
+                           self.eikr[(ikz, 2, i)];
+                potential += factor * (eikr * rho.conj()).real();
+            }
+            *energy += charges[i] * potential / FOUR_PI_EPSILON_0;
+        }
+    }
+
     /// k-space contribution to the forces
     fn kspace_forces(&mut self, configuration: &Configuration, forces: &mut [Vector3D]) {
         assert_eq!(forces.len(), configuration.size());
@@ -961,6 +1027,15 @@ impl GlobalPotential for SharedEwald {
         let kspace = ewald.kspace_molecular_virial(configuration);
         return real + kspace;
     }
+
+    fn atomic_energies(&self, configuration: &Configuration, energies: &mut [f64]) {
+        assert_eq!(energies.len(), configuration.size());
+        let mut ewald = self.write();
+        ewald.precompute(&configuration.cell);
+        ewald.real_space_atomic_energies(configuration, energies);
+        ewald.self_atomic_energies(configuration, energies);
+        ewald.kspace_atomic_energies(configuration, energies);
+    }
 }
 
 impl CoulombicPotential for SharedEwald {
@@ -998,7 +1073,8 @@ impl GlobalCache for SharedEwald {
 #[cfg(test)]
 mod tests {
     pub use super::*;
-    use crate::System;
+    use crate::{GlobalPotential, System};
+    use crate::{Molecule, Particle, UnitCell};
     use crate::utils::system_from_xyz;
 
     pub fn nacl_pair() -> System {
@@ -1040,6 +1116,69 @@ mod tests {
         assert_eq!(ewald.kmax, 5);
     }
 
+    #[test]
+    fn cscl_madelung() {
+        // CsCl crystal made of 3 x 3 x 3 unit cells
+        let a = 4.123;
+        let mut system = System::with_cell(UnitCell::cubic(3.0 * a));
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    let corner = Vector3D::new(i as f64, j as f64, k as f64) * a;
+                    let mut cs = Particle::with_position("Cs", corner);
+                    cs.charge = 1.0;
+                    system.add_molecule(Molecule::new(cs));
+
+                    let center = corner + Vector3D::new(0.5, 0.5, 0.5) * a;
+                    let mut cl = Particle::with_position("Cl", center);
+                    cl.charge = -1.0;
+                    system.add_molecule(Molecule::new(cl));
+                }
+            }
+        }
+
+        let ewald = SharedEwald::new(Ewald::with_accuracy(6.0, 1e-6, &system));
+        let energy = ewald.energy(&system);
+
+        // energy per ion pair is -M / (4 pi epsilon_0 r0), with r0 the
+        // distance between nearest neighbors and M the Madelung constant
+        const CSCL_MADELUNG: f64 = 1.762675;
+        let r0 = f64::sqrt(3.0) / 2.0 * a;
+        let npairs = system.size() as f64 / 2.0;
+        let madelung = -energy * FOUR_PI_EPSILON_0 * r0 / npairs;
+        assert_relative_eq!(madelung, CSCL_MADELUNG, max_relative = 1e-5);
+    }
+
+    #[test]
+    fn triclinic_cell() {
+        let positions = [
+            (1.0, Vector3D::new(1.0, 2.0, 3.0)),
+            (-1.0, Vector3D::new(4.5, 1.5, 7.0)),
+            (0.5, Vector3D::new(8.0, 9.5, 2.5)),
+            (-0.5, Vector3D::new(2.0, 11.0, 9.0)),
+            (1.0, Vector3D::new(6.0, 6.0, 6.0)),
+            (-1.0, Vector3D::new(9.0, 3.0, 10.0)),
+            (2.0, Vector3D::new(3.5, 7.5, 0.5)),
+            (-2.0, Vector3D::new(7.5, 12.0, 4.0)),
+        ];
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 13.0, 11.0));
+        for &(charge, position) in &positions {
+            let mut particle = Particle::with_position("Na", position);
+            particle.charge = charge;
+            system.add_molecule(Molecule::new(particle));
+        }
+
+        let ewald = SharedEwald::new(Ewald::new(3.9, 20, 1.0));
+        let energy = ewald.energy(&system);
+
+        // The same periodic system, described by the cell vectors
+        // (10, 0, 0), (10, 13, 0) and (0, 0, 11)
+        let gamma = f64::atan2(13.0, 10.0).to_degrees();
+        system.cell = UnitCell::triclinic(10.0, f64::hypot(10.0, 13.0), 11.0, 90.0, 90.0, gamma);
+        let ewald = SharedEwald::new(Ewald::new(3.9, 20, 1.0));
+        assert_relative_eq!(ewald.energy(&system), energy, max_relative = 1e-6);
+    }
+
     mod errors {
         use super::*;
         use crate::GlobalPotential;
@@ -1075,7 +1214,7 @@ mod tests {
 
     mod pairs {
         use super::*;
-        use crate::GlobalPotential;
+        use crate::{GlobalPotential, Molecule, Particle};
         use approx::{assert_ulps_eq, assert_relative_eq};
 
         #[test]
@@ -1093,6 +1232,22 @@ mod tests {
             let _ = ewald.energy(&system);
         }
 
+        #[test]
+        fn atomic_energies() {
+            let mut system = nacl_pair();
+            let mut particle = Particle::with_position("Na", [4.0, 1.0, 0.0].into());
+            particle.charge = 0.5;
+            system.add_molecule(Molecule::new(particle));
+            let ewald = SharedEwald::new(Ewald::new(8.0, 10, None));
+
+            let mut energies = vec![0.0; system.size()];
+            ewald.atomic_energies(&system, &mut energies);
+            let energy = ewald.energy(&system);
+            assert_relative_eq!(energies.iter().sum::<f64>(), energy, max_relative=1e-12);
+            // The energy is not distributed evenly between the particles
+            assert!(f64::abs(energies[0] - energies[2]) > 1e-3);
+        }
+
         #[test]
         fn real_forces_finite_differences() {
             let mut system = nacl_pair();
@@ -1171,6 +1326,19 @@ mod tests {
             assert_ulps_eq!(energy, expected);
         }
 
+        #[test]
+        fn atomic_energies() {
+            let system = water();
+            let mut ewald = SharedEwald::new(Ewald::new(8.0, 10, None));
+            ewald.set_restriction(PairRestriction::InterMolecular);
+
+            // The molecular correction for excluded pairs is included
+            let mut energies = vec![0.0; system.size()];
+            ewald.atomic_energies(&system, &mut energies);
+            let energy = ewald.energy(&system);
+            assert_relative_eq!(energies.iter().sum::<f64>(), energy, epsilon=1e-12);
+        }
+
         #[test]
         fn real_space_forces_finite_differences() {
             let mut system = water();
@@ -0,0 +1,483 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! External potentials, acting independently on each particle in the system.
+//!
+//! These potentials use the positions of the particles as stored in the
+//! configuration, without applying periodic boundary conditions.
+use soa_derive::soa_zip;
+
+use crate::Configuration;
+use crate::{Matrix3, Vector3D};
+
+use super::{GlobalCache, GlobalPotential};
+
+/// An external potential, acting independently on each particle
+trait ExternalPotential {
+    /// Get the energy of a particle with the given `charge` at the given
+    /// `position`
+    fn particle_energy(&self, charge: f64, position: &Vector3D) -> f64;
+    /// Get the force acting on a particle with the given `charge` at the
+    /// given `position`
+    fn particle_force(&self, charge: f64, position: &Vector3D) -> Vector3D;
+
+    /// Get the total energy of particles with the given `charges` at the
+    /// given `positions`
+    fn total_energy(&self, charges: &[f64], positions: &[Vector3D]) -> f64 {
+        assert_eq!(charges.len(), positions.len());
+        charges.iter()
+               .zip(positions)
+               .map(|(&charge, position)| self.particle_energy(charge, position))
+               .sum()
+    }
+}
+
+/// Implement `GlobalPotential` and `GlobalCache` for a type implementing
+/// `ExternalPotential`.
+macro_rules! impl_global_for_external {
+    ($Type: ty) => {
+        impl GlobalPotential for $Type {
+            fn cutoff(&self) -> Option<f64> {
+                None
+            }
+
+            fn energy(&self, configuration: &Configuration) -> f64 {
+                let particles = configuration.particles();
+                self.total_energy(particles.charge, particles.position)
+            }
+
+            fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+                assert_eq!(forces.len(), configuration.size());
+                let particles = configuration.particles();
+                for (i, force) in forces.iter_mut().enumerate() {
+                    *force += self.particle_force(particles.charge[i], &particles.position[i]);
+                }
+            }
+
+            fn atomic_virial(&self, configuration: &Configuration) -> Matrix3 {
+                let mut virial = Matrix3::zero();
+                for (&charge, position) in soa_zip!(configuration.particles(), [charge, position]) {
+                    virial += self.particle_force(charge, position).tensorial(position);
+                }
+                return virial;
+            }
+
+            fn atomic_energies(&self, configuration: &Configuration, energies: &mut [f64]) {
+                assert_eq!(energies.len(), configuration.size());
+                let particles = configuration.particles();
+                for (i, energy) in energies.iter_mut().enumerate() {
+                    *energy += self.particle_energy(particles.charge[i], &particles.position[i]);
+                }
+            }
+        }
+
+        impl GlobalCache for $Type {
+            fn move_molecule_cost(
+                &self,
+                configuration: &Configuration,
+                molecule_id: usize,
+                new_positions: &[Vector3D],
+            ) -> f64 {
+                let molecule = configuration.molecule(molecule_id);
+                let charges = molecule.particles().charge;
+                let old_energy = self.total_energy(charges, molecule.particles().position);
+                let new_energy = self.total_energy(charges, new_positions);
+                return new_energy - old_energy;
+            }
+
+            fn update(&self) {
+                // Nothing to do
+            }
+        }
+    };
+}
+
+/// Harmonic wall, pushing the particles back on one side of a plane.
+///
+/// The particles on the side of the plane pointed to by the normal vector
+/// $\vec n$ do not feel this potential, and the particles on the other side
+/// have the energy
+///
+/// $$ V(\vec r) = \frac12 k \left((\vec r - \vec r_0) \cdot \vec n\right)^2 $$
+///
+/// where $\vec r_0$ is a point in the plane. Two walls facing each other can
+/// be used to confine a fluid in a slab.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::HarmonicWall;
+/// # use lumol_core::{System, Molecule, Particle, UnitCell, Vector3D};
+/// // Keep all particles above the z = 0 plane
+/// let wall = HarmonicWall::new(Vector3D::zero(), Vector3D::new(0.0, 0.0, 1.0), 10.0);
+///
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// let particle = Particle::with_position("Ar", Vector3D::new(0.0, 0.0, -0.5));
+/// system.add_molecule(Molecule::new(particle));
+/// system.add_global_potential(Box::new(wall));
+///
+/// assert_eq!(system.potential_energy(), 0.5 * 10.0 * 0.25);
+/// assert_eq!(system.forces()[0], Vector3D::new(0.0, 0.0, 5.0));
+/// ```
+#[derive(Clone)]
+pub struct HarmonicWall {
+    /// A point in the plane of the wall
+    origin: Vector3D,
+    /// Unit vector normal to the plane, pointing to the allowed side
+    normal: Vector3D,
+    /// Force constant of the wall
+    k: f64,
+}
+
+impl HarmonicWall {
+    /// Create a new harmonic wall going through the point `origin`, and
+    /// pushing the particles in the direction of the `normal` vector with a
+    /// force constant `k`.
+    ///
+    /// # Panics
+    ///
+    /// If the normal vector is null, or if `k` is negative
+    pub fn new(origin: Vector3D, normal: Vector3D, k: f64) -> HarmonicWall {
+        assert!(normal.norm2() > 0.0, "the normal vector of a wall can not be null");
+        assert!(k >= 0.0, "the force constant of a wall must be positive");
+        HarmonicWall {
+            origin: origin,
+            normal: normal.normalized(),
+            k: k,
+        }
+    }
+}
+
+impl ExternalPotential for HarmonicWall {
+    fn particle_energy(&self, _: f64, position: &Vector3D) -> f64 {
+        let distance = (position - self.origin) * self.normal;
+        if distance >= 0.0 {
+            0.0
+        } else {
+            0.5 * self.k * distance * distance
+        }
+    }
+
+    fn particle_force(&self, _: f64, position: &Vector3D) -> Vector3D {
+        let distance = (position - self.origin) * self.normal;
+        if distance >= 0.0 {
+            Vector3D::zero()
+        } else {
+            -self.k * distance * self.normal
+        }
+    }
+}
+
+impl_global_for_external!(HarmonicWall);
+
+/// Spherical confinement, pushing the particles back inside a sphere.
+///
+/// The particles inside the sphere do not feel this potential, and the
+/// particles outside of it have the energy
+///
+/// $$ V(\vec r) = \frac12 k \left(|\vec r - \vec r_0| - R\right)^2 $$
+///
+/// where $\vec r_0$ is the center of the sphere and $R$ its radius. This can
+/// be used to simulate droplets.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::SphericalConfinement;
+/// # use lumol_core::{System, Molecule, Particle, UnitCell, Vector3D};
+/// let sphere = SphericalConfinement::new(Vector3D::zero(), 5.0, 10.0);
+///
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// let particle = Particle::with_position("Ar", Vector3D::new(6.0, 0.0, 0.0));
+/// system.add_molecule(Molecule::new(particle));
+/// system.add_global_potential(Box::new(sphere));
+///
+/// assert_eq!(system.potential_energy(), 0.5 * 10.0);
+/// assert_eq!(system.forces()[0], Vector3D::new(-10.0, 0.0, 0.0));
+/// ```
+#[derive(Clone)]
+pub struct SphericalConfinement {
+    /// Center of the sphere
+    center: Vector3D,
+    /// Radius of the sphere
+    radius: f64,
+    /// Force constant of the confinement
+    k: f64,
+}
+
+impl SphericalConfinement {
+    /// Create a new spherical confinement, keeping the particles inside the
+    /// sphere with the given `center` and `radius`, with a force constant
+    /// `k`.
+    ///
+    /// # Panics
+    ///
+    /// If `radius` or `k` are negative
+    pub fn new(center: Vector3D, radius: f64, k: f64) -> SphericalConfinement {
+        assert!(radius >= 0.0, "the radius of a spherical confinement must be positive");
+        assert!(k >= 0.0, "the force constant of a spherical confinement must be positive");
+        SphericalConfinement {
+            center: center,
+            radius: radius,
+            k: k,
+        }
+    }
+}
+
+impl ExternalPotential for SphericalConfinement {
+    fn particle_energy(&self, _: f64, position: &Vector3D) -> f64 {
+        let distance = (position - self.center).norm() - self.radius;
+        if distance <= 0.0 {
+            0.0
+        } else {
+            0.5 * self.k * distance * distance
+        }
+    }
+
+    fn particle_force(&self, _: f64, position: &Vector3D) -> Vector3D {
+        let r = position - self.center;
+        let distance = r.norm() - self.radius;
+        if distance <= 0.0 {
+            Vector3D::zero()
+        } else {
+            -self.k * distance / r.norm() * r
+        }
+    }
+}
+
+impl_global_for_external!(SphericalConfinement);
+
+/// Uniform external electric field, acting on the charged particles.
+///
+/// A particle with charge $q$ at position $\vec r$ feels the force
+/// $\vec F = q \vec E$, and has the energy
+///
+/// $$ V(\vec r) = - q \vec E \cdot \vec r. $$
+///
+/// The field is expressed in units of energy per charge per length, for
+/// example `units::from(0.1, "eV/A")` for a field of 0.1 V/Å.
+///
+/// # Periodic boundary conditions
+///
+/// **The energy depends on the absolute position of the particles, and is not
+/// periodic.** Wrapping a molecule with a total charge $Q$ in the unit cell,
+/// *i.e.* translating it by a cell vector $\vec L$, changes its energy by
+/// $- Q \vec E \cdot \vec L$, while the forces are unchanged. The Monte Carlo
+/// translation moves, the `Rewrap` molecular dynamics control and the
+/// Lees-Edwards boundary conditions all wrap molecules in the cell, creating
+/// jumps in the energy, and breaking the energy cache of Monte Carlo
+/// simulations. This field should only be used with these algorithms when
+/// all the molecules are neutral, since wrapping a neutral molecule does not
+/// change its energy.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::ElectricField;
+/// # use lumol_core::{System, Molecule, Particle, UnitCell, Vector3D};
+/// let field = ElectricField {
+///     field: Vector3D::new(0.0, 0.0, 0.5),
+/// };
+///
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// let mut particle = Particle::with_position("Na", Vector3D::new(0.0, 0.0, 2.0));
+/// particle.charge = 1.0;
+/// system.add_molecule(Molecule::new(particle));
+/// system.add_global_potential(Box::new(field));
+///
+/// assert_eq!(system.potential_energy(), -1.0);
+/// assert_eq!(system.forces()[0], Vector3D::new(0.0, 0.0, 0.5));
+/// ```
+#[derive(Clone)]
+pub struct ElectricField {
+    /// The electric field vector
+    pub field: Vector3D,
+}
+
+impl ExternalPotential for ElectricField {
+    fn particle_energy(&self, charge: f64, position: &Vector3D) -> f64 {
+        -charge * (self.field * position)
+    }
+
+    fn particle_force(&self, charge: f64, _: &Vector3D) -> Vector3D {
+        charge * self.field
+    }
+}
+
+impl_global_for_external!(ElectricField);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, System, UnitCell};
+
+    use approx::{assert_ulps_eq, assert_relative_eq};
+
+    fn testing_system(positions: &[Vector3D]) -> System {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        for &position in positions {
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        }
+        return system;
+    }
+
+    /// Check the forces and virial of the `potential` against finite
+    /// differences of the energy
+    fn check_finite_differences(potential: &dyn GlobalPotential, system: &mut System) {
+        let eps = 1e-6;
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        potential.forces(system, &mut forces);
+
+        for i in 0..system.size() {
+            for k in 0..3 {
+                system.particles_mut().position[i][k] += eps;
+                let energy_plus = potential.energy(system);
+                system.particles_mut().position[i][k] -= 2.0 * eps;
+                let energy_minus = potential.energy(system);
+                system.particles_mut().position[i][k] += eps;
+
+                let expected = -(energy_plus - energy_minus) / (2.0 * eps);
+                assert_relative_eq!(forces[i][k], expected, epsilon = 1e-6);
+            }
+        }
+
+        let mut energies = vec![0.0; system.size()];
+        potential.atomic_energies(system, &mut energies);
+        assert_ulps_eq!(energies.iter().sum::<f64>(), potential.energy(system));
+
+        let mut virial = Matrix3::zero();
+        for (force, position) in forces.iter().zip(system.particles().position) {
+            virial += force.tensorial(position);
+        }
+        assert_eq!(potential.atomic_virial(system), virial);
+    }
+
+    #[test]
+    fn harmonic_wall() {
+        let origin = Vector3D::new(0.0, 0.0, 1.0);
+        let wall = HarmonicWall::new(origin, Vector3D::new(0.0, 0.0, 2.0), 3.0);
+        let mut system = testing_system(&[
+            Vector3D::new(0.0, 0.0, 3.0),
+            Vector3D::new(1.0, 2.0, 1.0),
+            Vector3D::new(1.0, -4.0, 0.5),
+            Vector3D::new(0.0, 0.0, -1.0),
+        ]);
+
+        assert_eq!(wall.energy(&system), 0.5 * 3.0 * (0.25 + 4.0));
+
+        let mut forces = vec![Vector3D::zero(); 4];
+        wall.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::zero());
+        assert_eq!(forces[1], Vector3D::zero());
+        assert_eq!(forces[2], Vector3D::new(0.0, 0.0, 1.5));
+        assert_eq!(forces[3], Vector3D::new(0.0, 0.0, 6.0));
+
+        check_finite_differences(&wall, &mut system);
+
+        // Moving particles through the wall
+        let cost = wall.move_molecule_cost(&system, 0, &[Vector3D::new(0.0, 0.0, 0.0)]);
+        assert_eq!(cost, 0.5 * 3.0);
+        let cost = wall.move_molecule_cost(&system, 3, &[Vector3D::new(0.0, 0.0, 2.0)]);
+        assert_eq!(cost, -0.5 * 3.0 * 4.0);
+    }
+
+    #[test]
+    fn spherical_confinement() {
+        let center = Vector3D::new(1.0, 1.0, 1.0);
+        let sphere = SphericalConfinement::new(center, 4.0, 2.0);
+        let mut system = testing_system(&[
+            Vector3D::new(1.0, 1.0, 1.0),
+            Vector3D::new(3.0, -1.0, 2.0),
+            Vector3D::new(1.0, 7.0, 1.0),
+            Vector3D::new(-3.0, -3.0, 5.0),
+        ]);
+
+        // Only the particles outside of the sphere feel a force, pointing
+        // toward the center of the sphere
+        let mut forces = vec![Vector3D::zero(); 4];
+        sphere.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::zero());
+        assert_eq!(forces[1], Vector3D::zero());
+        assert_ulps_eq!(forces[2], Vector3D::new(0.0, -4.0, 0.0));
+
+        let r = system.particles().position[3] - center;
+        let expected = -2.0 * (r.norm() - 4.0) * r.normalized();
+        assert_ulps_eq!(forces[3], expected);
+        assert!(forces[3] * r < 0.0);
+
+        let energy = 0.5 * 2.0 * 4.0 + 0.5 * 2.0 * (r.norm() - 4.0) * (r.norm() - 4.0);
+        assert_ulps_eq!(sphere.energy(&system), energy);
+
+        check_finite_differences(&sphere, &mut system);
+    }
+
+    #[test]
+    fn electric_field() {
+        let field = ElectricField {
+            field: Vector3D::new(0.3, 0.0, -1.2),
+        };
+        let mut system = testing_system(&[
+            Vector3D::new(1.0, 0.0, 0.0),
+            Vector3D::new(-2.0, 1.0, 0.0),
+            Vector3D::new(0.0, 0.0, 4.0),
+        ]);
+        system.particles_mut().charge[0] = 1.0;
+        system.particles_mut().charge[1] = -1.0;
+
+        // Opposite charges feel opposite forces, and neutral particles do
+        // not feel the field
+        let mut forces = vec![Vector3D::zero(); 3];
+        field.forces(&system, &mut forces);
+        assert_eq!(forces[0], Vector3D::new(0.3, 0.0, -1.2));
+        assert_eq!(forces[1], Vector3D::new(-0.3, 0.0, 1.2));
+        assert_eq!(forces[2], Vector3D::zero());
+
+        assert_ulps_eq!(field.energy(&system), -0.3 - 0.6);
+
+        check_finite_differences(&field, &mut system);
+
+        // Moving a positive charge along the field lowers the energy
+        let cost = field.move_molecule_cost(&system, 0, &[Vector3D::new(1.0, 0.0, -1.0)]);
+        assert_ulps_eq!(cost, -1.2);
+        let cost = field.move_molecule_cost(&system, 1, &[Vector3D::new(-2.0, 1.0, -1.0)]);
+        assert_ulps_eq!(cost, 1.2);
+    }
+
+    #[test]
+    fn electric_field_wrapping() {
+        let field = ElectricField {
+            field: Vector3D::new(0.3, 0.0, 0.0),
+        };
+
+        let mut system = testing_system(&[Vector3D::new(23.0, 0.0, 0.0)]);
+        let first = Particle::with_position("Ar", Vector3D::new(21.0, 0.0, 0.0));
+        let second = Particle::with_position("Ar", Vector3D::new(22.0, 0.0, 0.0));
+        let mut molecule = Molecule::new(first);
+        molecule.add_particle_bonded_to(0, second);
+        system.add_molecule(molecule);
+        system.particles_mut().charge[0] = 1.0;
+        system.particles_mut().charge[1] = 1.0;
+        system.particles_mut().charge[2] = -1.0;
+
+        let cell = system.cell;
+        let mut forces = vec![Vector3D::zero(); 3];
+        field.forces(&system, &mut forces);
+        let energy = field.energy(&system);
+        assert_ulps_eq!(energy, -0.3 * 23.0 - 0.3 * 21.0 + 0.3 * 22.0);
+
+        // Wrapping a neutral molecule does not change the energy
+        system.molecule_mut(1).wrap(&cell);
+        assert_eq!(system.particles().position[1], Vector3D::new(1.0, 0.0, 0.0));
+        assert_ulps_eq!(field.energy(&system), energy);
+
+        // Wrapping a charged molecule changes the energy by -Q E.L, but not
+        // the forces
+        system.molecule_mut(0).wrap(&cell);
+        assert_eq!(system.particles().position[0], Vector3D::new(3.0, 0.0, 0.0));
+        assert_ulps_eq!(field.energy(&system), energy + 0.3 * 20.0);
+
+        let mut wrapped_forces = vec![Vector3D::zero(); 3];
+        field.forces(&system, &mut wrapped_forces);
+        assert_eq!(wrapped_forces, forces);
+    }
+}
@@ -102,6 +102,20 @@ pub trait GlobalPotential: GlobalCache + BoxCloneGlobal + Send + Sync {
     fn molecular_virial(&self, configuration: &Configuration) -> Matrix3 {
         return self.atomic_virial(configuration);
     }
+
+    /// Add the energetic contribution of this potential to the energy of each
+    /// particle in `energies`. The default implementation distributes the
+    /// total energy evenly between all the particles in the configuration.
+    fn atomic_energies(&self, configuration: &Configuration, energies: &mut [f64]) {
+        assert_eq!(energies.len(), configuration.size());
+        if configuration.size() == 0 {
+            return;
+        }
+        let energy = self.energy(configuration) / configuration.size() as f64;
+        for value in energies {
+            *value += energy;
+        }
+    }
 }
 
 impl_box_clone!(GlobalPotential, BoxCloneGlobal, box_clone_gobal);
@@ -208,3 +222,6 @@ pub use self::wolf::Wolf;
 
 mod ewald;
 pub use self::ewald::{Ewald, SharedEwald};
+
+mod external;
+pub use self::external::{ElectricField, HarmonicWall, SphericalConfinement};
@@ -65,9 +65,25 @@ pub struct Wolf {
 
 impl Wolf {
     /// Create a new Wolf summation, using a real-space cutoff of `cutoff`.
+    /// The damping parameter `alpha` is set to `π / cutoff`.
     pub fn new(cutoff: f64) -> Wolf {
         assert!(cutoff > 0.0, "Got a negative cutoff in Wolf summation");
-        let alpha = PI / cutoff;
+        return Wolf::with_alpha(cutoff, PI / cutoff);
+    }
+
+    /// Create a new Wolf summation, using a real-space cutoff of `cutoff` and
+    /// the damping parameter `alpha`. Good values of `alpha` are usually
+    /// between 0.2 and 0.3 Å<sup>-1</sup> for a cutoff around 10 Å.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::energy::Wolf;
+    /// let wolf = Wolf::with_alpha(11.0, 0.25);
+    /// ```
+    pub fn with_alpha(cutoff: f64, alpha: f64) -> Wolf {
+        assert!(cutoff > 0.0, "Got a negative cutoff in Wolf summation");
+        assert!(alpha > 0.0, "Got a negative alpha in Wolf summation");
 
         let alpha_cutoff = alpha * cutoff;
         let alpha_cutoff_2 = alpha_cutoff * alpha_cutoff;
@@ -322,6 +338,38 @@ impl GlobalPotential for Wolf {
          });
          return virials.sum();
      }
+
+    fn atomic_energies(&self, configuration: &Configuration, energies: &mut [f64]) {
+        assert_eq!(energies.len(), configuration.size());
+        let natoms = configuration.size();
+        let charges = configuration.particles().charge;
+
+        for i in 0..natoms {
+            let qi = charges[i];
+            if qi == 0.0 {
+                continue;
+            }
+            energies[i] -= self.energy_self(qi);
+
+            for j in i + 1..natoms {
+                let qj = charges[j];
+                if qj == 0.0 {
+                    continue;
+                }
+
+                let path = configuration.bond_path(i, j);
+                let info = self.restriction.information(path);
+                if info.excluded {
+                    continue;
+                }
+
+                let rij = configuration.distance(i, j);
+                let energy = 0.5 * info.scaling * self.energy_pair(qi * qj, rij);
+                energies[i] += energy;
+                energies[j] += energy;
+            }
+        }
+    }
 }
 
 impl CoulombicPotential for Wolf {
@@ -334,6 +382,7 @@ impl CoulombicPotential for Wolf {
 mod tests {
     pub use super::*;
     use crate::{System, Matrix3};
+    use crate::{Molecule, Particle, UnitCell};
     use crate::GlobalPotential;
     use crate::utils::system_from_xyz;
 
@@ -364,6 +413,62 @@ mod tests {
         assert_ulps_eq!(e, E_BRUTE_FORCE, epsilon = 1e-2);
     }
 
+    /// Rock salt NaCl crystal, made of `n x n x n` conventional unit cells
+    fn nacl_crystal(n: usize) -> System {
+        let a = 5.64;
+        let mut system = System::with_cell(UnitCell::cubic(n as f64 * a));
+        let sites = [
+            ("Na", [0.0, 0.0, 0.0]), ("Na", [0.5, 0.5, 0.0]),
+            ("Na", [0.5, 0.0, 0.5]), ("Na", [0.0, 0.5, 0.5]),
+            ("Cl", [0.5, 0.0, 0.0]), ("Cl", [0.0, 0.5, 0.0]),
+            ("Cl", [0.0, 0.0, 0.5]), ("Cl", [0.5, 0.5, 0.5]),
+        ];
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    for &(name, site) in &sites {
+                        let position = Vector3D::new(
+                            (i as f64 + site[0]) * a,
+                            (j as f64 + site[1]) * a,
+                            (k as f64 + site[2]) * a,
+                        );
+                        let mut particle = Particle::with_position(name, position);
+                        particle.charge = if name == "Na" { 1.0 } else { -1.0 };
+                        system.add_molecule(Molecule::new(particle));
+                    }
+                }
+            }
+        }
+        return system;
+    }
+
+    #[test]
+    fn madelung() {
+        const NACL_MADELUNG: f64 = 1.747565;
+        let system = nacl_crystal(4);
+        let r0 = 5.64 / 2.0;
+        let npairs = system.size() as f64 / 2.0;
+
+        // energy per ion pair is -M / (4 pi epsilon_0 r0)
+        let wolf = Wolf::new(11.0);
+        let madelung = -wolf.energy(&system) * FOUR_PI_EPSILON_0 * r0 / npairs;
+        assert_relative_eq!(madelung, NACL_MADELUNG, max_relative = 1e-4);
+
+        let wolf = Wolf::with_alpha(11.0, 0.25);
+        let madelung = -wolf.energy(&system) * FOUR_PI_EPSILON_0 * r0 / npairs;
+        assert_relative_eq!(madelung, NACL_MADELUNG, max_relative = 1e-3);
+    }
+
+    #[test]
+    fn atomic_energies() {
+        let system = testing_system();
+        let wolf = Wolf::new(8.0);
+
+        let mut energies = vec![0.0; system.size()];
+        wolf.atomic_energies(&system, &mut energies);
+        assert_ulps_eq!(energies.iter().sum::<f64>(), wolf.energy(&system), epsilon = 1e-12);
+    }
+
     #[test]
     fn forces() {
         let mut system = testing_system();
@@ -437,6 +542,49 @@ mod tests {
         assert_relative_eq!(virial, finite_diff, epsilon = 1e-5);
     }
 
+    #[test]
+    fn scale_14() {
+        // Four atoms chain, with charges on the first and last atoms only,
+        // making the 1-4 pair the only charged pair
+        let mut system = system_from_xyz(
+            "4
+            cell: 20.0
+            C 0.0 0.0 0.0
+            C 1.5 0.0 0.0
+            C 2.0 1.4 0.0
+            C 3.5 1.4 0.3
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(1, 2).is_empty());
+        assert!(system.add_bond(2, 3).is_empty());
+        system.particles_mut().charge[0] = 0.5;
+        system.particles_mut().charge[3] = -0.5;
+
+        fn energy(system: &System, restriction: PairRestriction) -> f64 {
+            let mut wolf = Wolf::new(8.0);
+            wolf.set_restriction(restriction);
+            return wolf.energy(system);
+        }
+
+        let excluded = energy(&system, PairRestriction::Exclude14);
+        let full = energy(&system, PairRestriction::Exclude13);
+        assert!(f64::abs(full - excluded) > 1e-6);
+
+        // The OPLS and AMBER conventions for the electrostatic interactions
+        let scaled = energy(&system, PairRestriction::Scale14(0.5));
+        assert_ulps_eq!(scaled, excluded + 0.5 * (full - excluded), epsilon = 1e-12);
+        let scaled = energy(&system, PairRestriction::Scale14(1.0 / 1.2));
+        assert_ulps_eq!(scaled, excluded + (full - excluded) / 1.2, epsilon = 1e-12);
+
+        // 1-3 pairs are still fully excluded
+        system.particles_mut().charge[2] = 0.5;
+        system.particles_mut().charge[3] = 0.0;
+        let scaled = energy(&system, PairRestriction::Scale14(0.5));
+        assert_ulps_eq!(scaled, energy(&system, PairRestriction::Exclude14), epsilon = 1e-15);
+        assert!(f64::abs(scaled - energy(&system, PairRestriction::None)) > 1e-6);
+    }
+
     mod cache {
         use super::*;
         use crate::{CoulombicPotential, GlobalCache, GlobalPotential, PairRestriction};
@@ -20,7 +20,7 @@
 //! - [`BondPotential`][BondPotential] for covalent bonds interactions;
 //! - [`AnglePotential`][AnglePotential] for covalent angles interactions;
 //! - [`DihedralPotential`][DihedralPotential] for covalent dihedral angles
-//!   interactions.
+//!   and improper dihedral angles interactions.
 //!
 //! ```
 //! use lumol_core::energy::{Potential, PairPotential, DihedralPotential};
@@ -100,6 +100,14 @@ pub trait Potential: Sync + Send {
     fn energy(&self, x: f64) -> f64;
     /// Get the force norm corresponding to the variable `x`
     fn force(&self, x: f64) -> f64;
+
+    /// Get the derivative of the force norm with respect to the variable `x`,
+    /// *i.e.* the opposite of the second derivative of the energy. The
+    /// default implementation uses centered finite differences of the force.
+    fn force_derivative(&self, x: f64) -> f64 {
+        let h = 1e-5 * f64::max(f64::abs(x), 1.0);
+        return (self.force(x + h) - self.force(x - h)) / (2.0 * h);
+    }
 }
 
 /// Marker trait for potentials that can be used for non-bonded two body
@@ -160,6 +168,24 @@ pub trait PairPotential: Potential + BoxClonePair {
     /// If this integral does not converge for the current potential, this
     /// function should then return 0.0 to disable tail corrections.
     fn tail_virial(&self, cutoff: f64) -> f64;
+
+    /// Get the derivative of the energy with respect to the coupling
+    /// parameter $\lambda$ at distance `r`, for potentials depending on such
+    /// a parameter like [`SoftCoreLJ`](struct.SoftCoreLJ.html). This is used
+    /// in thermodynamic integration. The default implementation returns 0,
+    /// for potentials not depending on $\lambda$.
+    fn lambda_derivative(&self, _r: f64) -> f64 {
+        0.0
+    }
+
+    /// Get the derivative of the force with respect to the coupling
+    /// parameter $\lambda$ at distance `r`. This is needed for the
+    /// derivative of shifted force interactions, where the energy depends on
+    /// the force at the cutoff. The default implementation returns 0, for
+    /// potentials not depending on $\lambda$.
+    fn lambda_force_derivative(&self, _r: f64) -> f64 {
+        0.0
+    }
 }
 impl_box_clone!(PairPotential, BoxClonePair, box_clone_pair);
 
@@ -217,6 +243,8 @@ pub trait AnglePotential: Potential + BoxCloneAngle {}
 impl_box_clone!(AnglePotential, BoxCloneAngle, box_clone_angle);
 
 /// Marker trait for potentials that can be used for molecular dihedral angles.
+/// The same potentials are also used for improper dihedral angles, as a
+/// function of the out-of-plane angle.
 ///
 /// # Example
 ///
@@ -239,10 +267,16 @@ pub trait DihedralPotential: Potential + BoxCloneDihedral {}
 impl_box_clone!(DihedralPotential, BoxCloneDihedral, box_clone_dihedral);
 
 mod functions;
-pub use self::functions::{BornMayerHuggins, Buckingham, Gaussian, Morse, Torsion};
-pub use self::functions::{CosineHarmonic, Harmonic, LennardJones, NullPotential};
+pub use self::functions::{BornMayerHuggins, Buckingham, CosineDihedral, Gaussian, Morse, Torsion};
+pub use self::functions::{CosineHarmonic, Harmonic, LennardJones, NullPotential, SoftCoreLJ};
 pub use self::functions::Mie;
 
+mod tabulated;
+pub use self::tabulated::TabulatedPair;
+
+mod registry;
+pub use self::registry::potential_from_name;
+
 mod computations;
 pub use self::computations::{Computation, TableComputation};
 
@@ -252,6 +286,7 @@ pub use self::restrictions::{PairRestriction, RestrictionInfo, BondPath};
 mod global;
 pub use self::global::{CoulombicPotential, GlobalCache, GlobalPotential};
 pub use self::global::{Ewald, SharedEwald, Wolf};
+pub use self::global::{ElectricField, HarmonicWall, SphericalConfinement};
 
 mod pairs;
 pub use self::pairs::PairInteraction;
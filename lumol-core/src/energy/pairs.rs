@@ -11,6 +11,13 @@ enum PairComputation {
     Cutoff,
     /// Using a cutoff distance and a shift
     Shifted(f64),
+    /// Using a cutoff distance, and shifting both the energy and the force
+    ShiftedForce {
+        /// Energy at the cutoff distance
+        energy: f64,
+        /// Force at the cutoff distance
+        force: f64,
+    },
 }
 
 /// A non-bonded interaction between two particle.
@@ -18,8 +25,8 @@ enum PairComputation {
 /// This is a thin wrapper around a [`Box<PairPotential>`][PairPotential]
 /// associated with a [pair restriction][PairRestriction]. It ensure that the
 /// potential is computed up to a cutoff distance. An additional shifting of the
-/// potential can be used in molecular dynamics, to ensure that the energy is
-/// continuous at the cutoff distance.
+/// potential can be used in molecular dynamics, to ensure that the energy (and
+/// optionally the force) is continuous at the cutoff distance.
 ///
 /// [PairPotential]: trait.PairPotential.html
 /// [PairRestriction]: enum.PairRestriction.html
@@ -94,6 +101,48 @@ impl PairInteraction {
         }
     }
 
+    /// Create a new `PairInteraction` with the given `cutoff`, using shifted
+    /// force computation of the energy and the force. Both the energy and the
+    /// force go to zero at the cutoff distance:
+    ///
+    /// $$ V_{sf}(r) = V(r) - V(r_c) + (r - r_c) f(r_c) $$
+    ///
+    /// $$ f_{sf}(r) = f(r) - f(r_c) $$
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::PairInteraction;
+    /// use lumol_core::energy::Harmonic;
+    ///
+    /// let potential = Box::new(Harmonic{x0: 0.5, k: 4.2});
+    /// let interaction = PairInteraction::shifted_force(potential, 2.0);
+    ///
+    /// assert!((interaction.energy(1.0) - 2.1).abs() < 1e-12);
+    /// assert!((interaction.force(1.0) - 4.2).abs() < 1e-12);
+    ///
+    /// // energy and force go smoothly to zero at the cutoff
+    /// assert!(interaction.energy(1.999).abs() < 1e-3);
+    /// assert!(interaction.force(1.999).abs() < 1e-2);
+    /// // energy and force after the cutoff are zero
+    /// assert_eq!(interaction.energy(2.0), 0.0);
+    /// assert_eq!(interaction.force(2.0), 0.0);
+    /// ```
+    pub fn shifted_force(potential: Box<dyn PairPotential>, cutoff: f64) -> PairInteraction {
+        let energy = potential.energy(cutoff);
+        let force = potential.force(cutoff);
+        PairInteraction {
+            potential: potential,
+            cutoff: cutoff,
+            restriction: PairRestriction::None,
+            computation: PairComputation::ShiftedForce {
+                energy: energy,
+                force: force,
+            },
+            tail: false,
+        }
+    }
+
     /// Enable the use of tail corrections for energy and virial contribution
     /// of this pair interaction.
     ///
@@ -190,6 +239,9 @@ impl PairInteraction {
             match self.computation {
                 PairComputation::Cutoff => energy,
                 PairComputation::Shifted(shift) => energy - shift,
+                PairComputation::ShiftedForce { energy: shift, force } => {
+                    energy - shift + (r - self.cutoff) * force
+                }
             }
         }
     }
@@ -213,7 +265,35 @@ impl PairInteraction {
         if r >= self.cutoff {
             0.0
         } else {
-            self.potential.force(r)
+            let force = self.potential.force(r);
+            match self.computation {
+                PairComputation::Cutoff | PairComputation::Shifted(_) => force,
+                PairComputation::ShiftedForce { force: shift, .. } => force - shift,
+            }
+        }
+    }
+
+    /// Get the derivative of the force norm for this pair interaction at the
+    /// distance `r`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::PairInteraction;
+    /// use lumol_core::energy::Harmonic;
+    ///
+    /// let potential = Box::new(Harmonic{x0: 0.5, k: 4.2});
+    /// let interaction = PairInteraction::new(potential, 2.0);
+    ///
+    /// assert_eq!(interaction.force_derivative(1.0), -4.2);
+    /// // force derivative at and after the cutoff is zero
+    /// assert_eq!(interaction.force_derivative(2.0), 0.0);
+    /// ```
+    pub fn force_derivative(&self, r: f64) -> f64 {
+        if r >= self.cutoff {
+            0.0
+        } else {
+            self.potential.force_derivative(r)
         }
     }
 
@@ -235,10 +315,58 @@ impl PairInteraction {
     /// assert_eq!(interaction.virial(&r), r.tensorial(&force));
     /// ```
     pub fn virial(&self, r: &Vector3D) -> Matrix3 {
-        if r.norm() >= self.cutoff {
+        let rn = r.norm();
+        if rn >= self.cutoff {
             Matrix3::zero()
         } else {
-            self.potential.virial(r)
+            let virial = self.potential.virial(r);
+            match self.computation {
+                PairComputation::Cutoff | PairComputation::Shifted(_) => virial,
+                PairComputation::ShiftedForce { force: shift, .. } => {
+                    let force = shift * r / rn;
+                    virial - force.tensorial(r)
+                }
+            }
+        }
+    }
+
+    /// Get the derivative of the energy with respect to the coupling
+    /// parameter $\lambda$ of the potential for this pair interaction at the
+    /// distance `r`, including the energy shifts at the cutoff.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::PairInteraction;
+    /// use lumol_core::energy::{Harmonic, SoftCoreLJ};
+    ///
+    /// let potential = Box::new(SoftCoreLJ{sigma: 1.0, epsilon: 2.0, lambda: 0.5, alpha: 0.5});
+    /// let interaction = PairInteraction::new(potential, 3.0);
+    /// assert!(interaction.lambda_derivative(1.0) > 0.0);
+    /// // derivative at and after the cutoff is zero
+    /// assert_eq!(interaction.lambda_derivative(3.0), 0.0);
+    ///
+    /// // potentials without coupling parameter do not depend on lambda
+    /// let potential = Box::new(Harmonic{x0: 0.5, k: 4.2});
+    /// let interaction = PairInteraction::new(potential, 2.0);
+    /// assert_eq!(interaction.lambda_derivative(1.0), 0.0);
+    /// ```
+    pub fn lambda_derivative(&self, r: f64) -> f64 {
+        if r >= self.cutoff {
+            0.0
+        } else {
+            let derivative = self.potential.lambda_derivative(r);
+            match self.computation {
+                PairComputation::Cutoff => derivative,
+                PairComputation::Shifted(_) => {
+                    derivative - self.potential.lambda_derivative(self.cutoff)
+                }
+                PairComputation::ShiftedForce { .. } => {
+                    let rc = self.cutoff;
+                    derivative - self.potential.lambda_derivative(rc)
+                        + (r - rc) * self.potential.lambda_force_derivative(rc)
+                }
+            }
         }
     }
 
@@ -299,10 +427,10 @@ impl PairInteraction {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{LennardJones, NullPotential, PairRestriction};
+    use crate::{LennardJones, NullPotential, PairRestriction, SoftCoreLJ};
     use crate::Potential;
 
-    use approx::assert_ulps_eq;
+    use approx::{assert_relative_eq, assert_ulps_eq};
 
     #[test]
     fn restriction() {
@@ -345,6 +473,89 @@ mod tests {
         assert_eq!(pairs.energy(4.1), 0.0);
     }
 
+    #[test]
+    fn shifted_continuity() {
+        let lj = LennardJones {
+            sigma: 1.0,
+            epsilon: 2.0,
+        };
+        let rc = 2.5;
+        let eps = 1e-9;
+        let cutoff = PairInteraction::new(Box::new(lj), rc);
+        let shifted = PairInteraction::shifted(Box::new(lj), rc);
+
+        // the truncated energy is discontinuous
+        assert!(cutoff.energy(rc - eps).abs() > 1e-2);
+        // the shifted energy is continuous, but not the force
+        assert!(shifted.energy(rc - eps).abs() < 1e-9);
+        assert!(shifted.force(rc - eps).abs() > 1e-2);
+        assert_ulps_eq!(shifted.force(2.0), lj.force(2.0));
+    }
+
+    #[test]
+    fn shifted_force() {
+        let lj = LennardJones {
+            sigma: 1.0,
+            epsilon: 2.0,
+        };
+        let rc = 2.5;
+        let eps = 1e-9;
+        let pairs = PairInteraction::shifted_force(Box::new(lj), rc);
+
+        // energy and force are continuous at the cutoff
+        assert!(pairs.energy(rc - eps).abs() < 1e-9);
+        assert!(pairs.force(rc - eps).abs() < 1e-7);
+        assert_eq!(pairs.energy(rc), 0.0);
+        assert_eq!(pairs.force(rc), 0.0);
+
+        assert_ulps_eq!(pairs.force(2.0), lj.force(2.0) - lj.force(rc));
+        let energy = lj.energy(2.0) - lj.energy(rc) - 0.5 * lj.force(rc);
+        assert_ulps_eq!(pairs.energy(2.0), energy);
+
+        // the force is the derivative of the energy
+        for &r in &[1.0, 1.5, 2.0, 2.4] {
+            let e0 = pairs.energy(r - 1e-6);
+            let e1 = pairs.energy(r + 1e-6);
+            assert_relative_eq!((e0 - e1) / 2e-6, pairs.force(r), epsilon = 1e-6);
+        }
+
+        // the virial uses the shifted force
+        let r = Vector3D::new(1.0, 0.5, -1.2);
+        let force = pairs.force(r.norm()) * r / r.norm();
+        assert_relative_eq!(pairs.virial(&r), force.tensorial(&r), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn lambda_derivative() {
+        let interaction = |lambda, kind| {
+            let rc = 2.5;
+            let potential = Box::new(SoftCoreLJ {
+                sigma: 1.0,
+                epsilon: 2.0,
+                lambda: lambda,
+                alpha: 0.5,
+            });
+            match kind {
+                0 => PairInteraction::new(potential, rc),
+                1 => PairInteraction::shifted(potential, rc),
+                _ => PairInteraction::shifted_force(potential, rc),
+            }
+        };
+
+        let lambda = 0.4;
+        let h = 1e-6;
+        for kind in 0..3 {
+            let pairs = interaction(lambda, kind);
+            assert_eq!(pairs.lambda_derivative(2.5), 0.0);
+            for &r in &[0.0, 0.8, 1.2, 2.0, 2.4] {
+                let forward = interaction(lambda + h, kind).energy(r);
+                let backward = interaction(lambda - h, kind).energy(r);
+                let expected = (forward - backward) / (2.0 * h);
+                assert_relative_eq!(pairs.lambda_derivative(r), expected, epsilon = 1e-6);
+            }
+        }
+    }
+
     #[test]
     fn tail_corrections() {
         let lj = LennardJones {
@@ -0,0 +1,256 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Create pair potentials from their name and a set of numeric parameters.
+use std::collections::HashMap;
+
+use log::warn;
+
+use super::{PairPotential, Potential};
+use super::{BornMayerHuggins, Buckingham, Gaussian, Harmonic, LennardJones, Mie};
+use super::{Morse, NullPotential, SoftCoreLJ};
+
+/// Get the parameter with the given `name` for the `potential`, or an error
+/// message if it is missing.
+fn parameter(
+    parameters: &HashMap<String, f64>,
+    name: &str,
+    potential: &str,
+) -> Result<f64, String> {
+    match parameters.get(name) {
+        Some(&value) => Ok(value),
+        None => Err(format!("missing '{}' parameter for {} potential", name, potential)),
+    }
+}
+
+/// Create a new pair potential from its `name` and numeric `parameters`.
+///
+/// This allows to create potentials dynamically, for example when reading
+/// them from configuration files. All the parameters must be given in
+/// internal units. The supported potentials and their parameters are:
+///
+/// - `"null"`: [`NullPotential`], without parameters;
+/// - `"lennard-jones"` or `"lj"`: [`LennardJones`], with `sigma` and `epsilon`;
+/// - `"soft-core-lj"`: [`SoftCoreLJ`], with `sigma`, `epsilon`, `lambda`
+///   and `alpha`;
+/// - `"harmonic"`: [`Harmonic`], with `k` and `x0`;
+/// - `"morse"`: [`Morse`], with `A`, `depth` and `x0`;
+/// - `"buckingham"`: [`Buckingham`], with `A`, `C`, `rho` and optionally
+///   `r_min`;
+/// - `"born-mayer-huggins"` or `"born"`: [`BornMayerHuggins`], with `A`,
+///   `C`, `D`, `sigma` and `rho`;
+/// - `"gaussian"`: [`Gaussian`], with `A` and `B`;
+/// - `"mie"`: [`Mie`], with `sigma`, `epsilon`, `n` and `m`.
+///
+/// An error message is returned if the potential name is unknown, if an
+/// unexpected parameter is given, or if a parameter is missing or has an
+/// invalid value.
+///
+/// [`NullPotential`]: struct.NullPotential.html
+/// [`LennardJones`]: struct.LennardJones.html
+/// [`SoftCoreLJ`]: struct.SoftCoreLJ.html
+/// [`Harmonic`]: struct.Harmonic.html
+/// [`Morse`]: struct.Morse.html
+/// [`Buckingham`]: struct.Buckingham.html
+/// [`BornMayerHuggins`]: struct.BornMayerHuggins.html
+/// [`Gaussian`]: struct.Gaussian.html
+/// [`Mie`]: struct.Mie.html
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use lumol_core::energy::Potential;
+/// use lumol_core::energy::potential_from_name;
+///
+/// let mut parameters = HashMap::new();
+/// parameters.insert(String::from("sigma"), 2.0);
+/// parameters.insert(String::from("epsilon"), 10.0);
+///
+/// let potential = potential_from_name("lennard-jones", &parameters).unwrap();
+/// assert_eq!(potential.energy(2.0), 0.0);
+///
+/// assert!(potential_from_name("harmonic", &parameters).is_err());
+/// assert!(potential_from_name("unknown", &parameters).is_err());
+/// ```
+pub fn potential_from_name(
+    name: &str,
+    parameters: &HashMap<String, f64>,
+) -> Result<Box<dyn PairPotential>, String> {
+    let expected: &[&str] = match name {
+        "null" => &[],
+        "lennard-jones" | "lj" => &["sigma", "epsilon"],
+        "soft-core-lj" => &["sigma", "epsilon", "lambda", "alpha"],
+        "harmonic" => &["k", "x0"],
+        "morse" => &["A", "depth", "x0"],
+        "buckingham" => &["A", "C", "rho", "r_min"],
+        "born-mayer-huggins" | "born" => &["A", "C", "D", "sigma", "rho"],
+        "gaussian" => &["A", "B"],
+        "mie" => &["sigma", "epsilon", "n", "m"],
+        other => return Err(format!("unknown pair potential '{}'", other)),
+    };
+
+    for key in parameters.keys() {
+        if !expected.contains(&key.as_str()) {
+            return Err(format!("unexpected '{}' parameter for '{}' potential", key, name));
+        }
+    }
+
+    let get = |parameter_name, potential| parameter(parameters, parameter_name, potential);
+    let potential: Box<dyn PairPotential> = match name {
+        "null" => Box::new(NullPotential),
+        "lennard-jones" | "lj" => Box::new(LennardJones {
+            sigma: get("sigma", "Lennard-Jones")?,
+            epsilon: get("epsilon", "Lennard-Jones")?,
+        }),
+        "soft-core-lj" => Box::new(SoftCoreLJ {
+            sigma: get("sigma", "soft-core Lennard-Jones")?,
+            epsilon: get("epsilon", "soft-core Lennard-Jones")?,
+            lambda: get("lambda", "soft-core Lennard-Jones")?,
+            alpha: get("alpha", "soft-core Lennard-Jones")?,
+        }),
+        "harmonic" => Box::new(Harmonic {
+            k: get("k", "harmonic")?,
+            x0: get("x0", "harmonic")?,
+        }),
+        "morse" => Box::new(Morse {
+            a: get("A", "Morse")?,
+            depth: get("depth", "Morse")?,
+            x0: get("x0", "Morse")?,
+        }),
+        "buckingham" => {
+            let a = get("A", "Buckingham")?;
+            let c = get("C", "Buckingham")?;
+            let rho = get("rho", "Buckingham")?;
+            match parameters.get("r_min") {
+                None => Box::new(Buckingham::new(a, c, rho)),
+                Some(&r_min) => {
+                    if r_min <= 0.0 {
+                        return Err(String::from(
+                            "'r_min' parameter has to be positive in Buckingham potential",
+                        ));
+                    }
+                    if Buckingham::new(a, c, rho).force(r_min) <= 0.0 {
+                        return Err(String::from(
+                            "'r_min' parameter has to be after the maximum of Buckingham potential",
+                        ));
+                    }
+                    Box::new(Buckingham::with_r_min(a, c, rho, r_min))
+                }
+            }
+        }
+        "born-mayer-huggins" | "born" => Box::new(BornMayerHuggins {
+            a: get("A", "Born-Mayer-Huggins")?,
+            c: get("C", "Born-Mayer-Huggins")?,
+            d: get("D", "Born-Mayer-Huggins")?,
+            sigma: get("sigma", "Born-Mayer-Huggins")?,
+            rho: get("rho", "Born-Mayer-Huggins")?,
+        }),
+        "gaussian" => {
+            let a = get("A", "Gaussian")?;
+            let b = get("B", "Gaussian")?;
+            if b <= 0.0 {
+                return Err(String::from("'B' parameter has to be positive in Gaussian potential"));
+            }
+            Box::new(Gaussian::new(a, b))
+        }
+        "mie" => {
+            let sigma = get("sigma", "Mie")?;
+            let epsilon = get("epsilon", "Mie")?;
+            let n = get("n", "Mie")?;
+            let m = get("m", "Mie")?;
+            if m >= n {
+                return Err(String::from(
+                    "the repulsive exponent 'n' has to be larger than the attractive exponent 'm'",
+                ));
+            }
+            if m < 3.0 {
+                warn!("'m' is smaller than 3. Tail corrections for Mie potential are set to zero.");
+            }
+            Box::new(Mie::new(sigma, epsilon, n, m))
+        }
+        _ => unreachable!(),
+    };
+
+    return Ok(potential);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PairPotential, Potential};
+
+    fn parameters(values: &[(&str, f64)]) -> HashMap<String, f64> {
+        values.iter().map(|&(name, value)| (String::from(name), value)).collect()
+    }
+
+    #[test]
+    fn lennard_jones() {
+        let lj = LennardJones {
+            sigma: 3.4,
+            epsilon: 0.9,
+        };
+        let potential = potential_from_name(
+            "lennard-jones",
+            &parameters(&[("sigma", 3.4), ("epsilon", 0.9)]),
+        ).unwrap();
+
+        for &r in &[3.0, 3.4, 3.8, 5.0, 8.0] {
+            assert_eq!(potential.force(r), lj.force(r));
+            assert_eq!(potential.energy(r), lj.energy(r));
+        }
+        assert_eq!(potential.tail_energy(8.0), lj.tail_energy(8.0));
+
+        let alias = potential_from_name("lj", &parameters(&[("sigma", 3.4), ("epsilon", 0.9)]));
+        assert_eq!(alias.unwrap().force(3.8), lj.force(3.8));
+    }
+
+    #[test]
+    fn all_potentials() {
+        let check = |name, values: &[(&str, f64)]| {
+            let potential = potential_from_name(name, &parameters(values)).unwrap();
+            assert!(potential.energy(2.5).is_finite());
+        };
+
+        check("null", &[]);
+        check("soft-core-lj", &[("sigma", 2.0), ("epsilon", 1.0), ("lambda", 0.5), ("alpha", 0.5)]);
+        check("harmonic", &[("k", 2.0), ("x0", 1.0)]);
+        check("morse", &[("A", 2.0), ("depth", 1.0), ("x0", 1.0)]);
+        check("buckingham", &[("A", 2.0), ("C", 1.0), ("rho", 0.3)]);
+        check("buckingham", &[("A", 2.0), ("C", 1.0), ("rho", 1.0), ("r_min", 1.5)]);
+        check("born", &[("A", 2.0), ("C", 1.0), ("D", 1.0), ("sigma", 1.0), ("rho", 0.3)]);
+        check("gaussian", &[("A", 2.0), ("B", 1.0)]);
+        check("mie", &[("sigma", 2.0), ("epsilon", 1.0), ("n", 12.0), ("m", 6.0)]);
+    }
+
+    #[test]
+    fn errors() {
+        let lj = parameters(&[("sigma", 3.4), ("epsilon", 0.9)]);
+        assert_eq!(
+            potential_from_name("foo", &lj).err().unwrap(),
+            "unknown pair potential 'foo'"
+        );
+
+        let missing = parameters(&[("sigma", 3.4)]);
+        assert_eq!(
+            potential_from_name("lennard-jones", &missing).err().unwrap(),
+            "missing 'epsilon' parameter for Lennard-Jones potential"
+        );
+
+        let unexpected = parameters(&[("sigma", 3.4), ("epsilon", 0.9), ("k", 1.0)]);
+        assert_eq!(
+            potential_from_name("lj", &unexpected).err().unwrap(),
+            "unexpected 'k' parameter for 'lj' potential"
+        );
+
+        assert!(potential_from_name("gaussian", &parameters(&[("A", 2.0), ("B", -1.0)])).is_err());
+        let mie = parameters(&[("sigma", 2.0), ("epsilon", 1.0), ("n", 6.0), ("m", 12.0)]);
+        assert!(potential_from_name("mie", &mie).is_err());
+
+        let buckingham = parameters(&[("A", 2.0), ("C", 1.0), ("rho", 1.0), ("r_min", 1.0)]);
+        assert_eq!(
+            potential_from_name("buckingham", &buckingham).err().unwrap(),
+            "'r_min' parameter has to be after the maximum of Buckingham potential"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use crate::{PairPotential, Potential};
+
+/// Tabulated pair potential, interpolated from a set of data points.
+///
+/// This potential can be used for interactions without closed form, for
+/// example coming from a fit to *ab initio* data. The energy is interpolated
+/// between the data points using cubic Hermite splines, using the tabulated
+/// energy and force at each point. The force is computed as the derivative of
+/// the interpolated energy, so that energy and forces are consistent.
+///
+/// Below the first point, the potential is continued linearly, using the
+/// energy and force at the first point. After the last point, both the energy
+/// and the force are zero: the last point should be at or after the cutoff
+/// distance of the interaction. Because nothing is known about the potential
+/// after the last point, this potential does not provide tail corrections.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// use lumol_core::energy::{TabulatedPair, LennardJones};
+///
+/// let lj = LennardJones { sigma: 3.0, epsilon: 0.5 };
+/// let table = TabulatedPair::from_function(&lj, 2.5, 10.0, 1000);
+///
+/// assert!(f64::abs(table.energy(4.2) - lj.energy(4.2)) < 1e-9);
+/// assert!(f64::abs(table.force(4.2) - lj.force(4.2)) < 1e-7);
+///
+/// // No interaction after the last point
+/// assert_eq!(table.energy(12.0), 0.0);
+/// ```
+#[derive(Clone)]
+pub struct TabulatedPair {
+    /// Positions of the data points, sorted in increasing order
+    r: Vec<f64>,
+    /// Energy at each data point
+    energy: Vec<f64>,
+    /// Force at each data point
+    force: Vec<f64>,
+}
+
+impl TabulatedPair {
+    /// Create a new `TabulatedPair` from the `energy` and `force` values at
+    /// the distances in `r`.
+    ///
+    /// # Panics
+    ///
+    /// If `r`, `energy` and `force` do not have the same length, if there are
+    /// less than two points, or if the values in `r` are not strictly
+    /// increasing.
+    pub fn from_points(r: Vec<f64>, energy: Vec<f64>, force: Vec<f64>) -> TabulatedPair {
+        assert_eq!(r.len(), energy.len(), "r and energy must have the same size in TabulatedPair");
+        assert_eq!(r.len(), force.len(), "r and force must have the same size in TabulatedPair");
+        assert!(r.len() >= 2, "TabulatedPair needs at least two points");
+        assert!(
+            r.windows(2).all(|w| w[0] < w[1]),
+            "r values must be strictly increasing in TabulatedPair"
+        );
+
+        TabulatedPair {
+            r: r,
+            energy: energy,
+            force: force,
+        }
+    }
+
+    /// Create a new `TabulatedPair` by tabulating `potential` on `n` points
+    /// regularly spaced between `r_min` and `r_max`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is smaller than 2, or if `r_max` is not larger than `r_min`.
+    pub fn from_function<P>(potential: &P, r_min: f64, r_max: f64, n: usize) -> TabulatedPair
+        where P: Potential + ?Sized
+    {
+        assert!(n >= 2, "TabulatedPair needs at least two points");
+        assert!(r_max > r_min, "r_max must be larger than r_min in TabulatedPair");
+
+        let delta = (r_max - r_min) / (n - 1) as f64;
+        let r = (0..n).map(|i| r_min + i as f64 * delta).collect::<Vec<_>>();
+        let energy = r.iter().map(|&r| potential.energy(r)).collect();
+        let force = r.iter().map(|&r| potential.force(r)).collect();
+        return TabulatedPair::from_points(r, energy, force);
+    }
+
+    /// Get the index `i` of the interval `[r[i], r[i + 1]]` containing `r`.
+    /// The value of `r` must be inside the tabulated range.
+    fn interval(&self, r: f64) -> usize {
+        debug_assert!(r >= self.r[0] && r <= self.r[self.r.len() - 1]);
+        let search = self.r.binary_search_by(|x| {
+            x.partial_cmp(&r).expect("got a NaN value in TabulatedPair")
+        });
+        match search {
+            Ok(i) => usize::min(i, self.r.len() - 2),
+            Err(i) => i - 1,
+        }
+    }
+}
+
+impl Potential for TabulatedPair {
+    fn energy(&self, r: f64) -> f64 {
+        let last = self.r.len() - 1;
+        if r < self.r[0] {
+            return self.energy[0] + self.force[0] * (self.r[0] - r);
+        } else if r > self.r[last] {
+            return 0.0;
+        }
+
+        let i = self.interval(r);
+        let h = self.r[i + 1] - self.r[i];
+        let t = (r - self.r[i]) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        // Hermite basis functions, the derivative of the energy is the
+        // opposite of the force.
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+        return h00 * self.energy[i] - h10 * h * self.force[i]
+             + h01 * self.energy[i + 1] - h11 * h * self.force[i + 1];
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        let last = self.r.len() - 1;
+        if r < self.r[0] {
+            return self.force[0];
+        } else if r > self.r[last] {
+            return 0.0;
+        }
+
+        let i = self.interval(r);
+        let h = self.r[i + 1] - self.r[i];
+        let t = (r - self.r[i]) / h;
+        let t2 = t * t;
+
+        // Derivatives of the Hermite basis functions with respect to t
+        let dh00 = 6.0 * t2 - 6.0 * t;
+        let dh10 = 3.0 * t2 - 4.0 * t + 1.0;
+        let dh01 = -6.0 * t2 + 6.0 * t;
+        let dh11 = 3.0 * t2 - 2.0 * t;
+        let derivative = (dh00 * self.energy[i] + dh01 * self.energy[i + 1]) / h
+                       - dh10 * self.force[i] - dh11 * self.force[i + 1];
+        return -derivative;
+    }
+}
+
+impl PairPotential for TabulatedPair {
+    fn tail_energy(&self, _: f64) -> f64 {
+        0.0
+    }
+
+    fn tail_virial(&self, _: f64) -> f64 {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::energy::{LennardJones, PairInteraction};
+    use crate::Vector3D;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn data_points() {
+        let table = TabulatedPair::from_points(
+            vec![1.0, 1.5, 3.0],
+            vec![4.0, 2.0, 1.0],
+            vec![3.0, 1.0, 0.5],
+        );
+
+        // The table goes through the data points
+        assert_eq!(table.energy(1.0), 4.0);
+        assert_eq!(table.energy(1.5), 2.0);
+        assert_eq!(table.energy(3.0), 1.0);
+        assert_eq!(table.force(1.0), 3.0);
+        assert_eq!(table.force(1.5), 1.0);
+        assert_eq!(table.force(3.0), 0.5);
+
+        // Linear continuation before the first point
+        assert_eq!(table.force(0.2), 3.0);
+        assert_eq!(table.energy(0.5), 4.0 + 3.0 * 0.5);
+
+        // No interaction after the last point
+        assert_eq!(table.energy(3.1), 0.0);
+        assert_eq!(table.force(3.1), 0.0);
+
+        // Forces are consistent with the energy
+        let eps = 1e-7;
+        for &r in &[1.1, 1.4, 1.7, 2.5] {
+            let e0 = table.energy(r - eps);
+            let e1 = table.energy(r + eps);
+            assert_relative_eq!((e0 - e1) / (2.0 * eps), table.force(r), epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn lennard_jones() {
+        let lj = LennardJones {
+            sigma: 3.405,
+            epsilon: 1.0,
+        };
+        let table = TabulatedPair::from_function(&lj, 3.0, 12.0, 2000);
+
+        let mut r = 3.0;
+        while r < 12.0 {
+            assert_relative_eq!(table.energy(r), lj.energy(r), epsilon = 1e-8);
+            assert_relative_eq!(table.force(r), lj.force(r), epsilon = 1e-5);
+            r += 0.0137;
+        }
+
+        // The table can be used as any other pair potential
+        let table = PairInteraction::new(Box::new(table), 10.0);
+        let lj = PairInteraction::new(Box::new(lj), 10.0);
+        let r = Vector3D::new(1.0, 2.5, 3.0);
+        assert_relative_eq!(table.virial(&r), lj.virial(&r), epsilon = 1e-6);
+        assert_eq!(table.energy(11.0), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted_points() {
+        let _ = TabulatedPair::from_points(vec![1.0, 3.0, 2.0], vec![0.0; 3], vec![0.0; 3]);
+    }
+}
@@ -0,0 +1,273 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Fluent construction of systems from crystal lattices.
+use crate::energy::PairInteraction;
+use crate::{Molecule, Particle, System, UnitCell, Vector3D};
+
+/// A cubic crystal lattice, made of identical particles.
+///
+/// The lattice is defined by the positions of the particles in the
+/// conventional unit cell, in fractional coordinates.
+#[derive(Clone, Debug)]
+pub struct Lattice {
+    /// Name of the particles in the lattice
+    name: String,
+    /// Fractional positions of the particles in the conventional unit cell
+    basis: Vec<Vector3D>,
+}
+
+impl Lattice {
+    /// Create a simple cubic lattice, with one particle named `name` in the
+    /// conventional unit cell.
+    pub fn sc<S: Into<String>>(name: S) -> Lattice {
+        Lattice {
+            name: name.into(),
+            basis: vec![Vector3D::zero()],
+        }
+    }
+
+    /// Create a body-centered cubic lattice, with two particles named `name`
+    /// in the conventional unit cell.
+    pub fn bcc<S: Into<String>>(name: S) -> Lattice {
+        Lattice {
+            name: name.into(),
+            basis: vec![Vector3D::zero(), Vector3D::new(0.5, 0.5, 0.5)],
+        }
+    }
+
+    /// Create a face-centered cubic lattice, with four particles named `name`
+    /// in the conventional unit cell.
+    pub fn fcc<S: Into<String>>(name: S) -> Lattice {
+        Lattice {
+            name: name.into(),
+            basis: vec![
+                Vector3D::zero(),
+                Vector3D::new(0.5, 0.5, 0.0),
+                Vector3D::new(0.5, 0.0, 0.5),
+                Vector3D::new(0.0, 0.5, 0.5),
+            ],
+        }
+    }
+
+    /// Get the number of particles in the conventional unit cell of this
+    /// lattice.
+    pub fn size(&self) -> usize {
+        self.basis.len()
+    }
+}
+
+/// A [`System`](struct.System.html) builder, to construct a system step by
+/// step.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{SystemBuilder, Lattice, UnitCell};
+/// # use lumol_core::energy::{LennardJones, PairInteraction};
+/// let lj = LennardJones {
+///     sigma: 3.4,
+///     epsilon: 1e-4,
+/// };
+///
+/// let system = SystemBuilder::new()
+///     .cell(UnitCell::cubic(5.0 * 5.26))
+///     .add_lattice(Lattice::fcc("Ar"), 5.26, 5, 5, 5)
+///     .pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 10.0))
+///     .build();
+///
+/// assert_eq!(system.size(), 500);
+/// ```
+pub struct SystemBuilder {
+    cell: UnitCell,
+    molecules: Vec<Molecule>,
+    pairs: Vec<((String, String), PairInteraction)>,
+}
+
+impl SystemBuilder {
+    /// Create a new builder for an empty system with an infinite unit cell.
+    pub fn new() -> SystemBuilder {
+        SystemBuilder {
+            cell: UnitCell::infinite(),
+            molecules: Vec::new(),
+            pairs: Vec::new(),
+        }
+    }
+
+    /// Use the given unit `cell` for the system.
+    pub fn cell(mut self, cell: UnitCell) -> SystemBuilder {
+        self.cell = cell;
+        return self;
+    }
+
+    /// Add particles in the system, placed on `nx`, `ny` and `nz` repetitions
+    /// of the conventional unit cell of `lattice`, starting at the origin.
+    /// The conventional unit cell is a cube with side length
+    /// `lattice_constant`. Each particle is added as a separate molecule.
+    ///
+    /// The unit cell of the system is not modified, and should be set with
+    /// [`SystemBuilder::cell`](struct.SystemBuilder.html#method.cell).
+    pub fn add_lattice(mut self, lattice: Lattice, lattice_constant: f64, nx: usize, ny: usize, nz: usize) -> SystemBuilder {
+        assert!(lattice_constant > 0.0, "the lattice constant must be positive");
+        let Lattice { name, basis } = lattice;
+        self.molecules.reserve(basis.len() * nx * ny * nz);
+        for i in 0..nx {
+            for j in 0..ny {
+                for k in 0..nz {
+                    let origin = Vector3D::new(i as f64, j as f64, k as f64);
+                    for &site in &basis {
+                        let position = (origin + site) * lattice_constant;
+                        let particle = Particle::with_position(name.clone(), position);
+                        self.molecules.push(Molecule::new(particle));
+                    }
+                }
+            }
+        }
+        return self;
+    }
+
+    /// Add a `molecule` in the system.
+    pub fn add_molecule(mut self, molecule: Molecule) -> SystemBuilder {
+        self.molecules.push(molecule);
+        return self;
+    }
+
+    /// Use the given pair `potential` between particles with names `i` and
+    /// `j` in the system.
+    pub fn pair_potential(mut self, (i, j): (&str, &str), potential: PairInteraction) -> SystemBuilder {
+        self.pairs.push(((i.into(), j.into()), potential));
+        return self;
+    }
+
+    /// Build the system.
+    ///
+    /// # Panics
+    ///
+    /// If the cutoff of one of the pair potentials is bigger than half of the
+    /// unit cell.
+    pub fn build(self) -> System {
+        let mut system = System::with_cell(self.cell);
+        for molecule in self.molecules {
+            system.add_molecule(molecule);
+        }
+        for ((i, j), potential) in self.pairs {
+            system.set_pair_potential((i.as_str(), j.as_str()), potential);
+        }
+        return system;
+    }
+}
+
+impl System {
+    /// Create a system containing a face-centered cubic crystal of particles
+    /// named `name`, with `cells` conventional unit cells of side `a` in each
+    /// direction. The system has a cubic unit cell of side `cells * a`, and
+    /// contains `4 * cells³` particles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::System;
+    /// let system = System::fcc("Ar", 5.26, 3);
+    /// assert_eq!(system.size(), 4 * 27);
+    /// ```
+    pub fn fcc(name: &str, a: f64, cells: usize) -> System {
+        SystemBuilder::new()
+            .cell(UnitCell::cubic(cells as f64 * a))
+            .add_lattice(Lattice::fcc(name), a, cells, cells, cells)
+            .build()
+    }
+
+    /// Create a system containing a body-centered cubic crystal of particles
+    /// named `name`, with `cells` conventional unit cells of side `a` in each
+    /// direction. The system has a cubic unit cell of side `cells * a`, and
+    /// contains `2 * cells³` particles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::System;
+    /// let system = System::bcc("Fe", 2.87, 3);
+    /// assert_eq!(system.size(), 2 * 27);
+    /// ```
+    pub fn bcc(name: &str, a: f64, cells: usize) -> System {
+        SystemBuilder::new()
+            .cell(UnitCell::cubic(cells as f64 * a))
+            .add_lattice(Lattice::bcc(name), a, cells, cells, cells)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_ulps_eq;
+
+    #[test]
+    fn simple_cubic() {
+        let system = SystemBuilder::new()
+            .cell(UnitCell::ortho(6.0, 9.0, 12.0))
+            .add_lattice(Lattice::sc("Ar"), 3.0, 2, 3, 4)
+            .build();
+
+        assert_eq!(system.size(), 2 * 3 * 4);
+        assert_eq!(system.molecules().count(), 2 * 3 * 4);
+        assert!(system.particles().name.iter().all(|name| name == "Ar"));
+
+        let positions = system.particles().position;
+        assert_eq!(positions[0], Vector3D::new(0.0, 0.0, 0.0));
+        assert_eq!(positions[1], Vector3D::new(0.0, 0.0, 3.0));
+        assert_eq!(positions[4], Vector3D::new(0.0, 3.0, 0.0));
+        assert_eq!(positions[12], Vector3D::new(3.0, 0.0, 0.0));
+        assert_eq!(positions[23], Vector3D::new(3.0, 6.0, 9.0));
+    }
+
+    /// Get the shortest distance between two particles in the system, and
+    /// the number of neighbors of the first particle at this distance.
+    fn nearest_neighbors(system: &System) -> (f64, usize) {
+        let positions = system.particles().position;
+        let mut min = f64::INFINITY;
+        for i in 0..system.size() {
+            for j in (i + 1)..system.size() {
+                min = f64::min(min, system.cell.distance(&positions[i], &positions[j]));
+            }
+        }
+
+        let count = (1..system.size())
+            .filter(|&j| system.cell.distance(&positions[0], &positions[j]) < min + 1e-9)
+            .count();
+        return (min, count);
+    }
+
+    #[test]
+    fn cubic_lattices() {
+        let system = SystemBuilder::new().add_lattice(Lattice::bcc("Fe"), 2.87, 3, 3, 3).build();
+        assert_eq!(system.size(), 2 * 27);
+        assert_eq!(system.particles().position[1], Vector3D::new(1.435, 1.435, 1.435));
+
+        let system = SystemBuilder::new().add_lattice(Lattice::fcc("Cu"), 3.6, 3, 3, 3).build();
+        assert_eq!(system.size(), 4 * 27);
+        assert_eq!(system.particles().position[1], Vector3D::new(1.8, 1.8, 0.0));
+    }
+
+    #[test]
+    fn fcc() {
+        let system = System::fcc("Ar", 5.26, 3);
+        assert_eq!(system.size(), 4 * 27);
+        assert_eq!(system.cell, UnitCell::cubic(3.0 * 5.26));
+
+        let (distance, count) = nearest_neighbors(&system);
+        assert_ulps_eq!(distance, 5.26 / f64::sqrt(2.0), epsilon = 1e-12);
+        assert_eq!(count, 12);
+    }
+
+    #[test]
+    fn bcc() {
+        let system = System::bcc("Fe", 2.87, 3);
+        assert_eq!(system.size(), 2 * 27);
+        assert_eq!(system.cell, UnitCell::cubic(3.0 * 2.87));
+
+        let (distance, count) = nearest_neighbors(&system);
+        assert_ulps_eq!(distance, 2.87 * f64::sqrt(3.0) / 2.0, epsilon = 1e-12);
+        assert_eq!(count, 8);
+    }
+}
@@ -9,7 +9,7 @@
 //! energy components, by storing them and providing update callbacks.
 use std::mem;
 
-use crate::System;
+use crate::{Configuration, System};
 use crate::{Array2, Vector3D};
 
 /// Callback for updating a cache. It also take an `&mut System` argument for
@@ -32,8 +32,12 @@ pub struct EnergyCache {
     bonds: f64,
     /// Energy of all the angles in the system
     angles: f64,
+    /// Energy of all the Urey-Bradley terms in the system
+    urey_bradleys: f64,
     /// Energy of all the dihedrals angles in the system
     dihedrals: f64,
+    /// Energy of all the improper dihedrals angles in the system
+    impropers: f64,
     /// Energy of coulombic interactions
     coulomb: f64,
     /// Energy of global interactions
@@ -51,7 +55,9 @@ impl EnergyCache {
             pairs_tail: 0.0,
             bonds: 0.0,
             angles: 0.0,
+            urey_bradleys: 0.0,
             dihedrals: 0.0,
+            impropers: 0.0,
             coulomb: 0.0,
             global: 0.0,
             updater: None,
@@ -65,7 +71,9 @@ impl EnergyCache {
         self.pairs_tail = 0.0;
         self.bonds = 0.0;
         self.angles = 0.0;
+        self.urey_bradleys = 0.0;
         self.dihedrals = 0.0;
+        self.impropers = 0.0;
         self.coulomb = 0.0;
         self.global = 0.0;
     }
@@ -74,28 +82,32 @@ impl EnergyCache {
     /// function, the cache is only usable with the same system. To change
     /// the associated system, one must call this function again.
     pub fn init(&mut self, system: &System) {
-        self.clear();
-        self.pairs_cache.resize_if_different((system.size(), system.size()));
-
-        let evaluator = system.energy_evaluator();
-
-        for i in 0..system.size() {
-            for j in (i + 1)..system.size() {
-                let r = system.nearest_image(i, j).norm();
-                let path = system.bond_path(i, j);
-                let energy = evaluator.pair(path, r, i, j);
-                self.pairs_cache[(i, j)] = energy;
-                self.pairs_cache[(j, i)] = energy;
-                self.pairs += energy;
+        system.install(|| {
+            self.clear();
+            self.pairs_cache.resize_if_different((system.size(), system.size()));
+
+            let evaluator = system.energy_evaluator();
+
+            for i in 0..system.size() {
+                for j in (i + 1)..system.size() {
+                    let r = system.nearest_image(i, j).norm();
+                    let path = system.bond_path(i, j);
+                    let energy = evaluator.pair(path, r, i, j);
+                    self.pairs_cache[(i, j)] = energy;
+                    self.pairs_cache[(j, i)] = energy;
+                    self.pairs += energy;
+                }
             }
-        }
 
-        self.pairs_tail = evaluator.pairs_tail();
-        self.bonds = evaluator.bonds();
-        self.angles = evaluator.angles();
-        self.dihedrals = evaluator.dihedrals();
-        self.coulomb = evaluator.coulomb();
-        self.global = evaluator.global();
+            self.pairs_tail = evaluator.pairs_tail();
+            self.bonds = evaluator.bonds();
+            self.angles = evaluator.angles();
+            self.urey_bradleys = evaluator.urey_bradleys();
+            self.dihedrals = evaluator.dihedrals();
+            self.impropers = evaluator.impropers();
+            self.coulomb = evaluator.coulomb();
+            self.global = evaluator.global();
+        })
     }
 
     /// Get the cached energy
@@ -106,7 +118,9 @@ impl EnergyCache {
 
         energy += self.bonds;
         energy += self.angles;
+        energy += self.urey_bradleys;
         energy += self.dihedrals;
+        energy += self.impropers;
 
         energy += self.coulomb;
         energy += self.global;
@@ -137,6 +151,69 @@ impl EnergyCache {
     }
 }
 
+/// Energy of the intramolecular terms of a single molecule
+struct IntramolecularEnergy {
+    bonds: f64,
+    angles: f64,
+    urey_bradleys: f64,
+    dihedrals: f64,
+    impropers: f64,
+}
+
+impl IntramolecularEnergy {
+    /// Compute the energy of the intramolecular terms of the molecule at
+    /// `molecule_id` in the `system`
+    fn new(system: &System, molecule_id: usize) -> IntramolecularEnergy {
+        let evaluator = system.energy_evaluator();
+        let molecule = system.molecule(molecule_id);
+
+        let mut bonds = 0.0;
+        for bond in molecule.bonds() {
+            let (i, j) = (bond.i(), bond.j());
+            let r = system.nearest_image(i, j).norm();
+            bonds += evaluator.bond(r, i, j);
+        }
+
+        let mut angles = 0.0;
+        for angle in molecule.angles() {
+            let (i, j, k) = (angle.i(), angle.j(), angle.k());
+            angles += evaluator.angle(system.angle(i, j, k), i, j, k);
+        }
+
+        let mut urey_bradleys = 0.0;
+        for angle in molecule.urey_bradleys() {
+            let (i, j, k) = (angle.i(), angle.j(), angle.k());
+            let r = system.nearest_image(i, k).norm();
+            urey_bradleys += evaluator.urey_bradley(r, i, j, k);
+        }
+
+        let mut dihedrals = 0.0;
+        for dihedral in molecule.dihedrals() {
+            let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+            dihedrals += evaluator.dihedral(system.dihedral(i, j, k, m), i, j, k, m);
+        }
+
+        let mut impropers = 0.0;
+        for improper in molecule.impropers() {
+            let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+            impropers += evaluator.improper(system.out_of_plane(i, j, k, m), i, j, k, m);
+        }
+
+        IntramolecularEnergy {
+            bonds: bonds,
+            angles: angles,
+            urey_bradleys: urey_bradleys,
+            dihedrals: dihedrals,
+            impropers: impropers,
+        }
+    }
+
+    /// Get the sum of all the intramolecular energies
+    fn total(&self) -> f64 {
+        self.bonds + self.angles + self.urey_bradleys + self.dihedrals + self.impropers
+    }
+}
+
 impl EnergyCache {
     /// Get the cost of moving a rigid molecule at `molecule_id` in the system
     /// to `new_positions`.
@@ -149,78 +226,81 @@ impl EnergyCache {
         molecule_id: usize,
         new_positions: &[Vector3D],
     ) -> f64 {
-        let evaluator = system.energy_evaluator();
-        let positions = system.particles().position;
-        let molecule = system.molecule(molecule_id);
+        system.install(|| {
+            let evaluator = system.energy_evaluator();
+            let positions = system.particles().position;
+            let molecule = system.molecule(molecule_id);
 
-        let mut new_pairs = Array2::<f64>::zeros((system.size(), system.size()));
-        let mut pairs_delta = 0.0;
+            let mut new_pairs = Array2::<f64>::zeros((system.size(), system.size()));
+            let mut pairs_delta = 0.0;
 
-        // Iterate over all interactions between a particle in the moved
-        // molecule and a particle in another molecule
-        for (i, part_i) in molecule.indexes().enumerate() {
-            for (_, other_molecule) in system.molecules().enumerate().filter(|(id, _)| molecule_id != *id) {
-                for part_j in other_molecule.indexes() {
-                    let r = system.cell.distance(&positions[part_j], &new_positions[i]);
-                    let path = system.bond_path(part_i, part_j);
-                    let energy = evaluator.pair(path, r, part_i, part_j);
+            // Iterate over all interactions between a particle in the moved
+            // molecule and a particle in another molecule
+            for (i, part_i) in molecule.indexes().enumerate() {
+                let others = system.molecules().enumerate().filter(|(id, _)| molecule_id != *id);
+                for (_, other_molecule) in others {
+                    for part_j in other_molecule.indexes() {
+                        let r = system.cell.distance(&positions[part_j], &new_positions[i]);
+                        let path = system.bond_path(part_i, part_j);
+                        let energy = evaluator.pair(path, r, part_i, part_j);
 
-                    pairs_delta += energy;
-                    new_pairs[(part_i, part_j)] += energy;
-                    new_pairs[(part_j, part_i)] += energy;
+                        pairs_delta += energy;
+                        new_pairs[(part_i, part_j)] += energy;
+                        new_pairs[(part_j, part_i)] += energy;
 
-                    pairs_delta -= self.pairs_cache[(part_i, part_j)];
+                        pairs_delta -= self.pairs_cache[(part_i, part_j)];
+                    }
                 }
             }
-        }
 
-        // Pairs tail correction do not change when moving a single molecule
+            // Pairs tail correction do not change when moving a single molecule
 
-        // Bonds / Angles / Dihedrals terms do not change
+            // Bonds / Angles / Dihedrals terms do not change
 
-        let coulomb_delta = if let Some(coulomb) = system.coulomb_potential() {
-            coulomb.move_molecule_cost(system, molecule_id, new_positions)
-        } else {
-            0.0
-        };
+            let coulomb_delta = if let Some(coulomb) = system.coulomb_potential() {
+                coulomb.move_molecule_cost(system, molecule_id, new_positions)
+            } else {
+                0.0
+            };
 
-        let mut global_delta = 0.0;
-        for global in system.global_potentials() {
-            global_delta += global.move_molecule_cost(system, molecule_id, new_positions);
-        }
+            let mut global_delta = 0.0;
+            for global in system.global_potentials() {
+                global_delta += global.move_molecule_cost(system, molecule_id, new_positions);
+            }
 
-        let cost = pairs_delta + coulomb_delta + global_delta;
+            let cost = pairs_delta + coulomb_delta + global_delta;
 
-        self.updater = Some(Box::new(move |cache, system| {
-            cache.pairs += pairs_delta;
-            cache.coulomb += coulomb_delta;
-            cache.global += global_delta;
+            self.updater = Some(Box::new(move |cache, system| {
+                cache.pairs += pairs_delta;
+                cache.coulomb += coulomb_delta;
+                cache.global += global_delta;
 
-            let (n, m) = new_pairs.dim();
-            debug_assert_eq!(n, m);
-            debug_assert_eq!((n, m), cache.pairs_cache.dim());
+                let (n, m) = new_pairs.dim();
+                debug_assert_eq!(n, m);
+                debug_assert_eq!((n, m), cache.pairs_cache.dim());
 
-            let molecule = system.molecule(molecule_id);
-            for i in molecule.indexes() {
-                for j in 0..n {
-                    if molecule.contains(j) {
-                        continue;
+                let molecule = system.molecule(molecule_id);
+                for i in molecule.indexes() {
+                    for j in 0..n {
+                        if molecule.contains(j) {
+                            continue;
+                        }
+                        cache.pairs_cache[(i, j)] = new_pairs[(i, j)];
+                        cache.pairs_cache[(j, i)] = new_pairs[(i, j)];
                     }
-                    cache.pairs_cache[(i, j)] = new_pairs[(i, j)];
-                    cache.pairs_cache[(j, i)] = new_pairs[(i, j)];
                 }
-            }
 
-            // Update the cache for the global potentials
-            if let Some(coulomb) = system.coulomb_potential() {
-                coulomb.update();
-            }
+                // Update the cache for the global potentials
+                if let Some(coulomb) = system.coulomb_potential() {
+                    coulomb.update();
+                }
 
-            for global in system.global_potentials() {
-                global.update();
-            }
-        }));
-        return cost;
+                for global in system.global_potentials() {
+                    global.update();
+                }
+            }));
+            return cost;
+        })
     }
 
     /// Return the cost for moving all **rigid** molecules of the system.
@@ -240,59 +320,225 @@ impl EnergyCache {
     /// This function ***DOES NOT*** update the cache, the `update` function
     /// MUST be called if the molecules are effectively moved.
     pub fn move_all_molecules_cost(&mut self, system: &System) -> f64 {
-        let evaluator = system.energy_evaluator();
+        system.install(|| {
+            let evaluator = system.energy_evaluator();
 
-        let mut new_pairs = Array2::<f64>::zeros((system.size(), system.size()));
-        let mut pairs_delta = 0.0;
-        // Loop over all molecule pairs
-        for (i, mol_i) in system.molecules().enumerate() {
-            for mol_j in system.molecules().skip(i + 1) {
-                // Loop over all particles in the molecules
-                for part_i in mol_i.indexes() {
-                    for part_j in mol_j.indexes() {
-                        let r = system.distance(part_i, part_j);
-                        let path = system.bond_path(part_i, part_j);
-                        let energy = evaluator.pair(path, r, part_i, part_j);
-                        pairs_delta += energy;
-                        new_pairs[(part_i, part_j)] += energy;
-                        new_pairs[(part_j, part_i)] += energy;
-                        pairs_delta -= self.pairs_cache[(part_i, part_j)];
+            let mut new_pairs = Array2::<f64>::zeros((system.size(), system.size()));
+            let mut pairs_delta = 0.0;
+            // Loop over all molecule pairs
+            for (i, mol_i) in system.molecules().enumerate() {
+                for mol_j in system.molecules().skip(i + 1) {
+                    // Loop over all particles in the molecules
+                    for part_i in mol_i.indexes() {
+                        for part_j in mol_j.indexes() {
+                            let r = system.distance(part_i, part_j);
+                            let path = system.bond_path(part_i, part_j);
+                            let energy = evaluator.pair(path, r, part_i, part_j);
+                            pairs_delta += energy;
+                            new_pairs[(part_i, part_j)] += energy;
+                            new_pairs[(part_j, part_i)] += energy;
+                            pairs_delta -= self.pairs_cache[(part_i, part_j)];
+                        }
                     }
                 }
             }
-        }
 
-        // temporarily, recompute all interactions
-        let new_coulomb = evaluator.coulomb();
-        let new_global = evaluator.global();
+            // temporarily, recompute all interactions
+            let new_coulomb = evaluator.coulomb();
+            let new_global = evaluator.global();
+
+            // compute the new tail correction
+            let pairs_tail = evaluator.pairs_tail();
+
+            let cost = pairs_delta + (pairs_tail - self.pairs_tail) + (new_coulomb - self.coulomb)
+                + (new_global - self.global);
+
+            self.updater = Some(Box::new(move |cache, system| {
+                cache.pairs += pairs_delta;
+                cache.pairs_tail = pairs_tail;
+                cache.coulomb = new_coulomb;
+                cache.global = new_global;
+
+                let (n, m) = new_pairs.dim();
+                debug_assert_eq!(n, m);
+                debug_assert_eq!((n, m), cache.pairs_cache.dim());
+                for (i, mol_i) in system.molecules().enumerate() {
+                    for mol_j in system.molecules().skip(i + 1) {
+                        for part_i in mol_i.indexes() {
+                            for part_j in mol_j.indexes() {
+                                cache.pairs_cache[(part_i, part_j)] = new_pairs[(part_i, part_j)];
+                                cache.pairs_cache[(part_j, part_i)] = new_pairs[(part_i, part_j)];
+                            }
+                        }
+                    }
+                }
+            }));
+            cost
+        })
+    }
 
-        // compute the new tail correction
-        let pairs_tail = evaluator.pairs_tail();
+    /// Get the cost of inserting the molecule at `molecule_id` in the system.
+    /// The new molecule must already be in the `system`, as the last molecule.
+    ///
+    /// The cost is the full energy difference between the system with and
+    /// without the new molecule, including the intramolecular energy of the
+    /// new molecule.
+    ///
+    /// This function ***DOES NOT*** update the cache, the `update` function
+    /// MUST be called if the molecule is effectively inserted.
+    pub fn insert_molecule_cost(&mut self, system: &System, molecule_id: usize) -> f64 {
+        system.install(|| {
+            let evaluator = system.energy_evaluator();
+            let molecule = system.molecule(molecule_id);
+            assert_eq!(
+                molecule.end(), system.size(),
+                "the inserted molecule must be the last one in EnergyCache::insert_molecule_cost"
+            );
 
-        let cost = pairs_delta + (pairs_tail - self.pairs_tail) + (new_coulomb - self.coulomb)
-            + (new_global - self.global);
+            // Interactions of all the particles in the new molecule with all the
+            // other particles, including the particles in the same molecule.
+            let first = molecule.start();
+            let mut new_pairs = Array2::<f64>::zeros((molecule.size(), system.size()));
+            let mut pairs_delta = 0.0;
+            for part_i in molecule.indexes() {
+                for part_j in 0..system.size() {
+                    if part_i == part_j {
+                        continue;
+                    }
+                    let r = system.nearest_image(part_i, part_j).norm();
+                    let path = system.bond_path(part_i, part_j);
+                    let energy = evaluator.pair(path, r, part_i, part_j);
+                    new_pairs[(part_i - first, part_j)] = energy;
 
-        self.updater = Some(Box::new(move |cache, system| {
-            cache.pairs += pairs_delta;
-            cache.pairs_tail = pairs_tail;
-            cache.coulomb = new_coulomb;
-            cache.global = new_global;
+                    // Pairs inside the new molecule are seen twice
+                    let already_counted = molecule.contains(part_j) && part_j < part_i;
+                    if !already_counted {
+                        pairs_delta += energy;
+                    }
+                }
+            }
 
-            let (n, m) = new_pairs.dim();
-            debug_assert_eq!(n, m);
-            debug_assert_eq!((n, m), cache.pairs_cache.dim());
-            for (i, mol_i) in system.molecules().enumerate() {
-                for mol_j in system.molecules().skip(i + 1) {
-                    for part_i in mol_i.indexes() {
-                        for part_j in mol_j.indexes() {
-                            cache.pairs_cache[(part_i, part_j)] = new_pairs[(part_i, part_j)];
-                            cache.pairs_cache[(part_j, part_i)] = new_pairs[(part_i, part_j)];
-                        }
+            // Intramolecular terms of the new molecule
+            let intramolecular = IntramolecularEnergy::new(system, molecule_id);
+
+            // The tail correction only depends on the number of particles
+            let pairs_tail = evaluator.pairs_tail();
+
+            // temporarily, recompute the coulombic and global interactions
+            let new_coulomb = evaluator.coulomb();
+            let new_global = evaluator.global();
+
+            let cost = pairs_delta + (pairs_tail - self.pairs_tail) + intramolecular.total()
+                + (new_coulomb - self.coulomb) + (new_global - self.global);
+
+            self.updater = Some(Box::new(move |cache, system| {
+                cache.pairs += pairs_delta;
+                cache.pairs_tail = pairs_tail;
+                cache.bonds += intramolecular.bonds;
+                cache.angles += intramolecular.angles;
+                cache.urey_bradleys += intramolecular.urey_bradleys;
+                cache.dihedrals += intramolecular.dihedrals;
+                cache.impropers += intramolecular.impropers;
+                cache.coulomb = new_coulomb;
+                cache.global = new_global;
+
+                let n = system.size();
+                let mut pairs_cache = Array2::<f64>::zeros((n, n));
+                for i in 0..first {
+                    for j in 0..first {
+                        pairs_cache[(i, j)] = cache.pairs_cache[(i, j)];
+                    }
+                }
+                for i in first..n {
+                    for j in 0..n {
+                        pairs_cache[(i, j)] = new_pairs[(i - first, j)];
+                        pairs_cache[(j, i)] = new_pairs[(i - first, j)];
                     }
                 }
+                cache.pairs_cache = pairs_cache;
+            }));
+            return cost;
+        })
+    }
+
+    /// Get the cost of removing the molecule at `molecule_id` from the system.
+    ///
+    /// The cost is the full energy difference between the system with and
+    /// without the molecule, including the intramolecular energy of the
+    /// removed molecule.
+    ///
+    /// This function ***DOES NOT*** update the cache, the `update` function
+    /// MUST be called if the molecule is effectively removed.
+    pub fn remove_molecule_cost(&mut self, system: &System, molecule_id: usize) -> f64 {
+        system.install(|| {
+            let molecule = system.molecule(molecule_id);
+            let first = molecule.start();
+            let size = molecule.size();
+
+            // All the pairs involving a particle of the removed molecule disappear
+            let mut pairs_delta = 0.0;
+            for part_i in molecule.indexes() {
+                for part_j in 0..system.size() {
+                    if molecule.contains(part_j) && part_j <= part_i {
+                        continue;
+                    }
+                    pairs_delta -= self.pairs_cache[(part_i, part_j)];
+                }
             }
-        }));
-        cost
+
+            // Intramolecular terms of the removed molecule disappear
+            let intramolecular = IntramolecularEnergy::new(system, molecule_id);
+
+            // The tail correction only depends on the number of particles
+            let mut composition = system.composition();
+            for &kind in molecule.particles().kind {
+                composition.remove_particle(kind);
+            }
+            let evaluator = system.energy_evaluator();
+            let pairs_tail = evaluator.pairs_tail_with_composition(&composition);
+
+            // temporarily, recompute the coulombic and global interactions in a
+            // copy of the configuration without the molecule
+            let mut new_coulomb = 0.0;
+            let mut new_global = 0.0;
+            if system.coulomb_potential().is_some() || !system.global_potentials().is_empty() {
+                let mut configuration: Configuration = (**system).clone();
+                configuration.remove_molecule(molecule_id);
+                if let Some(coulomb) = system.coulomb_potential() {
+                    new_coulomb = coulomb.energy(&configuration);
+                }
+                for global in system.global_potentials() {
+                    new_global += global.energy(&configuration);
+                }
+            }
+
+            let cost = pairs_delta + (pairs_tail - self.pairs_tail) - intramolecular.total()
+                + (new_coulomb - self.coulomb) + (new_global - self.global);
+
+            self.updater = Some(Box::new(move |cache, system| {
+                cache.pairs += pairs_delta;
+                cache.pairs_tail = pairs_tail;
+                cache.bonds -= intramolecular.bonds;
+                cache.angles -= intramolecular.angles;
+                cache.urey_bradleys -= intramolecular.urey_bradleys;
+                cache.dihedrals -= intramolecular.dihedrals;
+                cache.impropers -= intramolecular.impropers;
+                cache.coulomb = new_coulomb;
+                cache.global = new_global;
+
+                // Index of the particle `i` before the removal of the molecule
+                let old_index = |i: usize| if i < first { i } else { i + size };
+                let n = system.size();
+                let mut pairs_cache = Array2::<f64>::zeros((n, n));
+                for i in 0..n {
+                    for j in 0..n {
+                        pairs_cache[(i, j)] = cache.pairs_cache[(old_index(i), old_index(j))];
+                    }
+                }
+                cache.pairs_cache = pairs_cache;
+            }));
+            return cost;
+        })
     }
 }
 
@@ -485,4 +731,58 @@ mod tests {
         let new_energy = new_system.potential_energy();
         assert_ulps_eq!(cost, new_energy - old_energy, epsilon = 1e-12);
     }
+
+    #[test]
+    fn insert_remove_molecule() {
+        let mut system = testing_system();
+        let mut cache = EnergyCache::new();
+        let old_energy = system.potential_energy();
+        cache.init(&system);
+
+        // Insert a copy of the first molecule
+        let mut molecule = system.molecule(0).to_owned();
+        for position in molecule.particles_mut().position {
+            *position += Vector3D::new(0.0, 2.5, 0.3);
+        }
+        system.add_molecule(molecule);
+        let cost = cache.insert_molecule_cost(&system, 2);
+        let new_energy = system.potential_energy();
+        assert_relative_eq!(cost, new_energy - old_energy, max_relative = 1e-9);
+
+        cache.update(&mut system);
+        assert_relative_eq!(cache.energy(), new_energy, max_relative = 1e-9);
+
+        // Check that the cache is really updated
+        let old_energy = new_energy;
+        let new_positions = &[
+            Vector3D::new(-0.987061, 0.59401, 0.427533),
+            Vector3D::new(-1.0744137409578138, 1.2111820514074991, -0.2893833856814936),
+            Vector3D::new(-1.4352068561309008, 2.5425486908430286, 0.24698514382209652),
+            Vector3D::new(-1.5225595970887147, 3.159720742250528, -0.46993124185939705),
+        ];
+        let cost = cache.move_molecule_cost(&system, 2, new_positions);
+        for (i, &position) in new_positions.iter().enumerate() {
+            system.particles_mut().position[8 + i] = position;
+        }
+        let new_energy = system.potential_energy();
+        assert_relative_eq!(cost, new_energy - old_energy, max_relative = 1e-9);
+        cache.update(&mut system);
+
+        // Remove the molecule in the middle of the system
+        let old_energy = new_energy;
+        let cost = cache.remove_molecule_cost(&system, 1);
+        system.remove_molecule(1);
+        let new_energy = system.potential_energy();
+        assert_relative_eq!(cost, new_energy - old_energy, max_relative = 1e-9);
+
+        cache.update(&mut system);
+        assert_relative_eq!(cache.energy(), new_energy, max_relative = 1e-9);
+
+        // Check that the cache is really updated
+        let old_energy = new_energy;
+        let cost = cache.remove_molecule_cost(&system, 1);
+        system.remove_molecule(1);
+        let new_energy = system.potential_energy();
+        assert_relative_eq!(cost, new_energy - old_energy, max_relative = 1e-9);
+    }
 }
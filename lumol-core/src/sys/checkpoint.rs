@@ -0,0 +1,148 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Checkpoints of a system configuration, used to restart simulations.
+//!
+//! Checkpoints are JSON files, containing the simulation step, the unit cell,
+//! and all the molecules with their particles and bonds. All the floating
+//! point values are written with enough digits to be read back exactly.
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Configuration, Molecule, System, UnitCell};
+
+/// The configuration of a system, as saved in checkpoints.
+///
+/// The interactions are not part of the checkpoint, and neither is the state
+/// of the propagators (random number generators, thermostats, ...), which is
+/// saved by `lumol_sim::Simulation::save_checkpoint`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SystemCheckpoint {
+    /// The simulation step
+    pub step: u64,
+    /// The unit cell
+    pub cell: UnitCell,
+    /// All the molecules in the system, with their particles and bonds
+    pub molecules: Vec<Molecule>,
+}
+
+impl System {
+    /// Get the checkpoint of the configuration of this system, containing the
+    /// current step, the unit cell and the positions, velocities, masses,
+    /// charges and bonds of all the particles.
+    pub fn checkpoint(&self) -> SystemCheckpoint {
+        SystemCheckpoint {
+            step: self.step,
+            cell: self.cell,
+            molecules: self.molecules().map(|molecule| molecule.to_owned()).collect(),
+        }
+    }
+
+    /// Restore the configuration of this system from a `checkpoint`. All the
+    /// particles in the system are replaced by the ones in the checkpoint,
+    /// the interactions are kept.
+    pub fn restore_checkpoint(&mut self, checkpoint: SystemCheckpoint) {
+        **self = Configuration::new();
+        self.cell = checkpoint.cell;
+        self.step = checkpoint.step;
+        for molecule in checkpoint.molecules {
+            self.add_molecule(molecule);
+        }
+    }
+
+    /// Save the configuration of this system in a checkpoint file at `path`.
+    /// The file is replaced if it already exists.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::{System, UnitCell};
+    /// let system = System::with_cell(UnitCell::cubic(10.0));
+    /// system.save_checkpoint("checkpoint.json").unwrap();
+    /// ```
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut file, &self.checkpoint())?;
+        return file.flush();
+    }
+
+    /// Restore the configuration of this system from the checkpoint file at
+    /// `path`, created by `save_checkpoint`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::System;
+    /// let mut system = System::new();
+    /// // set the interactions here
+    /// system.load_checkpoint("checkpoint.json").unwrap();
+    /// ```
+    pub fn load_checkpoint<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let file = BufReader::new(File::open(path)?);
+        let checkpoint = serde_json::from_reader(file)?;
+        self.restore_checkpoint(checkpoint);
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{System, Molecule, Particle, UnitCell, Vector3D};
+
+    fn testing_system() -> System {
+        let mut system = System::with_cell(UnitCell::triclinic(10.0, 11.0, 12.5, 90.0, 80.0, 100.0));
+        let mut water = Molecule::new(Particle::with_position("O", Vector3D::new(0.1, 0.2, 0.3)));
+        water.add_particle_bonded_to(0, Particle::with_position("H", Vector3D::new(1.0, 0.2, 0.3)));
+        water.add_particle_bonded_to(0, Particle::with_position("H", Vector3D::new(0.1, 1.0 / 3.0, 0.3)));
+        system.add_molecule(water);
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(5.0, -2.0, 1e-12))));
+
+        system.particles_mut().velocity[0] = Vector3D::new(1e-3, -2.0 / 7.0, 0.0);
+        system.particles_mut().velocity[3] = Vector3D::new(0.1, 0.2, 0.3);
+        system.particles_mut().charge[0] = -0.8476;
+        system.particles_mut().charge[1] = 0.4238;
+        system.particles_mut().charge[2] = 0.4238;
+        system.step = 4242;
+        return system;
+    }
+
+    #[test]
+    fn round_trip() {
+        let system = testing_system();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        system.save_checkpoint(file.path()).unwrap();
+
+        let mut restored = System::new();
+        restored.add_molecule(Molecule::new(Particle::new("Zn")));
+        restored.load_checkpoint(file.path()).unwrap();
+
+        assert_eq!(restored.step, 4242);
+        assert_eq!(restored.cell, system.cell);
+        assert_eq!(restored.size(), 4);
+        assert_eq!(restored.molecules().count(), 2);
+
+        assert_eq!(restored.particles().name, system.particles().name);
+        assert_eq!(restored.particles().mass, system.particles().mass);
+        assert_eq!(restored.particles().charge, system.particles().charge);
+        assert_eq!(restored.particles().position, system.particles().position);
+        assert_eq!(restored.particles().velocity, system.particles().velocity);
+
+        let water = restored.molecule(0);
+        assert_eq!(water.bonds(), system.molecule(0).bonds());
+        assert_eq!(water.angles(), system.molecule(0).angles());
+    }
+
+    #[test]
+    fn invalid_files() {
+        let mut system = System::new();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "not a checkpoint\n").unwrap();
+        assert!(system.load_checkpoint(file.path()).is_err());
+
+        std::fs::write(file.path(), r#"{"step": 2, "cell": {"matrix": [1, 2]}}"#).unwrap();
+        assert!(system.load_checkpoint(file.path()).is_err());
+    }
+}
@@ -8,8 +8,7 @@ use std::sync::Once;
 use soa_derive::soa_zip;
 use log::warn;
 
-use crate::sys::Permutation;
-use crate::{Molecule, Particle, ParticleRef, System, UnitCell, CellShape};
+use crate::{Bond, Molecule, Particle, ParticleRef, System, UnitCell, CellShape};
 use crate::Vector3D;
 
 impl<'a> From<&'a chemfiles::Atom> for Particle {
@@ -60,39 +59,16 @@ impl From<chemfiles::Frame> for System {
             }
         }
 
-        let mut bonds = frame.topology().bonds();
-        while let Some(bond) = bonds.pop() {
-            let permutations = system.add_bond(bond[0] as usize, bond[1] as usize);
-            apply_particle_permutation(&mut bonds, &permutations);
-        }
+        let bonds = frame.topology()
+                         .bonds()
+                         .iter()
+                         .map(|bond| Bond::new(bond[0] as usize, bond[1] as usize))
+                         .collect::<Vec<_>>();
+        let _ = system.rebuild_molecules_from_bonds(&bonds);
         return system;
     }
 }
 
-fn apply_particle_permutation(bonds: &mut Vec<[u64; 2]>, permutations: &[Permutation]) {
-    for bond in bonds {
-        // Search for a permutation applying to the first atom of the bond. We
-        // need to stop just after the first permutations is found, because we
-        // can have a permutation looking like this: [1 -> 2, 2 -> 3, 3 -> 4].
-        // If we do not stop after the first match, then all indexes in 1-3
-        // range will become 4.
-        for permutation in permutations {
-            if bond[0] == permutation.old as u64 {
-                bond[0] = permutation.new as u64;
-                break;
-            }
-        }
-
-        // Now we look for permutations applying to the second atom of the bond
-        for permutation in permutations {
-            if bond[1] == permutation.old as u64 {
-                bond[1] = permutation.new as u64;
-                break;
-            }
-        }
-    }
-}
-
 impl<'a> From<ParticleRef<'a>> for chemfiles::Atom {
     fn from(particle: ParticleRef<'a>) -> chemfiles::Atom {
         let mut atom = chemfiles::Atom::new(&**particle.name);
@@ -129,13 +105,17 @@ impl<'a> From<&'a System> for chemfiles::Frame {
         }
 
         frame.add_velocities();
-        for (velocity, chfl_velocity) in soa_zip!(system.particles(), [position], frame.positions_mut()) {
+        for (velocity, chfl_velocity) in soa_zip!(system.particles(), [velocity], frame.velocities_mut()) {
             *chfl_velocity = **velocity;
         }
 
         let mut topology = chemfiles::Topology::new();
-        for particle in system.particles().iter() {
-            topology.add_atom(&particle.into());
+        for (i, particle) in system.particles().iter().enumerate() {
+            let mut atom = chemfiles::Atom::from(particle);
+            for (name, values) in system.particle_attributes() {
+                atom.set(name, values[i]);
+            }
+            topology.add_atom(&atom);
         }
 
         for molecule in system.molecules() {
@@ -313,7 +293,9 @@ impl Trajectory {
         return Ok(frame.into());
     }
 
-    /// Write the system to the trajectory.
+    /// Write the system to the trajectory. The per-particle attributes of the
+    /// system are written as atomic properties, for the formats supporting
+    /// them.
     ///
     /// # Examples
     ///
@@ -446,6 +428,40 @@ CONECT    1    2    3
 CONECT    2    1
 CONECT    3    1
 END
+";
+
+    static LAMMPS_WATER: &'static str = "LAMMPS data file for a water molecule
+
+3 atoms
+2 bonds
+1 angles
+2 atom types
+1 bond types
+1 angle types
+
+0.0 10.0 xlo xhi
+0.0 10.0 ylo yhi
+0.0 10.0 zlo zhi
+
+Masses
+
+1 15.999
+2 1.008
+
+Atoms # full
+
+1 1 1 -0.8 5.0 5.0 5.0
+2 1 2 0.4 5.9572 5.0 5.0
+3 1 2 0.4 4.76 5.9266 5.0
+
+Bonds
+
+1 1 1 2
+2 1 1 3
+
+Angles
+
+1 1 2 1 3
 ";
 
     #[test]
@@ -492,6 +508,9 @@ END
         assert_eq!(molecule.bonds().len(), 2);
         assert!(molecule.bonds().contains(&Bond::new(0, 1)));
         assert!(molecule.bonds().contains(&Bond::new(0, 2)));
+        assert_eq!(molecule.angles().len(), 1);
+        assert!(molecule.angles().contains(&Angle::new(1, 0, 2)));
+        assert!(molecule.dihedrals().is_empty());
 
         assert_eq!(system.particles().name[0], "O");
         assert_eq!(system.particles().name[1], "H");
@@ -501,6 +520,61 @@ END
         assert_eq!(system.particles().name[5], "H");
     }
 
+    #[test]
+    fn read_pdb_guess_bonds() {
+        // Remove the CONECT records
+        let pdb = PDB_WATER.lines()
+            .filter(|line| !line.starts_with("CONECT"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut file = tempfile::Builder::new().suffix(".pdb").tempfile().unwrap();
+        write!(file, "{}", pdb).unwrap();
+
+        let system = TrajectoryBuilder::new()
+            .open(&file).unwrap()
+            .read_guess_bonds().unwrap();
+
+        assert_eq!(system.size(), 6);
+        assert_eq!(system.molecules().count(), 2);
+
+        for molecule in system.molecules() {
+            let start = molecule.start();
+            assert_eq!(molecule.bonds().len(), 2);
+            assert!(molecule.bonds().contains(&Bond::new(start, start + 1)));
+            assert!(molecule.bonds().contains(&Bond::new(start, start + 2)));
+            assert_eq!(molecule.angles().len(), 1);
+            assert!(molecule.angles().contains(&Angle::new(start + 1, start, start + 2)));
+        }
+    }
+
+    #[test]
+    fn read_lammps_data() {
+        let mut file = tempfile::Builder::new().suffix(".lmp").tempfile().unwrap();
+        write!(file, "{}", LAMMPS_WATER).unwrap();
+
+        let system = TrajectoryBuilder::new()
+            .format("LAMMPS Data")
+            .open(&file).unwrap()
+            .read().unwrap();
+
+        assert_eq!(system.size(), 3);
+        assert_eq!(system.molecules().count(), 1);
+        assert_eq!(system.cell, UnitCell::cubic(10.0));
+
+        // LAMMPS numeric atom types are used as particle names
+        assert_eq!(system.particles().name[0], "1");
+        assert_eq!(system.particles().name[1], "2");
+        assert_eq!(system.particles().name[2], "2");
+        assert_eq!(system.particles().position[1], Vector3D::new(5.9572, 5.0, 5.0));
+
+        let molecule = system.molecule(0);
+        assert_eq!(molecule.bonds().len(), 2);
+        assert!(molecule.bonds().contains(&Bond::new(0, 1)));
+        assert!(molecule.bonds().contains(&Bond::new(0, 2)));
+        assert_eq!(molecule.angles().len(), 1);
+        assert!(molecule.angles().contains(&Angle::new(1, 0, 2)));
+    }
+
     #[test]
     fn read_propane() {
         let mut file = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
@@ -517,4 +591,46 @@ END
         // free to change the value if the molecule type algorithm change.
         assert_eq!(molecule.hash(), MoleculeHash::new(10634064187773497961));
     }
+
+    #[test]
+    fn xyz_round_trip() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(1.0, 2.0, 3.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Kr", Vector3D::new(-1.5, 0.25, 8.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(4.0, 5.0, 6.5))));
+        system.particles_mut().velocity[1] = Vector3D::new(0.1, 0.2, 0.3);
+
+        let file = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
+        {
+            let mut trajectory = TrajectoryBuilder::new()
+                .mode(OpenMode::Write)
+                .open(file.path()).unwrap();
+            trajectory.write(&system).unwrap();
+        }
+
+        let mut trajectory = TrajectoryBuilder::new().open(file.path()).unwrap();
+        // XYZ files do not contain the unit cell
+        trajectory.set_cell(&UnitCell::cubic(10.0));
+        let read = trajectory.read().unwrap();
+
+        assert_eq!(read.size(), 3);
+        assert_eq!(read.cell, system.cell);
+        assert_eq!(read.particles().name, system.particles().name);
+        assert_eq!(read.particles().kind, system.particles().kind);
+        assert_eq!(read.particles().position, system.particles().position);
+
+        // XYZ files do not contain velocities either
+        for velocity in read.particles().velocity {
+            assert_eq!(*velocity, Vector3D::zero());
+        }
+    }
+
+    #[test]
+    fn malformed_xyz() {
+        let mut file = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
+        write!(file, "5\n\nO 0.0 0.0 0.0\nH 1.0 0.0 0.0\n").unwrap();
+
+        let mut trajectory = TrajectoryBuilder::new().open(file.path()).unwrap();
+        assert!(trajectory.read().is_err());
+    }
 }
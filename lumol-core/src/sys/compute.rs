@@ -3,17 +3,19 @@
 
 //! Algorithm to compute physical properties of a System
 
+use std::collections::{BTreeSet, HashSet, VecDeque};
 use std::f64::consts::PI;
 
 use rayon::prelude::*;
 use soa_derive::soa_zip;
 use log_once::warn_once;
 
-use crate::consts::K_BOLTZMANN;
-use crate::{Matrix3, Vector3D};
+use crate::consts::{K_BOLTZMANN, FOUR_PI_EPSILON_0};
+use crate::{Array2, Matrix3, Vector3D};
 use crate::{System, DegreesOfFreedom};
+use crate::Bond;
 
-use crate::utils::ThreadLocalVec;
+use crate::utils::{each_i_fold_reduce, each_i_reduce};
 
 /// The `Compute` trait allow to compute properties of a system, without
 /// modifying this system. The `Output` type is the type of the computed
@@ -27,17 +29,38 @@ pub trait Compute {
 
 /// Compute all the forces acting on the system, and return a vector of
 /// force acting on each particles
+///
+/// The forces coming from each category of interactions are also available
+/// separately with [`Forces::pair_forces`], [`Forces::bonded_forces`],
+/// [`Forces::coulomb_forces`] and [`Forces::global_forces`]. The sum of these
+/// is equal to the result of `Forces::compute`.
+///
+/// [`Forces::pair_forces`]: struct.Forces.html#method.pair_forces
+/// [`Forces::bonded_forces`]: struct.Forces.html#method.bonded_forces
+/// [`Forces::coulomb_forces`]: struct.Forces.html#method.coulomb_forces
+/// [`Forces::global_forces`]: struct.Forces.html#method.global_forces
 pub struct Forces;
 impl Compute for Forces {
     type Output = Vec<Vector3D>;
     fn compute(&self, system: &System) -> Vec<Vector3D> {
-        let natoms = system.size();
-        let thread_local_forces = ThreadLocalVec::with_size(natoms);
+        let mut forces = self.pair_forces(system);
+        add_bonded_forces(system, &mut forces);
+        add_coulomb_forces(system, &mut forces);
+        add_global_forces(system, &mut forces);
+        return forces;
+    }
+}
 
-        (0..natoms).into_par_iter().for_each(|i| {
-            let mut forces = thread_local_forces.borrow_mut();
+impl Forces {
+    /// Compute the forces coming from the pair interactions
+    pub fn pair_forces(&self, system: &System) -> Vec<Vector3D> {
+        let natoms = system.size();
+        // Each block of particles accumulates forces in its own vector, and
+        // the vectors are summed in a fixed order. The result does not depend
+        // on the number of threads.
+        return each_i_fold_reduce(natoms, || vec![Vector3D::zero(); natoms], |forces, i| {
             let mut force_i = Vector3D::zero();
-            for j in (i + 1)..system.size() {
+            for j in (i + 1)..natoms {
                 let path = system.bond_path(i, j);
                 let d = system.nearest_image(i, j);
                 let dn = d.normalized();
@@ -52,58 +75,145 @@ impl Compute for Forces {
                 }
             }
             forces[i] += force_i;
+        }, |mut forces, other| {
+            for (force, other) in forces.iter_mut().zip(other) {
+                *force += other;
+            }
+            forces
         });
+    }
 
-        // At this point all the forces are computed, but the results are
-        // scattered across all thread local Vecs, here we gather them.
-        let mut forces = vec![Vector3D::zero(); natoms];
-        thread_local_forces.sum_into(&mut forces);
+    /// Compute the forces coming from the bonded interactions: bonds, angles,
+    /// Urey-Bradley terms, dihedral angles and improper dihedral angles
+    pub fn bonded_forces(&self, system: &System) -> Vec<Vector3D> {
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        add_bonded_forces(system, &mut forces);
+        return forces;
+    }
 
-        for molecule in system.molecules() {
-            for bond in molecule.bonds() {
-                let (i, j) = (bond.i(), bond.j());
-                let d = system.nearest_image(i, j);
-                let dn = d.normalized();
-                let r = d.norm();
-                if let Some(potential) = system.bond_potential(i, j) {
-                    let force = potential.force(r) * dn;
-                    forces[i] += force;
-                    forces[j] -= force;
-                }
+    /// Compute the forces coming from the coulombic potential
+    pub fn coulomb_forces(&self, system: &System) -> Vec<Vector3D> {
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        add_coulomb_forces(system, &mut forces);
+        return forces;
+    }
+
+    /// Compute the forces coming from the global potentials
+    pub fn global_forces(&self, system: &System) -> Vec<Vector3D> {
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        add_global_forces(system, &mut forces);
+        return forces;
+    }
+}
+
+/// Compute the forces coming from the bonded interactions (bonds, angles,
+/// Urey-Bradley terms, dihedral angles and improper dihedral angles) acting
+/// on the system, and return a vector of force acting on each particles.
+///
+/// The sum of `BondedForces` and [`NonBondedForces`] is equal to [`Forces`].
+///
+/// [`NonBondedForces`]: struct.NonBondedForces.html
+/// [`Forces`]: struct.Forces.html
+pub struct BondedForces;
+impl Compute for BondedForces {
+    type Output = Vec<Vector3D>;
+    fn compute(&self, system: &System) -> Vec<Vector3D> {
+        return Forces.bonded_forces(system);
+    }
+}
+
+/// Compute the forces coming from the non-bonded interactions (pairs,
+/// coulombic and global potentials) acting on the system, and return a vector
+/// of force acting on each particles.
+///
+/// The sum of [`BondedForces`] and `NonBondedForces` is equal to [`Forces`].
+///
+/// [`BondedForces`]: struct.BondedForces.html
+/// [`Forces`]: struct.Forces.html
+pub struct NonBondedForces;
+impl Compute for NonBondedForces {
+    type Output = Vec<Vector3D>;
+    fn compute(&self, system: &System) -> Vec<Vector3D> {
+        let mut forces = Forces.pair_forces(system);
+        add_coulomb_forces(system, &mut forces);
+        add_global_forces(system, &mut forces);
+        return forces;
+    }
+}
+
+/// Add the forces coming from the bonded interactions to `forces`
+fn add_bonded_forces(system: &System, forces: &mut [Vector3D]) {
+    for molecule in system.molecules() {
+        for bond in molecule.bonds() {
+            let (i, j) = (bond.i(), bond.j());
+            let d = system.nearest_image(i, j);
+            let dn = d.normalized();
+            let r = d.norm();
+            if let Some(potential) = system.bond_potential(i, j) {
+                let force = potential.force(r) * dn;
+                forces[i] += force;
+                forces[j] -= force;
             }
+        }
 
-            for angle in molecule.angles() {
-                let (i, j, k) = (angle.i(), angle.j(), angle.k());
-                let (theta, d1, d2, d3) = system.angle_and_derivatives(i, j, k);
-                if let Some(potential) = system.angle_potential(i, j, k) {
-                    let force = potential.force(theta);
-                    forces[i] += force * d1;
-                    forces[j] += force * d2;
-                    forces[k] += force * d3;
-                }
+        for angle in molecule.angles() {
+            let (i, j, k) = (angle.i(), angle.j(), angle.k());
+            let (theta, d1, d2, d3) = system.angle_and_derivatives(i, j, k);
+            if let Some(potential) = system.angle_potential(i, j, k) {
+                let force = potential.force(theta);
+                forces[i].add_scaled(force, &d1);
+                forces[j].add_scaled(force, &d2);
+                forces[k].add_scaled(force, &d3);
             }
+        }
 
-            for dihedral in molecule.dihedrals() {
-                let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
-                let (phi, d1, d2, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
-                if let Some(potential) = system.dihedral_potential(i, j, k, m) {
-                    let force = potential.force(phi);
-                    forces[i] += force * d1;
-                    forces[j] += force * d2;
-                    forces[k] += force * d3;
-                    forces[m] += force * d4;
-                }
+        for angle in molecule.urey_bradleys() {
+            let (i, j, k) = (angle.i(), angle.j(), angle.k());
+            if let Some(potential) = system.urey_bradley_potential(i, j, k) {
+                let d = system.nearest_image(i, k);
+                let force = potential.force(d.norm()) * d.normalized();
+                forces[i] += force;
+                forces[k] -= force;
             }
         }
 
-        if let Some(coulomb) = system.coulomb_potential() {
-            coulomb.forces(system, &mut forces);
+        for dihedral in molecule.dihedrals() {
+            let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+            let (phi, d1, d2, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
+            if let Some(potential) = system.dihedral_potential(i, j, k, m) {
+                let force = potential.force(phi);
+                forces[i].add_scaled(force, &d1);
+                forces[j].add_scaled(force, &d2);
+                forces[k].add_scaled(force, &d3);
+                forces[m].add_scaled(force, &d4);
+            }
         }
 
-        for global in system.global_potentials() {
-            global.forces(system, &mut forces);
+        for improper in molecule.impropers() {
+            let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+            if let Some(potential) = system.improper_potential(i, j, k, m) {
+                let (chi, d1, d2, d3, d4) = system.out_of_plane_and_derivatives(i, j, k, m);
+                let force = potential.force(chi);
+                forces[i].add_scaled(force, &d1);
+                forces[j].add_scaled(force, &d2);
+                forces[k].add_scaled(force, &d3);
+                forces[m].add_scaled(force, &d4);
+            }
         }
-        return forces;
+    }
+}
+
+/// Add the forces coming from the coulombic potential to `forces`
+fn add_coulomb_forces(system: &System, forces: &mut [Vector3D]) {
+    if let Some(coulomb) = system.coulomb_potential() {
+        coulomb.forces(system, forces);
+    }
+}
+
+/// Add the forces coming from the global potentials to `forces`
+fn add_global_forces(system: &System, forces: &mut [Vector3D]) {
+    for global in system.global_potentials() {
+        global.forces(system, forces);
     }
 }
 
@@ -118,7 +228,9 @@ impl Compute for PotentialEnergy {
         energy += evaluator.pairs_tail();
         energy += evaluator.bonds();
         energy += evaluator.angles();
+        energy += evaluator.urey_bradleys();
         energy += evaluator.dihedrals();
+        energy += evaluator.impropers();
         energy += evaluator.coulomb();
         energy += evaluator.global();
 
@@ -127,6 +239,144 @@ impl Compute for PotentialEnergy {
     }
 }
 
+/// Compute the derivative of the potential energy with respect to the
+/// coupling parameter $\lambda$ of the pair potentials, as given by
+/// [`PairPotential::lambda_derivative`].
+///
+/// Averaging this value over simulations at different $\lambda$ values
+/// gives the free energy difference between the decoupled and the fully
+/// coupled states using thermodynamic integration:
+///
+/// $$ \Delta F = \int_0^1 \left\langle \frac{\partial U}{\partial \lambda}
+///    \right\rangle_\lambda d\lambda $$
+///
+/// Tail corrections are not included in this derivative.
+///
+/// [`PairPotential::lambda_derivative`]: ../energy/trait.PairPotential.html#method.lambda_derivative
+pub struct LambdaDerivative;
+impl Compute for LambdaDerivative {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        let size = system.size();
+        return each_i_reduce(size, || 0.0, |i| {
+            let mut derivative = 0.0;
+            for j in (i + 1)..size {
+                let path = system.bond_path(i, j);
+                if let Some(potential) = system.pair_potential(i, j) {
+                    let info = potential.restriction().information(path);
+                    if !info.excluded {
+                        let r = system.nearest_image(i, j).norm();
+                        derivative += info.scaling * potential.lambda_derivative(r);
+                    }
+                }
+            }
+            derivative
+        }, |a, b| a + b);
+    }
+}
+
+/// Compute the potential energy of each particle in the system.
+///
+/// The energy of each pair interaction is split evenly between the two
+/// particles, and the energy of bonds, angles and dihedral angles is split
+/// evenly between all the particles involved. Coulombic and global potentials
+/// provide their own per-particle contribution with
+/// [`GlobalPotential::atomic_energies`].
+///
+/// The sum of all the particles energies is equal to the
+/// [`PotentialEnergy`](struct.PotentialEnergy.html) of the system.
+///
+/// [`GlobalPotential::atomic_energies`]: ../energy/trait.GlobalPotential.html#method.atomic_energies
+pub struct PerAtomEnergy;
+impl Compute for PerAtomEnergy {
+    type Output = Vec<f64>;
+    fn compute(&self, system: &System) -> Vec<f64> {
+        let natoms = system.size();
+        let evaluator = system.energy_evaluator();
+        let mut energies = vec![0.0; natoms];
+
+        for i in 0..natoms {
+            for j in (i + 1)..natoms {
+                let r = system.nearest_image(i, j).norm();
+                let path = system.bond_path(i, j);
+                let energy = 0.5 * evaluator.pair(path, r, i, j);
+                energies[i] += energy;
+                energies[j] += energy;
+            }
+        }
+
+        // Tail correction for pair potentials contribution
+        if !system.cell.is_infinite() {
+            let volume = system.volume();
+            let composition = system.composition();
+            for (energy, &kind_i) in energies.iter_mut().zip(system.particles().kind) {
+                for (kind_j, nj) in composition.all_particles() {
+                    if let Some(potential) = system.interactions().pair((kind_i, kind_j)) {
+                        *energy += 2.0 * PI * (nj as f64) / volume * potential.tail_energy();
+                    }
+                }
+            }
+        }
+
+        for molecule in system.molecules() {
+            for bond in molecule.bonds() {
+                let (i, j) = (bond.i(), bond.j());
+                let r = system.nearest_image(i, j).norm();
+                let energy = evaluator.bond(r, i, j) / 2.0;
+                energies[i] += energy;
+                energies[j] += energy;
+            }
+
+            for angle in molecule.angles() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                let theta = system.angle(i, j, k);
+                let energy = evaluator.angle(theta, i, j, k) / 3.0;
+                energies[i] += energy;
+                energies[j] += energy;
+                energies[k] += energy;
+            }
+
+            for angle in molecule.urey_bradleys() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                let r = system.nearest_image(i, k).norm();
+                let energy = evaluator.urey_bradley(r, i, j, k) / 2.0;
+                energies[i] += energy;
+                energies[k] += energy;
+            }
+
+            for dihedral in molecule.dihedrals() {
+                let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+                let phi = system.dihedral(i, j, k, m);
+                let energy = evaluator.dihedral(phi, i, j, k, m) / 4.0;
+                energies[i] += energy;
+                energies[j] += energy;
+                energies[k] += energy;
+                energies[m] += energy;
+            }
+
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                let chi = system.out_of_plane(i, j, k, m);
+                let energy = evaluator.improper(chi, i, j, k, m) / 4.0;
+                energies[i] += energy;
+                energies[j] += energy;
+                energies[k] += energy;
+                energies[m] += energy;
+            }
+        }
+
+        if let Some(coulomb) = system.coulomb_potential() {
+            coulomb.atomic_energies(system, &mut energies);
+        }
+
+        for global in system.global_potentials() {
+            global.atomic_energies(system, &mut energies);
+        }
+
+        return energies;
+    }
+}
+
 /// Compute the kinetic energy of the system
 ///
 /// $$ K = \sum_i m_i \vec v_i \cdot \vec v_i $$
@@ -171,129 +421,560 @@ impl Compute for Temperature {
     }
 }
 
-/// Compute the volume of the system
-pub struct Volume;
-impl Compute for Volume {
+/// Get the indexes of the particles in the group named `name` in the
+/// `system`, panicking if there is no such group.
+fn group_indexes<'a>(system: &'a System, name: &str) -> &'a BTreeSet<usize> {
+    match system.group(name) {
+        Some(indexes) => indexes,
+        None => panic!("no group named '{}' in this system", name),
+    }
+}
+
+/// Compute the kinetic energy of the particles in a group, defined with
+/// [`System::define_group`](../struct.System.html#method.define_group).
+///
+/// $$ K = \frac{1}{2} \sum_{i \in group} m_i \vec v_i \cdot \vec v_i $$
+///
+/// # Panics
+///
+/// If there is no group with the given name in the system
+pub struct GroupKineticEnergy {
+    /// Name of the group
+    pub group: String,
+}
+
+impl Compute for GroupKineticEnergy {
     type Output = f64;
-    #[inline]
     fn compute(&self, system: &System) -> f64 {
-        return system.cell.volume();
+        let particles = system.particles();
+        let mut energy = 0.0;
+        for &i in group_indexes(system, &self.group) {
+            energy += 0.5 * particles.mass[i] * particles.velocity[i].norm2();
+        }
+        assert!(energy.is_finite(), "Kinetic energy is infinite!");
+        return energy;
     }
 }
 
+/// Compute the instantaneous temperature of the particles in a group,
+/// defined with [`System::define_group`](../struct.System.html#method.define_group).
+///
+/// $$ T = \frac{2 K}{k_B N_f} $$
+///
+/// where $K$ is the kinetic energy and $N_f$ the number of degrees of freedom
+/// of the group. When all particles are simulated, $K$ is the
+/// [`GroupKineticEnergy`] and $N_f$ is three times the number of particles in
+/// the group. When molecules are simulated as rigid bodies, only the
+/// molecules with all their particles in the group are used: $K$ is the
+/// kinetic energy of their particles and $N_f$ three times the number of
+/// these molecules. Degrees of freedom removed from the whole system
+/// (constraints, center-of-mass motion, *etc.*) can not be attributed to a
+/// single group, and are not accounted for.
+///
+/// # Panics
+///
+/// If there is no group with the given name in the system, or if the group
+/// does not have any degree of freedom: if it is empty, or if it does not
+/// contain any whole molecule when molecules are simulated.
+///
+/// [`GroupKineticEnergy`]: struct.GroupKineticEnergy.html
+pub struct GroupTemperature {
+    /// Name of the group
+    pub group: String,
+}
+
+impl Compute for GroupTemperature {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        let indexes = group_indexes(system, &self.group);
+        let (kinetic, dof) = match system.simulated_degrees_of_freedom {
+            DegreesOfFreedom::Particles | DegreesOfFreedom::Frozen(_) => {
+                let kinetic = GroupKineticEnergy { group: self.group.clone() }.compute(system);
+                (kinetic, 3 * indexes.len())
+            }
+            DegreesOfFreedom::Molecules | DegreesOfFreedom::FrozenMolecules(_) => {
+                let mut kinetic = 0.0;
+                let mut molecules = 0;
+                for molecule in system.molecules() {
+                    if molecule.indexes().all(|i| indexes.contains(&i)) {
+                        for (&mass, velocity) in soa_zip!(molecule.particles(), [mass, velocity]) {
+                            kinetic += 0.5 * mass * velocity.norm2();
+                        }
+                        molecules += 1;
+                    }
+                }
+                (kinetic, 3 * molecules)
+            }
+        };
+        assert!(dof > 0, "the '{}' group does not have any degree of freedom", self.group);
+        return 2.0 * kinetic / (dof as f64 * K_BOLTZMANN);
+    }
+}
 
-/// Compute the virial tensor of the system using the atomic definition.
+/// Compute the configurational temperature of the system, using only the
+/// positions of the particles.
 ///
-/// $$ \underline{W} = \sum_i \vec r_i \otimes \vec f_i - \underline H
-///    \frac{\partial U}{\partial \underline H} $$
+/// $$ T_{conf} = \frac{\sum_i \vec f_i \cdot \vec f_i}{k_B \sum_i \nabla_i^2 U} $$
 ///
-/// where $\underline{H}$ is the unit cell matrix, $\vec f_i$ the force acting
-/// on the atom $i$ and $\vec r_i$ the position of the atom $i$
+/// where $\vec f_i$ is the force acting on particle $i$, $k_B$ is the
+/// Boltzman constant and $\nabla_i^2 U$ the laplacian of the potential energy
+/// with respect to the position of particle $i$ [Rugh1997]. This is a
+/// fluctuating quantity, and only its average over a simulation is equal to
+/// the thermodynamic temperature.
 ///
-/// If all the interactions are pair interactions, this definition reduces to
-/// $$ \underline{W} = \sum_i \sum_{j > i} \vec r_{ij} \otimes \vec f_{ij} $$
-pub struct AtomicVirial;
-impl Compute for AtomicVirial {
-    type Output = Matrix3;
-    fn compute(&self, system: &System) -> Matrix3 {
-        assert!(!system.cell.is_infinite(), "Can not compute virial for infinite cell");
+/// The laplacian of the energy is only computed for pair, bond and
+/// Urey-Bradley potentials. The contributions of angles, dihedral angles,
+/// coulombic and global potentials are ignored.
+///
+/// [Rugh1997]: Rugh, H. H. Phys. Rev. Lett. 78, 772 (1997).
+pub struct ConfigurationalTemperature;
+impl Compute for ConfigurationalTemperature {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        let forces = Forces.compute(system);
+        let forces2 = forces.iter().map(|force| force.norm2()).sum::<f64>();
 
-        // Pair potentials contributions
-        let pair_virials = (0..system.size()).into_par_iter().map(|i| {
-            let mut local_virial = Matrix3::zero();
+        // The laplacian of a potential U(r) depending on the distance between
+        // two particles is 2 (U''(r) + 2 U'(r) / r), where the factor 2 comes
+        // from the two particles.
+        let mut laplacian = 0.0;
+        for i in 0..system.size() {
             for j in (i + 1)..system.size() {
                 let path = system.bond_path(i, j);
                 if let Some(potential) = system.pair_potential(i, j) {
                     let info = potential.restriction().information(path);
                     if !info.excluded {
-                        let d = system.nearest_image(i, j);
-                        local_virial += info.scaling * potential.virial(&d);
+                        let r = system.distance(i, j);
+                        let second = potential.force_derivative(r) + 2.0 * potential.force(r) / r;
+                        laplacian -= 2.0 * info.scaling * second;
                     }
                 }
             }
-            return local_virial;
-        });
-        let mut virial = pair_virials.sum();
-
-        // Tail correction for pair potentials contribution
-        let volume = system.cell.volume();
-        let composition = system.composition();
-        for (i, ni) in composition.all_particles() {
-            for (j, nj) in composition.all_particles() {
-                let two_pi_density = 2.0 * PI * (ni as f64) * (nj as f64) / volume;
-                if let Some(potential) = system.interactions().pair((i, j)) {
-                    virial += two_pi_density * potential.tail_virial();
-                }
-            }
         }
 
-        // Bond potentials contributions
         for molecule in system.molecules() {
             for bond in molecule.bonds() {
                 let (i, j) = (bond.i(), bond.j());
-                let r = system.nearest_image(i, j);
                 if let Some(potential) = system.bond_potential(i, j) {
-                    virial += potential.virial(&r);
+                    let r = system.distance(i, j);
+                    let second = potential.force_derivative(r) + 2.0 * potential.force(r) / r;
+                    laplacian -= 2.0 * second;
                 }
             }
-        }
 
-        // Angles and dihedrals potentials do not contribute as they only have
-        // an angular part (see DL_POLY 4 manual page 18, or Smith, W., 1993,
-        // CCP5 Information Quarterly, 39, 14. 18, 21, 24).
+            for angle in molecule.urey_bradleys() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.urey_bradley_potential(i, j, k) {
+                    let r = system.distance(i, k);
+                    let second = potential.force_derivative(r) + 2.0 * potential.force(r) / r;
+                    laplacian -= 2.0 * second;
+                }
+            }
 
-        if let Some(coulomb) = system.coulomb_potential() {
-            virial += coulomb.atomic_virial(system);
+            if !molecule.angles().is_empty() || !molecule.dihedrals().is_empty()
+                || !molecule.impropers().is_empty()
+            {
+                warn_once!("angles and dihedrals are ignored in configurational temperature");
+            }
         }
 
-        for global in system.global_potentials() {
-            virial += global.atomic_virial(system);
+        if system.coulomb_potential().is_some() || !system.global_potentials().is_empty() {
+            warn_once!("coulombic and global potentials are ignored in configurational temperature");
         }
 
-        return virial;
+        return forces2 / (K_BOLTZMANN * laplacian);
     }
 }
 
-/// Compute the virial tensor of the system using the molecular definition
+/// Compute the center of mass of the system.
 ///
-/// This differs from the [`AtomicVirial`](struct.AtomicVirial.html) when using
-/// rigid molecules, as it will contains the right contributions of the forces
-/// maintaining the molecules rigid without needing to compute them.
+/// $$ \vec r_{com} = \frac{\sum_i m_i \vec r_i}{\sum_i m_i} $$
 ///
-/// $$ \underline{W} = \sum_i \vec r_i \otimes \vec f_i - \underline H
-///    \frac{\partial U}{\partial \underline H} $$
+/// The positions of the particles in each molecule are taken using the
+/// minimum image convention relative to the first particle of the molecule,
+/// so that molecules split by the periodic boundary conditions are handled
+/// correctly. If the total mass of the system is zero (for example for an
+/// empty system), the center of mass is set to the origin.
+pub struct CenterOfMass;
+impl Compute for CenterOfMass {
+    type Output = Vector3D;
+    fn compute(&self, system: &System) -> Vector3D {
+        let masses = system.particles().mass;
+        let positions = system.particles().position;
+
+        let mut total_mass = 0.0;
+        let mut com = Vector3D::zero();
+        for molecule in system.molecules() {
+            let reference = positions[molecule.start()];
+            for i in molecule.indexes() {
+                let mut delta = positions[i] - reference;
+                system.cell.vector_image(&mut delta);
+                total_mass += masses[i];
+                com += masses[i] * (reference + delta);
+            }
+        }
+
+        if total_mass == 0.0 {
+            return Vector3D::zero();
+        }
+        return com / total_mass;
+    }
+}
+
+/// Compute the total momentum of the system.
 ///
-/// where $\underline{H}$ is the unit cell matrix, $\vec f_i$ the force acting
-/// on the atom $i$ (comprising the force needed to keep the molecules rigid)
-/// and $\vec r_i$ the position of the atom $i$
+/// $$ \vec p = \sum_i m_i \vec v_i $$
 ///
-/// If all the interactions are pair interaction, this definition reduces to
+/// where $m_i$ is the mass of particle $i$ and $\vec v_i$ its velocity.
+pub struct TotalMomentum;
+impl Compute for TotalMomentum {
+    type Output = Vector3D;
+    fn compute(&self, system: &System) -> Vector3D {
+        let mut momentum = Vector3D::zero();
+        for (&mass, velocity) in soa_zip!(system.particles(), [mass, velocity]) {
+            momentum += mass * velocity;
+        }
+        return momentum;
+    }
+}
+
+/// Compute the radius of gyration of each molecule in the system.
 ///
-/// $$ \underline{W} = \sum_i \sum_{j > i} \sum_{a \in i} \sum_{b \in i}
-///    \frac{\vec r_{ab} \otimes \vec f_{ab}}{r_{ab}^2} \vec r_{ab} \cdot \vec
-///    r_{ij} $$
+/// $$ R_g^2 = \frac{\sum_i m_i \left| \vec r_i - \vec r_{com} \right|^2}{\sum_i m_i} $$
 ///
-/// where $i$ and $j$ run over all the molecules in the system, while $a$ and
-/// $b$ run over all the particles in these molecules
-pub struct MolecularVirial;
-impl Compute for MolecularVirial {
-    type Output = Matrix3;
-    fn compute(&self, system: &System) -> Matrix3 {
-        assert!(!system.cell.is_infinite(), "Can not compute virial for infinite cell");
+/// where the sum runs over the particles in the molecule, $m_i$ is the mass of
+/// particle $i$ and $\vec r_{com}$ the center of mass of the molecule. The
+/// molecules are unwrapped by following the bonds between particles, so that
+/// molecules spanning a periodic boundary are handled correctly.
+pub struct RadiusOfGyration;
+impl Compute for RadiusOfGyration {
+    type Output = Vec<f64>;
+    fn compute(&self, system: &System) -> Vec<f64> {
+        let masses = system.particles().mass;
+        let mut radii = Vec::new();
+        for molecule in system.molecules() {
+            let start = molecule.start();
+            let positions = unwrapped_molecule(system, start, molecule.end(), molecule.bonds());
 
-        // Pair potentials contributions, using the molecular virial definition
-        // This is defined in Allen & Tildesley in equations 2.54; 2.61; 2.63.
-        let pair_virials = system.molecules().enumerate().par_bridge().map(|(i, molecule_i)| {
-            let mut local_virial = Matrix3::zero();
-            let ri = molecule_i.center_of_mass();
+            let mut total_mass = 0.0;
+            let mut com = Vector3D::zero();
+            for (i, position) in positions.iter().enumerate() {
+                total_mass += masses[start + i];
+                com += masses[start + i] * position;
+            }
+            com /= total_mass;
 
-            for molecule_j in system.molecules().skip(i + 1) {
-                let rj = molecule_j.center_of_mass();
-                let mut r_ij = ri - rj;
-                system.cell.vector_image(&mut r_ij);
+            let mut rg2 = 0.0;
+            for (i, position) in positions.iter().enumerate() {
+                rg2 += masses[start + i] * (position - com).norm2();
+            }
+            radii.push(f64::sqrt(rg2 / total_mass));
+        }
+        return radii;
+    }
+}
 
-                for part_a in molecule_i.indexes() {
-                    for part_b in molecule_j.indexes() {
+/// Get the positions of the particles between `start` and `end`, unwrapped
+/// by following the `bonds` between particles using the minimum image
+/// convention. Particles not connected to the first one by bonds use the
+/// minimum image relative to the first particle.
+fn unwrapped_molecule(system: &System, start: usize, end: usize, bonds: &HashSet<Bond>) -> Vec<Vector3D> {
+    let size = end - start;
+    let positions = system.particles().position;
+
+    let mut neighbors = vec![Vec::new(); size];
+    for bond in bonds {
+        neighbors[bond.i() - start].push(bond.j() - start);
+        neighbors[bond.j() - start].push(bond.i() - start);
+    }
+
+    let mut unwrapped: Vec<Option<Vector3D>> = vec![None; size];
+    unwrapped[0] = Some(positions[start]);
+    let mut stack = vec![0];
+    while let Some(i) = stack.pop() {
+        let position_i = unwrapped[i].expect("missing unwrapped position");
+        for &j in &neighbors[i] {
+            if unwrapped[j].is_none() {
+                unwrapped[j] = Some(position_i + system.nearest_image(start + j, start + i));
+                stack.push(j);
+            }
+        }
+    }
+
+    let reference = positions[start];
+    return unwrapped.into_iter().enumerate().map(|(i, position)| {
+        position.unwrap_or_else(|| reference + system.nearest_image(start + i, start))
+    }).collect();
+}
+
+/// Compute the total dipole moment of the system.
+///
+/// $$ \vec M = \sum_i q_i \left(\vec r_i - \vec r_{com}\right) $$
+///
+/// where $q_i$ is the charge of particle $i$ and $\vec r_{com}$ the
+/// [`CenterOfMass`] of the system. The molecules are unwrapped by following
+/// the bonds between particles, and then placed at the minimum image of the
+/// center of mass.
+///
+/// [`CenterOfMass`]: struct.CenterOfMass.html
+pub struct DipoleMoment;
+impl Compute for DipoleMoment {
+    type Output = Vector3D;
+    fn compute(&self, system: &System) -> Vector3D {
+        if system.size() == 0 {
+            return Vector3D::zero();
+        }
+
+        let com = CenterOfMass.compute(system);
+        let charges = system.particles().charge;
+        let mut dipole = Vector3D::zero();
+        for molecule in system.molecules() {
+            let start = molecule.start();
+            let positions = unwrapped_molecule(system, start, molecule.end(), molecule.bonds());
+
+            let mut shift = positions[0] - com;
+            system.cell.vector_image(&mut shift);
+            let shift = shift - (positions[0] - com);
+
+            for (i, position) in positions.iter().enumerate() {
+                dipole += charges[start + i] * (position + shift - com);
+            }
+        }
+        return dipole;
+    }
+}
+
+/// Accumulate dipole moment samples to compute the static dielectric constant
+/// of the system from the dipole fluctuations.
+///
+/// $$ \varepsilon = 1 + \frac{\langle \vec M^2 \rangle - \langle \vec M
+///    \rangle^2}{3 \varepsilon_0 V k_B T} $$
+///
+/// where $\vec M$ is the [`DipoleMoment`] of the system, $V$ the volume, $k_B$
+/// the Boltzman constant and $T$ the temperature. This assumes conducting
+/// (tin-foil) boundary conditions, as used by Ewald summation.
+///
+/// [`DipoleMoment`]: struct.DipoleMoment.html
+#[derive(Clone, Debug, Default)]
+pub struct DielectricConstant {
+    /// Number of samples
+    count: usize,
+    /// Sum of the dipole samples
+    sum: Vector3D,
+    /// Sum of the squared norm of the dipole samples
+    sum2: f64,
+}
+
+impl DielectricConstant {
+    /// Create a new `DielectricConstant` accumulator without any sample.
+    pub fn new() -> DielectricConstant {
+        DielectricConstant {
+            count: 0,
+            sum: Vector3D::zero(),
+            sum2: 0.0,
+        }
+    }
+
+    /// Add a new `dipole` sample to this accumulator
+    pub fn push(&mut self, dipole: Vector3D) {
+        self.count += 1;
+        self.sum += dipole;
+        self.sum2 += dipole.norm2();
+    }
+
+    /// Get the number of samples in this accumulator
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Get the dielectric constant for a system with the given `volume` at
+    /// the given `temperature` from the samples accumulated so far. This
+    /// function returns 1 if there is no sample.
+    pub fn value(&self, volume: f64, temperature: f64) -> f64 {
+        assert!(temperature > 0.0, "The temperature must be positive in dielectric constant");
+        assert!(volume > 0.0, "The volume must be positive in dielectric constant");
+        if self.count == 0 {
+            return 1.0;
+        }
+        let count = self.count as f64;
+        let mean = self.sum / count;
+        let fluctuations = self.sum2 / count - mean.norm2();
+        let epsilon_0 = FOUR_PI_EPSILON_0 / (4.0 * PI);
+        return 1.0 + fluctuations / (3.0 * epsilon_0 * volume * K_BOLTZMANN * temperature);
+    }
+}
+
+/// Compute the volume of the system
+pub struct Volume;
+impl Compute for Volume {
+    type Output = f64;
+    #[inline]
+    fn compute(&self, system: &System) -> f64 {
+        return system.cell.volume();
+    }
+}
+
+
+/// Compute the virial tensor of the system using the atomic definition.
+///
+/// $$ \underline{W} = \sum_i \vec r_i \otimes \vec f_i - \underline H
+///    \frac{\partial U}{\partial \underline H} $$
+///
+/// where $\underline{H}$ is the unit cell matrix, $\vec f_i$ the force acting
+/// on the atom $i$ and $\vec r_i$ the position of the atom $i$
+///
+/// If all the interactions are pair interactions, this definition reduces to
+/// $$ \underline{W} = \sum_i \sum_{j > i} \vec r_{ij} \otimes \vec f_{ij} $$
+pub struct AtomicVirial;
+impl Compute for AtomicVirial {
+    type Output = Matrix3;
+    fn compute(&self, system: &System) -> Matrix3 {
+        assert!(!system.cell.is_infinite(), "Can not compute virial for infinite cell");
+
+        // Pair potentials contributions
+        let size = system.size();
+        let mut virial = each_i_reduce(size, Matrix3::zero, |i| {
+            let mut local_virial = Matrix3::zero();
+            for j in (i + 1)..size {
+                let path = system.bond_path(i, j);
+                if let Some(potential) = system.pair_potential(i, j) {
+                    let info = potential.restriction().information(path);
+                    if !info.excluded {
+                        let d = system.nearest_image(i, j);
+                        local_virial += info.scaling * potential.virial(&d);
+                    }
+                }
+            }
+            return local_virial;
+        }, |a, b| a + b);
+
+        // Tail correction for pair potentials contribution
+        let volume = system.cell.volume();
+        let composition = system.composition();
+        for (i, ni) in composition.all_particles() {
+            for (j, nj) in composition.all_particles() {
+                let two_pi_density = 2.0 * PI * (ni as f64) * (nj as f64) / volume;
+                if let Some(potential) = system.interactions().pair((i, j)) {
+                    virial += two_pi_density * potential.tail_virial();
+                }
+            }
+        }
+
+        // Bonded potentials contributions
+        for molecule in system.molecules() {
+            for bond in molecule.bonds() {
+                let (i, j) = (bond.i(), bond.j());
+                let r = system.nearest_image(i, j);
+                if let Some(potential) = system.bond_potential(i, j) {
+                    virial += potential.virial(&r);
+                }
+            }
+
+            for angle in molecule.urey_bradleys() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.urey_bradley_potential(i, j, k) {
+                    virial += potential.virial(&system.nearest_image(i, k));
+                }
+            }
+
+            // Angles and dihedrals potentials do not contribute to the trace
+            // of the virial as they only have an angular part (see DL_POLY 4
+            // manual page 18, or Smith, W., 1993, CCP5 Information Quarterly,
+            // 39, 14. 18, 21, 24), but they do contribute to the other
+            // components of the tensor. The positions are taken relative to
+            // the second particle, using the minimum image along the chain.
+            for angle in molecule.angles() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.angle_potential(i, j, k) {
+                    let (theta, d1, _, d3) = system.angle_and_derivatives(i, j, k);
+                    let force = potential.force(theta);
+                    let r_ij = system.nearest_image(i, j);
+                    let r_kj = system.nearest_image(k, j);
+                    virial += (force * d1).tensorial(&r_ij);
+                    virial += (force * d3).tensorial(&r_kj);
+                }
+            }
+
+            for dihedral in molecule.dihedrals() {
+                let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+                if let Some(potential) = system.dihedral_potential(i, j, k, m) {
+                    let (phi, d1, _, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
+                    let force = potential.force(phi);
+                    let r_ij = system.nearest_image(i, j);
+                    let r_kj = system.nearest_image(k, j);
+                    let r_mj = system.nearest_image(m, k) + r_kj;
+                    virial += (force * d1).tensorial(&r_ij);
+                    virial += (force * d3).tensorial(&r_kj);
+                    virial += (force * d4).tensorial(&r_mj);
+                }
+            }
+
+            // The positions of improper dihedral angles are taken relative to
+            // the central particle.
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                if let Some(potential) = system.improper_potential(i, j, k, m) {
+                    let (chi, _, d2, d3, d4) = system.out_of_plane_and_derivatives(i, j, k, m);
+                    let force = potential.force(chi);
+                    virial += (force * d2).tensorial(&system.nearest_image(j, i));
+                    virial += (force * d3).tensorial(&system.nearest_image(k, i));
+                    virial += (force * d4).tensorial(&system.nearest_image(m, i));
+                }
+            }
+        }
+
+        if let Some(coulomb) = system.coulomb_potential() {
+            virial += coulomb.atomic_virial(system);
+        }
+
+        for global in system.global_potentials() {
+            virial += global.atomic_virial(system);
+        }
+
+        return virial;
+    }
+}
+
+/// Compute the virial tensor of the system using the molecular definition
+///
+/// This differs from the [`AtomicVirial`](struct.AtomicVirial.html) when using
+/// rigid molecules, as it will contains the right contributions of the forces
+/// maintaining the molecules rigid without needing to compute them.
+///
+/// $$ \underline{W} = \sum_i \vec r_i \otimes \vec f_i - \underline H
+///    \frac{\partial U}{\partial \underline H} $$
+///
+/// where $\underline{H}$ is the unit cell matrix, $\vec f_i$ the force acting
+/// on the atom $i$ (comprising the force needed to keep the molecules rigid)
+/// and $\vec r_i$ the position of the atom $i$
+///
+/// If all the interactions are pair interaction, this definition reduces to
+///
+/// $$ \underline{W} = \sum_i \sum_{j > i} \sum_{a \in i} \sum_{b \in i}
+///    \frac{\vec r_{ab} \otimes \vec f_{ab}}{r_{ab}^2} \vec r_{ab} \cdot \vec
+///    r_{ij} $$
+///
+/// where $i$ and $j$ run over all the molecules in the system, while $a$ and
+/// $b$ run over all the particles in these molecules
+pub struct MolecularVirial;
+impl Compute for MolecularVirial {
+    type Output = Matrix3;
+    fn compute(&self, system: &System) -> Matrix3 {
+        assert!(!system.cell.is_infinite(), "Can not compute virial for infinite cell");
+
+        // Pair potentials contributions, using the molecular virial definition
+        // This is defined in Allen & Tildesley in equations 2.54; 2.61; 2.63.
+        let pair_virials = system.molecules().enumerate().par_bridge().map(|(i, molecule_i)| {
+            let mut local_virial = Matrix3::zero();
+            let ri = molecule_i.center_of_mass();
+
+            for molecule_j in system.molecules().skip(i + 1) {
+                let rj = molecule_j.center_of_mass();
+                let mut r_ij = ri - rj;
+                system.cell.vector_image(&mut r_ij);
+
+                for part_a in molecule_i.indexes() {
+                    for part_b in molecule_j.indexes() {
                         let path = system.bond_path(part_a, part_b);
                         let r_ab = system.nearest_image(part_a, part_b);
                         if let Some(potential) = system.pair_potential(part_a, part_b) {
@@ -345,11 +1026,20 @@ impl Compute for MolecularVirial {
                     }
                 }
             }
+
+            for angle in molecule.urey_bradleys() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.urey_bradley_potential(i, j, k) {
+                    if potential.virial(&system.nearest_image(i, k)).norm() > 1e-30 {
+                        warn_once!("Ignoring non null Urey-Bradley potential in molecular virial")
+                    }
+                }
+            }
         }
 
-        // Angles and dihedrals potentials do not contribute as they only have
-        // an angular part (see DL_POLY 4 manual page 18, or Smith, W., 1993,
-        // CCP5 Information Quarterly, 39, 14. 18, 21, 24).
+        // Angles, dihedrals and impropers potentials do not contribute as they
+        // only have an angular part (see DL_POLY 4 manual page 18, or Smith,
+        // W., 1993, CCP5 Information Quarterly, 39, 14. 18, 21, 24).
 
         if let Some(coulomb) = system.coulomb_potential() {
             virial += coulomb.molecular_virial(system);
@@ -374,7 +1064,9 @@ impl Compute for Virial {
     type Output = Matrix3;
     fn compute(&self, system: &System) -> Matrix3 {
         match system.simulated_degrees_of_freedom {
-            DegreesOfFreedom::Molecules => MolecularVirial.compute(system),
+            DegreesOfFreedom::Molecules | DegreesOfFreedom::FrozenMolecules(_) => {
+                MolecularVirial.compute(system)
+            }
             DegreesOfFreedom::Particles | DegreesOfFreedom::Frozen(_) => AtomicVirial.compute(system),
         }
     }
@@ -409,7 +1101,7 @@ impl Compute for PressureAtTemperature {
 
 /// Compute the pressure of the system using the virial definition.
 ///
-/// $$ p = \sum_i m_i \vec v_i \cdot \vec v_i + \frac{Tr(\underline{W})}{3V} $$
+/// $$ p = \frac{1}{3V} \left(\sum_i m_i \vec v_i \cdot \vec v_i + Tr(\underline{W}) \right) $$
 ///
 /// where $m_i$ is the mass of particle $i$, $\vec v_i$ the velocity of particle
 /// $i$, $V$ the simulation volume, $Tr$ is the matricial trace, and
@@ -427,6 +1119,45 @@ impl Compute for Pressure {
     }
 }
 
+/// Compute the pressure of the system using the molecular virial definition.
+///
+/// $$ p = \frac{1}{3V} \left(\sum_I M_I \vec V_I \cdot \vec V_I + Tr(\underline{W}) \right) $$
+///
+/// where $M_I$ is the mass of molecule $I$, $\vec V_I$ the velocity of the
+/// center of mass of molecule $I$, $V$ the simulation volume, $Tr$ is the
+/// matricial trace, and $\underline{W}$ the [`MolecularVirial`].
+///
+/// Contrary to [`Pressure`], this always uses the molecular definition, where
+/// only the motion of the molecules centers of mass and the intermolecular
+/// forces contribute. This gives the right pressure for rigid or constrained
+/// molecules, without needing to compute the constraint forces. Both
+/// definitions only give the same value for systems of single particles,
+/// with $3N$ degrees of freedom.
+///
+/// [`MolecularVirial`]: struct.MolecularVirial.html
+/// [`Pressure`]: struct.Pressure.html
+pub struct MolecularPressure;
+impl Compute for MolecularPressure {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        assert!(!system.cell.is_infinite(), "Can not compute pressure for infinite cell");
+
+        let mut kinetic = 0.0;
+        for molecule in system.molecules() {
+            let mut total_mass = 0.0;
+            let mut momentum = Vector3D::zero();
+            for (&mass, velocity) in soa_zip!(molecule.particles(), [mass, velocity]) {
+                total_mass += mass;
+                momentum += mass * velocity;
+            }
+            kinetic += momentum.norm2() / total_mass;
+        }
+
+        let virial = MolecularVirial.compute(system).trace();
+        return (kinetic + virial) / (3.0 * system.volume());
+    }
+}
+
 /// Compute the stress tensor of the system from the virial definition, at the
 /// given temperature.
 ///
@@ -458,10 +1189,22 @@ impl Compute for StressAtTemperature {
 ///
 /// $$ \underline{\sigma} = \frac{1}{V} \left( \sum_i m_i \vec v_i \otimes \vec v_i + \underline{W} \right) $$
 ///
-/// where $N_f$ is the number of degrees of freedom in the system, $k_B$ is the
-/// Boltzman constant, $T$ the instantaneous system temperature, $V$ the
-/// simulation volume, $Tr$ is the matricial trace, and $\underline{W}$ the
+/// where $m_i$ is the mass of particle $i$, $\vec v_i$ the velocity of
+/// particle $i$, $V$ the simulation volume, and $\underline{W}$ the
 /// [`Virial`].
+///
+/// This is the full pressure tensor of the system, including the kinetic
+/// contribution of each direction separately. It can be used for anisotropic
+/// pressure control. When the system has $3N$ degrees of freedom
+/// ([`DegreesOfFreedom::Particles`]) and the instantaneous temperature is
+/// used, the trace of this tensor divided by 3 is the scalar [`Pressure`].
+/// This does not hold in general for other degrees of freedom, where the
+/// kinetic part of [`Pressure`] comes from the temperature computed with
+/// $N_f \neq 3N$.
+///
+/// [`Virial`]: struct.Virial.html
+/// [`Pressure`]: struct.Pressure.html
+/// [`DegreesOfFreedom::Particles`]: ../enum.DegreesOfFreedom.html#variant.Particles
 pub struct Stress;
 impl Compute for Stress {
     type Output = Matrix3;
@@ -479,308 +1222,3297 @@ impl Compute for Stress {
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::System;
-    use crate::consts::K_BOLTZMANN;
-    use crate::{Harmonic, NullPotential, PairInteraction};
-    use crate::utils::system_from_xyz;
-    use crate::units;
+/// Compute the enthalpy of the system.
+///
+/// $$ H = U + p V $$
+///
+/// where $U$ is the [`PotentialEnergy`] of the system, $p$ the [`Pressure`]
+/// and $V$ the [`Volume`]. This is the configurational enthalpy, which does
+/// not include the kinetic energy of the particles.
+///
+/// [`PotentialEnergy`]: struct.PotentialEnergy.html
+/// [`Pressure`]: struct.Pressure.html
+/// [`Volume`]: struct.Volume.html
+pub struct Enthalpy;
+impl Compute for Enthalpy {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        let energy = PotentialEnergy.compute(system);
+        let pressure = Pressure.compute(system);
+        let volume = Volume.compute(system);
+        return energy + pressure * volume;
+    }
+}
 
-    use approx::assert_ulps_eq;
+/// Accumulate energy samples to compute the heat capacity of the system from
+/// the energy fluctuations.
+///
+/// $$ C_v = \frac{\langle E^2 \rangle - \langle E \rangle^2}{k_B T^2} $$
+///
+/// where $\langle E \rangle$ is the average of the energy samples, $k_B$ is
+/// the Boltzman constant and $T$ the temperature. The energy samples should
+/// come from a simulation in the canonical ensemble at this temperature.
+#[derive(Clone, Debug, Default)]
+pub struct HeatCapacity {
+    /// Number of samples
+    count: usize,
+    /// Sum of the energy samples
+    sum: f64,
+    /// Sum of the squared energy samples
+    sum2: f64,
+}
 
-    fn test_pairs_system() -> System {
-        let mut system = system_from_xyz(
-            "2
-            cell: 10.0
-            F 0.0 0.0 0.0 -0.007225222699367925 -0.002405756495275919  0.0026065109398392215
-            F 1.3 0.0 0.0  0.001179633958023287  0.003525262341736351 -0.0004132774783154952
-            ",
-        );
+impl HeatCapacity {
+    /// Create a new `HeatCapacity` accumulator without any sample.
+    pub fn new() -> HeatCapacity {
+        HeatCapacity {
+            count: 0,
+            sum: 0.0,
+            sum2: 0.0,
+        }
+    }
 
-        let mut interaction = PairInteraction::new(
-            Box::new(Harmonic {
-                k: units::from(300.0, "kJ/mol/A^2").unwrap(),
-                x0: units::from(1.2, "A").unwrap(),
-            }),
-            5.0,
-        );
-        interaction.enable_tail_corrections();
-        system.set_pair_potential(("F", "F"), interaction);
+    /// Add a new `energy` sample to this accumulator
+    pub fn push(&mut self, energy: f64) {
+        self.count += 1;
+        self.sum += energy;
+        self.sum2 += energy * energy;
+    }
 
-        // unused interaction to check that we do handle this right
-        system.set_pair_potential(("H", "O"), PairInteraction::new(Box::new(NullPotential), 0.0));
+    /// Get the number of samples in this accumulator
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Get the heat capacity at the given `temperature` from the samples
+    /// accumulated so far. This function returns 0 if there is no sample.
+    pub fn value(&self, temperature: f64) -> f64 {
+        assert!(temperature > 0.0, "The temperature must be positive in heat capacity");
+        if self.count == 0 {
+            return 0.0;
+        }
+        let count = self.count as f64;
+        let mean = self.sum / count;
+        let fluctuations = self.sum2 / count - mean * mean;
+        return fluctuations / (K_BOLTZMANN * temperature * temperature);
+    }
+}
 
+/// Minimal number of blocks for a blocking level to be used in error estimates
+const MIN_BLOCKS: usize = 16;
 
-        return system;
+/// Running mean and variance of the block averages at one level of the
+/// blocking analysis
+#[derive(Clone, Debug, Default)]
+struct BlockingLevel {
+    /// Number of blocks at this level
+    count: usize,
+    /// Mean of the blocks
+    mean: f64,
+    /// Sum of the squared deviations of the blocks from the mean
+    m2: f64,
+    /// Block waiting for a partner to create a block at the next level
+    pending: Option<f64>,
+}
+
+impl BlockingLevel {
+    /// Add a new block to this level
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
     }
 
-    fn test_molecular_system() -> System {
-        let mut system = system_from_xyz(
-            "4
-            cell: 10.0
-            F 0.0 0.0 0.0
-            F 1.0 0.0 0.0
-            F 1.0 1.0 0.0
-            F 2.0 1.0 0.0
+    /// Get the estimated standard error on the mean at this level, or `None`
+    /// if there are not enough blocks
+    fn std_error(&self) -> Option<f64> {
+        if self.count < 2 {
+            return None;
+        }
+        let count = self.count as f64;
+        return Some(f64::sqrt(self.m2 / (count * (count - 1.0))));
+    }
+}
+
+/// Online blocking analysis, following Flyvbjerg & Petersen. Each level
+/// contains the averages of pairs of blocks from the previous level, and the
+/// first level contains the raw samples.
+#[derive(Clone, Debug, Default)]
+struct Blocking {
+    levels: Vec<BlockingLevel>,
+}
+
+impl Blocking {
+    /// Add a new sample to the blocking analysis
+    fn push(&mut self, value: f64) {
+        let mut value = value;
+        let mut level = 0;
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(BlockingLevel::default());
+            }
+
+            let current = &mut self.levels[level];
+            current.push(value);
+            match current.pending.take() {
+                Some(previous) => {
+                    value = 0.5 * (previous + value);
+                    level += 1;
+                }
+                None => {
+                    current.pending = Some(value);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Get the number of samples
+    fn count(&self) -> usize {
+        self.levels.first().map_or(0, |level| level.count)
+    }
+
+    /// Get the mean of the samples
+    fn mean(&self) -> f64 {
+        self.levels.first().map_or(0.0, |level| level.mean)
+    }
+
+    /// Get the variance of the samples
+    fn variance(&self) -> f64 {
+        match self.levels.first() {
+            Some(level) if level.count > 0 => level.m2 / level.count as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Get the largest standard error estimate, using the raw samples and all
+    /// the levels with at least `MIN_BLOCKS` blocks.
+    fn std_error(&self) -> f64 {
+        self.levels.iter()
+                   .enumerate()
+                   .filter(|&(i, level)| i == 0 || level.count >= MIN_BLOCKS)
+                   .filter_map(|(_, level)| level.std_error())
+                   .fold(0.0, f64::max)
+    }
+
+    /// Get the full result of the blocking analysis
+    fn result(&self) -> BlockingResult {
+        let mut curve = Vec::new();
+        for (i, level) in self.levels.iter().enumerate() {
+            if let Some(std_error) = level.std_error() {
+                let count = level.count as f64;
+                curve.push(BlockError {
+                    block_size: 1 << i,
+                    blocks: level.count,
+                    std_error: std_error,
+                    uncertainty: std_error / f64::sqrt(2.0 * (count - 1.0)),
+                });
+            }
+        }
+
+        BlockingResult {
+            mean: self.mean(),
+            std_error: self.std_error(),
+            curve: curve,
+        }
+    }
+}
+
+/// Standard error estimate for one block size in a blocking analysis
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockError {
+    /// Number of samples in each block
+    pub block_size: usize,
+    /// Number of blocks of this size
+    pub blocks: usize,
+    /// Estimated standard error on the mean, from the variance of the blocks
+    pub std_error: f64,
+    /// Statistical uncertainty on the standard error estimate
+    pub uncertainty: f64,
+}
+
+/// Result of a blocking analysis, as returned by [`block_average`].
+///
+/// [`block_average`]: fn.block_average.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockingResult {
+    /// Mean of the samples
+    pub mean: f64,
+    /// Standard error on the mean, taken as the largest estimate over the
+    /// block sizes with at least 16 blocks.
+    pub std_error: f64,
+    /// Standard error estimates for increasing block sizes. The estimates
+    /// increase with the block size until the blocks are uncorrelated, and
+    /// then reach a plateau: the value of the plateau is the actual standard
+    /// error on the mean.
+    pub curve: Vec<BlockError>,
+}
+
+/// Estimate the standard error on the mean of correlated `samples` using the
+/// blocking method of Flyvbjerg & Petersen [1].
+///
+/// The samples are repeatedly averaged by pairs, and the standard error is
+/// estimated from the variance of the resulting blocks at each step. This
+/// accounts for the correlations between successive samples, as found in
+/// molecular dynamics trajectories. For $n$ blocks, the uncertainty on the
+/// standard error estimate $\epsilon$ is $\epsilon / \sqrt{2 (n - 1)}$.
+///
+/// [1] Flyvbjerg & Petersen, J. Chem. Phys. 91, 461 (1989); doi: 10.1063/1.457480
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::sys::compute::block_average;
+/// let samples = (0..1000).map(|i| f64::sin(i as f64)).collect::<Vec<_>>();
+/// let result = block_average(&samples);
+///
+/// assert!(f64::abs(result.mean) < 1e-2);
+/// assert_eq!(result.curve[0].block_size, 1);
+/// assert_eq!(result.curve[0].blocks, 1000);
+/// assert_eq!(result.curve[1].block_size, 2);
+/// assert_eq!(result.curve[1].blocks, 500);
+/// ```
+pub fn block_average(samples: &[f64]) -> BlockingResult {
+    let mut blocking = Blocking::default();
+    for &sample in samples {
+        blocking.push(sample);
+    }
+    return blocking.result();
+}
+
+/// Accumulate the average of a scalar property over a simulation.
+///
+/// The property is computed by `C`, once every `every` calls to
+/// [`update`](struct.Average.html#method.update). The standard error on the
+/// mean is estimated with a blocking analysis [1], accounting for the
+/// correlations between successive samples: the samples are averaged by
+/// blocks of increasing size, until the blocks become uncorrelated. Only the
+/// mean and variance of the blocks at each level are stored, so the memory
+/// used by this accumulator grows logarithmically with the number of samples.
+///
+/// [1] Flyvbjerg & Petersen, J. Chem. Phys. 91, 461 (1989); doi: 10.1063/1.457480
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::sys::compute::{Average, Volume};
+/// # use lumol_core::{System, UnitCell};
+/// let system = System::with_cell(UnitCell::cubic(10.0));
+/// let mut volume = Average::new(Volume, 10);
+///
+/// for _ in 0..100 {
+///     volume.update(&system);
+/// }
+///
+/// assert_eq!(volume.count(), 10);
+/// assert_eq!(volume.mean(), 1000.0);
+/// assert_eq!(volume.std_error(), 0.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Average<C> {
+    /// The property to average
+    compute: C,
+    /// Number of updates between two samples
+    every: usize,
+    /// Number of updates so far
+    updates: usize,
+    /// Blocking analysis of the samples
+    blocking: Blocking,
+}
+
+impl<C: Compute<Output = f64>> Average<C> {
+    /// Create a new `Average` accumulator, sampling the `compute` property
+    /// once every `every` updates.
+    ///
+    /// # Panics
+    ///
+    /// If `every` is zero
+    pub fn new(compute: C, every: usize) -> Average<C> {
+        assert!(every > 0, "the sampling frequency of an average must be positive");
+        Average {
+            compute: compute,
+            every: every,
+            updates: 0,
+            blocking: Blocking::default(),
+        }
+    }
+
+    /// Update this accumulator with the given `system`. The property is
+    /// sampled on the first update, and then once every `every` updates.
+    pub fn update(&mut self, system: &System) {
+        if self.updates % self.every == 0 {
+            let value = self.compute.compute(system);
+            self.push(value);
+        }
+        self.updates += 1;
+    }
+
+    /// Add a new `value` sample to this accumulator
+    pub fn push(&mut self, value: f64) {
+        self.blocking.push(value);
+    }
+
+    /// Get the number of samples in this accumulator
+    pub fn count(&self) -> usize {
+        self.blocking.count()
+    }
+
+    /// Get the mean of the samples accumulated so far. This function returns
+    /// 0 if there is no sample.
+    pub fn mean(&self) -> f64 {
+        self.blocking.mean()
+    }
+
+    /// Get the variance of the samples accumulated so far. This function
+    /// returns 0 if there is no sample.
+    pub fn variance(&self) -> f64 {
+        self.blocking.variance()
+    }
+
+    /// Get the standard error on the mean of the samples accumulated so far,
+    /// estimated by blocking analysis. This is the largest error estimate
+    /// over all the blocking levels containing at least 16 blocks. This
+    /// function returns 0 if there are less than two samples.
+    pub fn std_error(&self) -> f64 {
+        self.blocking.std_error()
+    }
+
+    /// Get the full result of the blocking analysis of the samples
+    /// accumulated so far, including the standard error estimates for all
+    /// the block sizes.
+    pub fn blocking(&self) -> BlockingResult {
+        self.blocking.result()
+    }
+}
+
+/// Compute the radial distribution function $g(r)$ of the system, as an
+/// histogram of pair distances normalized by the ideal gas distribution.
+///
+/// $$ g(r) = \frac{V}{N_{pairs}} \frac{n(r)}{\frac{4}{3} \pi \left[(r + \delta
+///    r)^3 - r^3\right]} $$
+///
+/// where $V$ is the volume of the system, $N_{pairs}$ the number of pairs
+/// considered, and $n(r)$ the number of pairs at a distance between $r$ and
+/// $r + \delta r$. The output contains one value for each bin of width `width`,
+/// the bin `k` covering distances between `k * width` and `(k + 1) * width`.
+pub struct RadialDistribution {
+    /// Width of the histogram bins
+    pub width: f64,
+    /// Maximal distance to consider in the histogram
+    pub max: f64,
+    /// Only use pairs between particles with these names, or all the pairs
+    /// if this is `None`.
+    pub pair: Option<(String, String)>,
+}
+
+impl RadialDistribution {
+    /// Check if the pair of particles with the names `name_i` and `name_j`
+    /// should be used in this radial distribution function.
+    fn use_pair(&self, name_i: &str, name_j: &str) -> bool {
+        match self.pair {
+            Some((ref a, ref b)) => {
+                (name_i == a && name_j == b) || (name_i == b && name_j == a)
+            }
+            None => true,
+        }
+    }
+}
+
+impl Compute for RadialDistribution {
+    type Output = Vec<f64>;
+    fn compute(&self, system: &System) -> Vec<f64> {
+        assert!(!system.cell.is_infinite(), "Can not compute radial distribution for infinite cell");
+        assert!(self.width > 0.0, "The bin width must be positive in radial distribution");
+        assert!(self.max > 0.0, "The maximal distance must be positive in radial distribution");
+
+        let nbins = f64::ceil(self.max / self.width) as usize;
+        let mut histogram = vec![0.0; nbins];
+        let names = system.particles().name;
+        for i in 0..system.size() {
+            for j in (i + 1)..system.size() {
+                if !self.use_pair(&names[i], &names[j]) {
+                    continue;
+                }
+                let r = system.distance(i, j);
+                if r < self.max {
+                    let bin = f64::floor(r / self.width) as usize;
+                    if bin < nbins {
+                        histogram[bin] += 1.0;
+                    }
+                }
+            }
+        }
+
+        let npairs = match self.pair {
+            Some((ref a, ref b)) => {
+                let na = names.iter().filter(|name| *name == a).count();
+                if a == b {
+                    na * na.saturating_sub(1) / 2
+                } else {
+                    let nb = names.iter().filter(|name| *name == b).count();
+                    na * nb
+                }
+            }
+            None => system.size() * system.size().saturating_sub(1) / 2,
+        };
+
+        if npairs == 0 {
+            return histogram;
+        }
+
+        let npairs = npairs as f64;
+        let volume = system.volume();
+        for (bin, value) in histogram.iter_mut().enumerate() {
+            let r_min = bin as f64 * self.width;
+            let r_max = r_min + self.width;
+            let shell = 4.0 / 3.0 * PI * (r_max * r_max * r_max - r_min * r_min * r_min);
+            *value *= volume / (npairs * shell);
+        }
+
+        return histogram;
+    }
+}
+
+/// Compute the density profile of the system along one of the unit cell
+/// vectors, as an histogram of the particles positions.
+///
+/// The cell is divided in `nbins` slabs of equal volume along the cell
+/// vector with index `axis`, and the output contains the density in each
+/// slab, the slab `k` covering fractional coordinates between `k / nbins` and
+/// `(k + 1) / nbins` along this vector. Particles outside of the unit cell are
+/// wrapped back inside.
+pub struct DensityProfile {
+    /// Index of the cell vector (0, 1 or 2) along which the profile is
+    /// computed
+    pub axis: usize,
+    /// Number of bins in the profile
+    pub nbins: usize,
+    /// Only use particles with this name, or all the particles if this is
+    /// `None`.
+    pub name: Option<String>,
+    /// Compute the mass density if `true`, and the number density otherwise
+    pub mass: bool,
+}
+
+impl Compute for DensityProfile {
+    type Output = Vec<f64>;
+    fn compute(&self, system: &System) -> Vec<f64> {
+        assert!(!system.cell.is_infinite(), "Can not compute density profile for infinite cell");
+        assert!(self.axis < 3, "The axis must be 0, 1 or 2 in density profile");
+        assert!(self.nbins > 0, "The number of bins must be positive in density profile");
+
+        let mut histogram = vec![0.0; self.nbins];
+        for (name, &mass, position) in soa_zip!(system.particles(), [name, mass, position]) {
+            if let Some(ref selected) = self.name {
+                if name != selected {
+                    continue;
+                }
+            }
+
+            let fractional = system.cell.fractional(position)[self.axis];
+            let fractional = fractional - f64::floor(fractional);
+            // Guard against rounding errors giving exactly 1.0
+            let bin = usize::min((fractional * self.nbins as f64) as usize, self.nbins - 1);
+            histogram[bin] += if self.mass { mass } else { 1.0 };
+        }
+
+        let slab_volume = system.volume() / self.nbins as f64;
+        for value in &mut histogram {
+            *value /= slab_volume;
+        }
+        return histogram;
+    }
+}
+
+/// Compute the static structure factor $S(k)$ of the system, averaged over
+/// the directions of the wave vector $\vec k$.
+///
+/// $$ S(\vec k) = \frac{1}{N} \left| \sum_j e^{i \vec k \cdot \vec r_j} \right|^2 $$
+///
+/// Only the wave vectors compatible with the periodic boundary conditions
+/// are used, *i.e.* the reciprocal lattice vectors of the unit cell. For each
+/// of the requested `magnitudes`, the structure factor is averaged over all
+/// the reciprocal lattice vectors with a norm within `tolerance` of this
+/// magnitude. The output contains one value for each requested magnitude, or
+/// zero if no reciprocal lattice vector has a matching norm.
+pub struct StructureFactor {
+    /// Norms of the wave vectors at which the structure factor is computed
+    pub magnitudes: Vec<f64>,
+    /// Tolerance on the norm of the wave vectors
+    pub tolerance: f64,
+}
+
+impl Compute for StructureFactor {
+    type Output = Vec<f64>;
+    fn compute(&self, system: &System) -> Vec<f64> {
+        assert!(!system.cell.is_infinite(), "Can not compute structure factor for infinite cell");
+        assert!(self.tolerance > 0.0, "The tolerance must be positive in structure factor");
+
+        let mut sums = vec![0.0; self.magnitudes.len()];
+        let mut counts = vec![0; self.magnitudes.len()];
+        if system.size() == 0 {
+            return sums;
+        }
+
+        // The reciprocal lattice vector with index n verifies k.a_i = 2 pi n_i
+        // for all the cell vectors a_i, which gives an upper bound on n_i.
+        let k_max = self.magnitudes.iter().cloned().fold(0.0, f64::max) + self.tolerance;
+        let lengths = system.cell.lengths();
+        let max_index = |i: usize| f64::ceil(k_max * lengths[i] / (2.0 * PI)) as i64;
+        let (max_x, max_y, max_z) = (max_index(0), max_index(1), max_index(2));
+
+        let positions = system.particles().position;
+        for nx in -max_x..=max_x {
+            for ny in -max_y..=max_y {
+                for nz in -max_z..=max_z {
+                    if nx == 0 && ny == 0 && nz == 0 {
+                        continue;
+                    }
+                    let kvec = system.cell.k_vector([nx as f64, ny as f64, nz as f64]);
+                    let norm = kvec.norm();
+
+                    // Only compute the structure factor for this vector if needed
+                    let mut factor = None;
+                    for (i, &magnitude) in self.magnitudes.iter().enumerate() {
+                        if f64::abs(norm - magnitude) > self.tolerance {
+                            continue;
+                        }
+
+                        let value = *factor.get_or_insert_with(|| {
+                            let (mut real, mut imaginary) = (0.0, 0.0);
+                            for position in positions {
+                                let (sin, cos) = (kvec * position).sin_cos();
+                                real += cos;
+                                imaginary += sin;
+                            }
+                            (real * real + imaginary * imaginary) / positions.len() as f64
+                        });
+                        sums[i] += value;
+                        counts[i] += 1;
+                    }
+                }
+            }
+        }
+
+        for (sum, &count) in sums.iter_mut().zip(&counts) {
+            if count != 0 {
+                *sum /= count as f64;
+            }
+        }
+        return sums;
+    }
+}
+
+/// Get the full neighbor list of the system: for each particle `i`, the
+/// indexes `j` of all the other particles closer than `cutoff`, together with
+/// the nearest image vector from `i` to `j`.
+fn neighbor_list(system: &System, cutoff: f64) -> Vec<Vec<(usize, Vector3D)>> {
+    let mut neighbors = vec![Vec::new(); system.size()];
+    for i in 0..system.size() {
+        for j in (i + 1)..system.size() {
+            let rij = system.nearest_image(j, i);
+            if rij.norm() < cutoff {
+                neighbors[i].push((j, rij));
+                neighbors[j].push((i, -rij));
+            }
+        }
+    }
+    return neighbors;
+}
+
+/// Compute the coordination number of each particle, *i.e.* the number of
+/// other particles closer than `cutoff`.
+pub struct CoordinationNumber {
+    /// Radius of the first coordination shell
+    pub cutoff: f64,
+}
+
+impl Compute for CoordinationNumber {
+    type Output = Vec<usize>;
+    fn compute(&self, system: &System) -> Vec<usize> {
+        assert!(self.cutoff > 0.0, "The cutoff must be positive in coordination number");
+        return neighbor_list(system, self.cutoff).iter().map(Vec::len).collect();
+    }
+}
+
+/// Compute the Steinhardt local bond-order parameter $q_l$ of each particle
+/// [1], which can be used to distinguish between liquid and different
+/// crystalline environments.
+///
+/// $$ q_l(i) = \sqrt{\frac{4 \pi}{2 l + 1} \sum_{m = -l}^{l} \left|
+///    \frac{1}{N_i} \sum_j Y_{lm}(\hat r_{ij}) \right|^2} $$
+///
+/// where the sum over $j$ runs over the $N_i$ neighbors of particle $i$
+/// closer than `cutoff`, and $Y_{lm}$ are the spherical harmonics. Using the
+/// addition theorem, this is computed as
+///
+/// $$ q_l(i) = \frac{1}{N_i} \sqrt{\sum_{j, k} P_l(\hat r_{ij} \cdot
+///    \hat r_{ik})} $$
+///
+/// where $P_l$ is the Legendre polynomial of degree $l$. Particles without
+/// any neighbor have $q_l = 0$. Typical values for $q_6$ are 0.575 for FCC,
+/// 0.485 for HCP and 0.629 for BCC crystals, when using the first shell of
+/// neighbors.
+///
+/// [1] Steinhardt, Nelson & Ronchetti, Phys. Rev. B 28, 784 (1983);
+/// doi: 10.1103/PhysRevB.28.784
+pub struct SteinhardtOrder {
+    /// Degree of the spherical harmonics
+    pub l: usize,
+    /// Cutoff distance for the neighbors of each particle
+    pub cutoff: f64,
+}
+
+impl Compute for SteinhardtOrder {
+    type Output = Vec<f64>;
+    fn compute(&self, system: &System) -> Vec<f64> {
+        assert!(self.cutoff > 0.0, "The cutoff must be positive in Steinhardt order parameter");
+
+        return neighbor_list(system, self.cutoff).iter().map(|neighbors| {
+            if neighbors.is_empty() {
+                return 0.0;
+            }
+            let directions = neighbors.iter().map(|(_, rij)| rij.normalized()).collect::<Vec<_>>();
+            let mut sum = 0.0;
+            for u in &directions {
+                for v in &directions {
+                    sum += legendre(self.l, u * v);
+                }
+            }
+            // Guard against rounding errors for vanishing order parameters
+            return f64::sqrt(f64::max(sum, 0.0)) / directions.len() as f64;
+        }).collect();
+    }
+}
+
+/// Evaluate the Legendre polynomial of degree `l` at `x`
+fn legendre(l: usize, x: f64) -> f64 {
+    if l == 0 {
+        return 1.0;
+    }
+    let (mut previous, mut current) = (1.0, x);
+    for n in 1..l {
+        let n = n as f64;
+        let next = ((2.0 * n + 1.0) * x * current - n * previous) / (n + 1.0);
+        previous = current;
+        current = next;
+    }
+    return current;
+}
+
+/// Local crystal structure around a particle, as identified by
+/// [`CommonNeighborAnalysis`](struct.CommonNeighborAnalysis.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrystalStructure {
+    /// Face-centered cubic
+    Fcc,
+    /// Hexagonal close-packed
+    Hcp,
+    /// Body-centered cubic
+    Bcc,
+    /// Icosahedral
+    Icosahedral,
+    /// Any other structure, including liquid and defects
+    Other,
+}
+
+/// Identify the local crystal structure around each particle using the
+/// common neighbor analysis (CNA) [1].
+///
+/// Each bond between a particle and one of its neighbors closer than
+/// `cutoff` is characterized by three numbers: the number of neighbors
+/// common to both particles, the number of bonds between these common
+/// neighbors, and the number of bonds in the longest chain formed by these
+/// bonds. The set of signatures of all the bonds of a particle identifies
+/// its environment:
+///
+/// - FCC: 12 neighbors, all with the (4, 2, 1) signature;
+/// - HCP: 12 neighbors, 6 with the (4, 2, 1) and 6 with the (4, 2, 2)
+///   signature;
+/// - BCC: 14 neighbors, 8 with the (6, 6, 6) and 6 with the (4, 4, 4)
+///   signature;
+/// - icosahedral: 12 neighbors, all with the (5, 5, 5) signature.
+///
+/// The `cutoff` should lie between the first and second neighbors shells for
+/// close-packed structures, and between the second and third neighbors
+/// shells for BCC structures.
+///
+/// [1] Honeycutt & Andersen, J. Phys. Chem. 91, 4950 (1987);
+/// doi: 10.1021/j100303a014
+pub struct CommonNeighborAnalysis {
+    /// Cutoff distance for the neighbors of each particle
+    pub cutoff: f64,
+}
+
+impl CommonNeighborAnalysis {
+    /// Get the CNA signature of the bond between the particles `i` and `j`,
+    /// using the sorted lists of `neighbors` of all the particles.
+    fn signature(neighbors: &[Vec<usize>], i: usize, j: usize) -> (usize, usize, usize) {
+        let common = neighbors[i].iter()
+                                 .cloned()
+                                 .filter(|k| neighbors[j].binary_search(k).is_ok())
+                                 .collect::<Vec<_>>();
+
+        // Bonds between common neighbors, as indexes in `common`
+        let mut bonds = Vec::new();
+        for (a, &k) in common.iter().enumerate() {
+            for (b, &l) in common.iter().enumerate().skip(a + 1) {
+                if neighbors[k].binary_search(&l).is_ok() {
+                    bonds.push((a, b));
+                }
+            }
+        }
+
+        // Find the clusters of connected bonds, giving the same label to all
+        // the common neighbors in a cluster
+        let mut labels = (0..common.len()).collect::<Vec<_>>();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &(a, b) in &bonds {
+                let label = usize::min(labels[a], labels[b]);
+                if labels[a] != label || labels[b] != label {
+                    labels[a] = label;
+                    labels[b] = label;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut chains = vec![0; common.len()];
+        for &(a, _) in &bonds {
+            chains[labels[a]] += 1;
+        }
+        let longest = chains.into_iter().max().unwrap_or(0);
+
+        return (common.len(), bonds.len(), longest);
+    }
+
+    /// Get the crystal structure corresponding to the given bond `signatures`
+    fn structure(signatures: &[(usize, usize, usize)]) -> CrystalStructure {
+        let count = |signature| signatures.iter().filter(|&&s| s == signature).count();
+        match signatures.len() {
+            12 => {
+                if count((4, 2, 1)) == 12 {
+                    CrystalStructure::Fcc
+                } else if count((4, 2, 1)) == 6 && count((4, 2, 2)) == 6 {
+                    CrystalStructure::Hcp
+                } else if count((5, 5, 5)) == 12 {
+                    CrystalStructure::Icosahedral
+                } else {
+                    CrystalStructure::Other
+                }
+            }
+            14 => {
+                if count((6, 6, 6)) == 8 && count((4, 4, 4)) == 6 {
+                    CrystalStructure::Bcc
+                } else {
+                    CrystalStructure::Other
+                }
+            }
+            _ => CrystalStructure::Other,
+        }
+    }
+}
+
+impl Compute for CommonNeighborAnalysis {
+    type Output = Vec<CrystalStructure>;
+    fn compute(&self, system: &System) -> Vec<CrystalStructure> {
+        assert!(self.cutoff > 0.0, "The cutoff must be positive in common neighbor analysis");
+
+        let neighbors = neighbor_list(system, self.cutoff).into_iter().map(|list| {
+            let mut indexes = list.into_iter().map(|(j, _)| j).collect::<Vec<_>>();
+            indexes.sort_unstable();
+            return indexes;
+        }).collect::<Vec<_>>();
+
+        return neighbors.iter().enumerate().map(|(i, list)| {
+            let signatures = list.iter()
+                                 .map(|&j| CommonNeighborAnalysis::signature(&neighbors, i, j))
+                                 .collect::<Vec<_>>();
+            return CommonNeighborAnalysis::structure(&signatures);
+        }).collect();
+    }
+}
+
+/// A face of a convex polyhedron, in the plane `normal * x = offset`
+#[derive(Clone, Debug)]
+struct Face {
+    /// Unit vector normal to the face, pointing outside of the polyhedron
+    normal: Vector3D,
+    /// Distance between the origin and the plane of the face
+    offset: f64,
+    /// Vertices of the face, in order around the face
+    vertices: Vec<Vector3D>,
+}
+
+/// A convex polyhedron containing the origin, used to build Voronoi cells
+#[derive(Clone, Debug)]
+struct ConvexPolyhedron {
+    faces: Vec<Face>,
+    /// Tolerance for geometric comparisons
+    epsilon: f64,
+}
+
+impl ConvexPolyhedron {
+    /// Create a cube centered on the origin, with half side length `half`
+    fn cube(half: f64) -> ConvexPolyhedron {
+        let mut faces = Vec::new();
+        for axis in 0..3 {
+            for &sign in &[-1.0, 1.0] {
+                let mut normal = Vector3D::zero();
+                normal[axis] = sign;
+                let mut u = Vector3D::zero();
+                u[(axis + 1) % 3] = 1.0;
+                let v = normal ^ u;
+
+                let center = half * normal;
+                let vertices = [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)].iter()
+                    .map(|&(a, b)| center + half * (a * u + b * v))
+                    .collect();
+                faces.push(Face {
+                    normal: normal,
+                    offset: half,
+                    vertices: vertices,
+                });
+            }
+        }
+
+        ConvexPolyhedron {
+            faces: faces,
+            epsilon: 1e-10 * half,
+        }
+    }
+
+    /// Get the largest distance between the origin and a vertex
+    fn radius(&self) -> f64 {
+        self.faces.iter()
+                  .flat_map(|face| &face.vertices)
+                  .map(Vector3D::norm)
+                  .fold(0.0, f64::max)
+    }
+
+    /// Get the volume of the polyhedron
+    fn volume(&self) -> f64 {
+        let mut volume = 0.0;
+        for face in &self.faces {
+            let n = face.vertices.len();
+            let mut area = Vector3D::zero();
+            for k in 0..n {
+                area += face.vertices[k] ^ face.vertices[(k + 1) % n];
+            }
+            // Pyramid with the face as base and the origin as apex
+            volume += face.offset * f64::abs(area * face.normal) / 6.0;
+        }
+        return volume;
+    }
+
+    /// Cut the polyhedron with the plane `normal * x = offset`, only keeping
+    /// the part containing the origin.
+    fn clip(&mut self, normal: Vector3D, offset: f64) {
+        let epsilon = self.epsilon;
+        let outside = |point: &Vector3D| normal * point - offset;
+        let cut = self.faces.iter().flat_map(|face| &face.vertices).any(|v| outside(v) > epsilon);
+        if !cut {
+            return;
+        }
+
+        let mut points: Vec<Vector3D> = Vec::new();
+        let mut add_point = |point: Vector3D| {
+            if points.iter().all(|other| (point - other).norm() > epsilon) {
+                points.push(point);
+            }
+        };
+
+        let mut faces = Vec::new();
+        for face in &self.faces {
+            let n = face.vertices.len();
+            let mut vertices = Vec::new();
+            for k in 0..n {
+                let current = face.vertices[k];
+                let next = face.vertices[(k + 1) % n];
+                let d_current = outside(&current);
+                let d_next = outside(&next);
+
+                if d_current <= epsilon {
+                    vertices.push(current);
+                }
+                if f64::abs(d_current) <= epsilon {
+                    add_point(current);
+                }
+                let crossing = (d_current < -epsilon && d_next > epsilon) ||
+                               (d_current > epsilon && d_next < -epsilon);
+                if crossing {
+                    let t = d_current / (d_current - d_next);
+                    let intersection = current + t * (next - current);
+                    vertices.push(intersection);
+                    add_point(intersection);
+                }
+            }
+
+            if vertices.len() >= 3 {
+                faces.push(Face {
+                    normal: face.normal,
+                    offset: face.offset,
+                    vertices: vertices,
+                });
+            }
+        }
+
+        // Create the new face from the points in the cutting plane, ordered
+        // by angle around their center
+        if points.len() >= 3 {
+            let sum = points.iter().fold(Vector3D::zero(), |sum, point| sum + point);
+            let center = sum / points.len() as f64;
+            let u = (points[0] - center).normalized();
+            let v = normal ^ u;
+            let angle = |point: &Vector3D| {
+                let delta = point - center;
+                f64::atan2(delta * v, delta * u)
+            };
+            points.sort_by(|a, b| angle(a).partial_cmp(&angle(b)).expect("got NaN angle"));
+            faces.push(Face {
+                normal: normal,
+                offset: offset,
+                vertices: points,
+            });
+        }
+
+        self.faces = faces;
+    }
+}
+
+/// Particles sorted in a grid of cells covering the unit cell, used to find
+/// the neighbors of a particle (including periodic images) closer than a
+/// given distance without looping over all the particles.
+///
+/// The grid uses the positions of the particles when it is created, and is
+/// not updated if the particles move afterward.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::sys::compute::NeighborGrid;
+/// # use lumol_core::{System, Molecule, Particle, UnitCell, Vector3D};
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// for &x in &[0.0, 2.0, 4.0, 19.0] {
+///     let position = Vector3D::new(x, 0.0, 0.0);
+///     system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+/// }
+///
+/// let grid = NeighborGrid::new(&system, 3.0);
+/// assert_eq!(grid.neighbors_of(0, 2.5), vec![1, 3]);
+/// assert_eq!(grid.neighbors_of(2, 2.5), vec![1]);
+/// ```
+pub struct NeighborGrid<'a> {
+    system: &'a System,
+    /// Fractional coordinates of the particles, wrapped inside the unit cell
+    fractional: Vec<Vector3D>,
+    /// Number of grid cells along each unit cell vector
+    shape: [usize; 3],
+    /// Distances between opposite faces of the grid cells
+    widths: [f64; 3],
+    /// Indexes of the particles in each grid cell
+    cells: Vec<Vec<usize>>,
+}
+
+impl<'a> NeighborGrid<'a> {
+    /// Sort the particles in the `system` in a grid of cells, with distances
+    /// between opposite faces of the grid cells larger than `size`. Using a
+    /// `size` close to the cutoff of the neighbors searches is usually the
+    /// most efficient.
+    ///
+    /// # Panics
+    ///
+    /// If the system unit cell is infinite, or if `size` is not positive.
+    pub fn new(system: &'a System, size: f64) -> NeighborGrid<'a> {
+        assert!(!system.cell.is_infinite(), "can not create a neighbor grid with an infinite cell");
+        assert!(size > 0.0, "the size of the neighbor grid cells must be positive");
+        let matrix = system.cell.matrix();
+        let vectors = [
+            Vector3D::new(matrix[0][0], matrix[1][0], matrix[2][0]),
+            Vector3D::new(matrix[0][1], matrix[1][1], matrix[2][1]),
+            Vector3D::new(matrix[0][2], matrix[1][2], matrix[2][2]),
+        ];
+        let volume = system.volume();
+        // Distances between opposite faces of the unit cell
+        let cell_widths = [
+            volume / (vectors[1] ^ vectors[2]).norm(),
+            volume / (vectors[2] ^ vectors[0]).norm(),
+            volume / (vectors[0] ^ vectors[1]).norm(),
+        ];
+
+        let mut shape = [1; 3];
+        let mut widths = [0.0; 3];
+        for (k, &cell_width) in cell_widths.iter().enumerate() {
+            shape[k] = usize::max(1, f64::floor(cell_width / size) as usize);
+            widths[k] = cell_width / shape[k] as f64;
+        }
+
+        let fractional = system.particles().position.iter().map(|position| {
+            let fractional = system.cell.fractional(position);
+            Vector3D::new(
+                fractional[0] - f64::floor(fractional[0]),
+                fractional[1] - f64::floor(fractional[1]),
+                fractional[2] - f64::floor(fractional[2]),
+            )
+        }).collect::<Vec<_>>();
+
+        let mut grid = NeighborGrid {
+            system: system,
+            fractional: Vec::new(),
+            shape: shape,
+            widths: widths,
+            cells: vec![Vec::new(); shape[0] * shape[1] * shape[2]],
+        };
+        for (i, position) in fractional.iter().enumerate() {
+            let index = grid.linear_index(grid.cell_index(position));
+            grid.cells[index].push(i);
+        }
+        grid.fractional = fractional;
+        return grid;
+    }
+
+    /// Get the index of the grid cell containing the `fractional` position
+    fn cell_index(&self, fractional: &Vector3D) -> [usize; 3] {
+        let mut index = [0; 3];
+        for (k, &n) in self.shape.iter().enumerate() {
+            // Clamping guards against rounding errors for positions very
+            // close to the upper faces of the unit cell
+            index[k] = usize::min(f64::floor(fractional[k] * n as f64) as usize, n - 1);
+        }
+        return index;
+    }
+
+    /// Get the position of the grid cell with the given `index` in `cells`
+    fn linear_index(&self, index: [usize; 3]) -> usize {
+        (index[0] * self.shape[1] + index[1]) * self.shape[2] + index[2]
+    }
+
+    /// Get the indexes of all the particles with at least one periodic image
+    /// closer than `cutoff` to the particle `i`, sorted in increasing order.
+    /// The particle `i` itself is not included, even if one of its periodic
+    /// images is closer than `cutoff`.
+    ///
+    /// Only the particles in the grid cells around `i` are checked, making
+    /// this much faster than checking all the other particles in large
+    /// systems.
+    pub fn neighbors_of(&self, i: usize, cutoff: f64) -> Vec<usize> {
+        let mut neighbors = Vec::new();
+        self.for_each_neighbor(i, cutoff, |j, _| {
+            if j != i {
+                neighbors.push(j);
+            }
+        });
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        return neighbors;
+    }
+
+    /// Get all the particles and periodic images closer than `cutoff` to the
+    /// particle `i`, as pairs of distance and vector from `i` to the
+    /// neighbor. The particle `i` itself is not included, but its periodic
+    /// images are.
+    fn neighbors(&self, i: usize, cutoff: f64) -> Vec<(f64, Vector3D)> {
+        let mut neighbors = Vec::new();
+        self.for_each_neighbor(i, cutoff, |_, r| neighbors.push((r.norm(), r)));
+        return neighbors;
+    }
+
+    /// Call `callback` with the index of the particle and the vector from `i`
+    /// for all the particles and periodic images closer than `cutoff` to the
+    /// particle `i`, excluding `i` itself but not its periodic images.
+    fn for_each_neighbor<F>(&self, i: usize, cutoff: f64, mut callback: F)
+        where F: FnMut(usize, Vector3D)
+    {
+        let center = self.cell_index(&self.fractional[i]);
+        let max = |k: usize| f64::ceil(cutoff / self.widths[k]) as i64;
+        let (max_a, max_b, max_c) = (max(0), max(1), max(2));
+
+        for a in -max_a..=max_a {
+            for b in -max_b..=max_b {
+                for c in -max_c..=max_c {
+                    // Grid cells outside of the unit cell are mapped back
+                    // inside, and the corresponding periodic shift is
+                    // applied to the particles they contain
+                    let mut index = [0; 3];
+                    let mut shift = Vector3D::zero();
+                    for (k, &delta) in [a, b, c].iter().enumerate() {
+                        let n = self.shape[k] as i64;
+                        let unwrapped = center[k] as i64 + delta;
+                        index[k] = unwrapped.rem_euclid(n) as usize;
+                        shift[k] = unwrapped.div_euclid(n) as f64;
+                    }
+
+                    for &j in &self.cells[self.linear_index(index)] {
+                        let delta = self.fractional[j] + shift - self.fractional[i];
+                        let r = self.system.cell.cartesian(&delta);
+                        let distance = r.norm();
+                        if distance > 0.0 && distance < cutoff {
+                            callback(j, r);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Compute the volume of the Voronoi cell of each particle, *i.e.* the
+/// volume of the region of space closer to this particle than to any other
+/// particle, taking the periodic boundary conditions into account.
+///
+/// The sum of the Voronoi volumes of all particles is equal to the volume of
+/// the unit cell. Each cell is built by cutting a large box with the planes
+/// bisecting the segments between the particle and its neighbors, in order
+/// of increasing distance, until the remaining neighbors are too far away to
+/// modify the cell. The neighbors are found using a grid of cells, so this
+/// algorithm scales linearly with the number of particles for homogeneous
+/// systems.
+pub struct VoronoiVolume;
+
+impl Compute for VoronoiVolume {
+    type Output = Vec<f64>;
+    fn compute(&self, system: &System) -> Vec<f64> {
+        assert!(!system.cell.is_infinite(), "Can not compute Voronoi volume for infinite cell");
+        let size = system.size();
+        if size == 0 {
+            return Vec::new();
+        }
+
+        let matrix = system.cell.matrix();
+        // The Voronoi cell is always inside a box of this size, since the
+        // periodic images of the particle are included as neighbors
+        let half = (0..3).map(|k| {
+            Vector3D::new(matrix[0][k], matrix[1][k], matrix[2][k]).norm()
+        }).sum::<f64>();
+
+        // Initial guess for the neighbors search radius
+        let initial_search = 2.0 * f64::cbrt(system.volume() / size as f64);
+        let grid = NeighborGrid::new(system, initial_search);
+
+        return (0..size).into_par_iter().map(|i| {
+            let mut search = initial_search;
+            loop {
+                let mut neighbors = grid.neighbors(i, search);
+                neighbors.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("got NaN distance"));
+
+                let mut cell = ConvexPolyhedron::cube(half);
+                let mut radius = cell.radius();
+                for (distance, r) in neighbors {
+                    // Neighbors further than twice the largest vertex
+                    // distance can not cut the cell
+                    if distance > 2.0 * radius {
+                        break;
+                    }
+                    cell.clip(r / distance, 0.5 * distance);
+                    radius = cell.radius();
+                }
+
+                if 2.0 * radius < search {
+                    return cell.volume();
+                }
+                // Some neighbors outside of the search radius could cut the
+                // cell, try again with a larger radius
+                search = 2.0 * radius * (1.0 + 1e-6);
+            }
+        }).collect();
+    }
+}
+
+/// Compute the mean squared displacement of the particles since a reference
+/// configuration.
+///
+/// $$ MSD = \frac{1}{N} \sum_i \left| \vec r_i(t) - \vec r_i(0) \right|^2 $$
+///
+/// Particles crossing the periodic boundaries are unwrapped by following the
+/// minimum image displacement of each particle between two calls to
+/// [`update`](struct.MeanSquaredDisplacement.html#method.update). This
+/// function should be called often enough for the particles not to move by
+/// more than half of the cell size between two calls.
+pub struct MeanSquaredDisplacement {
+    /// Positions of the particles in the reference configuration
+    initial: Vec<Vector3D>,
+    /// Unwrapped positions of the particles at the last update
+    unwrapped: Vec<Vector3D>,
+    /// Positions of the particles in the system at the last update
+    last: Vec<Vector3D>,
+}
+
+impl MeanSquaredDisplacement {
+    /// Create a new `MeanSquaredDisplacement` using the current positions of
+    /// the particles in `system` as the reference configuration.
+    pub fn new(system: &System) -> MeanSquaredDisplacement {
+        let positions = system.particles().position.to_vec();
+        MeanSquaredDisplacement {
+            initial: positions.clone(),
+            unwrapped: positions.clone(),
+            last: positions,
+        }
+    }
+
+    /// Update the unwrapped positions of the particles with the positions in
+    /// `system`.
+    pub fn update(&mut self, system: &System) {
+        self.unwrapped = self.unwrapped_positions(system);
+        self.last = system.particles().position.to_vec();
+    }
+
+    /// Get the self-diffusion coefficient from the long-time slope of the
+    /// mean squared displacement, using the Einstein relation $MSD = 6 D t$.
+    /// The slope is obtained by a least-squares linear fit of `msd` as a
+    /// function of `times`.
+    pub fn diffusion_coefficient(times: &[f64], msd: &[f64]) -> f64 {
+        assert_eq!(times.len(), msd.len(), "times and msd must have the same size");
+        assert!(times.len() > 1, "at least two points are needed to compute the diffusion coefficient");
+
+        let n = times.len() as f64;
+        let mean_t = times.iter().sum::<f64>() / n;
+        let mean_msd = msd.iter().sum::<f64>() / n;
+
+        let mut covariance = 0.0;
+        let mut variance = 0.0;
+        for (&t, &value) in times.iter().zip(msd) {
+            covariance += (t - mean_t) * (value - mean_msd);
+            variance += (t - mean_t) * (t - mean_t);
+        }
+        return covariance / variance / 6.0;
+    }
+
+    /// Get the unwrapped positions corresponding to the positions in `system`
+    fn unwrapped_positions(&self, system: &System) -> Vec<Vector3D> {
+        assert_eq!(
+            system.size(), self.initial.len(),
+            "the number of particles changed in mean squared displacement"
+        );
+        let positions = system.particles().position;
+        return self.unwrapped.iter().zip(&self.last).zip(positions).map(|((unwrapped, last), position)| {
+            let mut displacement = position - last;
+            system.cell.vector_image(&mut displacement);
+            return unwrapped + displacement;
+        }).collect();
+    }
+}
+
+impl Compute for MeanSquaredDisplacement {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        if self.initial.is_empty() {
+            return 0.0;
+        }
+        let unwrapped = self.unwrapped_positions(system);
+        let sum = unwrapped.iter().zip(&self.initial).map(|(position, initial)| {
+            (position - initial).norm2()
+        }).sum::<f64>();
+        return sum / self.initial.len() as f64;
+    }
+}
+
+/// Accumulate the velocity autocorrelation function (VACF) of the particles.
+///
+/// $$ VACF(t) = \frac{1}{N} \sum_i \langle \vec v_i(t_0) \cdot \vec v_i(t_0 + t) \rangle $$
+///
+/// The average is taken over all the time origins $t_0$ seen by this
+/// accumulator. Only the last `window` velocity snapshots are kept in memory,
+/// which limits the largest time lag to `window - 1` updates. The
+/// self-diffusion coefficient can then be obtained from the Green-Kubo
+/// relation
+///
+/// $$ D = \frac{1}{3} \int_0^\infty VACF(t) \ dt, $$
+///
+/// which complements the Einstein relation used with the
+/// [`MeanSquaredDisplacement`](struct.MeanSquaredDisplacement.html).
+pub struct VelocityAutocorrelation {
+    /// Maximal number of snapshots to keep in memory
+    window: usize,
+    /// Velocities of the particles at the last updates, the most recent last
+    snapshots: VecDeque<Vec<Vector3D>>,
+    /// Sum of the velocity products for each time lag
+    sums: Vec<f64>,
+    /// Number of time origins for each time lag
+    counts: Vec<usize>,
+}
+
+impl VelocityAutocorrelation {
+    /// Create a new `VelocityAutocorrelation` accumulator, computing the
+    /// autocorrelation function for time lags up to `window - 1` updates.
+    ///
+    /// # Panics
+    ///
+    /// If `window` is zero
+    pub fn new(window: usize) -> VelocityAutocorrelation {
+        assert!(window > 0, "the window of velocity autocorrelation must be positive");
+        VelocityAutocorrelation {
+            window: window,
+            snapshots: VecDeque::with_capacity(window),
+            sums: vec![0.0; window],
+            counts: vec![0; window],
+        }
+    }
+
+    /// Add the current velocities of the particles in `system` to this
+    /// accumulator.
+    pub fn update(&mut self, system: &System) {
+        let velocities = system.particles().velocity;
+        if let Some(previous) = self.snapshots.front() {
+            assert_eq!(
+                previous.len(), velocities.len(),
+                "the number of particles changed in velocity autocorrelation"
+            );
+        }
+
+        if self.snapshots.len() == self.window {
+            let _ = self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(velocities.to_vec());
+
+        // The snapshot at lag k is k updates before the current one
+        for (lag, snapshot) in self.snapshots.iter().rev().enumerate() {
+            self.sums[lag] += snapshot.iter().zip(velocities).map(|(v0, v)| v0 * v).sum::<f64>();
+            self.counts[lag] += 1;
+        }
+    }
+
+    /// Get the number of updates of this accumulator
+    pub fn count(&self) -> usize {
+        self.counts[0]
+    }
+
+    /// Get the velocity autocorrelation function for time lags of 0, 1, 2,
+    /// ... updates. The returned vector only contains the time lags for which
+    /// at least one time origin is available.
+    pub fn values(&self) -> Vec<f64> {
+        let natoms = self.snapshots.front().map_or(0, |snapshot| snapshot.len());
+        if natoms == 0 {
+            return Vec::new();
+        }
+        return self.sums.iter().zip(&self.counts)
+                              .take_while(|&(_, &count)| count > 0)
+                              .map(|(&sum, &count)| sum / (count * natoms) as f64)
+                              .collect();
+    }
+
+    /// Get the self-diffusion coefficient by integrating the velocity
+    /// autocorrelation function with the trapezoidal rule, where `timestep`
+    /// is the time between two updates. The integral only covers the time
+    /// lags available in the window, which should be larger than the
+    /// decorrelation time of the velocities.
+    pub fn diffusion_coefficient(&self, timestep: f64) -> f64 {
+        let values = self.values();
+        let integral = values.windows(2).map(|pair| 0.5 * (pair[0] + pair[1])).sum::<f64>();
+        return integral * timestep / 3.0;
+    }
+}
+
+/// Compute the contribution of each particle to the stress tensor of the
+/// system, using the atomic virial definition.
+///
+/// $$ \underline{\sigma_i} V = m_i \vec v_i \otimes \vec v_i + \underline{W_i} $$
+///
+/// where $m_i$ is the mass of particle $i$, $\vec v_i$ its velocity, and
+/// $\underline{W_i}$ the part of the [`AtomicVirial`] associated with this
+/// particle. The virial of each pair interaction is split evenly between the
+/// two particles, and the virial of bonds, angles and dihedral angles between
+/// all the particles involved. Coulombic and global potentials only provide a
+/// total virial, which is distributed evenly between all the particles.
+///
+/// The returned tensors are not divided by a volume, so that the sum of all
+/// the particles contributions divided by the volume of the system is equal
+/// to the [`Stress`] of the system when using the atomic virial.
+///
+/// [`AtomicVirial`]: struct.AtomicVirial.html
+/// [`Stress`]: struct.Stress.html
+pub struct PerAtomStress;
+impl Compute for PerAtomStress {
+    type Output = Vec<Matrix3>;
+    fn compute(&self, system: &System) -> Vec<Matrix3> {
+        assert!(!system.cell.is_infinite(), "Can not compute stress for infinite cell");
+        let natoms = system.size();
+
+        let mut stress = Vec::with_capacity(natoms);
+        for (&mass, velocity) in soa_zip!(system.particles(), [mass, velocity]) {
+            stress.push(mass * velocity.tensorial(velocity));
+        }
+
+        // Pair potentials contributions
+        for i in 0..natoms {
+            for j in (i + 1)..natoms {
+                let path = system.bond_path(i, j);
+                if let Some(potential) = system.pair_potential(i, j) {
+                    let info = potential.restriction().information(path);
+                    if !info.excluded {
+                        let d = system.nearest_image(i, j);
+                        let w = 0.5 * info.scaling * potential.virial(&d);
+                        stress[i] += w;
+                        stress[j] += w;
+                    }
+                }
+            }
+        }
+
+        // Tail correction for pair potentials contribution
+        let volume = system.cell.volume();
+        let composition = system.composition();
+        for (tensor, &kind_i) in stress.iter_mut().zip(system.particles().kind) {
+            for (kind_j, nj) in composition.all_particles() {
+                if let Some(potential) = system.interactions().pair((kind_i, kind_j)) {
+                    *tensor += 2.0 * PI * (nj as f64) / volume * potential.tail_virial();
+                }
+            }
+        }
+
+        // Bonded potentials contributions
+        for molecule in system.molecules() {
+            for bond in molecule.bonds() {
+                let (i, j) = (bond.i(), bond.j());
+                let r = system.nearest_image(i, j);
+                if let Some(potential) = system.bond_potential(i, j) {
+                    let w = 0.5 * potential.virial(&r);
+                    stress[i] += w;
+                    stress[j] += w;
+                }
+            }
+
+            for angle in molecule.urey_bradleys() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.urey_bradley_potential(i, j, k) {
+                    let w = 0.5 * potential.virial(&system.nearest_image(i, k));
+                    stress[i] += w;
+                    stress[k] += w;
+                }
+            }
+
+            for angle in molecule.angles() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.angle_potential(i, j, k) {
+                    let (theta, d1, _, d3) = system.angle_and_derivatives(i, j, k);
+                    let force = potential.force(theta);
+                    let r_ij = system.nearest_image(i, j);
+                    let r_kj = system.nearest_image(k, j);
+                    let w = (force * d1).tensorial(&r_ij) + (force * d3).tensorial(&r_kj);
+                    let w = w / 3.0;
+                    stress[i] += w;
+                    stress[j] += w;
+                    stress[k] += w;
+                }
+            }
+
+            for dihedral in molecule.dihedrals() {
+                let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+                if let Some(potential) = system.dihedral_potential(i, j, k, m) {
+                    let (phi, d1, _, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
+                    let force = potential.force(phi);
+                    let r_ij = system.nearest_image(i, j);
+                    let r_kj = system.nearest_image(k, j);
+                    let r_mj = system.nearest_image(m, k) + r_kj;
+                    let w = (force * d1).tensorial(&r_ij)
+                          + (force * d3).tensorial(&r_kj)
+                          + (force * d4).tensorial(&r_mj);
+                    let w = w / 4.0;
+                    stress[i] += w;
+                    stress[j] += w;
+                    stress[k] += w;
+                    stress[m] += w;
+                }
+            }
+
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                if let Some(potential) = system.improper_potential(i, j, k, m) {
+                    let (chi, _, d2, d3, d4) = system.out_of_plane_and_derivatives(i, j, k, m);
+                    let force = potential.force(chi);
+                    let w = (force * d2).tensorial(&system.nearest_image(j, i))
+                          + (force * d3).tensorial(&system.nearest_image(k, i))
+                          + (force * d4).tensorial(&system.nearest_image(m, i));
+                    let w = w / 4.0;
+                    stress[i] += w;
+                    stress[j] += w;
+                    stress[k] += w;
+                    stress[m] += w;
+                }
+            }
+        }
+
+        let mut global_virial = Matrix3::zero();
+        if let Some(coulomb) = system.coulomb_potential() {
+            global_virial += coulomb.atomic_virial(system);
+        }
+        for global in system.global_potentials() {
+            global_virial += global.atomic_virial(system);
+        }
+        if natoms != 0 {
+            let w = global_virial / natoms as f64;
+            for tensor in &mut stress {
+                *tensor += w;
+            }
+        }
+
+        return stress;
+    }
+}
+
+/// Compute the Hessian matrix of the potential energy, *i.e.* the second
+/// derivatives of the energy with respect to the positions of the particles.
+///
+/// $$ H_{i\alpha, j\beta} = \frac{\partial^2 U}{\partial r_{i\alpha} \partial r_{j\beta}} $$
+///
+/// The Hessian is returned as a $3N \times 3N$ array, where the row and the
+/// column $3 i + \alpha$ correspond to the coordinate $\alpha$ of particle
+/// $i$. This is mostly useful for normal modes analysis of a configuration at
+/// an energy minimum.
+///
+/// Only pair, bond and Urey-Bradley potentials contribute to the Hessian. The
+/// contributions of angles, dihedral angles, coulombic and global potentials
+/// are ignored.
+pub struct Hessian;
+
+impl Hessian {
+    /// Compute the vibrational frequencies of the normal modes of the
+    /// `system`, from the eigenvalues $\lambda$ of the mass-weighted Hessian
+    /// $H_{i\alpha, j\beta} / \sqrt{m_i m_j}$. The angular frequencies
+    /// $\omega = \sqrt{\lambda}$ are returned in internal units, in
+    /// increasing order. Negative eigenvalues correspond to imaginary
+    /// frequencies, and are returned as $-\sqrt{-\lambda}$.
+    ///
+    /// The configuration should be at a minimum of the potential energy for
+    /// the normal modes to be meaningful.
+    #[cfg(feature = "normal-modes")]
+    pub fn frequencies(&self, system: &System) -> Vec<f64> {
+        let hessian = self.compute(system);
+        let masses = system.particles().mass;
+        let n = 3 * system.size();
+        let weighted = nalgebra::DMatrix::from_fn(n, n, |a, b| {
+            hessian[(a, b)] / f64::sqrt(masses[a / 3] * masses[b / 3])
+        });
+
+        let mut frequencies = weighted.symmetric_eigenvalues()
+            .iter()
+            .map(|&lambda| f64::signum(lambda) * f64::sqrt(f64::abs(lambda)))
+            .collect::<Vec<_>>();
+        frequencies.sort_by(|a, b| a.partial_cmp(b).expect("got a NaN frequency"));
+        return frequencies;
+    }
+}
+
+impl Compute for Hessian {
+    type Output = Array2<f64>;
+    fn compute(&self, system: &System) -> Array2<f64> {
+        let natoms = system.size();
+        let mut hessian = Array2::zeros((3 * natoms, 3 * natoms));
+
+        for i in 0..natoms {
+            for j in (i + 1)..natoms {
+                let path = system.bond_path(i, j);
+                if let Some(potential) = system.pair_potential(i, j) {
+                    let info = potential.restriction().information(path);
+                    if !info.excluded {
+                        let rij = system.nearest_image(i, j);
+                        let r = rij.norm();
+                        let block = distance_hessian(&rij, potential.force(r), potential.force_derivative(r));
+                        add_hessian_block(&mut hessian, i, j, &(block * info.scaling));
+                    }
+                }
+            }
+        }
+
+        for molecule in system.molecules() {
+            for bond in molecule.bonds() {
+                let (i, j) = (bond.i(), bond.j());
+                if let Some(potential) = system.bond_potential(i, j) {
+                    let rij = system.nearest_image(i, j);
+                    let r = rij.norm();
+                    let block = distance_hessian(&rij, potential.force(r), potential.force_derivative(r));
+                    add_hessian_block(&mut hessian, i, j, &block);
+                }
+            }
+
+            for angle in molecule.urey_bradleys() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.urey_bradley_potential(i, j, k) {
+                    let rik = system.nearest_image(i, k);
+                    let r = rik.norm();
+                    let force = potential.force(r);
+                    let block = distance_hessian(&rik, force, potential.force_derivative(r));
+                    add_hessian_block(&mut hessian, i, k, &block);
+                }
+            }
+
+            if !molecule.angles().is_empty() || !molecule.dihedrals().is_empty()
+                || !molecule.impropers().is_empty()
+            {
+                warn_once!("angles and dihedrals are ignored in the Hessian");
+            }
+        }
+
+        if system.coulomb_potential().is_some() || !system.global_potentials().is_empty() {
+            warn_once!("coulombic and global potentials are ignored in the Hessian");
+        }
+
+        return hessian;
+    }
+}
+
+/// Get the second derivatives of a potential $U(r)$ depending on the distance
+/// $r$ between two particles, with respect to the position of one of the
+/// particles; from the vector `rij` between the particles, the `force`
+/// $-U'(r)$ and the `force_derivative` $-U''(r)$.
+///
+/// $$ \underline{B} = U''(r) \hat r \otimes \hat r + \frac{U'(r)}{r} \left(
+///    \underline{I} - \hat r \otimes \hat r \right) $$
+fn distance_hessian(rij: &Vector3D, force: f64, force_derivative: f64) -> Matrix3 {
+    let r = rij.norm();
+    let projector = rij.tensorial(rij) / (r * r);
+    return projector * (-force_derivative) - (Matrix3::one() - projector) * (force / r);
+}
+
+/// Add the `block` of second derivatives for a pair interaction between the
+/// particles `i` and `j` to the `hessian`
+fn add_hessian_block(hessian: &mut Array2<f64>, i: usize, j: usize, block: &Matrix3) {
+    for alpha in 0..3 {
+        for beta in 0..3 {
+            let value = block[alpha][beta];
+            hessian[(3 * i + alpha, 3 * i + beta)] += value;
+            hessian[(3 * j + alpha, 3 * j + beta)] += value;
+            hessian[(3 * i + alpha, 3 * j + beta)] -= value;
+            hessian[(3 * j + alpha, 3 * i + beta)] -= value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::System;
+    use crate::consts::K_BOLTZMANN;
+    use crate::{Harmonic, LennardJones, NullPotential, PairInteraction, PairRestriction, Wolf};
+    use crate::SoftCoreLJ;
+    use crate::{Molecule, Particle, UnitCell};
+    use crate::utils::system_from_xyz;
+    use crate::units;
+
+    use approx::{assert_ulps_eq, assert_relative_eq};
+
+    fn test_pairs_system() -> System {
+        let mut system = system_from_xyz(
+            "2
+            cell: 10.0
+            F 0.0 0.0 0.0 -0.007225222699367925 -0.002405756495275919  0.0026065109398392215
+            F 1.3 0.0 0.0  0.001179633958023287  0.003525262341736351 -0.0004132774783154952
+            ",
+        );
+
+        let mut interaction = PairInteraction::new(
+            Box::new(Harmonic {
+                k: units::from(300.0, "kJ/mol/A^2").unwrap(),
+                x0: units::from(1.2, "A").unwrap(),
+            }),
+            5.0,
+        );
+        interaction.enable_tail_corrections();
+        system.set_pair_potential(("F", "F"), interaction);
+
+        // unused interaction to check that we do handle this right
+        system.set_pair_potential(("H", "O"), PairInteraction::new(Box::new(NullPotential), 0.0));
+
+
+        return system;
+    }
+
+    fn test_molecular_system() -> System {
+        let mut system = system_from_xyz(
+            "4
+            cell: 10.0
+            F 0.0 0.0 0.0
+            F 1.0 0.0 0.0
+            F 1.0 1.0 0.0
+            F 2.0 1.0 0.0
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(1, 2).is_empty());
+        assert!(system.add_bond(2, 3).is_empty());
+        assert_eq!(system.molecules().count(), 1);
+        assert_eq!(system.molecule(0).bonds().len(), 3);
+
+        system.set_pair_potential(("F", "F"), PairInteraction::new(Box::new(NullPotential), 0.0));
+
+        system.set_bond_potential(
+            ("F", "F"),
+            Box::new(Harmonic {
+                k: units::from(100.0, "kJ/mol/A^2").unwrap(),
+                x0: units::from(2.0, "A").unwrap(),
+            }),
+        );
+
+        system.set_angle_potential(
+            ("F", "F", "F"),
+            Box::new(Harmonic {
+                k: units::from(100.0, "kJ/mol/deg^2").unwrap(),
+                x0: units::from(88.0, "deg").unwrap(),
+            }),
+        );
+
+        system.set_dihedral_potential(
+            ("F", "F", "F", "F"),
+            Box::new(Harmonic {
+                k: units::from(100.0, "kJ/mol/deg^2").unwrap(),
+                x0: units::from(185.0, "deg").unwrap(),
+            }),
+        );
+
+        // unused interaction to check that we do handle this right
+        system.set_pair_potential(("H", "O"), PairInteraction::new(Box::new(NullPotential), 0.0));
+
+        return system;
+    }
+
+    #[test]
+    fn forces_pairs() {
+        let system = &test_pairs_system();
+        let res = Forces.compute(system);
+
+        let forces_tot = res[0] + res[1];
+        assert_eq!(forces_tot, Vector3D::zero());
+
+        let force = units::from(30.0, "kJ/mol/A").unwrap();
+        assert_ulps_eq!(res[0][0], force);
+        assert_ulps_eq!(res[0][1], 0.0);
+        assert_ulps_eq!(res[0][1], 0.0);
+
+        assert_ulps_eq!(res[1][0], -force);
+        assert_ulps_eq!(res[1][1], 0.0);
+        assert_ulps_eq!(res[1][1], 0.0);
+    }
+
+    #[test]
+    fn force_molecular() {
+        let system = test_molecular_system();
+        let res = Forces.compute(&system);
+        let forces_tot = res[0] + res[1] + res[2] + res[3];
+        assert_ulps_eq!(forces_tot.norm2(), 0.0);
+    }
+
+    #[test]
+    fn bonded_and_nonbonded_forces() {
+        let system = test_molecular_system();
+        let forces = Forces.compute(&system);
+        let bonded = BondedForces.compute(&system);
+        let nonbonded = NonBondedForces.compute(&system);
+        for i in 0..system.size() {
+            let sum = bonded[i] + nonbonded[i];
+            for k in 0..3 {
+                assert_ulps_eq!(sum[k], forces[i][k], epsilon = 1e-12);
+            }
+        }
+        assert_ulps_eq!((bonded[0] + bonded[1] + bonded[2] + bonded[3]).norm(), 0.0);
+    }
+
+    #[test]
+    fn forces_categories() {
+        let mut system = test_molecular_system();
+        system.particles_mut().charge[0] = 0.5;
+        system.particles_mut().charge[3] = -0.5;
+        system.set_coulomb_potential(Box::new(Wolf::new(4.0)));
+        system.add_global_potential(Box::new(Wolf::new(3.0)));
+
+        let forces = Forces.compute(&system);
+        let pairs = Forces.pair_forces(&system);
+        let bonded = Forces.bonded_forces(&system);
+        let coulomb = Forces.coulomb_forces(&system);
+        let global = Forces.global_forces(&system);
+        assert!(coulomb.iter().any(|force| force.norm() > 0.0));
+        assert!(global.iter().any(|force| force.norm() > 0.0));
+
+        for i in 0..system.size() {
+            let sum = pairs[i] + bonded[i] + coulomb[i] + global[i];
+            for k in 0..3 {
+                assert_ulps_eq!(sum[k], forces[i][k], epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn impropers() {
+        // Planar formaldehyde, with an improper dihedral angle keeping the
+        // oxygen in the plane of the carbon and hydrogens
+        let mut molecule = Molecule::new(Particle::with_position("C", Vector3D::zero()));
+        let oxygen = Particle::with_position("O", Vector3D::new(1.2, 0.0, 0.0));
+        molecule.add_particle_bonded_to(0, oxygen);
+        let hydrogen = Particle::with_position("H", Vector3D::new(-0.6, 0.9, 0.0));
+        molecule.add_particle_bonded_to(0, hydrogen);
+        let hydrogen = Particle::with_position("H", Vector3D::new(-0.6, -0.9, 0.0));
+        molecule.add_particle_bonded_to(0, hydrogen);
+        molecule.add_improper(0, 2, 3, 1);
+
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(molecule);
+        system.set_improper_potential(
+            ("C", "H", "H", "O"),
+            Box::new(Harmonic {
+                k: units::from(100.0, "kJ/mol/deg^2").unwrap(),
+                x0: 0.0,
+            }),
+        );
+
+        // The planar configuration is at the minimum of energy
+        assert_eq!(system.potential_energy(), 0.0);
+        for force in system.forces() {
+            assert_eq!(force, Vector3D::zero());
+        }
+
+        // Moving the oxygen out of the plane increases the energy, and the
+        // forces bring it back in the plane
+        system.particles_mut().position[1][2] = 0.2;
+        let energy = system.potential_energy();
+        assert!(energy > 0.0);
+
+        let chi = system.out_of_plane(0, 2, 3, 1);
+        let k = units::from(100.0, "kJ/mol/deg^2").unwrap();
+        assert_ulps_eq!(energy, 0.5 * k * chi * chi);
+
+        let forces = system.forces();
+        assert!(forces[1][2] < 0.0);
+        let forces_tot = forces[0] + forces[1] + forces[2] + forces[3];
+        assert_relative_eq!(forces_tot.norm() / forces[1].norm(), 0.0, epsilon = 1e-12);
+
+        // Check the force on the oxygen by comparison to finite differences
+        const EPS: f64 = 1e-9;
+        system.particles_mut().position[1][2] += EPS;
+        let finite_differences = -(system.potential_energy() - energy) / EPS;
+        assert_relative_eq!(finite_differences, forces[1][2], max_relative = 1e-5);
+    }
+
+    #[test]
+    fn urey_bradleys() {
+        let mut molecule = Molecule::new(Particle::with_position("O", Vector3D::zero()));
+        let hydrogen = Particle::with_position("H", Vector3D::new(1.0, 0.0, 0.0));
+        molecule.add_particle_bonded_to(0, hydrogen);
+        let hydrogen = Particle::with_position("H", Vector3D::new(0.0, 1.0, 0.0));
+        molecule.add_particle_bonded_to(0, hydrogen);
+        molecule.add_urey_bradley(1, 0, 2);
+
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(molecule);
+        system.set_bond_potential(("O", "H"), Box::new(Harmonic { k: 1.0, x0: 1.0 }));
+        system.set_urey_bradley_potential(("H", "O", "H"), Box::new(Harmonic { k: 1.0, x0: 1.6 }));
+
+        // The 1-3 term contributes to the energy
+        let expected = 0.5 * (1.6 - f64::sqrt(2.0)) * (1.6 - f64::sqrt(2.0));
+        assert_ulps_eq!(system.potential_energy(), expected);
+        let forces = system.forces();
+        assert_ulps_eq!((forces[0] + forces[1] + forces[2]).norm(), 0.0);
+
+        // Without any angle potential, relaxing the system brings the
+        // hydrogen atoms at the Urey-Bradley equilibrium distance
+        for _ in 0..1000 {
+            let forces = system.forces();
+            for (position, force) in system.particles_mut().position.iter_mut().zip(&forces) {
+                position.add_scaled(0.1, force);
+            }
+        }
+
+        assert_relative_eq!(system.distance(1, 2), 1.6, epsilon = 1e-6);
+        assert_relative_eq!(system.distance(0, 1), 1.0, epsilon = 1e-6);
+        assert_relative_eq!(system.distance(0, 2), 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn forces_thread_count() {
+        let mut system = System::fcc("Ar", 5.26, 3);
+        for (i, position) in system.particles_mut().position.iter_mut().enumerate() {
+            let i = i as f64;
+            *position += Vector3D::new(f64::sin(i), f64::cos(2.0 * i), f64::sin(3.0 * i)) * 0.3;
+        }
+        let lj = LennardJones {
+            sigma: units::from(3.405, "A").unwrap(),
+            epsilon: units::from(1.0, "kJ/mol").unwrap(),
+        };
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 7.5));
+
+        let compute_with_threads = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| Forces.compute(&system))
+        };
+
+        // Forces computed in parallel must match the serial reference, up to
+        // the order of the floating point additions
+        let serial = compute_with_threads(1);
+        let parallel = compute_with_threads(4);
+        let scale = serial.iter().map(|force| force.norm()).fold(0.0, f64::max);
+        for (serial, parallel) in serial.iter().zip(&parallel) {
+            for k in 0..3 {
+                assert_ulps_eq!(serial[k], parallel[k], epsilon = 1e-12 * scale);
+            }
+        }
+    }
+
+    #[test]
+    fn reductions_thread_count() {
+        let mut system = System::fcc("Ar", 5.26, 3);
+        for (i, position) in system.particles_mut().position.iter_mut().enumerate() {
+            let i = i as f64;
+            *position += Vector3D::new(f64::sin(i), f64::cos(2.0 * i), f64::sin(3.0 * i)) * 0.3;
+        }
+        let lj = LennardJones {
+            sigma: units::from(3.405, "A").unwrap(),
+            epsilon: units::from(1.0, "kJ/mol").unwrap(),
+        };
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 7.5));
+
+        let compute_with_threads = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| (
+                PotentialEnergy.compute(&system),
+                AtomicVirial.compute(&system),
+                Forces.compute(&system),
+            ))
+        };
+
+        // Pair energy, virial and forces are reduced in a fixed order, and do
+        // not depend on the number of threads
+        let (serial_energy, serial_virial, serial_forces) = compute_with_threads(1);
+        let (parallel_energy, parallel_virial, parallel_forces) = compute_with_threads(8);
+        assert_eq!(serial_energy.to_bits(), parallel_energy.to_bits());
+        assert_eq!(serial_virial, parallel_virial);
+        assert_eq!(serial_forces, parallel_forces);
+    }
+
+    #[test]
+    fn energy_pairs() {
+        let system = &test_pairs_system();
+        let kinetic = KineticEnergy.compute(system);
+        let potential = PotentialEnergy.compute(system);
+        let total = TotalEnergy.compute(system);
+
+        assert_eq!(kinetic + potential, total);
+        assert_ulps_eq!(kinetic, 0.0007483016557453698);
+
+        assert_eq!(kinetic, system.kinetic_energy());
+        assert_eq!(potential, system.potential_energy());
+        assert_eq!(total, system.total_energy());
+    }
+
+    #[test]
+    fn energy_molecular() {
+        let system = test_molecular_system();
+        assert_ulps_eq!(PotentialEnergy.compute(&system), units::from(1800.0, "kJ/mol").unwrap());
+    }
+
+    #[test]
+    fn lambda_derivative() {
+        let system_at = |lambda| {
+            let mut system = test_molecular_system();
+            let mut interaction = PairInteraction::shifted(
+                Box::new(SoftCoreLJ {
+                    sigma: 1.2,
+                    epsilon: units::from(0.5, "kJ/mol").unwrap(),
+                    lambda: lambda,
+                    alpha: 0.5,
+                }),
+                4.0,
+            );
+            interaction.set_restriction(PairRestriction::Scale14(0.5));
+            system.set_pair_potential(("F", "F"), interaction);
+            return system;
+        };
+
+        let lambda = 0.6;
+        let h = 1e-6;
+        let forward = system_at(lambda + h).energy_evaluator().pairs();
+        let backward = system_at(lambda - h).energy_evaluator().pairs();
+        let expected = (forward - backward) / (2.0 * h);
+
+        let derivative = LambdaDerivative.compute(&system_at(lambda));
+        assert!(derivative.abs() > 1e-6);
+        assert_relative_eq!(derivative, expected, max_relative = 1e-6);
+
+        // potentials without coupling parameter do not contribute
+        assert_eq!(LambdaDerivative.compute(&test_pairs_system()), 0.0);
+    }
+
+    #[test]
+    fn temperature() {
+        let system = &test_pairs_system();
+        let temperature = Temperature.compute(system);
+        assert_ulps_eq!(temperature, 300.0);
+        assert_eq!(temperature, system.temperature());
+    }
+
+    #[test]
+    fn group_temperature() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        for i in 0..2 {
+            let mut particle = Particle::with_position("Ar", Vector3D::new(i as f64, 0.0, 0.0));
+            particle.velocity = Vector3D::new(0.01, 0.0, 0.0);
+            system.add_molecule(Molecule::new(particle));
+        }
+        for i in 0..3 {
+            let mut particle = Particle::with_position("He", Vector3D::new(i as f64, 2.0, 0.0));
+            particle.velocity = Vector3D::new(0.0, 0.02, 0.02);
+            system.add_molecule(Molecule::new(particle));
+        }
+        system.define_group("argon", &[0, 1]);
+        system.define_group("helium", &[2, 3, 4]);
+
+        let argon = GroupKineticEnergy { group: "argon".into() }.compute(&system);
+        let helium = GroupKineticEnergy { group: "helium".into() }.compute(&system);
+        let mass_ar = system.particles().mass[0];
+        let mass_he = system.particles().mass[2];
+        assert_ulps_eq!(argon, 2.0 * 0.5 * mass_ar * 0.01 * 0.01);
+        assert_ulps_eq!(helium, 3.0 * 0.5 * mass_he * 2.0 * 0.02 * 0.02);
+        assert_ulps_eq!(argon + helium, KineticEnergy.compute(&system));
+
+        let temperature = GroupTemperature { group: "argon".into() }.compute(&system);
+        assert_ulps_eq!(temperature, 2.0 * argon / (6.0 * K_BOLTZMANN));
+        let temperature = GroupTemperature { group: "helium".into() }.compute(&system);
+        assert_ulps_eq!(temperature, 2.0 * helium / (9.0 * K_BOLTZMANN));
+    }
+
+    #[test]
+    fn group_temperature_molecules() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        for i in 0..2 {
+            let position = Vector3D::new(i as f64 * 3.0, 0.0, 0.0);
+            let mut molecule = Molecule::new(Particle::with_position("N", position));
+            let position = Vector3D::new(i as f64 * 3.0 + 1.1, 0.0, 0.0);
+            molecule.add_particle_bonded_to(0, Particle::with_position("N", position));
+            system.add_molecule(molecule);
+        }
+        for (i, velocity) in system.particles_mut().velocity.iter_mut().enumerate() {
+            *velocity = Vector3D::new(0.01 * i as f64, 0.0, 0.02);
+        }
+        system.simulated_degrees_of_freedom = DegreesOfFreedom::Molecules;
+
+        // The first molecule is in the group, but not the second one
+        system.define_group("nitrogen", &[0, 1, 2]);
+        let velocities = system.particles().velocity;
+        let mass = system.particles().mass[0];
+        let kinetic = 0.5 * mass * (velocities[0].norm2() + velocities[1].norm2());
+        let temperature = GroupTemperature { group: "nitrogen".into() }.compute(&system);
+        assert_ulps_eq!(temperature, 2.0 * kinetic / (3.0 * K_BOLTZMANN));
+    }
+
+    #[test]
+    #[should_panic(expected = "the 'partial' group does not have any degree of freedom")]
+    fn group_temperature_partial_molecule() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        let mut molecule = Molecule::new(Particle::new("N"));
+        let position = Vector3D::new(1.1, 0.0, 0.0);
+        molecule.add_particle_bonded_to(0, Particle::with_position("N", position));
+        system.add_molecule(molecule);
+        system.simulated_degrees_of_freedom = DegreesOfFreedom::Molecules;
+
+        system.define_group("partial", &[0]);
+        let _ = GroupTemperature { group: "partial".into() }.compute(&system);
+    }
+
+    #[test]
+    #[should_panic(expected = "the 'empty' group does not have any degree of freedom")]
+    fn empty_group_temperature() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        system.define_group("empty", &[]);
+        let _ = GroupTemperature { group: "empty".into() }.compute(&system);
+    }
+
+    #[test]
+    #[should_panic(expected = "no group named 'wall' in this system")]
+    fn undefined_group_temperature() {
+        let system = System::with_cell(UnitCell::cubic(10.0));
+        let _ = GroupTemperature { group: "wall".into() }.compute(&system);
+    }
+
+    #[test]
+    fn volume() {
+        let system = &test_pairs_system();
+        let volume = Volume.compute(system);
+        assert_eq!(volume, 1000.0);
+        assert_eq!(volume, system.volume());
+    }
+
+    #[test]
+    #[should_panic]
+    fn virial_infinite_cell() {
+        let _ = Virial.compute(&System::new());
+    }
+
+    #[test]
+    fn virial_pairs() {
+        let system = &test_pairs_system();
+        let virial = Virial.compute(system);
+
+        let mut expected = Matrix3::zero();
+        let force = units::from(30.0, "kJ/mol/A^2").unwrap();
+        expected[0][0] = -force * 1.3;
+
+        assert_ulps_eq!(virial, expected);
+        assert_eq!(virial, system.virial());
+    }
+
+    #[test]
+    fn virial_molecular() {
+        let system = &test_molecular_system();
+        let virial = Virial.compute(system);
+
+        // Only the bonds contribute to the diagonal of the virial
+        let w = units::from(100.0, "kJ/mol/A").unwrap();
+        assert_ulps_eq!(virial[0][0], 2.0 * w, epsilon = 1e-12);
+        assert_ulps_eq!(virial[1][1], 1.0 * w, epsilon = 1e-12);
+        assert_ulps_eq!(virial[2][2], 0.0, epsilon = 1e-12);
+        assert_ulps_eq!(virial.trace(), 3.0 * w, epsilon = 1e-12);
+
+        // The virial is symmetric
+        assert_ulps_eq!(virial, virial.transposed(), epsilon = 1e-12);
+        assert_eq!(virial, system.virial());
+    }
+
+    #[test]
+    fn virial_molecular_finite_differences() {
+        fn scaled(system: &System, i: usize, j: usize, eps: f64) -> System {
+            let mut scaling = Matrix3::one();
+            scaling[i][j] += eps;
+            let mut system = system.clone();
+            let old_cell = system.cell;
+            let new_cell = system.cell.scale(scaling);
+            for position in system.particles_mut().position {
+                *position = new_cell.cartesian(&old_cell.fractional(position));
+            }
+            system.cell = new_cell;
+            return system;
+        }
+
+        let mut system = test_molecular_system();
+        // Move the last particle out of plane to have a dihedral angle far
+        // from 180°
+        system.particles_mut().position[3] = Vector3D::new(2.0, 1.0, 0.7);
+        let virial = AtomicVirial.compute(&system);
+
+        let eps = 1e-6;
+        let mut finite_diff = Matrix3::zero();
+        for i in 0..3 {
+            for j in 0..3 {
+                let energy_plus = PotentialEnergy.compute(&scaled(&system, i, j, eps));
+                let energy_minus = PotentialEnergy.compute(&scaled(&system, i, j, -eps));
+                finite_diff[i][j] = -(energy_plus - energy_minus) / (2.0 * eps);
+            }
+        }
+        assert_relative_eq!(virial, finite_diff, epsilon = 1e-6);
+
+        // Only the bonds contribute to the trace of the virial
+        let k = units::from(100.0, "kJ/mol/A^2").unwrap();
+        let r = f64::sqrt(1.49);
+        let expected = -k * (2.0 * (1.0 - 2.0) + (r - 2.0) * r);
+        assert_relative_eq!(virial.trace(), expected, epsilon = 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pressure_at_temperature_negative_temperature() {
+        let system = &test_pairs_system();
+        let pressure = PressureAtTemperature { temperature: -4.0 };
+        let _ = pressure.compute(system);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pressure_at_temperature_infinite_cell() {
+        let pressure = PressureAtTemperature { temperature: -4.0 };
+        let _ = pressure.compute(&System::new());
+    }
+
+    #[test]
+    fn pressure_at_temperature() {
+        let system = &mut test_pairs_system();
+
+        let temperature = 550.0;
+        let force = units::from(30.0, "kJ/mol/A").unwrap();
+        let virial = -force * 1.3;
+        let natoms = 2.0;
+        let volume = 1000.0;
+
+        // Direct computation
+        let expected = natoms * K_BOLTZMANN * temperature / volume + virial / (3.0 * volume);
+        let pressure = PressureAtTemperature {
+            temperature: temperature,
+        };
+        let pressure = pressure.compute(system);
+        assert_ulps_eq!(pressure, expected);
+
+        // Computation with the real system temperature
+        let pressure = PressureAtTemperature {
+            temperature: system.temperature(),
+        };
+        let pressure = pressure.compute(system);
+        assert_eq!(pressure, system.pressure());
+
+        // Computation with an external temperature for the system
+        let pressure = PressureAtTemperature {
+            temperature: temperature,
+        };
+        let pressure = pressure.compute(system);
+        system.simulated_temperature(Some(temperature));
+        assert_eq!(pressure, system.pressure());
+    }
+
+    #[test]
+    #[should_panic]
+    fn stress_at_temperature_negative_temperature() {
+        let system = &test_pairs_system();
+        let stress = StressAtTemperature { temperature: -4.0 };
+        let _ = stress.compute(system);
+    }
+
+    #[test]
+    #[should_panic]
+    fn stress_at_temperature_infinite_cell() {
+        let stress = StressAtTemperature { temperature: 300.0 };
+        let _ = stress.compute(&System::new());
+    }
+
+    #[test]
+    fn stress_at_temperature() {
+        let system = &mut test_pairs_system();
+        let temperature = 550.0;
+        let stress = StressAtTemperature {
+            temperature: temperature,
+        };
+        let stress = stress.compute(system);
+        let pressure = PressureAtTemperature {
+            temperature: temperature,
+        };
+        let pressure = pressure.compute(system);
+
+        // tail corrections are smaller than 1e-9
+        let trace = (stress[0][0] + stress[1][1] + stress[2][2]) / 3.0;
+        assert_ulps_eq!(trace, pressure);
+
+        system.simulated_temperature(Some(temperature));
+        assert_eq!(stress, system.stress());
+    }
+
+    #[test]
+    #[should_panic]
+    fn stress_infinite_cell() {
+        let _ = Stress.compute(&System::new());
+    }
+
+    #[test]
+    fn stress() {
+        let system = &test_pairs_system();
+        let stress = Stress.compute(system);
+        let pressure = Pressure.compute(system);
+
+        let trace = stress.trace() / 3.0;
+        assert_ulps_eq!(trace, pressure);
+        assert_eq!(stress, system.stress());
+
+        // The kinetic contribution is anisotropic
+        let volume = system.volume();
+        let kinetic = stress - system.virial() / volume;
+        let velocities = system.particles().velocity;
+        let masses = system.particles().mass;
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = (masses[0] * velocities[0][i] * velocities[0][j]
+                    + masses[1] * velocities[1][i] * velocities[1][j]) / volume;
+                assert_relative_eq!(kinetic[i][j], expected, max_relative = 1e-9);
+            }
+        }
+        assert!(kinetic[0][0] > 5.0 * kinetic[2][2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pressure_infinite_cell() {
+        let _ = Pressure.compute(&System::new());
+    }
+
+    #[test]
+    fn pressure() {
+        let system = &test_pairs_system();
+        let pressure = Pressure.compute(system);
+
+        let force = units::from(30.0, "kJ/mol/A").unwrap();
+        let virial = -force * 1.3;
+        let natoms = 2.0;
+        let temperature = 300.0;
+        let volume = 1000.0;
+        let expected = natoms * K_BOLTZMANN * temperature / volume + virial / (3.0 * volume);
+
+        assert_ulps_eq!(pressure, expected);
+        assert_eq!(pressure, system.pressure());
+    }
+
+    #[test]
+    #[should_panic]
+    fn molecular_pressure_infinite_cell() {
+        let _ = MolecularPressure.compute(&System::new());
+    }
+
+    #[test]
+    fn molecular_pressure() {
+        // Two rigid dimers along x, the first one rotating around its center
+        // of mass and the second one translating along x
+        let mut system = system_from_xyz(
+            "4
+            cell: 10.0
+            F 0.0 0.0 0.0 0.0  0.01 0.0
+            F 1.0 0.0 0.0 0.0 -0.01 0.0
+            F 0.0 3.0 0.0 0.01 0.0  0.0
+            F 1.0 3.0 0.0 0.01 0.0  0.0
             ",
         );
         assert!(system.add_bond(0, 1).is_empty());
-        assert!(system.add_bond(1, 2).is_empty());
         assert!(system.add_bond(2, 3).is_empty());
-        assert_eq!(system.molecules().count(), 1);
-        assert_eq!(system.molecule(0).bonds().len(), 3);
+        assert_eq!(system.molecules().count(), 2);
+
+        let k = units::from(100.0, "kJ/mol/A^2").unwrap();
+        let mut interaction = PairInteraction::new(Box::new(Harmonic { k: k, x0: 2.5 }), 5.0);
+        interaction.set_restriction(PairRestriction::InterMolecular);
+        system.set_pair_potential(("F", "F"), interaction);
+
+        let mass = system.particles().mass[0];
+        let volume = 1000.0;
+        // Virial of the two pairs of aligned atoms, at distance 3, and of the
+        // two pairs of atoms at distance sqrt(10)
+        let aligned = -2.0 * 3.0 * k * (3.0 - 2.5);
+        let r = f64::sqrt(10.0);
+        let crossed = -2.0 * r * k * (r - 2.5);
+
+        // All the velocities contribute to the atomic pressure
+        let kinetic = 4.0 * mass * 0.01 * 0.01;
+        let expected = (kinetic + aligned + crossed) / (3.0 * volume);
+        assert_ulps_eq!(Pressure.compute(&system), expected, epsilon = 1e-15);
+
+        // Only the translation of the second molecule contributes to the
+        // molecular pressure, and only the part of the crossed pairs forces
+        // along the line between the centers of mass contributes to the
+        // molecular virial.
+        let kinetic = 2.0 * mass * 0.01 * 0.01;
+        let expected = (kinetic + aligned + 9.0 / 10.0 * crossed) / (3.0 * volume);
+        let pressure = MolecularPressure.compute(&system);
+        assert_ulps_eq!(pressure, expected, epsilon = 1e-15);
+
+        // This is the same as the pressure when simulating the molecules
+        // degrees of freedom, if the molecules only translate
+        system.particles_mut().velocity[0] = Vector3D::zero();
+        system.particles_mut().velocity[1] = Vector3D::zero();
+        system.simulated_degrees_of_freedom = DegreesOfFreedom::Molecules;
+        assert_ulps_eq!(MolecularPressure.compute(&system), pressure, epsilon = 1e-15);
+        assert_ulps_eq!(Pressure.compute(&system), pressure, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn lennard_jones_tail_corrections() {
+        // particles are further apart than the cutoff, so only the tail
+        // corrections contribute to the energy and the virial
+        let mut system = system_from_xyz(
+            "2
+            cell: 20.0
+            Ar 0.0 0.0 0.0
+            Ar 10.0 0.0 0.0
+            ",
+        );
+        let sigma = units::from(3.405, "A").unwrap();
+        let epsilon = units::from(1.0, "kJ/mol").unwrap();
+        let cutoff = units::from(8.5, "A").unwrap();
+
+        let lj = LennardJones { sigma: sigma, epsilon: epsilon };
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), cutoff));
+        assert_eq!(PotentialEnergy.compute(&system), 0.0);
+        assert_eq!(Virial.compute(&system), Matrix3::zero());
+
+        let mut interaction = PairInteraction::new(Box::new(lj), cutoff);
+        interaction.enable_tail_corrections();
+        system.set_pair_potential(("Ar", "Ar"), interaction);
+
+        // Closed form expressions for the Lennard-Jones tail corrections
+        let natoms = 2.0;
+        let density = natoms / system.volume();
+        let sigma3 = f64::powi(sigma, 3);
+        let s3 = f64::powi(sigma / cutoff, 3);
+        let s9 = f64::powi(sigma / cutoff, 9);
+        let energy = 8.0 / 3.0 * PI * natoms * density * epsilon * sigma3 * (s9 / 3.0 - s3);
+        let pressure = 16.0 / 3.0 * PI * density * density * epsilon * sigma3 * (2.0 / 3.0 * s9 - s3);
+
+        assert!(energy < 0.0);
+        assert_relative_eq!(PotentialEnergy.compute(&system), energy, max_relative = 1e-12);
+        let at_zero = PressureAtTemperature { temperature: 0.0 };
+        assert_relative_eq!(at_zero.compute(&system), pressure, max_relative = 1e-12);
+    }
+
+    fn simple_cubic_system() -> System {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let name = if (i + j + k) % 2 == 0 { "Ar" } else { "Kr" };
+                    let position = 2.5 * Vector3D::new(i as f64, j as f64, k as f64);
+                    system.add_molecule(Molecule::new(Particle::with_position(name, position)));
+                }
+            }
+        }
+        return system;
+    }
+
+    #[test]
+    fn radial_distribution() {
+        let system = simple_cubic_system();
+        let rdf = RadialDistribution {
+            width: 0.2,
+            max: 4.9,
+            pair: None,
+        };
+        let gr = rdf.compute(&system);
+        assert_eq!(gr.len(), 25);
+
+        // The first peak is at the lattice spacing, in the bin [2.4, 2.6)
+        for value in &gr[..12] {
+            assert_eq!(*value, 0.0);
+        }
+        for value in &gr[13..17] {
+            assert_eq!(*value, 0.0);
+        }
+
+        // 64 atoms with 6 first neighbors each
+        let shell = 4.0 / 3.0 * PI * (2.6 * 2.6 * 2.6 - 2.4 * 2.4 * 2.4);
+        let expected = 1000.0 * (64.0 * 6.0 / 2.0) / (64.0 * 63.0 / 2.0) / shell;
+        assert_ulps_eq!(gr[12], expected, epsilon = 1e-12);
+        assert!(gr[12] > gr[17]);
+    }
+
+    #[test]
+    fn radial_distribution_pairs() {
+        let system = simple_cubic_system();
+        // All first neighbors are between Ar and Kr
+        let rdf = RadialDistribution {
+            width: 0.2,
+            max: 4.9,
+            pair: Some((String::from("Ar"), String::from("Ar"))),
+        };
+        let gr = rdf.compute(&system);
+        assert_eq!(gr[12], 0.0);
+
+        let rdf = RadialDistribution {
+            width: 0.2,
+            max: 4.9,
+            pair: Some((String::from("Kr"), String::from("Ar"))),
+        };
+        let gr = rdf.compute(&system);
+        let shell = 4.0 / 3.0 * PI * (2.6 * 2.6 * 2.6 - 2.4 * 2.4 * 2.4);
+        let expected = 1000.0 * (64.0 * 6.0 / 2.0) / (32.0 * 32.0) / shell;
+        assert_ulps_eq!(gr[12], expected, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn mean_squared_displacement() {
+        let mut system = system_from_xyz(
+            "1
+            cell: 10.0
+            Ar 1.0 2.0 3.0
+            ",
+        );
+        let velocity = Vector3D::new(0.7, -0.3, 0.4);
+        let mut msd = MeanSquaredDisplacement::new(&system);
+        assert_eq!(msd.compute(&system), 0.0);
+
+        let mut times = Vec::new();
+        let mut values = Vec::new();
+        for step in 1..100 {
+            let mut position = system.particles().position[0] + velocity;
+            system.cell.wrap_vector(&mut position);
+            system.particles_mut().position[0] = position;
+
+            let value = msd.compute(&system);
+            let t = step as f64;
+            assert_ulps_eq!(value, velocity.norm2() * t * t, epsilon = 1e-6);
+            msd.update(&system);
+            assert_eq!(msd.compute(&system), value);
+
+            times.push(t);
+            values.push(value);
+        }
+
+        // For ballistic motion, MSD = v^2 t^2 and the least-squares slope over
+        // evenly spaced times is 2 v^2 <t>, with <t> = 50 here.
+        let diffusion = MeanSquaredDisplacement::diffusion_coefficient(&times, &values);
+        assert_relative_eq!(diffusion, 100.0 * velocity.norm2() / 6.0, max_relative = 1e-9);
+
+        // Diffusive motion, with MSD = 6 D t
+        let values = times.iter().map(|t| 6.0 * 0.25 * t).collect::<Vec<_>>();
+        let diffusion = MeanSquaredDisplacement::diffusion_coefficient(&times, &values);
+        assert_ulps_eq!(diffusion, 0.25, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn density_profile() {
+        // Uniform simple cubic lattice
+        let mut system = System::with_cell(UnitCell::cubic(12.0));
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let index = Vector3D::new(i as f64, j as f64, k as f64);
+                    let position = (index + Vector3D::new(0.5, 0.5, 0.5)) * 3.0;
+                    system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+                }
+            }
+        }
+
+        let profile = DensityProfile {
+            axis: 2,
+            nbins: 4,
+            name: None,
+            mass: false,
+        };
+        let density = profile.compute(&system);
+        assert_eq!(density.len(), 4);
+        for &value in &density {
+            assert_ulps_eq!(value, 64.0 / (12.0 * 12.0 * 12.0));
+        }
+
+        let mass = system.particles().mass[0];
+        let density = DensityProfile { mass: true, ..profile }.compute(&system);
+        for &value in &density {
+            assert_ulps_eq!(value, mass * 64.0 / (12.0 * 12.0 * 12.0));
+        }
+    }
+
+    #[test]
+    fn density_profile_layers() {
+        // Two layers of different particles along the y axis, one of them
+        // crossing the periodic boundaries
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 20.0, 10.0));
+        for i in 0..3 {
+            let x = i as f64;
+            for &(name, y) in &[("Na", 3.0), ("Na", 4.5), ("Cl", 13.0), ("Cl", -6.0)] {
+                let position = Vector3D::new(x, y, 0.0);
+                system.add_molecule(Molecule::new(Particle::with_position(name, position)));
+            }
+        }
+
+        let slab_volume = 10.0 * 10.0 * 5.0;
+        let profile = DensityProfile {
+            axis: 1,
+            nbins: 4,
+            name: Some("Na".into()),
+            mass: false,
+        };
+        let density = profile.compute(&system);
+        assert_eq!(density, [6.0 / slab_volume, 0.0, 0.0, 0.0]);
+
+        let profile = DensityProfile {
+            axis: 1,
+            nbins: 4,
+            name: Some("Cl".into()),
+            mass: false,
+        };
+        let density = profile.compute(&system);
+        assert_eq!(density, [0.0, 0.0, 6.0 / slab_volume, 0.0]);
+
+        let profile = DensityProfile {
+            axis: 1,
+            nbins: 4,
+            name: None,
+            mass: false,
+        };
+        let density = profile.compute(&system);
+        assert_eq!(density, [6.0 / slab_volume, 0.0, 6.0 / slab_volume, 0.0]);
+    }
+
+    #[test]
+    fn structure_factor() {
+        // Simple cubic crystal with a lattice parameter of 3 A
+        let mut system = System::with_cell(UnitCell::cubic(12.0));
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let position = Vector3D::new(i as f64, j as f64, k as f64) * 3.0;
+                    system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+                }
+            }
+        }
+
+        let k = 2.0 * PI / 12.0;
+        let structure_factor = StructureFactor {
+            magnitudes: vec![k, 4.0 * k, f64::sqrt(32.0) * k, 6.0 * k, 0.5 * k],
+            tolerance: 1e-6,
+        };
+        let values = structure_factor.compute(&system);
+        assert_eq!(values.len(), 5);
+
+        // Bragg peaks for the (100) and (110) reflections
+        assert_relative_eq!(values[1], 64.0, max_relative = 1e-9);
+        assert_relative_eq!(values[2], 64.0, max_relative = 1e-9);
+
+        // Destructive interferences between the planes of the crystal
+        assert_relative_eq!(values[0], 0.0, epsilon = 1e-9);
+        assert_relative_eq!(values[3], 0.0, epsilon = 1e-9);
+
+        // There is no reciprocal lattice vector with this norm
+        assert_eq!(values[4], 0.0);
+    }
+
+    #[test]
+    fn coordination_number() {
+        let system = System::fcc("Ar", 4.0, 3);
+        let coordination = CoordinationNumber { cutoff: 3.4 }.compute(&system);
+        assert!(coordination.iter().all(|&n| n == 12));
+
+        let coordination = CoordinationNumber { cutoff: 4.2 }.compute(&system);
+        assert!(coordination.iter().all(|&n| n == 18));
+
+        let system = System::bcc("Fe", 3.0, 3);
+        let coordination = CoordinationNumber { cutoff: 2.8 }.compute(&system);
+        assert!(coordination.iter().all(|&n| n == 8));
+    }
+
+    #[test]
+    fn legendre_polynomials() {
+        for &x in &[-1.0, -0.3, 0.0, 0.5, 1.0] {
+            assert_ulps_eq!(legendre(0, x), 1.0);
+            assert_ulps_eq!(legendre(1, x), x);
+            assert_ulps_eq!(legendre(2, x), 0.5 * (3.0 * x * x - 1.0), epsilon = 1e-15);
+            let x3 = x * x * x;
+            assert_ulps_eq!(legendre(3, x), 0.5 * (5.0 * x3 - 3.0 * x), epsilon = 1e-15);
+        }
+        assert_ulps_eq!(legendre(6, 1.0), 1.0, epsilon = 1e-15);
+        assert_ulps_eq!(legendre(6, -1.0), 1.0, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn steinhardt_order() {
+        let system = System::fcc("Ar", 4.0, 3);
+        let q4 = SteinhardtOrder { l: 4, cutoff: 3.4 }.compute(&system);
+        let q6 = SteinhardtOrder { l: 6, cutoff: 3.4 }.compute(&system);
+        assert_eq!(q6.len(), system.size());
+        for i in 0..system.size() {
+            assert_relative_eq!(q4[i], 0.190_941, epsilon = 1e-6);
+            assert_relative_eq!(q6[i], 0.574_524, epsilon = 1e-6);
+        }
+
+        let system = System::bcc("Fe", 3.0, 3);
+        let q4 = SteinhardtOrder { l: 4, cutoff: 2.8 }.compute(&system);
+        let q6 = SteinhardtOrder { l: 6, cutoff: 2.8 }.compute(&system);
+        for i in 0..system.size() {
+            assert_relative_eq!(q4[i], 0.509_175, epsilon = 1e-6);
+            assert_relative_eq!(q6[i], 0.628_539, epsilon = 1e-6);
+        }
+
+        // Isolated particles have no order
+        let q6 = SteinhardtOrder { l: 6, cutoff: 1.0 }.compute(&system);
+        assert!(q6.iter().all(|&q| q == 0.0));
+    }
+
+    #[test]
+    fn common_neighbor_analysis() {
+        let system = System::fcc("Ar", 4.0, 3);
+        let structures = CommonNeighborAnalysis { cutoff: 3.4 }.compute(&system);
+        assert_eq!(structures.len(), system.size());
+        assert!(structures.iter().all(|&s| s == CrystalStructure::Fcc));
+
+        let system = System::bcc("Fe", 3.0, 4);
+        let structures = CommonNeighborAnalysis { cutoff: 3.6 }.compute(&system);
+        assert!(structures.iter().all(|&s| s == CrystalStructure::Bcc));
+
+        // With a cutoff excluding the second neighbors shell, BCC is not
+        // recognized
+        let structures = CommonNeighborAnalysis { cutoff: 2.8 }.compute(&system);
+        assert!(structures.iter().all(|&s| s == CrystalStructure::Other));
+    }
+
+    #[test]
+    fn common_neighbor_analysis_hcp() {
+        // Orthorhombic cell for the HCP lattice, containing four particles
+        let a = 3.0;
+        let cell = Vector3D::new(a, f64::sqrt(3.0) * a, f64::sqrt(8.0 / 3.0) * a);
+        let basis = [
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(0.5, 0.5, 0.0),
+            Vector3D::new(0.5, 5.0 / 6.0, 0.5),
+            Vector3D::new(0.0, 1.0 / 3.0, 0.5),
+        ];
+
+        let size = (4.0 * cell[0], 2.0 * cell[1], 2.0 * cell[2]);
+        let mut system = System::with_cell(UnitCell::ortho(size.0, size.1, size.2));
+        for i in 0..4 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    for site in &basis {
+                        let fractional = Vector3D::new(i as f64, j as f64, k as f64) + site;
+                        let position = Vector3D::new(
+                            fractional[0] * cell[0],
+                            fractional[1] * cell[1],
+                            fractional[2] * cell[2],
+                        );
+                        system.add_molecule(Molecule::new(Particle::with_position("Ti", position)));
+                    }
+                }
+            }
+        }
+
+        let structures = CommonNeighborAnalysis { cutoff: 3.6 }.compute(&system);
+        assert_eq!(structures.len(), 64);
+        assert!(structures.iter().all(|&s| s == CrystalStructure::Hcp));
+    }
+
+    #[test]
+    fn common_neighbor_analysis_icosahedral() {
+        // 13 particles icosahedral cluster
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::zero())));
+
+        let phi = (1.0 + f64::sqrt(5.0)) / 2.0;
+        let norm = f64::sqrt(1.0 + phi * phi);
+        for &s1 in &[-1.0, 1.0] {
+            for &s2 in &[-1.0, 1.0] {
+                for &vertex in &[
+                    Vector3D::new(0.0, s1, s2 * phi),
+                    Vector3D::new(s1, s2 * phi, 0.0),
+                    Vector3D::new(s2 * phi, 0.0, s1),
+                ] {
+                    let particle = Particle::with_position("Ar", vertex / norm);
+                    system.add_molecule(Molecule::new(particle));
+                }
+            }
+        }
+
+        let structures = CommonNeighborAnalysis { cutoff: 1.3 }.compute(&system);
+        assert_eq!(structures[0], CrystalStructure::Icosahedral);
+        // Particles at the surface only have 6 neighbors
+        assert!(structures[1..].iter().all(|&s| s == CrystalStructure::Other));
+    }
+
+    #[test]
+    fn voronoi_volume() {
+        // Simple cubic lattice
+        let mut system = System::with_cell(UnitCell::cubic(9.0));
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    let position = Vector3D::new(i as f64, j as f64, k as f64) * 3.0;
+                    system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+                }
+            }
+        }
+        let volumes = VoronoiVolume.compute(&system);
+        assert_eq!(volumes.len(), 27);
+        for &volume in &volumes {
+            assert_relative_eq!(volume, 27.0, max_relative = 1e-9);
+        }
+
+        // FCC lattice
+        let system = System::fcc("Ar", 4.0, 2);
+        for volume in VoronoiVolume.compute(&system) {
+            assert_relative_eq!(volume, 16.0, max_relative = 1e-9);
+        }
+
+        // BCC lattice
+        let system = System::bcc("Fe", 3.0, 2);
+        for volume in VoronoiVolume.compute(&system) {
+            assert_relative_eq!(volume, 13.5, max_relative = 1e-9);
+        }
+    }
+
+    #[test]
+    fn voronoi_volume_sum() {
+        // Disordered particles in a triclinic cell
+        let mut system = System::with_cell(UnitCell::triclinic(10.0, 8.0, 12.0, 80.0, 95.0, 70.0));
+        for i in 0..30 {
+            let x = i as f64;
+            let fractional = Vector3D::new(
+                0.5 + 0.5 * f64::sin(1.3 * x),
+                0.5 + 0.5 * f64::cos(2.1 * x + 0.4),
+                0.5 + 0.5 * f64::sin(0.7 * x + 1.1),
+            );
+            let position = system.cell.cartesian(&fractional);
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        }
+
+        let volumes = VoronoiVolume.compute(&system);
+        assert!(volumes.iter().all(|&volume| volume > 0.0));
+        assert_relative_eq!(volumes.iter().sum::<f64>(), system.volume(), max_relative = 1e-9);
+
+        // A single particle fills the whole cell
+        let mut system = System::with_cell(UnitCell::triclinic(10.0, 8.0, 12.0, 80.0, 95.0, 70.0));
+        let position = Vector3D::new(1.0, 2.0, 3.0);
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        let volumes = VoronoiVolume.compute(&system);
+        assert_relative_eq!(volumes[0], system.volume(), max_relative = 1e-9);
+    }
 
-        system.set_pair_potential(("F", "F"), PairInteraction::new(Box::new(NullPotential), 0.0));
+    #[test]
+    fn neighbors_of() {
+        let mut system = System::with_cell(UnitCell::triclinic(10.0, 8.0, 12.0, 80.0, 95.0, 70.0));
+        for i in 0..50 {
+            let x = i as f64;
+            let fractional = Vector3D::new(
+                1.5 * f64::sin(1.3 * x),
+                0.5 + 0.5 * f64::cos(2.1 * x + 0.4),
+                0.5 + 0.5 * f64::sin(0.7 * x + 1.1),
+            );
+            let position = system.cell.cartesian(&fractional);
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        }
 
-        system.set_bond_potential(
-            ("F", "F"),
-            Box::new(Harmonic {
-                k: units::from(100.0, "kJ/mol/A^2").unwrap(),
-                x0: units::from(2.0, "A").unwrap(),
-            }),
-        );
+        // Full neighbor list, using the nearest periodic image of all pairs
+        let cutoff = 3.0;
+        let list = (0..system.size()).map(|i| {
+            (0..system.size()).filter(|&j| j != i && system.distance(i, j) < cutoff).collect()
+        }).collect::<Vec<Vec<usize>>>();
 
-        system.set_angle_potential(
-            ("F", "F", "F"),
-            Box::new(Harmonic {
-                k: units::from(100.0, "kJ/mol/deg^2").unwrap(),
-                x0: units::from(88.0, "deg").unwrap(),
-            }),
-        );
+        let grid = NeighborGrid::new(&system, 2.0);
+        for (i, neighbors) in list.iter().enumerate() {
+            assert_eq!(&grid.neighbors_of(i, cutoff), neighbors);
+        }
+        assert!(list.iter().any(|neighbors| neighbors.len() > 1));
+    }
 
-        system.set_dihedral_potential(
-            ("F", "F", "F", "F"),
-            Box::new(Harmonic {
-                k: units::from(100.0, "kJ/mol/deg^2").unwrap(),
-                x0: units::from(185.0, "deg").unwrap(),
-            }),
-        );
+    #[test]
+    fn neighbor_grid() {
+        let mut system = System::with_cell(UnitCell::triclinic(10.0, 8.0, 12.0, 80.0, 95.0, 70.0));
+        for i in 0..30 {
+            let x = i as f64;
+            let fractional = Vector3D::new(
+                1.5 * f64::sin(1.3 * x),
+                0.5 + 0.5 * f64::cos(2.1 * x + 0.4),
+                0.5 + 0.5 * f64::sin(0.7 * x + 1.1),
+            );
+            let position = system.cell.cartesian(&fractional);
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        }
 
-        // unused interaction to check that we do handle this right
-        system.set_pair_potential(("H", "O"), PairInteraction::new(Box::new(NullPotential), 0.0));
+        // Cutoffs smaller and larger than the unit cell
+        for &cutoff in &[3.0, 7.0, 15.0] {
+            let grid = NeighborGrid::new(&system, 2.5);
+            for i in 0..system.size() {
+                let mut expected = Vec::new();
+                for j in 0..system.size() {
+                    let rij = system.nearest_image(j, i);
+                    for a in -3..=3 {
+                        for b in -3..=3 {
+                            for c in -3..=3 {
+                                let shift = Vector3D::new(a as f64, b as f64, c as f64);
+                                let r = rij + system.cell.cartesian(&shift);
+                                if r.norm() > 0.0 && r.norm() < cutoff {
+                                    expected.push(r.norm());
+                                }
+                            }
+                        }
+                    }
+                }
+                expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-        return system;
+                let mut actual = grid.neighbors(i, cutoff).iter().map(|n| n.0).collect::<Vec<_>>();
+                actual.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                assert_eq!(actual.len(), expected.len());
+                for (actual, expected) in actual.iter().zip(&expected) {
+                    assert_relative_eq!(actual, expected, max_relative = 1e-9);
+                }
+            }
+        }
     }
 
     #[test]
-    fn forces_pairs() {
-        let system = &test_pairs_system();
-        let res = Forces.compute(system);
+    fn velocity_autocorrelation() {
+        let mut system = system_from_xyz(
+            "2
+            cell: 10.0
+            Ar 1.0 2.0 3.0
+            Ar 5.0 2.0 3.0
+            ",
+        );
+        let velocity = Vector3D::new(0.7, -0.3, 0.4);
+        system.particles_mut().velocity[0] = velocity;
+        system.particles_mut().velocity[1] = -velocity;
 
-        let forces_tot = res[0] + res[1];
-        assert_eq!(forces_tot, Vector3D::zero());
+        let mut vacf = VelocityAutocorrelation::new(10);
+        assert_eq!(vacf.count(), 0);
+        assert!(vacf.values().is_empty());
 
-        let force = units::from(30.0, "kJ/mol/A").unwrap();
-        assert_ulps_eq!(res[0][0], force);
-        assert_ulps_eq!(res[0][1], 0.0);
-        assert_ulps_eq!(res[0][1], 0.0);
+        for _ in 0..4 {
+            vacf.update(&system);
+        }
+        assert_eq!(vacf.count(), 4);
+        assert_eq!(vacf.values().len(), 4);
 
-        assert_ulps_eq!(res[1][0], -force);
-        assert_ulps_eq!(res[1][1], 0.0);
-        assert_ulps_eq!(res[1][1], 0.0);
+        // Free particles have a constant autocorrelation function
+        for _ in 0..96 {
+            for position in system.particles_mut().position {
+                *position += 0.1 * velocity;
+            }
+            vacf.update(&system);
+        }
+        assert_eq!(vacf.count(), 100);
+        assert_eq!(vacf.snapshots.len(), 10);
+
+        let values = vacf.values();
+        assert_eq!(values.len(), 10);
+        for &value in &values {
+            assert_ulps_eq!(value, velocity.norm2(), epsilon = 1e-12);
+        }
+
+        // The integral over 9 time lags gives the speed of the particles
+        let diffusion = vacf.diffusion_coefficient(0.1);
+        assert_ulps_eq!(diffusion, velocity.norm2() * 0.9 / 3.0, epsilon = 1e-12);
     }
 
     #[test]
-    fn force_molecular() {
-        let system = test_molecular_system();
-        let res = Forces.compute(&system);
-        let forces_tot = res[0] + res[1] + res[2] + res[3];
-        assert_ulps_eq!(forces_tot.norm2(), 0.0);
+    fn velocity_autocorrelation_decay() {
+        let mut system = system_from_xyz(
+            "1
+            cell: 10.0
+            Ar 1.0 2.0 3.0
+            ",
+        );
+
+        // Alternating velocities are anti-correlated at odd time lags
+        let mut vacf = VelocityAutocorrelation::new(4);
+        for step in 0..20 {
+            let sign = if step % 2 == 0 { 1.0 } else { -1.0 };
+            system.particles_mut().velocity[0] = Vector3D::new(sign * 2.0, 0.0, 0.0);
+            vacf.update(&system);
+        }
+        assert_eq!(vacf.values(), [4.0, -4.0, 4.0, -4.0]);
+        assert_eq!(vacf.diffusion_coefficient(1.0), 0.0);
     }
 
     #[test]
-    fn energy_pairs() {
+    fn per_atom_energy() {
         let system = &test_pairs_system();
-        let kinetic = KineticEnergy.compute(system);
-        let potential = PotentialEnergy.compute(system);
-        let total = TotalEnergy.compute(system);
+        let energies = PerAtomEnergy.compute(system);
+        assert_eq!(energies.len(), 2);
+        assert_eq!(energies[0], energies[1]);
+        assert_ulps_eq!(energies[0] + energies[1], PotentialEnergy.compute(system), epsilon = 1e-12);
 
-        assert_eq!(kinetic + potential, total);
-        assert_ulps_eq!(kinetic, 0.0007483016557453698);
-
-        assert_eq!(kinetic, system.kinetic_energy());
-        assert_eq!(potential, system.potential_energy());
-        assert_eq!(total, system.total_energy());
+        let system = &test_molecular_system();
+        let energies = PerAtomEnergy.compute(system);
+        assert_eq!(energies.len(), 4);
+        assert_ulps_eq!(energies.iter().sum::<f64>(), PotentialEnergy.compute(system), epsilon = 1e-12);
     }
 
     #[test]
-    fn energy_molecular() {
-        let system = test_molecular_system();
-        assert_ulps_eq!(PotentialEnergy.compute(&system), units::from(1800.0, "kJ/mol").unwrap());
+    #[should_panic]
+    fn per_atom_stress_infinite_cell() {
+        let _ = PerAtomStress.compute(&System::new());
     }
 
     #[test]
-    fn temperature() {
+    fn per_atom_stress() {
         let system = &test_pairs_system();
-        let temperature = Temperature.compute(system);
-        assert_ulps_eq!(temperature, 300.0);
-        assert_eq!(temperature, system.temperature());
+        let stress = PerAtomStress.compute(system);
+        assert_eq!(stress.len(), 2);
+
+        let total = (stress[0] + stress[1]) / system.volume();
+        assert_ulps_eq!(total, Stress.compute(system), epsilon = 1e-15);
+
+        let system = &test_molecular_system();
+        let stress = PerAtomStress.compute(system);
+        assert_eq!(stress.len(), 4);
+
+        let total = (stress[0] + stress[1] + stress[2] + stress[3]) / system.volume();
+        assert_ulps_eq!(total, Stress.compute(system), epsilon = 1e-15);
     }
 
     #[test]
-    fn volume() {
+    fn configurational_temperature() {
         let system = &test_pairs_system();
-        let volume = Volume.compute(system);
-        assert_eq!(volume, 1000.0);
-        assert_eq!(volume, system.volume());
+        let temperature = ConfigurationalTemperature.compute(system);
+
+        let k = units::from(300.0, "kJ/mol/A^2").unwrap();
+        let r = 1.3;
+        let dr = r - 1.2;
+        let forces2 = 2.0 * k * k * dr * dr;
+        let laplacian = 2.0 * (k + 2.0 * k * dr / r);
+        assert_ulps_eq!(temperature, forces2 / (K_BOLTZMANN * laplacian), epsilon = 1e-9);
     }
 
     #[test]
-    #[should_panic]
-    fn virial_infinite_cell() {
-        let _ = Virial.compute(&System::new());
+    fn hessian() {
+        let mut system = system_from_xyz(
+            "3
+            cell: 20.0
+            Ar 0.0 0.0 0.0
+            Ar 2.1 0.3 0.0
+            Ar 0.4 2.4 -0.5
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        let lj = LennardJones { sigma: 2.0, epsilon: 0.8 };
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 8.0));
+        system.set_bond_potential(("Ar", "Ar"), Box::new(Harmonic { k: 50.0, x0: 2.0 }));
+
+        let hessian = Hessian.compute(&system);
+        assert_eq!(hessian.dim(), (9, 9));
+
+        // The Hessian is the opposite of the derivative of the forces
+        let h = 1e-6;
+        for i in 0..3 {
+            for alpha in 0..3 {
+                system.particles_mut().position[i][alpha] += h;
+                let forces_plus = Forces.compute(&system);
+                system.particles_mut().position[i][alpha] -= 2.0 * h;
+                let forces_minus = Forces.compute(&system);
+                system.particles_mut().position[i][alpha] += h;
+
+                for j in 0..3 {
+                    for beta in 0..3 {
+                        let expected = -(forces_plus[j][beta] - forces_minus[j][beta]) / (2.0 * h);
+                        let value = hessian[(3 * i + alpha, 3 * j + beta)];
+                        assert_relative_eq!(value, expected, epsilon = 1e-6, max_relative = 1e-6);
+                        assert_eq!(value, hessian[(3 * j + beta, 3 * i + alpha)]);
+                    }
+                }
+            }
+        }
     }
 
     #[test]
-    fn virial_pairs() {
-        let system = &test_pairs_system();
-        let virial = Virial.compute(system);
+    #[cfg(feature = "normal-modes")]
+    fn hessian_frequencies() {
+        let mut system = system_from_xyz(
+            "2
+            cell: 10.0
+            F 0.0 0.0 0.0
+            F 1.2 0.0 0.0
+            ",
+        );
+        system.particles_mut().mass[0] = 2.0;
+        system.particles_mut().mass[1] = 3.0;
+        let harmonic = Harmonic { k: 50.0, x0: 1.2 };
+        system.set_pair_potential(("F", "F"), PairInteraction::new(Box::new(harmonic), 5.0));
 
-        let mut expected = Matrix3::zero();
-        let force = units::from(30.0, "kJ/mol/A^2").unwrap();
-        expected[0][0] = -force * 1.3;
+        let frequencies = Hessian.frequencies(&system);
+        assert_eq!(frequencies.len(), 6);
+        for &frequency in &frequencies[..5] {
+            assert!(frequency.abs() < 1e-6);
+        }
+        let mu = 2.0 * 3.0 / (2.0 + 3.0);
+        assert_ulps_eq!(frequencies[5], f64::sqrt(50.0 / mu), epsilon = 1e-12);
+    }
 
-        assert_ulps_eq!(virial, expected);
-        assert_eq!(virial, system.virial());
+    #[test]
+    fn enthalpy() {
+        let system = &test_pairs_system();
+        let enthalpy = Enthalpy.compute(system);
+
+        // Harmonic energy of the stretched pair, and pV from the ideal gas
+        // and virial contributions
+        let energy = units::from(0.5 * 300.0 * 0.1 * 0.1, "kJ/mol").unwrap();
+        let force = units::from(30.0, "kJ/mol/A").unwrap();
+        let pv = 2.0 * K_BOLTZMANN * 300.0 - force * 1.3 / 3.0;
+        assert_ulps_eq!(enthalpy, energy + pv, epsilon = 1e-12);
     }
 
     #[test]
-    fn virial_molecular() {
-        let system = &test_molecular_system();
-        let virial = Virial.compute(system);
+    fn heat_capacity() {
+        let mut heat_capacity = HeatCapacity::new();
+        assert_eq!(heat_capacity.value(300.0), 0.0);
 
-        let mut expected = Matrix3::zero();
-        let w = units::from(100.0, "kJ/mol/A").unwrap();
-        expected[0][0] = 2.0 * w;
-        expected[1][1] = 1.0 * w;
+        heat_capacity.push(1.0);
+        assert_eq!(heat_capacity.value(300.0), 0.0);
 
-        assert_ulps_eq!(virial, expected);
-        assert_eq!(virial, system.virial());
+        heat_capacity.push(2.0);
+        heat_capacity.push(3.0);
+        assert_eq!(heat_capacity.count(), 3);
+
+        let expected = 2.0 / 3.0 / (K_BOLTZMANN * 300.0 * 300.0);
+        assert_ulps_eq!(heat_capacity.value(300.0), expected, epsilon = 1e-9);
     }
 
     #[test]
     #[should_panic]
-    fn pressure_at_temperature_negative_temperature() {
-        let system = &test_pairs_system();
-        let pressure = PressureAtTemperature { temperature: -4.0 };
-        let _ = pressure.compute(system);
+    fn heat_capacity_negative_temperature() {
+        let _ = HeatCapacity::new().value(-4.0);
     }
 
     #[test]
-    #[should_panic]
-    fn pressure_at_temperature_infinite_cell() {
-        let pressure = PressureAtTemperature { temperature: -4.0 };
-        let _ = pressure.compute(&System::new());
+    fn average() {
+        let mut average = Average::new(Volume, 1);
+        assert_eq!(average.count(), 0);
+        assert_eq!(average.mean(), 0.0);
+        assert_eq!(average.std_error(), 0.0);
+
+        // Blocks of four correlated samples
+        for i in 0..1024 {
+            if (i / 4) % 2 == 0 {
+                average.push(3.0);
+            } else {
+                average.push(1.0);
+            }
+        }
+        assert_eq!(average.count(), 1024);
+        assert_ulps_eq!(average.mean(), 2.0);
+        assert_ulps_eq!(average.variance(), 1.0);
+
+        // The naive error estimate from the raw samples is too small by a
+        // factor two, the blocking analysis finds the right value for blocks
+        // of size 4, and the larger blocks are all equal
+        assert_relative_eq!(average.blocking.levels[0].std_error().unwrap(), 1.0 / f64::sqrt(1023.0));
+        assert_relative_eq!(average.blocking.levels[2].std_error().unwrap(), 1.0 / f64::sqrt(255.0));
+        assert_ulps_eq!(average.blocking.levels[3].std_error().unwrap(), 0.0);
+        assert_relative_eq!(average.std_error(), 1.0 / f64::sqrt(255.0));
     }
 
     #[test]
-    fn pressure_at_temperature() {
-        let system = &mut test_pairs_system();
+    fn average_update() {
+        let mut system = test_pairs_system();
+        let mut volume = Average::new(Volume, 2);
+        for _ in 0..5 {
+            volume.update(&system);
+        }
+        assert_eq!(volume.count(), 3);
+        assert_eq!(volume.mean(), system.volume());
 
-        let temperature = 550.0;
-        let force = units::from(30.0, "kJ/mol/A").unwrap();
-        let virial = -force * 1.3;
-        let natoms = 2.0;
-        let volume = 1000.0;
+        system.cell = UnitCell::cubic(20.0);
+        volume.update(&system);
+        assert_eq!(volume.count(), 3);
+        volume.update(&system);
+        assert_eq!(volume.count(), 4);
+    }
 
-        // Direct computation
-        let expected = natoms * K_BOLTZMANN * temperature / volume + virial / (3.0 * volume);
-        let pressure = PressureAtTemperature {
-            temperature: temperature,
-        };
-        let pressure = pressure.compute(system);
-        assert_ulps_eq!(pressure, expected);
+    #[test]
+    fn block_average_autocorrelated() {
+        // AR(1) process x_{t+1} = phi x_t + e_t, with uniform noise e_t
+        // generated by a xorshift pseudo-random number generator
+        let phi = 0.9;
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut x = 0.0;
+        let samples = (0..65536).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let noise = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            x = phi * x + noise;
+            return x;
+        }).collect::<Vec<_>>();
 
-        // Computation with the real system temperature
-        let pressure = PressureAtTemperature {
-            temperature: system.temperature(),
-        };
-        let pressure = pressure.compute(system);
-        assert_eq!(pressure, system.pressure());
+        let result = block_average(&samples);
+        assert_eq!(result.curve.len(), 16);
+        assert_eq!(result.curve[8].block_size, 256);
+        assert_eq!(result.curve[8].blocks, 256);
 
-        // Computation with an external temperature for the system
-        let pressure = PressureAtTemperature {
-            temperature: temperature,
-        };
-        let pressure = pressure.compute(system);
-        system.simulated_temperature(Some(temperature));
-        assert_eq!(pressure, system.pressure());
+        // Expected standard error on the mean: sigma_e / (1 - phi) / sqrt(N)
+        let n = samples.len() as f64;
+        let expected = f64::sqrt(1.0 / 12.0 / n) / (1.0 - phi);
+
+        // The naive estimate from the raw samples is too small by a factor
+        // sqrt((1 + phi) / (1 - phi))
+        let naive = result.curve[0].std_error;
+        let correction = f64::sqrt((1.0 - phi) / (1.0 + phi));
+        assert_relative_eq!(naive, expected * correction, max_relative = 0.05);
+
+        // The blocks are uncorrelated for large enough block sizes
+        assert!(result.curve[2].std_error < result.curve[4].std_error);
+        assert_relative_eq!(result.curve[8].std_error, expected, max_relative = 0.15);
+        assert_relative_eq!(result.curve[9].std_error, expected, max_relative = 0.2);
+        assert!(result.std_error > 0.85 * expected);
+        assert!(result.std_error < 1.6 * expected);
+        assert!(f64::abs(result.mean) < 3.0 * expected);
     }
 
     #[test]
-    #[should_panic]
-    fn stress_at_temperature_negative_temperature() {
-        let system = &test_pairs_system();
-        let stress = StressAtTemperature { temperature: -4.0 };
-        let _ = stress.compute(system);
+    fn average_blocking() {
+        let mut average = Average::new(Volume, 1);
+        for i in 0..100 {
+            average.push(i as f64);
+        }
+        let samples = (0..100).map(|i| i as f64).collect::<Vec<_>>();
+        assert_eq!(average.blocking(), block_average(&samples));
+
+        let result = average.blocking();
+        assert_eq!(result.mean, average.mean());
+        assert_eq!(result.std_error, average.std_error());
+        // 100 samples give at least two blocks for sizes up to 32
+        let sizes = result.curve.iter().map(|level| level.block_size).collect::<Vec<_>>();
+        assert_eq!(sizes, [1, 2, 4, 8, 16, 32]);
+        let blocks = result.curve.iter().map(|level| level.blocks).collect::<Vec<_>>();
+        assert_eq!(blocks, [100, 50, 25, 12, 6, 3]);
     }
 
     #[test]
-    #[should_panic]
-    fn stress_at_temperature_infinite_cell() {
-        let stress = StressAtTemperature { temperature: 300.0 };
-        let _ = stress.compute(&System::new());
+    #[should_panic(expected = "the sampling frequency of an average must be positive")]
+    fn average_every_zero() {
+        let _ = Average::new(Volume, 0);
     }
 
     #[test]
-    fn stress_at_temperature() {
-        let system = &mut test_pairs_system();
-        let temperature = 550.0;
-        let stress = StressAtTemperature {
-            temperature: temperature,
-        };
-        let stress = stress.compute(system);
-        let pressure = PressureAtTemperature {
-            temperature: temperature,
-        };
-        let pressure = pressure.compute(system);
+    fn center_of_mass() {
+        let mut system = system_from_xyz(
+            "3
+            cell: 10.0
+            F 9.5 0.0 0.0
+            F 0.5 0.0 0.0
+            F 2.0 2.0 2.0
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
 
-        // tail corrections are smaller than 1e-9
-        let trace = (stress[0][0] + stress[1][1] + stress[2][2]) / 3.0;
-        assert_ulps_eq!(trace, pressure);
+        // The first molecule is split by the periodic boundary conditions
+        let com = CenterOfMass.compute(&system);
+        assert_ulps_eq!(com, Vector3D::new(9.5 + 10.5 + 2.0, 2.0, 2.0) / 3.0);
 
-        system.simulated_temperature(Some(temperature));
-        assert_eq!(stress, system.stress());
+        let system = System::with_cell(UnitCell::cubic(10.0));
+        assert_eq!(CenterOfMass.compute(&system), Vector3D::zero());
     }
 
     #[test]
-    #[should_panic]
-    fn stress_infinite_cell() {
-        let _ = Stress.compute(&System::new());
+    fn total_momentum() {
+        let system = &test_pairs_system();
+        let momentum = TotalMomentum.compute(system);
+        let mass = system.particles().mass[0];
+        let expected = mass * (system.particles().velocity[0] + system.particles().velocity[1]);
+        assert_ulps_eq!(momentum, expected);
     }
 
     #[test]
-    fn stress() {
-        let system = &test_pairs_system();
-        let stress = Stress.compute(system);
-        let pressure = Pressure.compute(system);
+    fn radius_of_gyration() {
+        let mut system = system_from_xyz(
+            "4
+            cell: 10.0
+            F 9.5 0.0 0.0
+            F 0.5 0.0 0.0
+            F 1.5 0.0 0.0
+            F 5.0 5.0 5.0
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(1, 2).is_empty());
 
-        let trace = stress.trace() / 3.0;
-        assert_ulps_eq!(trace, pressure);
-        assert_eq!(stress, system.stress());
+        let radii = RadiusOfGyration.compute(&system);
+        assert_eq!(radii.len(), 2);
+        assert_ulps_eq!(radii[0], f64::sqrt(2.0 / 3.0), epsilon = 1e-12);
+        assert_ulps_eq!(radii[1], 0.0, epsilon = 1e-12);
     }
 
     #[test]
-    #[should_panic]
-    fn pressure_infinite_cell() {
-        let _ = Pressure.compute(&System::new());
+    fn dipole_moment() {
+        let mut system = system_from_xyz(
+            "2
+            cell: 10.0
+            Na 9.5 0.0 0.0
+            Cl 1.0 0.0 0.0
+            ",
+        );
+        system.particles_mut().charge[0] = 1.0;
+        system.particles_mut().charge[1] = -1.0;
+
+        // The particles are 1.5 A apart using periodic boundary conditions
+        let dipole = DipoleMoment.compute(&system);
+        assert_ulps_eq!(dipole, Vector3D::new(-1.5, 0.0, 0.0), epsilon = 1e-12);
     }
 
     #[test]
-    fn pressure() {
-        let system = &test_pairs_system();
-        let pressure = Pressure.compute(system);
+    fn dielectric_constant() {
+        let mut dielectric = DielectricConstant::new();
+        assert_eq!(dielectric.value(1000.0, 300.0), 1.0);
 
-        let force = units::from(30.0, "kJ/mol/A").unwrap();
-        let virial = -force * 1.3;
-        let natoms = 2.0;
-        let temperature = 300.0;
-        let volume = 1000.0;
-        let expected = natoms * K_BOLTZMANN * temperature / volume + virial / (3.0 * volume);
+        dielectric.push(Vector3D::new(1.0, 0.0, 0.0));
+        dielectric.push(Vector3D::new(-1.0, 0.0, 0.0));
+        assert_eq!(dielectric.count(), 2);
 
-        assert_ulps_eq!(pressure, expected);
-        assert_eq!(pressure, system.pressure());
+        let epsilon_0 = FOUR_PI_EPSILON_0 / (4.0 * PI);
+        let expected = 1.0 + 1.0 / (3.0 * epsilon_0 * 1000.0 * K_BOLTZMANN * 300.0);
+        assert_ulps_eq!(dielectric.value(1000.0, 300.0), expected, epsilon = 1e-12);
     }
 }
@@ -5,13 +5,13 @@ use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 
-use crate::{Angle, Bond, BondDistances, Dihedral};
+use crate::{Angle, Bond, BondDistances, Dihedral, Improper};
 use crate::Array2;
 
 
 /// The basic building block for a topology. A `Bonding` contains data about
-/// the connectivity (bonds, angles, dihedrals) between particles in a single
-/// molecule.
+/// the connectivity (bonds, angles, dihedrals, impropers, Urey-Bradley terms)
+/// between particles in a single molecule.
 #[derive(Debug, Clone)]
 pub struct Bonding {
     /// All the bonds in the molecule.
@@ -21,6 +21,12 @@ pub struct Bonding {
     /// All the dihedral angles in the molecule. Rebuilt as needed from the
     /// bond list.
     dihedrals: HashSet<Dihedral>,
+    /// All the improper dihedral angles in the molecule. These are not
+    /// generated from the bonds, and must be added explicitly.
+    impropers: HashSet<Improper>,
+    /// All the angles with an Urey-Bradley term between their first and last
+    /// particles. These are a subset of `angles`, and must be added explicitly.
+    urey_bradleys: HashSet<Angle>,
     /// Matrix of bond distances in the molecule. The item at index `i, j`
     /// encode the bond distance between the particles `i + self.first` and
     /// `j + self.first`
@@ -36,6 +42,8 @@ impl Bonding {
             bonds: HashSet::new(),
             angles: HashSet::new(),
             dihedrals: HashSet::new(),
+            impropers: HashSet::new(),
+            urey_bradleys: HashSet::new(),
             distances: Array2::default((1, 1)),
             range: i..i + 1,
         }
@@ -61,7 +69,8 @@ impl Bonding {
         self.range.start <= i && i < self.range.end
     }
 
-    /// Hash the bonds in this molecule
+    /// Hash the bonds, improper dihedral angles and Urey-Bradley terms in
+    /// this molecule
     pub(crate) fn hash<H: Hasher + Sized>(&self, hasher: &mut H) {
         let mut bonds = self.bonds.iter()
             .map(|bond| Bond::new(bond.i() - self.start(), bond.j() - self.start()))
@@ -72,6 +81,30 @@ impl Bonding {
             bond.i().hash(hasher);
             bond.j().hash(hasher);
         }
+
+        let start = self.start();
+        let mut impropers = self.impropers.iter()
+            .map(|improper| (
+                improper.i() - start,
+                improper.j() - start,
+                improper.k() - start,
+                improper.m() - start,
+            ))
+            .collect::<Vec<_>>();
+
+        impropers.sort_unstable();
+        for improper in &impropers {
+            improper.hash(hasher);
+        }
+
+        let mut urey_bradleys = self.urey_bradleys.iter()
+            .map(|angle| (angle.i() - start, angle.j() - start, angle.k() - start))
+            .collect::<Vec<_>>();
+
+        urey_bradleys.sort_unstable();
+        for angle in &urey_bradleys {
+            angle.hash(hasher);
+        }
     }
 
     /// Rebuild the full list of angles and dihedral angles from the list of bonds
@@ -173,6 +206,14 @@ impl Bonding {
             let _ = self.dihedrals.insert(dihedral);
         }
 
+        for improper in other.impropers {
+            let _ = self.impropers.insert(improper);
+        }
+
+        for angle in other.urey_bradleys {
+            let _ = self.urey_bradleys.insert(angle);
+        }
+
         self.rebuild_connections();
     }
 
@@ -180,7 +221,7 @@ impl Bonding {
     pub fn translate_by(&mut self, delta: isize) {
         if delta < 0 {
             // We should not create negative indexes
-            assert!((delta.abs() as usize) < self.start());
+            assert!((delta.abs() as usize) <= self.start());
         }
 
         // The wrapping_add are necessary here, and produce the right result,
@@ -217,6 +258,27 @@ impl Bonding {
             ));
         }
         self.dihedrals = new_dihedrals;
+
+        let mut new_impropers = HashSet::new();
+        for improper in &self.impropers {
+            let _ = new_impropers.insert(Improper::new(
+                improper.i().wrapping_add(delta),
+                improper.j().wrapping_add(delta),
+                improper.k().wrapping_add(delta),
+                improper.m().wrapping_add(delta),
+            ));
+        }
+        self.impropers = new_impropers;
+
+        let mut new_urey_bradleys = HashSet::new();
+        for angle in &self.urey_bradleys {
+            let _ = new_urey_bradleys.insert(Angle::new(
+                angle.i().wrapping_add(delta),
+                angle.j().wrapping_add(delta),
+                angle.k().wrapping_add(delta),
+            ));
+        }
+        self.urey_bradleys = new_urey_bradleys;
     }
 
     /// Add a bond between the particles at indexes `i` and `j`. These particles
@@ -229,9 +291,44 @@ impl Bonding {
         self.rebuild();
     }
 
-    /// Removes particle at index `i` and any associated bonds, angle or
-    /// dihedral. This function also update the indexes for the
-    /// bonds/angles/dihedral by remove 1 to all the values `> i`
+    /// Add all the `bonds` to this molecule, rebuilding the angles and
+    /// dihedral angles only once. The particles in the bonds are assumed to
+    /// be in the molecule.
+    pub(crate) fn add_bonds<I: IntoIterator<Item = Bond>>(&mut self, bonds: I) {
+        for bond in bonds {
+            assert!(self.contains(bond.i()));
+            assert!(self.contains(bond.j()));
+            let _ = self.bonds.insert(bond);
+        }
+        self.rebuild();
+    }
+
+    /// Add an improper dihedral angle around the particle `i`, using the
+    /// particles `j`, `k` and `m`. These particles are assumed to be in the
+    /// molecule.
+    pub fn add_improper(&mut self, i: usize, j: usize, k: usize, m: usize) {
+        assert!(self.contains(i));
+        assert!(self.contains(j));
+        assert!(self.contains(k));
+        assert!(self.contains(m));
+        let _ = self.impropers.insert(Improper::new(i, j, k, m));
+    }
+
+    /// Add an Urey-Bradley term between the particles `i` and `k` of the
+    /// angle `i, j, k`. This angle is assumed to be in the molecule.
+    pub fn add_urey_bradley(&mut self, i: usize, j: usize, k: usize) {
+        let angle = Angle::new(i, j, k);
+        assert!(
+            self.angles.contains(&angle),
+            "there is no angle between {}, {} and {} in this molecule", i, j, k
+        );
+        let _ = self.urey_bradleys.insert(angle);
+    }
+
+    /// Removes particle at index `i` and any associated bonds, angle,
+    /// dihedral, improper or Urey-Bradley term. This function also update the
+    /// indexes for the bonds/angles/dihedral/impropers/Urey-Bradley terms by
+    /// remove 1 to all the values `> i`
     pub fn remove_particle(&mut self, i: usize) {
         assert!(self.contains(i));
         // Remove bonds containing the particle `i`
@@ -253,6 +350,36 @@ impl Bonding {
         }
 
         self.bonds = new_bonds;
+
+        let shift = |index: usize| if index > i { index - 1 } else { index };
+        let mut new_impropers = HashSet::new();
+        for improper in &self.impropers {
+            let indexes = [improper.i(), improper.j(), improper.k(), improper.m()];
+            if indexes.contains(&i) {
+                continue;
+            }
+
+            let _ = new_impropers.insert(Improper::new(
+                shift(improper.i()),
+                shift(improper.j()),
+                shift(improper.k()),
+                shift(improper.m()),
+            ));
+        }
+        self.impropers = new_impropers;
+
+        let mut new_urey_bradleys = HashSet::new();
+        for angle in &self.urey_bradleys {
+            if angle.i() == i || angle.j() == i || angle.k() == i {
+                continue;
+            }
+
+            let _ = new_urey_bradleys.insert(
+                Angle::new(shift(angle.i()), shift(angle.j()), shift(angle.k()))
+            );
+        }
+        self.urey_bradleys = new_urey_bradleys;
+
         self.range.end -= 1;
         self.rebuild();
     }
@@ -272,6 +399,16 @@ impl Bonding {
         &self.dihedrals
     }
 
+    /// Get the internal list of improper dihedrals
+    pub fn impropers(&self) -> &HashSet<Improper> {
+        &self.impropers
+    }
+
+    /// Get the internal list of angles with an Urey-Bradley term
+    pub fn urey_bradleys(&self) -> &HashSet<Angle> {
+        &self.urey_bradleys
+    }
+
     /// Get the all the possible bond paths the particles `i` and `j` in this molecule
     pub fn bond_distances(&self, i: usize, j: usize) -> BondDistances {
         assert!(self.contains(i) && self.contains(j));
@@ -319,6 +456,11 @@ mod test {
         assert!(bonding.bonds().contains(&Bond::new(4, 5)));
         assert!(bonding.angles().contains(&Angle::new(3, 4, 5)));
         assert!(bonding.dihedrals().contains(&Dihedral::new(2, 3, 4, 5)));
+
+        bonding.translate_by(-2);
+        assert_eq!(bonding.start(), 0);
+        assert_eq!(bonding.end(), 4);
+        assert!(bonding.bonds().contains(&Bond::new(2, 3)));
     }
 
     #[test]
@@ -460,4 +602,73 @@ mod test {
         assert_eq!(bonding.bonds().len(), 1);
         assert_eq!(bonding.size(), 4);
     }
+    #[test]
+    fn impropers() {
+        //       2
+        //       |
+        //   1 - 0 - 3
+        let mut bonding = Bonding::new(0);
+        for i in 1..4 {
+            bonding.merge_with(Bonding::new(i));
+        }
+        bonding.add_bond(0, 1);
+        bonding.add_bond(0, 2);
+        bonding.add_bond(0, 3);
+        assert!(bonding.impropers().is_empty());
+
+        bonding.add_improper(0, 1, 2, 3);
+        assert_eq!(bonding.impropers().len(), 1);
+        // Impropers are kept when rebuilding the angles and dihedrals
+        bonding.add_bond(1, 2);
+        assert!(bonding.impropers().contains(&Improper::new(0, 1, 2, 3)));
+
+        bonding.translate_by(4);
+        assert!(bonding.impropers().contains(&Improper::new(4, 5, 6, 7)));
+
+        let mut merged = Bonding::new(3);
+        merged.merge_with(bonding);
+        assert!(merged.impropers().contains(&Improper::new(4, 5, 6, 7)));
+
+        merged.remove_particle(3);
+        assert!(merged.impropers().contains(&Improper::new(3, 4, 5, 6)));
+
+        merged.remove_particle(5);
+        assert!(merged.impropers().is_empty());
+    }
+
+    #[test]
+    fn urey_bradleys() {
+        let mut bonding = Bonding::new(0);
+        for i in 1..4 {
+            bonding.merge_with(Bonding::new(i));
+        }
+        bonding.add_bond(0, 1);
+        bonding.add_bond(1, 2);
+        bonding.add_bond(2, 3);
+
+        bonding.add_urey_bradley(2, 1, 0);
+        assert_eq!(bonding.urey_bradleys().len(), 1);
+        assert!(bonding.urey_bradleys().contains(&Angle::new(0, 1, 2)));
+
+        bonding.translate_by(2);
+        assert!(bonding.urey_bradleys().contains(&Angle::new(2, 3, 4)));
+
+        bonding.remove_particle(5);
+        assert!(bonding.urey_bradleys().contains(&Angle::new(2, 3, 4)));
+
+        bonding.remove_particle(3);
+        assert!(bonding.urey_bradleys().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "there is no angle between 0, 2 and 1 in this molecule")]
+    fn urey_bradley_without_angle() {
+        let mut bonding = Bonding::new(0);
+        for i in 1..3 {
+            bonding.merge_with(Bonding::new(i));
+        }
+        bonding.add_bond(0, 1);
+        bonding.add_bond(1, 2);
+        bonding.add_urey_bradley(0, 2, 1);
+    }
 }
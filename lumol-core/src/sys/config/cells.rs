@@ -4,6 +4,7 @@
 //! Simulations in computational chemistry are often made using periodic
 //! boundaries conditions. The `UnitCell` type represents the enclosing box of
 //! a simulated system, with some type of periodic condition.
+use std::cmp::Ordering;
 use std::f64;
 use std::f64::consts::PI;
 
@@ -13,6 +14,7 @@ use crate::{Matrix3, Vector3D};
 /// The shape of a cell determine how we will be able to compute the periodic
 /// boundaries condition.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CellShape {
     /// Infinite unit cell, with no boundaries
     Infinite,
@@ -27,8 +29,13 @@ pub enum CellShape {
 /// The shape of the cell can be any of the [`CellShape`][CellShape], and will
 /// influence how periodic boundary conditions are applied.
 ///
+/// When the `serde` feature is enabled, only the cell matrix and shape are
+/// serialized, and the inverse matrix is recomputed when deserializing.
+///
 /// [CellShape]: enum.CellShape.html
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "SerializedCell", into = "SerializedCell"))]
 pub struct UnitCell {
     /// Unit cell matrix
     cell: Matrix3,
@@ -92,6 +99,19 @@ impl UnitCell {
         }
     }
 
+    /// Create an unit cell with the given `shape` from its matricial
+    /// representation, as returned by `UnitCell::matrix`.
+    pub(crate) fn from_matrix(matrix: Matrix3, shape: CellShape) -> UnitCell {
+        if shape == CellShape::Infinite {
+            return UnitCell::infinite();
+        }
+        UnitCell {
+            cell: matrix,
+            inv: matrix.inverse(),
+            shape: shape,
+        }
+    }
+
     /// Get the cell shape
     #[inline]
     pub fn shape(&self) -> CellShape {
@@ -222,8 +242,13 @@ impl UnitCell {
 
     /// Get the reciprocal vector with the given `index`. This vector is null
     /// for infinite cells.
+    ///
+    /// The reciprocal vectors are the rows of the inverse of the cell matrix,
+    /// such that the scalar product of the reciprocal vector with index
+    /// $(h, k, l)$ and the cell vectors $\vec a, \vec b, \vec c$ is
+    /// $2 \pi (h, k, l)$.
     pub fn k_vector(&self, index: [f64; 3]) -> Vector3D {
-        return 2.0 * PI * self.inv * Vector3D::from(index);
+        return 2.0 * PI * self.inv.transposed() * Vector3D::from(index);
     }
 
     /// Get the matricial representation of the unit cell
@@ -256,6 +281,75 @@ impl UnitCell {
     }
 }
 
+/// Get a reduced basis for the lattice generated by the `vectors`, using the
+/// greedy algorithm from [Nguyen2009]. In three dimensions, this gives a
+/// Minkowski-reduced basis: the vectors are sorted by increasing norm, and
+/// each one is the shortest possible while still completing a basis of the
+/// lattice with the previous ones.
+///
+/// This function returns the reduced vectors, and for each one of them the
+/// integer coefficients of its decomposition on the initial `vectors`.
+///
+/// [Nguyen2009]: Nguyen, P. Q. and Stehlé, D. ACM Trans. Algorithms 5, 46 (2009).
+fn reduce_basis(mut vectors: [Vector3D; 3]) -> ([Vector3D; 3], [[i32; 3]; 3]) {
+    let mut coefficients = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+    for _ in 0..100 {
+        let mut order = [0, 1, 2];
+        order.sort_by(|&i, &j| {
+            let (norm_i, norm_j) = (vectors[i].norm2(), vectors[j].norm2());
+            if abs(norm_i - norm_j) <= 1e-12 * f64::max(norm_i, norm_j) {
+                // Keep the initial order for vectors with the same length
+                Ordering::Equal
+            } else {
+                norm_i.partial_cmp(&norm_j).expect("got NaN in cell vectors")
+            }
+        });
+        vectors = [vectors[order[0]], vectors[order[1]], vectors[order[2]]];
+        coefficients = [coefficients[order[0]], coefficients[order[1]], coefficients[order[2]]];
+
+        // Gauss reduction of the second vector with the first one
+        let m = round(vectors[1] * vectors[0] / vectors[0].norm2());
+        vectors[1] -= m * vectors[0];
+        for k in 0..3 {
+            coefficients[1][k] -= m as i32 * coefficients[0][k];
+        }
+        if vectors[1].norm2() < (1.0 - 1e-12) * vectors[0].norm2() {
+            continue;
+        }
+
+        // Remove the closest vector in the lattice generated by the first
+        // two vectors from the third one. The real coordinates of the
+        // projection are rounded, and the neighboring points are checked.
+        let (a, b, c) = (vectors[0], vectors[1], vectors[2]);
+        let (aa, ab, bb) = (a * a, a * b, b * b);
+        let (ca, cb) = (c * a, c * b);
+        let determinant = aa * bb - ab * ab;
+        let x = round((ca * bb - cb * ab) / determinant);
+        let y = round((cb * aa - ca * ab) / determinant);
+
+        let mut best = (c, 0.0, 0.0);
+        for &i in &[-1.0, 0.0, 1.0] {
+            for &j in &[-1.0, 0.0, 1.0] {
+                let candidate = c - (x + i) * a - (y + j) * b;
+                if candidate.norm2() < best.0.norm2() {
+                    best = (candidate, x + i, y + j);
+                }
+            }
+        }
+
+        let (best, x, y) = best;
+        if best.norm2() < (1.0 - 1e-12) * c.norm2() {
+            vectors[2] = best;
+            for k in 0..3 {
+                coefficients[2][k] -= x as i32 * coefficients[0][k] + y as i32 * coefficients[1][k];
+            }
+        } else {
+            break;
+        }
+    }
+    return (vectors, coefficients);
+}
+
 /// Geometric operations using periodic boundary conditions
 impl UnitCell {
     /// Wrap a vector in the unit cell, obeying the periodic boundary conditions.
@@ -300,6 +394,118 @@ impl UnitCell {
         }
     }
 
+    /// Find the image of all the `vectors` in the unit cell, obeying the
+    /// periodic boundary conditions. This gives the same results as calling
+    /// [`UnitCell::vector_image`](struct.UnitCell.html#method.vector_image)
+    /// on each vector, but only looks at the cell shape once.
+    pub fn vector_images(&self, vectors: &mut [Vector3D]) {
+        match self.shape {
+            CellShape::Infinite => (),
+            CellShape::Orthorhombic => {
+                let (a, b, c) = (self.a(), self.b(), self.c());
+                for vect in vectors {
+                    vect[0] -= round(vect[0] / a) * a;
+                    vect[1] -= round(vect[1] / b) * b;
+                    vect[2] -= round(vect[2] / c) * c;
+                }
+            }
+            CellShape::Triclinic => {
+                for vect in vectors {
+                    self.vector_image(vect);
+                }
+            }
+        }
+    }
+
+    /// Get an equivalent reduced cell, generating the same periodic lattice
+    /// with shorter and more orthogonal vectors, together with the integer
+    /// transformation matrix $T$ such that the reduced cell matrix is
+    /// $H_\text{reduced} = H T$, where $H$ is the matrix of this cell.
+    ///
+    /// The vectors of the reduced cell are Minkowski-reduced: they are sorted
+    /// by increasing length, and each one is as short as possible. The
+    /// reduced cell has the same volume and handedness as this cell.
+    /// Infinite and orthorhombic cells are already reduced, and are returned
+    /// unchanged with the identity transformation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::UnitCell;
+    /// let cell = UnitCell::triclinic(10.0, 10.0, 10.0, 90.0, 90.0, 15.0);
+    /// let (reduced, _) = cell.reduced();
+    ///
+    /// assert!(reduced.a() < 3.0);
+    /// assert!((reduced.volume() - cell.volume()).abs() < 1e-9);
+    /// ```
+    pub fn reduced(&self) -> (UnitCell, [[i32; 3]; 3]) {
+        match self.shape {
+            CellShape::Infinite | CellShape::Orthorhombic => (*self, [[1, 0, 0], [0, 1, 0], [0, 0, 1]]),
+            CellShape::Triclinic => {
+                let (mut vectors, mut coefficients) = reduce_basis([self.vect_a(), self.vect_b(), self.vect_c()]);
+                if vectors[0] * (vectors[1] ^ vectors[2]) < 0.0 {
+                    // Keep the same handedness as the initial cell
+                    vectors[2] = -vectors[2];
+                    for coefficient in &mut coefficients[2] {
+                        *coefficient = -*coefficient;
+                    }
+                }
+
+                let matrix = Matrix3::new([
+                    [vectors[0][0], vectors[1][0], vectors[2][0]],
+                    [vectors[0][1], vectors[1][1], vectors[2][1]],
+                    [vectors[0][2], vectors[1][2], vectors[2][2]],
+                ]);
+                let transformation = [
+                    [coefficients[0][0], coefficients[1][0], coefficients[2][0]],
+                    [coefficients[0][1], coefficients[1][1], coefficients[2][1]],
+                    [coefficients[0][2], coefficients[1][2], coefficients[2][2]],
+                ];
+                (UnitCell::from_matrix(matrix, CellShape::Triclinic), transformation)
+            }
+        }
+    }
+
+    /// Find the shortest image of the `vect` using periodic boundary
+    /// conditions, *i.e.* the true minimum image.
+    ///
+    /// For orthorhombic cells, this is the same as
+    /// [`UnitCell::vector_image`](struct.UnitCell.html#method.vector_image).
+    /// For triclinic cells, the single shift in fractional coordinates used
+    /// by `vector_image` can miss the nearest image when the cell is strongly
+    /// sheared. This function uses the
+    /// [reduced cell](struct.UnitCell.html#method.reduced), and then checks
+    /// all the neighboring images, making it always correct but slower than
+    /// `vector_image`.
+    pub fn minimum_image(&self, vect: &mut Vector3D) {
+        match self.shape {
+            CellShape::Infinite | CellShape::Orthorhombic => self.vector_image(vect),
+            CellShape::Triclinic => {
+                let (reduced, _) = self.reduced();
+                let basis = [reduced.vect_a(), reduced.vect_b(), reduced.vect_c()];
+
+                let mut fractional = reduced.fractional(vect);
+                fractional[0] = round(fractional[0]);
+                fractional[1] = round(fractional[1]);
+                fractional[2] = round(fractional[2]);
+                let wrapped = *vect - reduced.cartesian(&fractional);
+
+                let mut best = wrapped;
+                for &i in &[-1.0, 0.0, 1.0] {
+                    for &j in &[-1.0, 0.0, 1.0] {
+                        for &k in &[-1.0, 0.0, 1.0] {
+                            let candidate = wrapped - i * basis[0] - j * basis[1] - k * basis[2];
+                            if candidate.norm2() < best.norm2() {
+                                best = candidate;
+                            }
+                        }
+                    }
+                }
+                *vect = best;
+            }
+        }
+    }
+
     /// Get the fractional representation of the `vector` in this cell
     #[inline]
     pub fn fractional(&self, vector: &Vector3D) -> Vector3D {
@@ -410,6 +616,62 @@ impl UnitCell {
         let phi = f64::atan2(r23_norm * v * r12, u * v);
         return (phi, d1, d2, d3, d4);
     }
+
+    /// Get the out-of-plane angle formed by the points at `r1`, `r2`, `r3`,
+    /// and `r4` using periodic boundary conditions. This is the angle between
+    /// the `r1-r4` vector and the plane containing `r1`, `r2` and `r3`, in the
+    /// `[-pi/2, pi/2]` range.
+    pub fn out_of_plane(&self, r1: &Vector3D, r2: &Vector3D, r3: &Vector3D, r4: &Vector3D) -> f64 {
+        let mut r12 = r2 - r1;
+        self.vector_image(&mut r12);
+        let mut r13 = r3 - r1;
+        self.vector_image(&mut r13);
+        let mut r14 = r4 - r1;
+        self.vector_image(&mut r14);
+
+        let normal = r12 ^ r13;
+        return f64::asin(normal * r14 / (normal.norm() * r14.norm()));
+    }
+
+    /// Get the out-of-plane angle and its derivatives defined by the points
+    /// at `r1`, `r2`, `r3`, and `r4` using periodic boundary conditions.
+    ///
+    /// The derivatives are not defined when the `r1-r4` vector is normal to
+    /// the plane, *i.e.* when the out-of-plane angle is `pi/2` or `-pi/2`.
+    pub fn out_of_plane_and_derivatives(
+        &self,
+        r1: &Vector3D,
+        r2: &Vector3D,
+        r3: &Vector3D,
+        r4: &Vector3D,
+    ) -> (f64, Vector3D, Vector3D, Vector3D, Vector3D) {
+        let mut r12 = r2 - r1;
+        self.vector_image(&mut r12);
+        let mut r13 = r3 - r1;
+        self.vector_image(&mut r13);
+        let mut r14 = r4 - r1;
+        self.vector_image(&mut r14);
+
+        let normal = r12 ^ r13;
+        let normal_norm = normal.norm();
+        let r14_norm = r14.norm();
+        let normal_n = normal / normal_norm;
+        let r14n = r14 / r14_norm;
+
+        let sin = normal_n * r14n;
+        let cos_inv = 1.0 / sqrt(1.0 - sin * sin);
+
+        // Derivatives of the sine with respect to the normal and to r14
+        let d_normal = (r14n - sin * normal_n) / normal_norm;
+        let d_r14 = (normal_n - sin * r14n) / r14_norm;
+
+        let d2 = cos_inv * (r13 ^ d_normal);
+        let d3 = cos_inv * (d_normal ^ r12);
+        let d4 = cos_inv * d_r14;
+        let d1 = -(d2 + d3 + d4);
+
+        return (f64::asin(sin), d1, d2, d3, d4);
+    }
 }
 
 /// Get the angles between the vectors `u` and `v`.
@@ -419,6 +681,31 @@ fn angle(u: Vector3D, v: Vector3D) -> f64 {
     acos(un * vn)
 }
 
+/// Serialized representation of an `UnitCell`, without the cached inverse
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedCell {
+    shape: CellShape,
+    matrix: Matrix3,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedCell> for UnitCell {
+    fn from(cell: SerializedCell) -> UnitCell {
+        UnitCell::from_matrix(cell.matrix, cell.shape)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<UnitCell> for SerializedCell {
+    fn from(cell: UnitCell) -> SerializedCell {
+        SerializedCell {
+            shape: cell.shape,
+            matrix: cell.cell,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -599,6 +886,16 @@ mod tests {
         let cell = UnitCell::infinite();
         let kvec = cell.k_vector([1.0, 1.0, 1.0]);
         assert_ulps_eq!(kvec, Vector3D::new(0.0, 0.0, 0.0));
+
+        // The reciprocal vectors are dual to the cell vectors
+        let cell = UnitCell::triclinic(10.0, 11.0, 12.0, 80.0, 95.0, 70.0);
+        let matrix = cell.matrix();
+        let index = [1.0, -2.0, 3.0];
+        let kvec = cell.k_vector(index);
+        for i in 0..3 {
+            let vector = Vector3D::new(matrix[0][i], matrix[1][i], matrix[2][i]);
+            assert_ulps_eq!(kvec * vector, 2.0 * PI * index[i], epsilon = 1e-12);
+        }
     }
 
     #[test]
@@ -678,6 +975,123 @@ mod tests {
         assert_ulps_eq!(v[2], res[2], max_ulps = 5);
     }
 
+    /// Find the minimum image of `vect` by checking all images up to `n`
+    /// cells away.
+    fn brute_force_image(cell: &UnitCell, vect: Vector3D, n: i32) -> Vector3D {
+        let mut best = vect;
+        for i in -n..=n {
+            for j in -n..=n {
+                for k in -n..=n {
+                    let shift = cell.cartesian(&Vector3D::new(i as f64, j as f64, k as f64));
+                    let candidate = vect - shift;
+                    if candidate.norm2() < best.norm2() {
+                        best = candidate;
+                    }
+                }
+            }
+        }
+        return best;
+    }
+
+    #[test]
+    fn minimum_image() {
+        // Strongly sheared cell, where the single shift in fractional
+        // coordinates gives the wrong image
+        let cell = UnitCell::triclinic(10.0, 10.0, 10.0, 90.0, 90.0, 15.0);
+        let vect = Vector3D::new(12.1, 1.6, 0.7);
+
+        let mut naive = vect;
+        cell.vector_image(&mut naive);
+        assert_ulps_eq!(naive.norm(), 7.655645358333841, epsilon = 1e-9);
+
+        let mut image = vect;
+        cell.minimum_image(&mut image);
+        assert_ulps_eq!(image.norm(), 2.7246542156326568, epsilon = 1e-9);
+        assert_ulps_eq!(image, brute_force_image(&cell, vect, 6), epsilon = 1e-12);
+
+        // The minimum image is a periodic image of the initial vector
+        let fractional = cell.fractional(&(vect - image));
+        for i in 0..3 {
+            assert_ulps_eq!(fractional[i], fractional[i].round(), epsilon = 1e-12);
+        }
+
+        let cells = [
+            UnitCell::triclinic(5.0, 6.0, 3.6, 90.0, 53.0, 77.0),
+            UnitCell::triclinic(10.0, 8.0, 12.0, 30.0, 150.0, 140.0),
+            UnitCell::triclinic(7.0, 13.0, 9.0, 100.0, 20.0, 95.0),
+        ];
+        let vectors = [
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(4.0, -7.5, 12.1),
+            Vector3D::new(-21.0, 3.3, 0.4),
+            Vector3D::new(9.9, 9.9, -9.9),
+            Vector3D::new(1.0, 2.0, 3.0),
+        ];
+        for cell in &cells {
+            for &vect in &vectors {
+                let mut image = vect;
+                cell.minimum_image(&mut image);
+                let expected = brute_force_image(cell, vect, 8);
+                assert_ulps_eq!(image.norm(), expected.norm(), epsilon = 1e-12);
+            }
+        }
+
+        // Same as vector_image for orthorhombic cells
+        let cell = UnitCell::ortho(3.0, 4.0, 5.0);
+        let mut v = Vector3D::new(1.0, 1.5, 6.0);
+        cell.minimum_image(&mut v);
+        assert_eq!(v, Vector3D::new(1.0, 1.5, 1.0));
+    }
+
+    #[test]
+    fn reduced() {
+        let identity = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+
+        // Already reduced cells are not modified
+        let cell = UnitCell::cubic(10.0);
+        assert_eq!(cell.reduced(), (cell, identity));
+
+        let cell = UnitCell::triclinic(10.0, 10.0, 10.0, 90.0, 90.0, 90.0);
+        assert_eq!(cell.reduced(), (cell, identity));
+
+        let cell = UnitCell::triclinic(10.0, 11.0, 12.0, 80.0, 95.0, 70.0);
+        assert_eq!(cell.reduced(), (cell, identity));
+
+        let cells = [
+            UnitCell::triclinic(10.0, 10.0, 10.0, 90.0, 90.0, 15.0),
+            UnitCell::triclinic(10.0, 8.0, 12.0, 30.0, 150.0, 140.0),
+            UnitCell::triclinic(7.0, 13.0, 9.0, 100.0, 20.0, 95.0),
+        ];
+        for cell in &cells {
+            let (reduced, transformation) = cell.reduced();
+            assert_eq!(reduced.shape(), CellShape::Triclinic);
+            assert_ulps_eq!(reduced.volume(), cell.volume(), epsilon = 1e-9);
+
+            // The reduced vectors are sorted, and shorter than the initial ones
+            assert!(reduced.a() <= reduced.b() + 1e-12 && reduced.b() <= reduced.c() + 1e-12);
+            assert!(reduced.a() + reduced.b() + reduced.c() < cell.a() + cell.b() + cell.c());
+
+            // The transformation is unimodular, and transforms the initial
+            // cell vectors to the reduced ones
+            let matrix = Matrix3::new([
+                [transformation[0][0] as f64, transformation[0][1] as f64, transformation[0][2] as f64],
+                [transformation[1][0] as f64, transformation[1][1] as f64, transformation[1][2] as f64],
+                [transformation[2][0] as f64, transformation[2][1] as f64, transformation[2][2] as f64],
+            ]);
+            assert_eq!(matrix.determinant(), 1.0);
+            for k in 0..3 {
+                let column = Vector3D::new(matrix[0][k], matrix[1][k], matrix[2][k]);
+                let expected = cell.cartesian(&column);
+                for i in 0..3 {
+                    assert_ulps_eq!(reduced.matrix()[i][k], expected[i], epsilon = 1e-9);
+                }
+            }
+        }
+
+        let (reduced, _) = cells[0].reduced();
+        assert_ulps_eq!(reduced.a(), 20.0 * f64::sin(7.5_f64.to_radians()), epsilon = 1e-12);
+    }
+
     #[test]
     fn fractional_cartesian() {
         let cell = UnitCell::cubic(5.0);
@@ -697,6 +1111,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fractional_corners() {
+        let cells = [
+            UnitCell::cubic(5.0),
+            UnitCell::ortho(3.0, 4.0, 5.0),
+            UnitCell::triclinic(5.0, 6.0, 3.6, 90.0, 53.0, 77.0),
+            UnitCell::triclinic(10.0, 10.0, 10.0, 60.0, 70.0, 120.0),
+        ];
+
+        for cell in &cells {
+            // The cell vectors are the columns of the cell matrix
+            let matrix = cell.matrix();
+            let a = Vector3D::new(matrix[0][0], matrix[1][0], matrix[2][0]);
+            let b = Vector3D::new(matrix[0][1], matrix[1][1], matrix[2][1]);
+            let c = Vector3D::new(matrix[0][2], matrix[1][2], matrix[2][2]);
+            assert_ulps_eq!(a.norm(), cell.a(), epsilon = 1e-12);
+            assert_ulps_eq!(b.norm(), cell.b(), epsilon = 1e-12);
+            assert_ulps_eq!(c.norm(), cell.c(), epsilon = 1e-12);
+
+            for &i in &[0.0, 1.0] {
+                for &j in &[0.0, 1.0] {
+                    for &k in &[0.0, 1.0] {
+                        let corner = i * a + j * b + k * c;
+                        let fractional = Vector3D::new(i, j, k);
+                        assert_ulps_eq!(cell.fractional(&corner), fractional, epsilon = 1e-14);
+                        assert_ulps_eq!(cell.cartesian(&fractional), corner, epsilon = 1e-14);
+                    }
+                }
+            }
+
+            // The center of the cell is at 1/2 in fractional coordinates
+            let center = 0.5 * (a + b + c);
+            assert_ulps_eq!(cell.fractional(&center), Vector3D::new(0.5, 0.5, 0.5), epsilon = 1e-14);
+        }
+    }
+
     #[test]
     fn angles() {
         let cell = UnitCell::infinite();
@@ -797,4 +1247,79 @@ mod tests {
             assert_ulps_eq!((cell.dihedral(&a, &b, &c, &p) - angle) / EPS, d4[i], epsilon = 1e-6);
         }
     }
+
+    #[test]
+    fn out_of_plane() {
+        let cell = UnitCell::infinite();
+
+        let a = Vector3D::zero();
+        let b = Vector3D::new(1.0, 0.0, 0.0);
+        let c = Vector3D::new(0.0, 1.0, 0.0);
+        let d = Vector3D::new(-1.0, -1.0, 0.0);
+        assert_eq!(cell.out_of_plane(&a, &b, &c, &d), 0.0);
+
+        let d = Vector3D::new(1.0, 0.0, 1.0);
+        assert_ulps_eq!(cell.out_of_plane(&a, &b, &c, &d), PI / 4.0);
+        assert_ulps_eq!(cell.out_of_plane(&a, &c, &b, &d), -PI / 4.0);
+
+        let cell = UnitCell::cubic(10.0);
+        let d = Vector3D::new(1.0, 0.0, 9.0);
+        assert_ulps_eq!(cell.out_of_plane(&a, &b, &c, &d), -PI / 4.0);
+    }
+
+    #[test]
+    fn out_of_plane_derivatives() {
+        const EPS: f64 = 1e-6;
+        let cell = UnitCell::infinite();
+        let a = Vector3D::new(-0.011, -0.441, 0.333);
+        let b = Vector3D::new(1.241, 0.444, 0.349);
+        let c = Vector3D::new(-1.176, 0.296, -0.332);
+        let d = Vector3D::new(0.104, -1.211, 0.819);
+
+        let (angle, d1, d2, d3, d4) = cell.out_of_plane_and_derivatives(&a, &b, &c, &d);
+        assert_eq!(angle, cell.out_of_plane(&a, &b, &c, &d));
+
+        // Check by comparison to finite differences
+        for i in 0..3 {
+            let mut p = a;
+            p[i] += EPS;
+            let delta = cell.out_of_plane(&p, &b, &c, &d) - angle;
+            assert_ulps_eq!(delta / EPS, d1[i], epsilon = 1e-6);
+        }
+
+        for i in 0..3 {
+            let mut p = b;
+            p[i] += EPS;
+            let delta = cell.out_of_plane(&a, &p, &c, &d) - angle;
+            assert_ulps_eq!(delta / EPS, d2[i], epsilon = 1e-6);
+        }
+
+        for i in 0..3 {
+            let mut p = c;
+            p[i] += EPS;
+            let delta = cell.out_of_plane(&a, &b, &p, &d) - angle;
+            assert_ulps_eq!(delta / EPS, d3[i], epsilon = 1e-6);
+        }
+
+        for i in 0..3 {
+            let mut p = d;
+            p[i] += EPS;
+            let delta = cell.out_of_plane(&a, &b, &c, &p) - angle;
+            assert_ulps_eq!(delta / EPS, d4[i], epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let cell = UnitCell::triclinic(10.0, 11.0, 12.5, 90.0, 80.0, 100.0);
+        let json = serde_json::to_string(&cell).unwrap();
+        let restored: UnitCell = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, cell);
+
+        let cell = UnitCell::infinite();
+        let json = serde_json::to_string(&cell).unwrap();
+        let restored: UnitCell = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, cell);
+    }
 }
@@ -2,13 +2,15 @@
 // Copyright (C) 2015-2016 Lumol's contributors — BSD license
 
 use std::cmp::{max, min};
+use std::collections::{BTreeMap, HashSet};
 use std::marker::PhantomData;
+use std::ops::Range;
 
 use log::trace;
 use log_once::warn_once;
 
 use crate::Vector3D;
-use crate::{BondDistances, Bonding, ParticleKind, UnitCell};
+use crate::{Angle, Bond, BondDistances, Bonding, Improper, ParticleKind, UnitCell};
 use crate::{ParticleSlice, ParticleSliceMut, ParticleVec, ParticlePtr, ParticlePtrMut};
 use crate::{Molecule, MoleculeRef, MoleculeRefMut};
 use crate::BondPath;
@@ -48,6 +50,8 @@ pub struct Configuration {
     bondings: Vec<Bonding>,
     /// Molecules indexes for all the particles
     molecule_ids: Vec<usize>,
+    /// Named per-particle attributes, with one value for each particle
+    attributes: BTreeMap<String, Vec<f64>>,
 }
 
 impl Configuration {
@@ -57,6 +61,7 @@ impl Configuration {
             particles: ParticleVec::new(),
             bondings: Vec::new(),
             molecule_ids: Vec::new(),
+            attributes: BTreeMap::new(),
             cell: UnitCell::infinite(),
         }
     }
@@ -153,6 +158,9 @@ impl Configuration {
             let _ = self.particles.remove(first);
             let _ = self.molecule_ids.remove(first);
         }
+        for values in self.attributes.values_mut() {
+            let _ = values.drain(first..first + size);
+        }
 
         for molecule in self.bondings.iter_mut().skip(molid) {
             molecule.translate_by(-(size as isize));
@@ -163,6 +171,150 @@ impl Configuration {
         }
     }
 
+    /// Remove the particle at index `i`, together with all the bonds, angles
+    /// and dihedral angles containing it. The indexes of all the particles
+    /// after `i` are decreased by one.
+    ///
+    /// # Warning
+    ///
+    /// If the particle was linking different parts of a molecule, this
+    /// molecule is split in multiple molecules, and particles may be moved in
+    /// the particles list to ensure that molecules are contiguous in memory.
+    ///
+    /// This function will return the list of atomic permutations that where
+    /// applied in this case. The `old` indexes in the permutations already
+    /// account for the removal of the particle `i`.
+    pub fn remove_particle(&mut self, i: usize) -> Vec<Permutation> {
+        assert!(i < self.particles.len());
+        let molid = self.molecule_ids[i];
+        if self.bondings[molid].size() == 1 {
+            self.remove_molecule(molid);
+            return Vec::new();
+        }
+
+        let _ = self.particles.remove(i);
+        let _ = self.molecule_ids.remove(i);
+        for values in self.attributes.values_mut() {
+            let _ = values.remove(i);
+        }
+
+        self.bondings[molid].remove_particle(i);
+        for molecule in self.bondings.iter_mut().skip(molid + 1) {
+            molecule.translate_by(-1);
+        }
+
+        let bonding = &self.bondings[molid];
+        let components = connected_components(bonding.indexes(), bonding.bonds());
+        if components.len() == 1 {
+            return Vec::new();
+        }
+        return self.split_molecule(molid, &components);
+    }
+
+    /// Split the molecule at index `molid` in multiple molecules, one for
+    /// each of the `components`. The components must contain all the
+    /// particles in the molecule, with no bonds between different components.
+    fn split_molecule(&mut self, molid: usize, components: &[Vec<usize>]) -> Vec<Permutation> {
+        let old_mol = self.bondings[molid].clone();
+        let start = old_mol.start();
+
+        // Move the particles to their new position. `current` contains the
+        // old index of the particles currently in the molecule.
+        let mut current = old_mol.indexes().collect::<Vec<_>>();
+        for (new_index, &i) in components.iter().flatten().enumerate() {
+            let old_index = current.iter().position(|&j| j == i).expect("missing particle");
+            if old_index != new_index {
+                let particle = self.particles.remove(start + old_index);
+                self.particles.insert(start + new_index, particle);
+                for values in self.attributes.values_mut() {
+                    let value = values.remove(start + old_index);
+                    values.insert(start + new_index, value);
+                }
+                let _ = current.remove(old_index);
+                current.insert(new_index, i);
+            }
+        }
+
+        let (bondings, permutations) = components_bondings(
+            start, components, old_mol.bonds(), old_mol.impropers(), old_mol.urey_bradleys()
+        );
+        for (id, bonding) in bondings.iter().enumerate() {
+            for i in bonding.indexes() {
+                self.molecule_ids[i] = molid + id;
+            }
+        }
+
+        let added = bondings.len() - 1;
+        let _ = self.bondings.splice(molid..molid + 1, bondings);
+        for molid in self.molecule_ids.iter_mut().skip(old_mol.end()) {
+            *molid += added;
+        }
+
+        return permutations;
+    }
+
+    /// Add all the `bonds` to the configuration, and rebuild the molecules
+    /// from the connected components of the resulting bonds graph. This is
+    /// much faster than calling [`Configuration::add_bond`] for every bond
+    /// when adding a lot of bonds at once, for example when reading a
+    /// configuration from a file.
+    ///
+    /// # Warning
+    ///
+    /// Particles are moved in the particles list to ensure that molecules are
+    /// contiguous in memory, sorted by their first particle. This invalidates
+    /// any previously stored index, and this function returns the list of
+    /// atomic permutations that where applied.
+    ///
+    /// # Panics
+    ///
+    /// If any of the bonds contains a particle which is not in the
+    /// configuration
+    ///
+    /// [`Configuration::add_bond`]: struct.Configuration.html#method.add_bond
+    pub fn rebuild_molecules_from_bonds(&mut self, bonds: &[Bond]) -> Vec<Permutation> {
+        let size = self.size();
+        let mut all_bonds = self.bondings.iter()
+                                         .flat_map(|bonding| bonding.bonds().iter().cloned())
+                                         .collect::<HashSet<_>>();
+        for bond in bonds {
+            assert!(
+                bond.j() < size,
+                "bond between particles {} and {} is out of bounds", bond.i(), bond.j()
+            );
+            let _ = all_bonds.insert(*bond);
+        }
+
+        let all_impropers = self.bondings.iter()
+                                         .flat_map(|bonding| bonding.impropers().iter().cloned())
+                                         .collect::<HashSet<_>>();
+        let all_urey_bradleys = self.bondings.iter()
+                                    .flat_map(|bonding| bonding.urey_bradleys().iter().cloned())
+                                    .collect::<HashSet<_>>();
+
+        let components = connected_components(0..size, &all_bonds);
+
+        let mut particles = ParticleVec::new();
+        for &i in components.iter().flatten() {
+            particles.append(&mut self.particles.slice(i..i + 1).to_vec());
+        }
+        self.particles = particles;
+        for values in self.attributes.values_mut() {
+            *values = components.iter().flatten().map(|&i| values[i]).collect();
+        }
+
+        let (bondings, permutations) = components_bondings(
+            0, &components, &all_bonds, &all_impropers, &all_urey_bradleys
+        );
+        self.molecule_ids.clear();
+        for (molid, bonding) in bondings.iter().enumerate() {
+            self.molecule_ids.extend(bonding.indexes().map(|_| molid));
+        }
+        self.bondings = bondings;
+
+        return permutations;
+    }
+
     /// Add a bond between the particles at indexes `i` and `j`. The particles
     /// should have been added to the configuration before calling this.
     ///
@@ -250,10 +402,65 @@ impl Configuration {
         bonding.translate_by(self.particles.len() as isize);
 
         self.molecule_ids.append(&mut vec![self.bondings.len(); bonding.size()]);
+        for values in self.attributes.values_mut() {
+            values.resize(values.len() + bonding.size(), 0.0);
+        }
         self.bondings.push(bonding);
         self.particles.append(&mut molecule.particles);
     }
 
+    /// Set the per-particle attribute named `name` to the given `values`,
+    /// replacing any existing attribute with the same name. Attributes can be
+    /// used to store custom data for each particle, for example the result of
+    /// an analysis.
+    ///
+    /// The attributes follow the particles when molecules are added to or
+    /// removed from the configuration. New particles get a value of 0 for all
+    /// existing attributes.
+    ///
+    /// An error is returned, and the attributes are not modified, if the
+    /// number of `values` is not the number of particles in the
+    /// configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, Molecule, Particle};
+    /// let mut system = System::new();
+    /// system.add_molecule(Molecule::new(Particle::new("Ar")));
+    /// system.add_molecule(Molecule::new(Particle::new("Ar")));
+    ///
+    /// system.set_particle_attribute("order", vec![0.3, 0.7]).unwrap();
+    /// assert_eq!(system.particle_attribute("order"), Some(&[0.3, 0.7][..]));
+    ///
+    /// system.add_molecule(Molecule::new(Particle::new("Ar")));
+    /// assert_eq!(system.particle_attribute("order"), Some(&[0.3, 0.7, 0.0][..]));
+    ///
+    /// assert!(system.set_particle_attribute("order", vec![0.3]).is_err());
+    /// ```
+    pub fn set_particle_attribute(&mut self, name: &str, values: Vec<f64>) -> Result<(), String> {
+        if values.len() != self.size() {
+            return Err(format!(
+                "wrong number of values for the '{}' attribute: expected {}, got {}",
+                name, self.size(), values.len()
+            ));
+        }
+        let _ = self.attributes.insert(name.into(), values);
+        return Ok(());
+    }
+
+    /// Get the values of the per-particle attribute named `name`, or `None`
+    /// if there is no such attribute.
+    pub fn particle_attribute(&self, name: &str) -> Option<&[f64]> {
+        self.attributes.get(name).map(|values| &**values)
+    }
+
+    /// Get an iterator over the names and values of all the per-particle
+    /// attributes in this configuration.
+    pub fn particle_attributes(&self) -> impl Iterator<Item = (&str, &[f64])> {
+        self.attributes.iter().map(|(name, values)| (&**name, &**values))
+    }
+
     /// Get the number of particles in this configuration
     #[inline]
     pub fn size(&self) -> usize {
@@ -341,6 +548,11 @@ impl Configuration {
                 let _ = self.molecule_ids.remove(i);
                 self.molecule_ids.insert(new_index, new_molid);
 
+                for values in self.attributes.values_mut() {
+                    let value = values.remove(i);
+                    values.insert(new_index, value);
+                }
+
                 new_index += 1;
             }
         }
@@ -386,6 +598,113 @@ fn check_molid_sorted(molecule_ids: &[usize]) -> bool {
     return true;
 }
 
+/// Get the connected components of the graph defined by `bonds` between the
+/// particles in `indexes`. The components are sorted by their first particle,
+/// and contain sorted particles indexes.
+fn connected_components(indexes: Range<usize>, bonds: &HashSet<Bond>) -> Vec<Vec<usize>> {
+    fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        return i;
+    }
+
+    // Union-find, using the smallest index in each set as its root
+    let start = indexes.start;
+    let size = indexes.len();
+    let mut parents = (0..size).collect::<Vec<_>>();
+    for bond in bonds {
+        let root_i = find_root(&mut parents, bond.i() - start);
+        let root_j = find_root(&mut parents, bond.j() - start);
+        parents[max(root_i, root_j)] = min(root_i, root_j);
+    }
+
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    let mut component_ids = vec![0; size];
+    for i in 0..size {
+        let root = find_root(&mut parents, i);
+        if root == i {
+            component_ids[i] = components.len();
+            components.push(vec![start + i]);
+        } else {
+            component_ids[i] = component_ids[root];
+            components[component_ids[root]].push(start + i);
+        }
+    }
+    return components;
+}
+
+/// Create one molecule for each of the `components`, with the `bonds`,
+/// `impropers` and `urey_bradleys` terms between the particles in this
+/// component. Impropers spanning multiple components are removed. The
+/// particles in the components are renumbered to be contiguous, starting at
+/// `start`. This function returns the new molecules and the corresponding
+/// particles permutations.
+fn components_bondings(
+    start: usize,
+    components: &[Vec<usize>],
+    bonds: &HashSet<Bond>,
+    impropers: &HashSet<Improper>,
+    urey_bradleys: &HashSet<Angle>,
+) -> (Vec<Bonding>, Vec<Permutation>) {
+    let size = components.iter().map(|component| component.len()).sum::<usize>();
+    let mut new_indexes = vec![0; size];
+    let mut component_ids = vec![0; size];
+    let mut permutations = Vec::new();
+    let mut bondings = Vec::new();
+
+    let mut new_index = start;
+    for (component_id, component) in components.iter().enumerate() {
+        let mut bonding = Bonding::new(new_index);
+        for &i in component {
+            if new_index != bonding.start() {
+                bonding.merge_with(Bonding::new(new_index));
+            }
+            if new_index != i {
+                permutations.push(Permutation::new(i, new_index));
+            }
+            new_indexes[i - start] = new_index;
+            component_ids[i - start] = component_id;
+            new_index += 1;
+        }
+        bondings.push(bonding);
+    }
+
+    let mut components_bonds = vec![Vec::new(); components.len()];
+    for bond in bonds {
+        let (i, j) = (bond.i() - start, bond.j() - start);
+        assert_eq!(component_ids[i], component_ids[j]);
+        components_bonds[component_ids[i]].push(Bond::new(new_indexes[i], new_indexes[j]));
+    }
+    for (bonding, bonds) in bondings.iter_mut().zip(components_bonds) {
+        bonding.add_bonds(bonds);
+    }
+
+    for improper in impropers {
+        let indexes = [improper.i(), improper.j(), improper.k(), improper.m()];
+        let component_id = component_ids[indexes[0] - start];
+        if indexes.iter().all(|&i| component_ids[i - start] == component_id) {
+            bondings[component_id].add_improper(
+                new_indexes[indexes[0] - start],
+                new_indexes[indexes[1] - start],
+                new_indexes[indexes[2] - start],
+                new_indexes[indexes[3] - start],
+            );
+        }
+    }
+
+    // Angles always belong to a single component, since their particles are
+    // bonded together
+    for angle in urey_bradleys {
+        let (i, j, k) = (angle.i() - start, angle.j() - start, angle.k() - start);
+        bondings[component_ids[j]].add_urey_bradley(new_indexes[i], new_indexes[j], new_indexes[k]);
+    }
+
+    permutations.sort_by_key(|permutation| permutation.old);
+    return (bondings, permutations);
+}
+
 /// `UnitCell` related functions
 impl Configuration {
     /// Get the distance between the particles at indexes `i` and `j`
@@ -402,6 +721,21 @@ impl Configuration {
         return res;
     }
 
+    /// Get the vectors between the nearest images of all the particles in
+    /// `others` with respect to particle `i`. This is the same as calling
+    /// [`Configuration::nearest_image`] for each particle in `others`, and
+    /// is useful to compute all the interactions of a particle at once.
+    ///
+    /// [`Configuration::nearest_image`]: struct.Configuration.html#method.nearest_image
+    pub fn nearest_images(&self, i: usize, others: &[usize]) -> Vec<Vector3D> {
+        let position = self.particles.position[i];
+        let mut vectors = others.iter()
+            .map(|&j| position - self.particles.position[j])
+            .collect::<Vec<_>>();
+        self.cell.vector_images(&mut vectors);
+        return vectors;
+    }
+
     /// Get the angle between the particles `i`, `j` and `k`
     pub fn angle(&self, i: usize, j: usize, k: usize) -> f64 {
         self.cell.angle(
@@ -452,6 +786,35 @@ impl Configuration {
             &self.particles.position[m]
         )
     }
+
+    /// Get the out-of-plane angle of the improper dihedral angle between the
+    /// particles `i`, `j`, `k` and `m`, where `i` is the central particle.
+    pub fn out_of_plane(&self, i: usize, j: usize, k: usize, m: usize) -> f64 {
+        self.cell.out_of_plane(
+            &self.particles.position[i],
+            &self.particles.position[j],
+            &self.particles.position[k],
+            &self.particles.position[m]
+        )
+    }
+
+    /// Get the out-of-plane angle and the derivatives of the out-of-plane
+    /// angle of the improper dihedral angle between the particles `i`, `j`,
+    /// `k` and `m`, where `i` is the central particle.
+    pub fn out_of_plane_and_derivatives(
+        &self,
+        i: usize,
+        j: usize,
+        k: usize,
+        m: usize,
+    ) -> (f64, Vector3D, Vector3D, Vector3D, Vector3D) {
+        self.cell.out_of_plane_and_derivatives(
+            &self.particles.position[i],
+            &self.particles.position[j],
+            &self.particles.position[k],
+            &self.particles.position[m]
+        )
+    }
 }
 
 /// An iterator over all the molecules in a `Configuration`
@@ -800,6 +1163,217 @@ mod tests {
         assert_eq!(configuration.molecules().count(), 1);
     }
 
+    #[test]
+    fn remove_particle() {
+        // A chain A-B-C-D-E and an argon atom
+        let mut molecule = Molecule::new(particle("A"));
+        molecule.add_particle_bonded_to(0, particle("B"));
+        molecule.add_particle_bonded_to(1, particle("C"));
+        molecule.add_particle_bonded_to(2, particle("D"));
+        molecule.add_particle_bonded_to(3, particle("E"));
+
+        let mut configuration = Configuration::new();
+        configuration.add_molecule(molecule);
+        configuration.add_molecule(Molecule::new(particle("Ar")));
+
+        // Removing an end of the chain keeps a single molecule
+        assert!(configuration.remove_particle(4).is_empty());
+        assert_eq!(configuration.molecules().count(), 2);
+        assert_eq!(configuration.particles().name, &["A", "B", "C", "D", "Ar"]);
+        let molecule = configuration.molecule(0);
+        assert_eq!(molecule.indexes(), 0..4);
+        assert_eq!(molecule.bonds().len(), 3);
+        assert!(molecule.bonds().contains(&Bond::new(2, 3)));
+        assert!(molecule.dihedrals().contains(&Dihedral::new(0, 1, 2, 3)));
+        assert_eq!(configuration.molecule(1).indexes(), 4..5);
+
+        // Removing the middle of the chain splits the molecule
+        assert!(configuration.remove_particle(1).is_empty());
+        assert_eq!(configuration.molecules().count(), 3);
+        assert_eq!(configuration.particles().name, &["A", "C", "D", "Ar"]);
+
+        let molecule = configuration.molecule(0);
+        assert_eq!(molecule.indexes(), 0..1);
+        assert!(molecule.bonds().is_empty());
+
+        let molecule = configuration.molecule(1);
+        assert_eq!(molecule.indexes(), 1..3);
+        assert_eq!(molecule.bonds().len(), 1);
+        assert!(molecule.bonds().contains(&Bond::new(1, 2)));
+        assert!(molecule.angles().is_empty());
+
+        assert_eq!(configuration.molecule(2).indexes(), 3..4);
+        for i in 0..4 {
+            assert!(configuration.molecule(configuration.molecule_id(i)).indexes().contains(&i));
+        }
+        assert_eq!(configuration.bond_path(1, 2), BondPath::OneBond);
+        assert_eq!(configuration.bond_path(0, 1), BondPath::None);
+
+        // Removing a single particle removes the whole molecule
+        assert!(configuration.remove_particle(3).is_empty());
+        assert_eq!(configuration.molecules().count(), 2);
+        assert_eq!(configuration.size(), 3);
+    }
+
+    #[test]
+    fn remove_particle_permutations() {
+        //     B - D
+        //    /
+        //   A
+        //    \
+        //     C - E
+        let mut molecule = Molecule::new(particle("A"));
+        molecule.add_particle_bonded_to(0, particle("B"));
+        molecule.add_particle_bonded_to(0, particle("C"));
+        molecule.add_particle_bonded_to(1, particle("D"));
+        molecule.add_particle_bonded_to(2, particle("E"));
+
+        let mut configuration = Configuration::new();
+        configuration.add_molecule(Molecule::new(particle("Ar")));
+        configuration.add_molecule(molecule);
+        configuration.add_molecule(Molecule::new(particle("Ar")));
+        let index = vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        configuration.set_particle_attribute("index", index).unwrap();
+
+        // After removing A, particles are B C D E Ar, and are then reordered
+        // to keep the molecules contiguous
+        let permutations = configuration.remove_particle(1);
+        assert_eq!(permutations, vec![Permutation::new(2, 3), Permutation::new(3, 2)]);
+
+        assert_eq!(configuration.particles().name, &["Ar", "B", "D", "C", "E", "Ar"]);
+        let attribute = configuration.particle_attribute("index").unwrap();
+        assert_eq!(attribute, &[0.0, 2.0, 4.0, 3.0, 5.0, 6.0]);
+
+        assert_eq!(configuration.molecules().count(), 4);
+        assert_eq!(configuration.molecule(1).indexes(), 1..3);
+        assert!(configuration.molecule(1).bonds().contains(&Bond::new(1, 2)));
+        assert_eq!(configuration.molecule(2).indexes(), 3..5);
+        assert!(configuration.molecule(2).bonds().contains(&Bond::new(3, 4)));
+        assert_eq!(configuration.molecule(3).indexes(), 5..6);
+
+        let molecule_ids = (0..6).map(|i| configuration.molecule_id(i)).collect::<Vec<_>>();
+        assert_eq!(molecule_ids, &[0, 1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn rebuild_molecules_from_bonds() {
+        let mut configuration = Configuration::new();
+        for &name in &["C", "Ar", "C", "Ar", "C", "C"] {
+            configuration.add_molecule(Molecule::new(particle(name)));
+        }
+        configuration.set_particle_attribute("index", vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+
+        // A ring between all the carbon atoms
+        let bonds = [Bond::new(0, 2), Bond::new(2, 4), Bond::new(4, 5), Bond::new(5, 0)];
+        let permutations = configuration.rebuild_molecules_from_bonds(&bonds);
+        assert_eq!(permutations, vec![
+            Permutation::new(1, 4),
+            Permutation::new(2, 1),
+            Permutation::new(3, 5),
+            Permutation::new(4, 2),
+            Permutation::new(5, 3),
+        ]);
+
+        assert_eq!(configuration.particles().name, &["C", "C", "C", "C", "Ar", "Ar"]);
+        let attribute = configuration.particle_attribute("index").unwrap();
+        assert_eq!(attribute, &[0.0, 2.0, 4.0, 5.0, 1.0, 3.0]);
+
+        assert_eq!(configuration.molecules().count(), 3);
+        let ring = configuration.molecule(0);
+        assert_eq!(ring.indexes(), 0..4);
+        assert_eq!(ring.bonds().len(), 4);
+        for bond in &[Bond::new(0, 1), Bond::new(1, 2), Bond::new(2, 3), Bond::new(3, 0)] {
+            assert!(ring.bonds().contains(bond));
+        }
+        assert_eq!(ring.angles().len(), 4);
+        assert_eq!(configuration.molecule(1).indexes(), 4..5);
+        assert_eq!(configuration.molecule(2).indexes(), 5..6);
+
+        let molecule_ids = (0..6).map(|i| configuration.molecule_id(i)).collect::<Vec<_>>();
+        assert_eq!(molecule_ids, &[0, 0, 0, 0, 1, 2]);
+
+        // Existing bonds are kept
+        let permutations = configuration.rebuild_molecules_from_bonds(&[Bond::new(3, 4)]);
+        assert!(permutations.is_empty());
+        assert_eq!(configuration.molecules().count(), 2);
+        assert_eq!(configuration.molecule(0).bonds().len(), 5);
+        assert_eq!(configuration.bond_path(0, 4), BondPath::TwoBonds);
+    }
+
+    #[test]
+    fn impropers() {
+        let mut molecule = Molecule::new(particle("C"));
+        molecule.add_particle_bonded_to(0, particle("O"));
+        molecule.add_particle_bonded_to(0, particle("H"));
+        molecule.add_particle_bonded_to(0, particle("H"));
+        molecule.add_improper(0, 2, 3, 1);
+
+        let mut configuration = Configuration::new();
+        configuration.add_molecule(Molecule::new(particle("Ar")));
+        configuration.add_molecule(molecule);
+        configuration.add_molecule(Molecule::new(particle("Ar")));
+        assert!(configuration.molecule(1).impropers().contains(&Improper::new(1, 3, 4, 2)));
+
+        assert!(configuration.remove_particle(0).is_empty());
+        assert!(configuration.molecule(0).impropers().contains(&Improper::new(0, 2, 3, 1)));
+
+        let permutations = configuration.rebuild_molecules_from_bonds(&[Bond::new(1, 4)]);
+        assert!(permutations.is_empty());
+        assert_eq!(configuration.molecules().count(), 1);
+        assert_eq!(configuration.molecule(0).impropers().len(), 1);
+        assert!(configuration.molecule(0).impropers().contains(&Improper::new(0, 2, 3, 1)));
+
+        // Removing the central particle splits the molecule and removes the
+        // improper
+        let _ = configuration.remove_particle(0);
+        assert_eq!(configuration.molecules().count(), 3);
+        for molecule in configuration.molecules() {
+            assert!(molecule.impropers().is_empty());
+        }
+    }
+
+    #[test]
+    fn particle_attributes() {
+        let mut configuration = Configuration::new();
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.add_molecule(Molecule::new(particle("H")));
+        assert_eq!(configuration.particle_attribute("charge"), None);
+
+        configuration.set_particle_attribute("charge", vec![0.4, 0.5]).unwrap();
+        assert_eq!(configuration.particle_attribute("charge"), Some(&[0.4, 0.5][..]));
+
+        // New particles are added with a default value
+        configuration.add_molecule(Molecule::new(particle("O")));
+        assert_eq!(configuration.particle_attribute("charge"), Some(&[0.4, 0.5, 0.0][..]));
+        configuration.set_particle_attribute("charge", vec![0.4, 0.5, -0.9]).unwrap();
+
+        // Attributes follow the particles when molecules are merged
+        let _ = configuration.add_bond(0, 2);
+        assert_eq!(configuration.particles().name, &["H", "O", "H"]);
+        assert_eq!(configuration.particle_attribute("charge"), Some(&[0.4, -0.9, 0.5][..]));
+
+        configuration.add_molecule(Molecule::new(particle("Ar")));
+        configuration.set_particle_attribute("order", vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+        let names = configuration.particle_attributes().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, &["charge", "order"]);
+
+        // And when molecules are removed
+        configuration.remove_molecule(0);
+        assert_eq!(configuration.particle_attribute("charge"), Some(&[0.5, 0.0][..]));
+        assert_eq!(configuration.particle_attribute("order"), Some(&[3.0, 4.0][..]));
+    }
+
+    #[test]
+    fn particle_attributes_size() {
+        let mut configuration = Configuration::new();
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.set_particle_attribute("charge", vec![0.4]).unwrap();
+
+        let error = configuration.set_particle_attribute("charge", vec![0.4, 0.5]).unwrap_err();
+        assert_eq!(error, "wrong number of values for the 'charge' attribute: expected 1, got 2");
+        assert_eq!(configuration.particle_attribute("charge"), Some(&[0.4][..]));
+    }
+
     #[test]
     fn particles() {
         let mut configuration = Configuration::new();
@@ -828,6 +1402,31 @@ mod tests {
         assert_eq!(configuration.distance(0, 1), 9.0);
     }
 
+    #[test]
+    fn nearest_images() {
+        let mut configuration = Configuration::new();
+        for i in 0..20 {
+            let i = i as f64;
+            let position = Vector3D::new(f64::sin(i) * 12.0, f64::cos(2.0 * i) * 15.0, i);
+            configuration.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        }
+        let others = [0, 3, 19, 7, 7, 12];
+
+        let cells = [
+            UnitCell::infinite(),
+            UnitCell::ortho(8.0, 9.0, 10.0),
+            UnitCell::triclinic(8.0, 9.0, 10.0, 80.0, 95.0, 110.0),
+        ];
+        for &cell in &cells {
+            configuration.cell = cell;
+            let images = configuration.nearest_images(5, &others);
+            assert_eq!(images.len(), others.len());
+            for (image, &j) in images.iter().zip(&others) {
+                assert_eq!(*image, configuration.nearest_image(5, j));
+            }
+        }
+    }
+
     #[test]
     fn hash() {
         let mut configuration = Configuration::new();
@@ -139,6 +139,67 @@ impl Dihedral {
 }
 
 
+/// An `Improper` dihedral angle formed by the particles at indexes `i`, `j`,
+/// `k` and `m`, where `i` is the central particle.
+///
+/// Contrary to [`Dihedral`], the order of the particles is significant and is
+/// kept as given: the associated out-of-plane angle is the angle between the
+/// `i-m` vector and the plane containing the particles `i`, `j` and `k`.
+///
+/// [`Dihedral`]: struct.Dihedral.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Improper {
+    i: usize,
+    j: usize,
+    k: usize,
+    m: usize,
+}
+
+impl Improper {
+    /// Create a new Improper dihedral around the `center` particle, using
+    /// the particles at indexes `first`, `second` and `third`.
+    pub fn new(center: usize, first: usize, second: usize, third: usize) -> Improper {
+        assert_ne!(center, first);
+        assert_ne!(center, second);
+        assert_ne!(center, third);
+        assert_ne!(first, second);
+        assert_ne!(first, third);
+        assert_ne!(second, third);
+        Improper {
+            i: center,
+            j: first,
+            k: second,
+            m: third,
+        }
+    }
+
+    /// Get the central particle in the improper dihedral angle
+    #[inline]
+    pub fn i(&self) -> usize {
+        self.i
+    }
+
+    /// Get the second particle in the improper dihedral angle
+    #[inline]
+    pub fn j(&self) -> usize {
+        self.j
+    }
+
+    /// Get the third particle in the improper dihedral angle
+    #[inline]
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Get the fourth particle in the improper dihedral angle, which is
+    /// moved out of the plane by the out-of-plane angle
+    #[inline]
+    pub fn m(&self) -> usize {
+        self.m
+    }
+}
+
+
 bitflags! {
     /// The `BondDistances` bitflag encode the topological distance between
     /// two particles in the molecule, i.e. the number of bonds between the
@@ -196,4 +257,14 @@ mod test {
         assert_eq!(dihedral.k, 6);
         assert_eq!(dihedral.m, 8);
     }
+
+    #[test]
+    fn improper() {
+        let improper = Improper::new(8, 7, 6, 0);
+        assert_eq!(improper.i, 8);
+        assert_eq!(improper.j, 7);
+        assert_eq!(improper.k, 6);
+        assert_eq!(improper.m, 0);
+        assert_ne!(improper, Improper::new(8, 6, 7, 0));
+    }
 }
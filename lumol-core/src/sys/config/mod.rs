@@ -19,7 +19,7 @@ mod cells;
 pub use self::cells::{CellShape, UnitCell};
 
 mod connect;
-pub use self::connect::{Angle, Bond, Dihedral};
+pub use self::connect::{Angle, Bond, Dihedral, Improper};
 pub use self::connect::BondDistances;
 
 mod bonding;
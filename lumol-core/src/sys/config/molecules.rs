@@ -28,12 +28,24 @@ impl MoleculeHash {
 ///
 /// [`Molecule`] implement `Deref` to a [`Bonding`] struct, to give read access
 /// to all the bonds. It does not implement `DerefMut`, adding new bonds should
-/// be done through [`Molecule::add_bond()`].
+/// be done through [`Molecule::add_bond()`]. The angles and dihedral angles
+/// are generated automatically from the bonds, and do not need to be added
+/// separately. Improper dihedral angles and Urey-Bradley terms can not be
+/// deduced from the bonds, and should be added with
+/// [`Molecule::add_improper()`] and [`Molecule::add_urey_bradley()`].
 ///
 /// [`Molecule`]: struct.Molecule.html
 /// [`Bonding`]: struct.Bonding.html
 /// [`Molecule::add_bond()`]: struct.Molecule.html#method.add_bond
+/// [`Molecule::add_improper()`]: struct.Molecule.html#method.add_improper
+/// [`Molecule::add_urey_bradley()`]: struct.Molecule.html#method.add_urey_bradley
+///
+/// When the `serde` feature is enabled, a `Molecule` is serialized as the list
+/// of its particles, and the lists of bonds, impropers and Urey-Bradley terms
+/// between them.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SerializedMolecule", into = "SerializedMolecule"))]
 pub struct Molecule {
     pub(crate) bonding: Bonding,
     pub(crate) particles: ParticleVec
@@ -126,6 +138,60 @@ impl Molecule {
     pub fn add_bond(&mut self, i: usize, j: usize) {
         self.bonding.add_bond(i, j);
     }
+
+    /// Add an improper dihedral angle between particles at indexes `i`, `j`,
+    /// `k` and `m` in this molecule. The particle `i` is the central
+    /// particle, and the associated out-of-plane angle is the angle between
+    /// the `i-m` vector and the plane containing `i`, `j` and `k`.
+    ///
+    /// Improper dihedral angles are typically used to keep planar groups
+    /// (aromatic rings, amides, *etc.*) flat.
+    ///
+    /// # Panics
+    ///
+    /// If any of the particles is not in this molecule, or if the same
+    /// particle is used twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Molecule, Particle, Improper};
+    /// let mut molecule = Molecule::new(Particle::new("C"));
+    /// molecule.add_particle_bonded_to(0, Particle::new("O"));
+    /// molecule.add_particle_bonded_to(0, Particle::new("H"));
+    /// molecule.add_particle_bonded_to(0, Particle::new("H"));
+    ///
+    /// molecule.add_improper(0, 2, 3, 1);
+    /// assert!(molecule.impropers().contains(&Improper::new(0, 2, 3, 1)));
+    /// ```
+    pub fn add_improper(&mut self, i: usize, j: usize, k: usize, m: usize) {
+        self.bonding.add_improper(i, j, k, m);
+    }
+
+    /// Add an Urey-Bradley term to the angle between particles at indexes
+    /// `i`, `j` and `k` in this molecule. This is an interaction depending on
+    /// the distance between the particles `i` and `k`, as used in the CHARMM
+    /// force field.
+    ///
+    /// # Panics
+    ///
+    /// If the particles `i`, `j` and `k` do not form an angle in this
+    /// molecule, *i.e.* if they are not bonded together as `i-j-k`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Molecule, Particle, Angle};
+    /// let mut molecule = Molecule::new(Particle::new("O"));
+    /// molecule.add_particle_bonded_to(0, Particle::new("H"));
+    /// molecule.add_particle_bonded_to(0, Particle::new("H"));
+    ///
+    /// molecule.add_urey_bradley(1, 0, 2);
+    /// assert!(molecule.urey_bradleys().contains(&Angle::new(1, 0, 2)));
+    /// ```
+    pub fn add_urey_bradley(&mut self, i: usize, j: usize, k: usize) {
+        self.bonding.add_urey_bradley(i, j, k);
+    }
 }
 
 impl Deref for Molecule {
@@ -264,9 +330,10 @@ impl_on!(Molecule, MoleculeRef<'a>, MoleculeRefMut<'a>, => {
     }
 
     /// Get a hash of this molecule. This is a hash of the particles names (in
-    /// order), and the set of bonds in the molecule. This means that two
-    /// molecules will have the same type if and only if they contains the same
-    /// atoms and the same bonds, **in the same order**.
+    /// order), and the set of bonds, improper dihedral angles and
+    /// Urey-Bradley terms in the molecule. This means that two molecules will
+    /// have the same type if and only if they contains the same atoms, the
+    /// same bonds, impropers and Urey-Bradley terms, **in the same order**.
     pub fn hash(&self) -> MoleculeHash {
         let mut hasher = DefaultHasher::new();
         self.bonding.hash(&mut hasher);
@@ -296,10 +363,115 @@ impl_on!(Molecule, MoleculeRefMut<'a>, => {
     }
 });
 
+/// Serialized representation of a `Molecule`, with bonds indexes relative to
+/// the first particle of the molecule.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedMolecule {
+    particles: Vec<Particle>,
+    bonds: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    impropers: Vec<(usize, usize, usize, usize)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    urey_bradleys: Vec<(usize, usize, usize)>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Molecule> for SerializedMolecule {
+    fn from(molecule: Molecule) -> SerializedMolecule {
+        let particles = soa_zip!(molecule.particles(), [name, kind, charge, mass, position, velocity])
+            .map(|(name, &kind, &charge, &mass, &position, &velocity)| Particle {
+                name: name.clone(),
+                kind: kind,
+                charge: charge,
+                mass: mass,
+                position: position,
+                velocity: velocity,
+            }).collect();
+
+        // Sort the bonds to get reproducible output
+        let start = molecule.start();
+        let mut bonds = molecule.bonds().iter()
+            .map(|bond| (bond.i() - start, bond.j() - start))
+            .collect::<Vec<_>>();
+        bonds.sort_unstable();
+
+        let mut impropers = molecule.impropers().iter()
+            .map(|improper| (
+                improper.i() - start,
+                improper.j() - start,
+                improper.k() - start,
+                improper.m() - start,
+            ))
+            .collect::<Vec<_>>();
+        impropers.sort_unstable();
+
+        let mut urey_bradleys = molecule.urey_bradleys().iter()
+            .map(|angle| (angle.i() - start, angle.j() - start, angle.k() - start))
+            .collect::<Vec<_>>();
+        urey_bradleys.sort_unstable();
+
+        SerializedMolecule {
+            particles: particles,
+            bonds: bonds,
+            impropers: impropers,
+            urey_bradleys: urey_bradleys,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<SerializedMolecule> for Molecule {
+    type Error = String;
+
+    fn try_from(serialized: SerializedMolecule) -> Result<Molecule, String> {
+        let size = serialized.particles.len();
+        let mut particles = serialized.particles.into_iter();
+        let mut molecule = match particles.next() {
+            Some(particle) => Molecule::new(particle),
+            None => return Err("a molecule must contain at least one particle".into()),
+        };
+        for (i, particle) in particles.enumerate() {
+            molecule.particles.push(particle);
+            molecule.bonding.merge_with(Bonding::new(i + 1));
+        }
+
+        for (i, j) in serialized.bonds {
+            if i >= size || j >= size || i == j {
+                return Err(format!("invalid bond between {} and {} in molecule", i, j));
+            }
+            molecule.add_bond(i, j);
+        }
+
+        for (i, j, k, m) in serialized.impropers {
+            let indexes = [i, j, k, m];
+            let repeated = (0..4).any(|a| (0..a).any(|b| indexes[a] == indexes[b]));
+            if indexes.iter().any(|&index| index >= size) || repeated {
+                return Err(format!(
+                    "invalid improper between {}, {}, {} and {} in molecule", i, j, k, m
+                ));
+            }
+            molecule.add_improper(i, j, k, m);
+        }
+
+        for (i, j, k) in serialized.urey_bradleys {
+            if i >= size || j >= size || k >= size || i == j || i == k || j == k
+                || !molecule.angles().contains(&crate::Angle::new(i, j, k))
+            {
+                return Err(format!(
+                    "invalid Urey-Bradley term between {}, {} and {} in molecule", i, j, k
+                ));
+            }
+            molecule.add_urey_bradley(i, j, k);
+        }
+        return Ok(molecule);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ParticleKind;
+    use crate::{Angle, Dihedral, ParticleKind};
 
     use lazy_static::lazy_static;
 
@@ -330,6 +502,46 @@ mod tests {
         assert_eq!(molecule.center_of_mass(), Vector3D::new(0.5, 0.0, 0.0));
     }
 
+    #[test]
+    fn angles_and_dihedrals() {
+        // Linear chain: 0 - 1 - 2 - 3
+        let mut molecule = Molecule::new(particle("C"));
+        for i in 0..3 {
+            molecule.add_particle_bonded_to(i, particle("C"));
+        }
+
+        assert_eq!(molecule.bonds().len(), 3);
+        assert_eq!(molecule.angles().len(), 2);
+        assert!(molecule.angles().contains(&Angle::new(0, 1, 2)));
+        assert!(molecule.angles().contains(&Angle::new(1, 2, 3)));
+        assert_eq!(molecule.dihedrals().len(), 1);
+        assert!(molecule.dihedrals().contains(&Dihedral::new(0, 1, 2, 3)));
+
+        // Branched molecule:
+        //       2
+        //       |
+        //   3 - 0 - 1 - 4
+        let mut molecule = Molecule::new(particle("C"));
+        molecule.add_particle_bonded_to(0, particle("C"));
+        molecule.add_particle_bonded_to(0, particle("C"));
+        molecule.add_particle_bonded_to(0, particle("C"));
+        molecule.add_particle_bonded_to(1, particle("C"));
+
+        let angles = [
+            Angle::new(1, 0, 2), Angle::new(1, 0, 3), Angle::new(2, 0, 3), Angle::new(0, 1, 4),
+        ];
+        assert_eq!(molecule.angles().len(), angles.len());
+        for angle in &angles {
+            assert!(molecule.angles().contains(angle));
+        }
+
+        let dihedrals = [Dihedral::new(2, 0, 1, 4), Dihedral::new(3, 0, 1, 4)];
+        assert_eq!(molecule.dihedrals().len(), dihedrals.len());
+        for dihedral in &dihedrals {
+            assert!(molecule.dihedrals().contains(dihedral));
+        }
+    }
+
     #[test]
     fn test_wrap_molecule() {
         let mut molecule = Molecule::new(particle("O"));
@@ -343,4 +555,37 @@ mod tests {
         assert_eq!(molecule.particles().position[1], Vector3D::new(5.0, 0.0, 0.0));
         assert_eq!(molecule.center_of_mass(), Vector3D::new(4.0, 0.0, 0.0))
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let mut molecule = Molecule::new(particle("O"));
+        molecule.add_particle_bonded_to(0, particle("H"));
+        molecule.add_particle_bonded_to(0, particle("H"));
+        molecule.add_particle_bonded_to(0, particle("H"));
+        molecule.add_improper(0, 1, 2, 3);
+        molecule.add_urey_bradley(1, 0, 2);
+        molecule.particles_mut().position[1] = Vector3D::new(1.0, 0.0, 0.0);
+        molecule.particles_mut().charge[0] = -0.8;
+
+        let json = serde_json::to_string(&molecule).unwrap();
+        let restored: Molecule = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.particles().name, molecule.particles().name);
+        assert_eq!(restored.particles().kind, molecule.particles().kind);
+        assert_eq!(restored.particles().charge, molecule.particles().charge);
+        assert_eq!(restored.particles().position, molecule.particles().position);
+        assert_eq!(restored.bonds(), molecule.bonds());
+        assert_eq!(restored.angles(), molecule.angles());
+        assert_eq!(restored.impropers(), molecule.impropers());
+        assert_eq!(restored.urey_bradleys(), molecule.urey_bradleys());
+        assert_eq!(restored.hash(), molecule.hash());
+
+        assert!(serde_json::from_str::<Molecule>(r#"{"particles": [], "bonds": []}"#).is_err());
+        let invalid = json.replace("[0,1]", "[0,4]");
+        assert!(serde_json::from_str::<Molecule>(&invalid).is_err());
+        let invalid = json.replace("[0,1,2,3]", "[0,1,1,3]");
+        assert!(serde_json::from_str::<Molecule>(&invalid).is_err());
+        let invalid = json.replace("[1,0,2]", "[0,1,2]");
+        assert!(serde_json::from_str::<Molecule>(&invalid).is_err());
+    }
 }
@@ -9,6 +9,7 @@ use crate::Vector3D;
 /// A particle kind. Particles with the same name will have the same kind. This
 /// is used for faster potential lookup.
 #[derive(Clone, Copy, Hash, PartialOrd, Ord, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParticleKind(pub u32);
 
 impl ParticleKind {
@@ -28,6 +29,7 @@ impl fmt::Display for ParticleKind {
 /// contained, so that it will be easy to send data between parallels processes.
 #[derive(Clone, Debug, StructOfArray)]
 #[soa_derive = "Clone, Debug"]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Particle {
     /// Particle name.
     pub name: String,
@@ -8,10 +8,9 @@
 
 use std::f64::consts::PI;
 
-use rayon::prelude::*;
-
-use crate::BondPath;
+use crate::{BondPath, Composition};
 use crate::System;
+use crate::utils::each_i_reduce;
 
 /// An helper struct to evaluate energy components of a system.
 pub struct EnergyEvaluator<'a> {
@@ -45,28 +44,34 @@ impl<'a> EnergyEvaluator<'a> {
 
     /// Compute the energy of all the pairs in the system
     pub fn pairs(&self) -> f64 {
-        let energies = (0..self.system.size()).into_par_iter().map(|i| {
+        let size = self.system.size();
+        return self.system.install(|| each_i_reduce(size, || 0.0, |i| {
             let mut local_energy = 0.0;
 
-            for j in (i + 1)..self.system.size() {
+            for j in (i + 1)..size {
                 let r = self.system.nearest_image(i, j).norm();
                 let path = self.system.bond_path(i, j);
                 local_energy += self.pair(path, r, i, j);
             }
             local_energy
-        });
-        return energies.sum();
+        }, |a, b| a + b));
     }
 
     /// Compute the energy due to long range corrections for the pairs
     #[inline]
     pub fn pairs_tail(&self) -> f64 {
+        return self.pairs_tail_with_composition(&self.system.composition());
+    }
+
+    /// Compute the long range correction energy for the pairs interactions,
+    /// if the system contained the particles in `composition` instead of its
+    /// own particles.
+    pub fn pairs_tail_with_composition(&self, composition: &Composition) -> f64 {
         if self.system.cell.is_infinite() {
             return 0.0;
         }
         let mut energy = 0.0;
         let volume = self.system.volume();
-        let composition = self.system.composition();
         for (i, ni) in composition.all_particles() {
             for (j, nj) in composition.all_particles() {
                 let two_pi_density = 2.0 * PI * (ni as f64) * (nj as f64) / volume;
@@ -140,11 +145,53 @@ impl<'a> EnergyEvaluator<'a> {
         return energy;
     }
 
+    /// Compute the energy associated with the Urey-Bradley term of the angle
+    /// `i, j, k`, at distance `r` between `i` and `k`
+    #[inline]
+    pub fn urey_bradley(&self, r: f64, i: usize, j: usize, k: usize) -> f64 {
+        self.system.urey_bradley_potential(i, j, k)
+                   .map_or(0.0, |potential| potential.energy(r))
+    }
+
+    /// Compute the energy of all the Urey-Bradley terms in the system
+    pub fn urey_bradleys(&self) -> f64 {
+        let mut energy = 0.0;
+        for molecule in self.system.molecules() {
+            for angle in molecule.urey_bradleys() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                let r = self.system.nearest_image(i, k).norm();
+                energy += self.urey_bradley(r, i, j, k);
+            }
+        }
+        return energy;
+    }
+
+    /// Compute the energy associated with the improper dihedral angle
+    /// `i, j, k, m` at out-of-plane angle `chi`
+    #[inline]
+    pub fn improper(&self, chi: f64, i: usize, j: usize, k: usize, m: usize) -> f64 {
+        self.system.improper_potential(i, j, k, m)
+                   .map_or(0.0, |potential| potential.energy(chi))
+    }
+
+    /// Compute the energy of all the improper dihedral angles in the system
+    pub fn impropers(&self) -> f64 {
+        let mut energy = 0.0;
+        for molecule in self.system.molecules() {
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                let chi = self.system.out_of_plane(i, j, k, m);
+                energy += self.improper(chi, i, j, k, m);
+            }
+        }
+        return energy;
+    }
+
     /// Compute the energy of the electrostatic interactions
     #[inline]
     pub fn coulomb(&self) -> f64 {
         if let Some(coulomb) = self.system.coulomb_potential() {
-            coulomb.energy(self.system)
+            self.system.install(|| coulomb.energy(self.system))
         } else {
             0.0
         }
@@ -155,7 +202,7 @@ impl<'a> EnergyEvaluator<'a> {
     pub fn global(&self) -> f64 {
         let mut energy = 0.0;
         for global in self.system.global_potentials() {
-            energy += global.energy(self.system);
+            energy += self.system.install(|| global.energy(self.system));
         }
         return energy;
     }
@@ -164,7 +211,7 @@ impl<'a> EnergyEvaluator<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Harmonic, LennardJones, NullPotential, PairInteraction};
+    use crate::{Harmonic, LennardJones, NullPotential, PairInteraction, PairRestriction};
     use crate::{System, UnitCell};
     use crate::utils::system_from_xyz;
     use crate::units;
@@ -234,6 +281,37 @@ mod tests {
         assert_ulps_eq!(evaluator.pairs_tail(), -0.0000028110338032153973);
     }
 
+    #[test]
+    fn pairs_restrictions() {
+        let mut system = testing_system();
+        let mut pair = PairInteraction::new(
+            Box::new(LennardJones {
+                epsilon: units::from(100.0, "kJ/mol/A^2").unwrap(),
+                sigma: units::from(0.8, "A").unwrap(),
+            }),
+            5.0,
+        );
+        pair.set_restriction(PairRestriction::Scale14(0.5));
+        system.set_pair_potential(("F", "F"), pair);
+
+        // All the pairs are inside the cutoff, and only the 1-4 pair is not
+        // excluded by the restriction.
+        let evaluator = EnergyEvaluator::new(&system);
+        let r = system.nearest_image(0, 3).norm();
+        let expected = 0.5 * system.pair_potential(0, 3).unwrap().energy(r);
+        assert_ulps_eq!(evaluator.pairs(), expected);
+
+        // Check against an explicit sum over all pairs
+        let mut all_pairs = 0.0;
+        for i in 0..system.size() {
+            for j in (i + 1)..system.size() {
+                let r = system.nearest_image(i, j).norm();
+                all_pairs += evaluator.pair(system.bond_path(i, j), r, i, j);
+            }
+        }
+        assert_ulps_eq!(evaluator.pairs(), all_pairs);
+    }
+
     #[test]
     fn pairs_tail_infinite_cell() {
         let mut system = testing_system();
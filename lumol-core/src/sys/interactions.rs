@@ -74,6 +74,12 @@ pub struct Interactions {
     angles: BTreeMap<AngleKind, Box<dyn AnglePotential>>,
     /// Dihedral angles potentials
     dihedrals: BTreeMap<DihedralKind, Box<dyn DihedralPotential>>,
+    /// Improper dihedral angles potentials, the first kind being the central
+    /// particle
+    impropers: BTreeMap<DihedralKind, Box<dyn DihedralPotential>>,
+    /// Urey-Bradley potentials, acting between the first and last particles
+    /// of angles
+    urey_bradleys: BTreeMap<AngleKind, Box<dyn BondPotential>>,
     /// Association particles names to particle kinds
     kinds: BTreeMap<String, ParticleKind>,
 }
@@ -88,6 +94,8 @@ impl Interactions {
             bonds: BTreeMap::new(),
             angles: BTreeMap::new(),
             dihedrals: BTreeMap::new(),
+            impropers: BTreeMap::new(),
+            urey_bradleys: BTreeMap::new(),
             kinds: BTreeMap::new(),
         }
     }
@@ -136,6 +144,35 @@ impl Interactions {
             warn!("replaced dihedral angle potential for ({}, {}, {}, {})", i, j, k, m);
         }
     }
+
+    /// Set the improper dihedral angle interaction `potential` for atoms with
+    /// types `i`, `j`, `k`, and `m`, where `i` is the central atom. The order
+    /// of the types is significant, and only improper dihedral angles with
+    /// the same order use this potential.
+    pub fn set_improper(
+        &mut self,
+        (i, j, k, m): (&str, &str, &str, &str),
+        potential: Box<dyn DihedralPotential>,
+    ) {
+        let kind = (self.get_kind(i), self.get_kind(j), self.get_kind(k), self.get_kind(m));
+        if self.impropers.insert(kind, potential).is_some() {
+            warn!("replaced improper potential for ({}, {}, {}, {})", i, j, k, m);
+        }
+    }
+
+    /// Set the Urey-Bradley interaction `potential` for the angles between
+    /// atoms with types `i`, `j`, and `k`. The potential acts on the distance
+    /// between the atoms `i` and `k`.
+    pub fn set_urey_bradley(
+        &mut self,
+        (i, j, k): (&str, &str, &str),
+        potential: Box<dyn BondPotential>,
+    ) {
+        let kind = normalize_angle((self.get_kind(i), self.get_kind(j), self.get_kind(k)));
+        if self.urey_bradleys.insert(kind, potential).is_some() {
+            warn!("replaced Urey-Bradley potential for ({}, {}, {})", i, j, k);
+        }
+    }
 }
 
 
@@ -164,6 +201,19 @@ impl Interactions {
         self.dihedrals.get(&kind).map(|potential| &**potential)
     }
 
+    /// Get the improper dihedral interactions corresponding to the
+    /// `improper`, if any exists.
+    pub fn improper(&self, improper: DihedralKind) -> Option<&dyn DihedralPotential> {
+        self.impropers.get(&improper).map(|potential| &**potential)
+    }
+
+    /// Get the Urey-Bradley interactions corresponding to the `angle`, if any
+    /// exists.
+    pub fn urey_bradley(&self, angle: AngleKind) -> Option<&dyn BondPotential> {
+        let kind = normalize_angle(angle);
+        self.urey_bradleys.get(&kind).map(|potential| &**potential)
+    }
+
     /// Get maximum cutoff from `coulomb`, `pairs` and `global` interactons.
     pub fn maximum_cutoff(&self) -> Option<f64> {
         // Coulomb potential, return cutoff
@@ -408,6 +458,28 @@ mod test {
         assert!(interactions.dihedral((Kind(55), Kind(55), Kind(55), Kind(55))).is_none());
     }
 
+    #[test]
+    fn impropers() {
+        let mut interactions = Interactions::new();
+
+        interactions.set_improper(("A", "B", "C", "D"), Box::new(NullPotential));
+        assert!(interactions.improper((Kind(0), Kind(1), Kind(2), Kind(3))).is_some());
+        assert!(interactions.improper((Kind(0), Kind(2), Kind(1), Kind(3))).is_none());
+        assert!(interactions.improper((Kind(3), Kind(2), Kind(1), Kind(0))).is_none());
+        assert!(interactions.dihedral((Kind(0), Kind(1), Kind(2), Kind(3))).is_none());
+    }
+
+    #[test]
+    fn urey_bradleys() {
+        let mut interactions = Interactions::new();
+
+        interactions.set_urey_bradley(("A", "B", "C"), Box::new(NullPotential));
+        assert!(interactions.urey_bradley((Kind(0), Kind(1), Kind(2))).is_some());
+        assert!(interactions.urey_bradley((Kind(2), Kind(1), Kind(0))).is_some());
+        assert!(interactions.urey_bradley((Kind(1), Kind(0), Kind(2))).is_none());
+        assert!(interactions.angle((Kind(0), Kind(1), Kind(2))).is_none());
+    }
+
     #[test]
     fn test_maximum_cutoff() {
         let mut interactions = Interactions::new();
@@ -0,0 +1,709 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Reading and writing systems in LAMMPS data files.
+//!
+//! The files use the `full` atom style, and contain the simulation box, the
+//! atoms with their molecule, type and charge, the velocities, and the bonds,
+//! angles and dihedral angles. Positions are written in Angstroms and
+//! velocities in Angstroms per femtoseconds, matching LAMMPS `real` units.
+//! Bonds, angles and dihedral angles types are created from the atom types of
+//! the particles involved.
+//!
+//! LAMMPS identifies atom types with numbers. The particle name associated
+//! with each type is written as a comment in the `Masses` section, which is
+//! the usual convention for LAMMPS data files.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use soa_derive::soa_zip;
+
+use crate::{Bond, CellShape, Matrix3, Molecule, Particle, System, UnitCell, Vector3D};
+
+fn invalid_input<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+fn invalid_data<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Parse the `field` of a `line` in a LAMMPS data file
+fn parse<T: FromStr>(field: &str, line: &str) -> io::Result<T> {
+    return field.parse().map_err(|_| invalid_data(format!(
+        "invalid value '{}' in LAMMPS data file line '{}'", field, line.trim()
+    )));
+}
+
+/// Check that a `line` with the given `fields` contains at least `count`
+/// values
+fn check_fields(fields: &[&str], count: usize, line: &str) -> io::Result<()> {
+    if fields.len() < count {
+        return Err(invalid_data(format!(
+            "expected {} values in LAMMPS data file line '{}'", count, line.trim()
+        )));
+    }
+    return Ok(());
+}
+
+/// Sections of a LAMMPS data file
+#[derive(Clone, Copy)]
+enum Section {
+    Header,
+    Masses,
+    Atoms,
+    Velocities,
+    Bonds,
+    /// Sections not used by Lumol: force field coefficients, angles,
+    /// dihedral angles, *etc.*
+    Ignored,
+}
+
+/// An atom in the `Atoms` section of a LAMMPS data file
+struct Atom {
+    id: usize,
+    kind: usize,
+    charge: f64,
+    position: Vector3D,
+    image: Vector3D,
+}
+
+/// Content of a LAMMPS data file used to create a system
+#[derive(Default)]
+struct DataFile {
+    natoms: usize,
+    /// Lower and upper bounds of the box in each direction
+    bounds: [[f64; 2]; 3],
+    /// The xy, xz and yz tilt factors of the box
+    tilts: [f64; 3],
+    masses: BTreeMap<usize, f64>,
+    /// Names from the comments in the `Masses` section
+    names: BTreeMap<usize, String>,
+    atoms: Vec<Atom>,
+    velocities: BTreeMap<usize, Vector3D>,
+    bonds: Vec<(usize, usize)>,
+}
+
+impl DataFile {
+    /// Read a LAMMPS data file from `file`
+    fn read<R: BufRead>(file: &mut R) -> io::Result<DataFile> {
+        let mut data = DataFile::default();
+        let mut section = Section::Header;
+        // The first line of the file is a title
+        for line in file.lines().skip(1) {
+            let line = line?;
+            let (content, comment) = match line.find('#') {
+                Some(i) => (&line[..i], line[i + 1..].trim()),
+                None => (&line[..], ""),
+            };
+            let fields = content.split_whitespace().collect::<Vec<_>>();
+            if fields.is_empty() {
+                continue;
+            }
+
+            // Section names are the only lines not starting with a number
+            if fields[0].parse::<f64>().is_err() {
+                section = match content.trim() {
+                    "Masses" => Section::Masses,
+                    "Atoms" => {
+                        if !comment.is_empty() && comment != "full" {
+                            return Err(invalid_data(format!(
+                                "unsupported '{}' atom style in LAMMPS data file, \
+                                 only 'full' is supported", comment
+                            )));
+                        }
+                        Section::Atoms
+                    }
+                    "Velocities" => Section::Velocities,
+                    "Bonds" => Section::Bonds,
+                    _ => Section::Ignored,
+                };
+                continue;
+            }
+
+            match section {
+                Section::Header => data.read_header(&fields, &line)?,
+                Section::Masses => {
+                    check_fields(&fields, 2, &line)?;
+                    let kind = parse::<usize>(fields[0], &line)?;
+                    let _ = data.masses.insert(kind, parse(fields[1], &line)?);
+                    if let Some(name) = comment.split_whitespace().next() {
+                        let _ = data.names.insert(kind, name.to_owned());
+                    }
+                }
+                Section::Atoms => data.atoms.push(read_atom(&fields, &line)?),
+                Section::Velocities => {
+                    check_fields(&fields, 4, &line)?;
+                    let velocity = Vector3D::new(
+                        parse(fields[1], &line)?,
+                        parse(fields[2], &line)?,
+                        parse(fields[3], &line)?,
+                    );
+                    let _ = data.velocities.insert(parse(fields[0], &line)?, velocity);
+                }
+                Section::Bonds => {
+                    check_fields(&fields, 4, &line)?;
+                    let i = parse(fields[2], &line)?;
+                    let j = parse(fields[3], &line)?;
+                    data.bonds.push((i, j));
+                }
+                Section::Ignored => {}
+            }
+        }
+        return Ok(data);
+    }
+
+    /// Read a `line` with the given `fields` in the header of the file
+    fn read_header(&mut self, fields: &[&str], line: &str) -> io::Result<()> {
+        match fields {
+            [count, "atoms"] => self.natoms = parse(count, line)?,
+            [lo, hi, "xlo", "xhi"] => self.bounds[0] = [parse(lo, line)?, parse(hi, line)?],
+            [lo, hi, "ylo", "yhi"] => self.bounds[1] = [parse(lo, line)?, parse(hi, line)?],
+            [lo, hi, "zlo", "zhi"] => self.bounds[2] = [parse(lo, line)?, parse(hi, line)?],
+            [xy, xz, yz, "xy", "xz", "yz"] => {
+                self.tilts = [parse(xy, line)?, parse(xz, line)?, parse(yz, line)?];
+            }
+            // Other counts and types are not needed
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    /// Get the unit cell corresponding to the box in this file
+    fn cell(&self) -> io::Result<UnitCell> {
+        let lengths = [
+            self.bounds[0][1] - self.bounds[0][0],
+            self.bounds[1][1] - self.bounds[1][0],
+            self.bounds[2][1] - self.bounds[2][0],
+        ];
+        if lengths.iter().any(|&length| length <= 0.0) {
+            return Err(invalid_data("missing or invalid box in LAMMPS data file"));
+        }
+
+        let [xy, xz, yz] = self.tilts;
+        let matrix = Matrix3::new([
+            [lengths[0], xy, xz],
+            [0.0, lengths[1], yz],
+            [0.0, 0.0, lengths[2]],
+        ]);
+        let shape = if xy == 0.0 && xz == 0.0 && yz == 0.0 {
+            CellShape::Orthorhombic
+        } else {
+            CellShape::Triclinic
+        };
+        return Ok(UnitCell::from_matrix(matrix, shape));
+    }
+}
+
+/// Read an atom in the `full` style from a `line` with the given `fields`
+fn read_atom(fields: &[&str], line: &str) -> io::Result<Atom> {
+    check_fields(fields, 7, line)?;
+    let position = Vector3D::new(
+        parse(fields[4], line)?,
+        parse(fields[5], line)?,
+        parse(fields[6], line)?,
+    );
+
+    let mut image = Vector3D::zero();
+    if fields.len() >= 10 {
+        image = Vector3D::new(
+            f64::from(parse::<i32>(fields[7], line)?),
+            f64::from(parse::<i32>(fields[8], line)?),
+            f64::from(parse::<i32>(fields[9], line)?),
+        );
+    }
+
+    return Ok(Atom {
+        id: parse(fields[0], line)?,
+        kind: parse(fields[2], line)?,
+        charge: parse(fields[3], line)?,
+        position: position,
+        image: image,
+    });
+}
+
+/// Get the 1-based index of `value` in `types`, adding it at the end if it is
+/// not already there.
+fn type_index<T: PartialEq>(types: &mut Vec<T>, value: T) -> usize {
+    if let Some(i) = types.iter().position(|t| *t == value) {
+        return i + 1;
+    }
+    types.push(value);
+    return types.len();
+}
+
+impl System {
+    /// Save this system in a LAMMPS data file at `path`, using the `full` atom
+    /// style. The file is replaced if it already exists.
+    ///
+    /// LAMMPS identifies atom types with numbers, and `types` gives the name of
+    /// the particles for each LAMMPS type: particles named `types[0]` get the
+    /// type 1, particles named `types[1]` the type 2, *etc.* The mass of each
+    /// type is taken from the first particle with this name.
+    ///
+    /// The names in `types` are also written as comments in the `Masses`
+    /// section, to be used when reading the file with `load_lammps_data`.
+    ///
+    /// An error is returned if the cell is infinite or not in the LAMMPS
+    /// restricted triclinic form, if a particle name is not in `types`, or if
+    /// no particle has one of the names in `types`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::{System, Molecule, Particle, UnitCell};
+    /// let mut system = System::with_cell(UnitCell::cubic(10.0));
+    /// system.add_molecule(Molecule::new(Particle::new("Na")));
+    /// system.add_molecule(Molecule::new(Particle::new("Cl")));
+    ///
+    /// // Na will be the type 1 and Cl the type 2 in the data file
+    /// system.save_lammps_data("nacl.lmp", &["Na", "Cl"]).unwrap();
+    /// ```
+    pub fn save_lammps_data<P: AsRef<Path>>(&self, path: P, types: &[&str]) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_lammps_data(&mut file, types)?;
+        return file.flush();
+    }
+
+    /// Write this system in the LAMMPS data format to `file`, as done by
+    /// `save_lammps_data`.
+    pub fn write_lammps_data<W: Write>(&self, file: &mut W, types: &[&str]) -> io::Result<()> {
+        if self.cell.is_infinite() {
+            return Err(invalid_input("can not write a LAMMPS data file with an infinite cell"));
+        }
+        let matrix = self.cell.matrix();
+        if matrix[1][0] != 0.0 || matrix[2][0] != 0.0 || matrix[2][1] != 0.0 {
+            return Err(invalid_input(
+                "the first cell vector must be along x and the second one in the xy plane \
+                 to write a LAMMPS data file"
+            ));
+        }
+
+        let mut atom_types = Vec::with_capacity(self.size());
+        for name in self.particles().name {
+            match types.iter().position(|t| t == name) {
+                Some(i) => atom_types.push(i + 1),
+                None => {
+                    return Err(invalid_input(format!(
+                        "missing LAMMPS atom type for particles named '{}'", name
+                    )));
+                }
+            }
+        }
+
+        let mut masses = Vec::with_capacity(types.len());
+        for name in types {
+            match self.particles().name.iter().position(|n| n == name) {
+                Some(i) => masses.push(self.particles().mass[i]),
+                None => {
+                    return Err(invalid_input(format!(
+                        "no particle named '{}' for the LAMMPS atom types", name
+                    )));
+                }
+            }
+        }
+
+        // Sort all the terms to get reproducible files, and use the atom
+        // types of the particles to get the type of each term.
+        let mut bonds = Vec::new();
+        let mut angles = Vec::new();
+        let mut dihedrals = Vec::new();
+        for molecule in self.molecules() {
+            bonds.extend(molecule.bonds().iter().map(|b| (b.i(), b.j())));
+            angles.extend(molecule.angles().iter().map(|a| (a.i(), a.j(), a.k())));
+            dihedrals.extend(molecule.dihedrals().iter().map(|d| (d.i(), d.j(), d.k(), d.m())));
+        }
+        bonds.sort_unstable();
+        angles.sort_unstable();
+        dihedrals.sort_unstable();
+
+        let mut bond_types = Vec::new();
+        let bonds = bonds.into_iter().map(|(i, j)| {
+            let (ti, tj) = (atom_types[i], atom_types[j]);
+            let kind = if ti <= tj { (ti, tj) } else { (tj, ti) };
+            (type_index(&mut bond_types, kind), i, j)
+        }).collect::<Vec<_>>();
+
+        let mut angle_types = Vec::new();
+        let angles = angles.into_iter().map(|(i, j, k)| {
+            let (ti, tj, tk) = (atom_types[i], atom_types[j], atom_types[k]);
+            let kind = if ti <= tk { (ti, tj, tk) } else { (tk, tj, ti) };
+            (type_index(&mut angle_types, kind), i, j, k)
+        }).collect::<Vec<_>>();
+
+        let mut dihedral_types = Vec::new();
+        let dihedrals = dihedrals.into_iter().map(|(i, j, k, m)| {
+            let kind = (atom_types[i], atom_types[j], atom_types[k], atom_types[m]);
+            let reversed = (kind.3, kind.2, kind.1, kind.0);
+            let kind = if kind <= reversed { kind } else { reversed };
+            (type_index(&mut dihedral_types, kind), i, j, k, m)
+        }).collect::<Vec<_>>();
+
+        writeln!(file, "LAMMPS data file written by Lumol")?;
+        writeln!(file)?;
+        writeln!(file, "{} atoms", self.size())?;
+        writeln!(file, "{} bonds", bonds.len())?;
+        writeln!(file, "{} angles", angles.len())?;
+        writeln!(file, "{} dihedrals", dihedrals.len())?;
+        writeln!(file)?;
+        writeln!(file, "{} atom types", types.len())?;
+        writeln!(file, "{} bond types", bond_types.len())?;
+        writeln!(file, "{} angle types", angle_types.len())?;
+        writeln!(file, "{} dihedral types", dihedral_types.len())?;
+        writeln!(file)?;
+
+        writeln!(file, "0.0 {:?} xlo xhi", matrix[0][0])?;
+        writeln!(file, "0.0 {:?} ylo yhi", matrix[1][1])?;
+        writeln!(file, "0.0 {:?} zlo zhi", matrix[2][2])?;
+        if matrix[0][1] != 0.0 || matrix[0][2] != 0.0 || matrix[1][2] != 0.0 {
+            writeln!(file, "{:?} {:?} {:?} xy xz yz", matrix[0][1], matrix[0][2], matrix[1][2])?;
+        }
+        writeln!(file)?;
+
+        writeln!(file, "Masses")?;
+        writeln!(file)?;
+        for (i, (mass, name)) in masses.iter().zip(types).enumerate() {
+            writeln!(file, "{} {:?} # {}", i + 1, mass, name)?;
+        }
+        writeln!(file)?;
+
+        writeln!(file, "Atoms # full")?;
+        writeln!(file)?;
+        for (i, (charge, position)) in soa_zip!(self.particles(), [charge, position]).enumerate() {
+            writeln!(
+                file, "{} {} {} {:?} {:?} {:?} {:?}",
+                i + 1, self.molecule_id(i) + 1, atom_types[i], charge,
+                position[0], position[1], position[2],
+            )?;
+        }
+        writeln!(file)?;
+
+        writeln!(file, "Velocities")?;
+        writeln!(file)?;
+        for (i, velocity) in self.particles().velocity.iter().enumerate() {
+            writeln!(file, "{} {:?} {:?} {:?}", i + 1, velocity[0], velocity[1], velocity[2])?;
+        }
+
+        if !bonds.is_empty() {
+            writeln!(file)?;
+            writeln!(file, "Bonds")?;
+            writeln!(file)?;
+            for (n, (kind, i, j)) in bonds.into_iter().enumerate() {
+                writeln!(file, "{} {} {} {}", n + 1, kind, i + 1, j + 1)?;
+            }
+        }
+
+        if !angles.is_empty() {
+            writeln!(file)?;
+            writeln!(file, "Angles")?;
+            writeln!(file)?;
+            for (n, (kind, i, j, k)) in angles.into_iter().enumerate() {
+                writeln!(file, "{} {} {} {} {}", n + 1, kind, i + 1, j + 1, k + 1)?;
+            }
+        }
+
+        if !dihedrals.is_empty() {
+            writeln!(file)?;
+            writeln!(file, "Dihedrals")?;
+            writeln!(file)?;
+            for (n, (kind, i, j, k, m)) in dihedrals.into_iter().enumerate() {
+                writeln!(file, "{} {} {} {} {} {}", n + 1, kind, i + 1, j + 1, k + 1, m + 1)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Read a system from the LAMMPS data file at `path`, using the `full`
+    /// atom style.
+    ///
+    /// LAMMPS identifies atom types with numbers. If `types` is `Some`, it
+    /// gives the name of the particles for each LAMMPS type: particles with
+    /// the type 1 are named `types[0]`, particles with the type 2 `types[1]`,
+    /// *etc.* Else, the names are taken from the comments in the `Masses`
+    /// section, as written by `save_lammps_data`.
+    ///
+    /// The molecules are created from the bonds, and the angles and dihedral
+    /// angles are deduced from the bonds: the `Angles` and `Dihedrals`
+    /// sections are not read. The positions are unwrapped using the image
+    /// flags when they are present, and the masses are taken from the
+    /// `Masses` section.
+    ///
+    /// An error is returned if the file is malformed, if some atoms are
+    /// missing, or if there is no name for one of the atom types.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::System;
+    /// // Use the names in the comments of the Masses section
+    /// let system = System::load_lammps_data("nacl.lmp", None).unwrap();
+    ///
+    /// // The particles with type 1 are Na and the ones with type 2 are Cl
+    /// let types = ["Na", "Cl"];
+    /// let system = System::load_lammps_data("nacl.lmp", Some(&types[..])).unwrap();
+    /// ```
+    pub fn load_lammps_data<P: AsRef<Path>>(
+        path: P,
+        types: Option<&[&str]>,
+    ) -> io::Result<System> {
+        let mut file = BufReader::new(File::open(path)?);
+        return System::read_lammps_data(&mut file, types);
+    }
+
+    /// Read a system from a LAMMPS data `file`, as done by
+    /// `load_lammps_data`.
+    pub fn read_lammps_data<R: BufRead>(
+        file: &mut R,
+        types: Option<&[&str]>,
+    ) -> io::Result<System> {
+        let mut data = DataFile::read(file)?;
+        let natoms = data.natoms;
+        data.atoms.sort_by_key(|atom| atom.id);
+        let contiguous = data.atoms.iter().enumerate().all(|(i, atom)| atom.id == i + 1);
+        if data.atoms.len() != natoms || !contiguous {
+            return Err(invalid_data(format!(
+                "expected atoms with ids from 1 to {} in LAMMPS data file", natoms
+            )));
+        }
+
+        let cell = data.cell()?;
+        let origin = Vector3D::new(data.bounds[0][0], data.bounds[1][0], data.bounds[2][0]);
+        let mut system = System::with_cell(cell);
+        for atom in &data.atoms {
+            let name = match types {
+                Some(types) => {
+                    atom.kind.checked_sub(1).and_then(|i| types.get(i)).map(|&name| name.into())
+                }
+                None => data.names.get(&atom.kind).cloned(),
+            };
+            let name = name.ok_or_else(|| invalid_data(format!(
+                "missing particle name for LAMMPS atom type {}", atom.kind
+            )))?;
+
+            let position = atom.position - origin + cell.cartesian(&atom.image);
+            let mut particle = Particle::with_position(name, position);
+            particle.charge = atom.charge;
+            if let Some(&mass) = data.masses.get(&atom.kind) {
+                particle.mass = mass;
+            }
+            if let Some(&velocity) = data.velocities.get(&atom.id) {
+                particle.velocity = velocity;
+            }
+            system.add_molecule(Molecule::new(particle));
+        }
+
+        let bonds = data.bonds.iter().map(|&(i, j)| {
+            if i == 0 || j == 0 || i > natoms || j > natoms || i == j {
+                return Err(invalid_data(format!(
+                    "invalid bond between atoms {} and {} in LAMMPS data file", i, j
+                )));
+            }
+            return Ok(Bond::new(i - 1, j - 1));
+        }).collect::<io::Result<Vec<_>>>()?;
+        let _ = system.rebuild_molecules_from_bonds(&bonds);
+
+        return Ok(system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{System, Molecule, Particle, UnitCell, Vector3D};
+
+    fn hydrogen_peroxide() -> System {
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 11.0, 12.5));
+        let particle = |name: &str, x, y, z| Particle::with_position(name, Vector3D::new(x, y, z));
+        let mut molecule = Molecule::new(particle("H", 0.9, 0.0, -0.3));
+        molecule.add_particle_bonded_to(0, particle("O", 0.0, 0.0, 0.0));
+        molecule.add_particle_bonded_to(1, particle("O", 0.0, 0.0, 1.5));
+        molecule.add_particle_bonded_to(2, particle("H", -0.9, 0.0, 1.8));
+        system.add_molecule(molecule);
+        system.add_molecule(Molecule::new(particle("Ar", 5.0, 6.0, 7.0)));
+
+        system.particles_mut().velocity[0] = Vector3D::new(1e-3, -2e-3, 0.0);
+        system.particles_mut().velocity[4] = Vector3D::new(0.1, 0.2, 0.3);
+        return system;
+    }
+
+    #[test]
+    fn write() {
+        let system = hydrogen_peroxide();
+        let mut output = Vec::new();
+        system.write_lammps_data(&mut output, &["O", "H", "Ar"]).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("5 atoms\n3 bonds\n2 angles\n1 dihedrals\n"));
+        assert!(output.contains("3 atom types\n2 bond types\n1 angle types\n1 dihedral types\n"));
+        assert!(output.contains("0.0 11.0 ylo yhi\n"));
+        assert!(!output.contains("xy xz yz"));
+        assert!(output.contains("Masses\n\n1 15.999 # O\n2 1.008 # H\n3 39.948 # Ar\n"));
+        assert!(output.contains("Atoms # full\n\n1 1 2 0.0 0.9 0.0 -0.3\n"));
+        assert!(output.contains("5 2 3 0.0 5.0 6.0 7.0\n"));
+        assert!(output.contains("Velocities\n\n1 0.001 -0.002 0.0\n"));
+        assert!(output.contains("Bonds\n\n1 1 1 2\n2 2 2 3\n3 1 3 4\n"));
+        assert!(output.contains("Dihedrals\n\n1 1 1 2 3 4\n"));
+    }
+
+    #[test]
+    fn triclinic() {
+        let mut system = hydrogen_peroxide();
+        system.cell = UnitCell::triclinic(10.0, 11.0, 12.5, 90.0, 80.0, 100.0);
+        let mut output = Vec::new();
+        system.write_lammps_data(&mut output, &["O", "H", "Ar"]).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let matrix = system.cell.matrix();
+        let tilts = format!("{:?} {:?} {:?} xy xz yz\n", matrix[0][1], matrix[0][2], matrix[1][2]);
+        assert!(output.contains(&tilts));
+    }
+
+    #[test]
+    fn errors() {
+        let mut system = hydrogen_peroxide();
+        let mut output = Vec::new();
+        assert!(system.write_lammps_data(&mut output, &["O", "H"]).is_err());
+        assert!(system.write_lammps_data(&mut output, &["O", "H", "Ar", "Zn"]).is_err());
+
+        system.cell = UnitCell::infinite();
+        assert!(system.write_lammps_data(&mut output, &["O", "H", "Ar"]).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut system = hydrogen_peroxide();
+        system.cell = UnitCell::triclinic(10.0, 11.0, 12.5, 90.0, 80.0, 100.0);
+        system.particles_mut().charge[1] = -0.41;
+        system.particles_mut().charge[2] = -0.41;
+        system.particles_mut().charge[0] = 0.41;
+        system.particles_mut().charge[3] = 0.41;
+
+        let file = tempfile::Builder::new().suffix(".lmp").tempfile().unwrap();
+        system.save_lammps_data(file.path(), &["O", "H", "Ar"]).unwrap();
+
+        // Names from the comments in the Masses section
+        let read = System::load_lammps_data(file.path(), None).unwrap();
+        assert_eq!(read.size(), system.size());
+        assert_eq!(read.cell, system.cell);
+        assert_eq!(read.molecules().count(), 2);
+
+        assert_eq!(read.particles().name, system.particles().name);
+        assert_eq!(read.particles().kind, system.particles().kind);
+        assert_eq!(read.particles().mass, system.particles().mass);
+        assert_eq!(read.particles().charge, system.particles().charge);
+        assert_eq!(read.particles().position, system.particles().position);
+        assert_eq!(read.particles().velocity, system.particles().velocity);
+
+        let molecule = read.molecule(0);
+        assert_eq!(molecule.bonds(), system.molecule(0).bonds());
+        assert_eq!(molecule.angles().len(), 2);
+        assert_eq!(molecule.dihedrals().len(), 1);
+
+        // Names supplied by the user
+        let types = ["Ow", "Hw", "Kr"];
+        let read = System::load_lammps_data(file.path(), Some(&types[..])).unwrap();
+        assert_eq!(read.particles().name[0], "Hw");
+        assert_eq!(read.particles().name[1], "Ow");
+        assert_eq!(read.particles().name[4], "Kr");
+        // The masses still come from the file
+        assert_eq!(read.particles().mass, system.particles().mass);
+    }
+
+    #[test]
+    fn read() {
+        let content = "water and argon
+
+            4 atoms
+            2 bonds
+            2 atom types
+            -5.0 5.0 xlo xhi
+            0.0 10.0 ylo yhi
+            0.0 10.0 zlo zhi
+
+            Masses
+
+            1 15.999
+            2 1.008
+
+            Pair Coeffs # lj/cut
+
+            1 0.1553 3.166
+            2 0.0 0.0
+
+            Atoms # full
+
+            1 1 1 -0.8476 0.0 5.0 5.0 0 0 0
+            3 1 2 0.4238 -4.0 5.0 5.0 1 0 0
+            2 1 2 0.4238 1.0 5.0 5.0 0 0 0
+            4 2 1 0.0 3.0 2.0 1.0 0 -1 0
+
+            Bonds
+
+            1 1 1 2
+            2 1 1 3
+
+            Angles
+
+            1 1 2 1 3
+        ";
+        let types = ["O", "H"];
+        let system = System::read_lammps_data(&mut content.as_bytes(), Some(&types[..])).unwrap();
+
+        assert_eq!(system.size(), 4);
+        assert_eq!(system.molecules().count(), 2);
+        assert_eq!(system.cell, UnitCell::cubic(10.0));
+        assert_eq!(system.particles().name, ["O", "H", "H", "O"]);
+        assert_eq!(system.particles().charge[0], -0.8476);
+
+        // The origin of the box is moved to zero, and image flags are used
+        assert_eq!(system.particles().position[0], Vector3D::new(5.0, 5.0, 5.0));
+        assert_eq!(system.particles().position[1], Vector3D::new(6.0, 5.0, 5.0));
+        assert_eq!(system.particles().position[2], Vector3D::new(11.0, 5.0, 5.0));
+        assert_eq!(system.particles().position[3], Vector3D::new(8.0, -8.0, 1.0));
+        assert_eq!(system.particles().velocity[0], Vector3D::zero());
+
+        let molecule = system.molecule(0);
+        assert_eq!(molecule.bonds().len(), 2);
+        assert_eq!(molecule.angles().len(), 1);
+    }
+
+    #[test]
+    fn read_errors() {
+        let read = |content: &str, types: Option<&[&str]>| {
+            System::read_lammps_data(&mut content.as_bytes(), types)
+        };
+        let argon = Some(&["Ar"][..]);
+        let header = "title\n1 atoms\n0.0 10.0 xlo xhi\n0.0 10.0 ylo yhi\n0.0 10.0 zlo zhi\n";
+
+        let file = format!("{}Masses\n\n1 39.948\n\nAtoms\n\n1 1 1 0.0 1.0 2.0 3.0\n", header);
+        let error = read(&file, None).err().unwrap();
+        assert_eq!(error.to_string(), "missing particle name for LAMMPS atom type 1");
+        assert!(read(&file, argon).is_ok());
+        assert!(read(&file, Some(&[][..])).is_err());
+
+        let file = format!("{}Atoms # atomic\n\n1 1 0.0 0.0 0.0\n", header);
+        let error = read(&file, argon).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "unsupported 'atomic' atom style in LAMMPS data file, only 'full' is supported"
+        );
+
+        let file = format!("{}Atoms\n\n2 1 1 0.0 1.0 2.0 3.0\n", header);
+        let error = read(&file, argon).err().unwrap();
+        assert_eq!(error.to_string(), "expected atoms with ids from 1 to 1 in LAMMPS data file");
+
+        let file = format!("{}Atoms\n\n1 1 1 0.0 1.0 2.0\n", header);
+        assert!(read(&file, argon).is_err());
+
+        let file = format!("{}Atoms\n\n1 1 1 0.0 1.0 2.0 3.0\n\nBonds\n\n1 1 1 2\n", header);
+        let error = read(&file, argon).err().unwrap();
+        assert_eq!(error.to_string(), "invalid bond between atoms 1 and 2 in LAMMPS data file");
+
+        let file = "title\n1 atoms\n\nAtoms\n\n1 1 1 0.0 1.0 2.0 3.0\n";
+        let error = read(file, argon).err().unwrap();
+        assert_eq!(error.to_string(), "missing or invalid box in LAMMPS data file");
+    }
+}
@@ -19,9 +19,20 @@ pub use self::energy::EnergyEvaluator;
 mod cache;
 pub use self::cache::EnergyCache;
 
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
+#[cfg(feature = "checkpoint")]
+pub use self::checkpoint::SystemCheckpoint;
+mod lammps;
+mod xyz;
+
+mod builder;
+pub use self::builder::{Lattice, SystemBuilder};
+
 mod chfl;
 pub use chemfiles::Error as TrajectoryError;
 pub use self::chfl::{OpenMode, Trajectory, TrajectoryBuilder};
 pub use self::chfl::read_molecule;
 
 pub mod compute;
+pub mod water;
@@ -1,8 +1,11 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) 2015-2016 Lumol's contributors — BSD license
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
+use rayon::ThreadPool;
 use soa_derive::soa_zip;
 use log_once::warn_once;
 
@@ -20,8 +23,14 @@ pub enum DegreesOfFreedom {
     /// All molecules are simulated as rigid bodies
     Molecules,
     /// All particles are explicitly simulated, but some degrees of freedom
-    /// are frozen. The usize value is the number of frozen degree of freedom.
+    /// are frozen, constrained or removed (for example by removing the
+    /// center-of-mass motion). The usize value is the number of frozen degree
+    /// of freedom.
     Frozen(usize),
+    /// All molecules are simulated as rigid bodies, but some degrees of
+    /// freedom are removed (for example by removing the center-of-mass
+    /// motion). The usize value is the number of removed degree of freedom.
+    FrozenMolecules(usize),
 }
 
 /// The `System` type hold all the data about a simulated system.
@@ -42,6 +51,10 @@ pub struct System {
     configuration: Configuration,
     /// All the interactions in this system
     interactions: Interactions,
+    /// Named groups of particles, containing the particles indexes
+    groups: BTreeMap<String, BTreeSet<usize>>,
+    /// Pairs of groups without pair interactions between them
+    disabled_groups: Vec<(String, String)>,
     /// Externally managed temperature for the system
     external_temperature: Option<f64>,
     /// Number of degrees of freedom simulated in the system. This default to
@@ -49,6 +62,9 @@ pub struct System {
     pub simulated_degrees_of_freedom: DegreesOfFreedom,
     /// The current simulation step
     pub step: u64,
+    /// Thread pool used for the parallel computations, or `None` to use the
+    /// global rayon thread pool
+    thread_pool: Option<Arc<ThreadPool>>,
 }
 
 impl System {
@@ -69,9 +85,12 @@ impl System {
         System {
             configuration: configuration,
             interactions: Interactions::new(),
+            groups: BTreeMap::new(),
+            disabled_groups: Vec::new(),
             step: 0,
             external_temperature: None,
             simulated_degrees_of_freedom: DegreesOfFreedom::Particles,
+            thread_pool: None,
         }
     }
 
@@ -107,6 +126,49 @@ impl System {
         }
         self.external_temperature = temperature;
     }
+
+    /// Set the thread pool used for the parallel computation of the system
+    /// properties (energy, forces, virial, ...), and return the previous one.
+    ///
+    /// With `None`, the global rayon thread pool is used. This is the default,
+    /// and the number of threads in the global pool can be set with the
+    /// `RAYON_NUM_THREADS` environment variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use lumol_core::System;
+    /// let mut system = System::new();
+    /// let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    /// let previous = system.set_thread_pool(Some(Arc::new(pool)));
+    /// assert!(previous.is_none());
+    /// ```
+    pub fn set_thread_pool(&mut self, pool: Option<Arc<ThreadPool>>) -> Option<Arc<ThreadPool>> {
+        return std::mem::replace(&mut self.thread_pool, pool);
+    }
+
+    /// Run `operation` in the thread pool of this system, or in the current
+    /// thread if no thread pool was set.
+    pub(crate) fn install<R, F>(&self, operation: F) -> R where F: FnOnce() -> R + Send, R: Send {
+        match self.thread_pool {
+            Some(ref pool) => pool.install(operation),
+            None => operation(),
+        }
+    }
+
+    /// Remove the center of mass motion of the system, by subtracting the
+    /// velocity of the center of mass from all the particles velocities.
+    pub fn remove_com_motion(&mut self) {
+        let total_mass = self.particles().mass.iter().sum::<f64>();
+        if total_mass == 0.0 {
+            return;
+        }
+        let com_velocity = TotalMomentum.compute(self) / total_mass;
+        for velocity in self.particles_mut().velocity {
+            *velocity -= com_velocity;
+        }
+    }
 }
 
 /// Functions related to interactions
@@ -152,6 +214,29 @@ impl System {
         self.interactions.set_dihedral((i, j, k, m), potential)
     }
 
+    /// Set the improper dihedral angle interaction `potential` for atoms with
+    /// types `i`, `j`, `k`, and `m`, where `i` is the central atom. The
+    /// potential is a function of the out-of-plane angle, and the order of
+    /// the types is significant.
+    pub fn set_improper_potential(
+        &mut self,
+        (i, j, k, m): (&str, &str, &str, &str),
+        potential: Box<dyn DihedralPotential>,
+    ) {
+        self.interactions.set_improper((i, j, k, m), potential)
+    }
+
+    /// Set the Urey-Bradley interaction `potential` for angles between atoms
+    /// with types `i`, `j`, and `k`. The potential is a function of the
+    /// distance between the atoms `i` and `k`.
+    pub fn set_urey_bradley_potential(
+        &mut self,
+        (i, j, k): (&str, &str, &str),
+        potential: Box<dyn BondPotential>,
+    ) {
+        self.interactions.set_urey_bradley((i, j, k), potential)
+    }
+
     /// Set the coulombic interaction for all pairs to `potential`
     pub fn set_coulomb_potential(&mut self, potential: Box<dyn CoulombicPotential>) {
         if let Some(cutoff) = potential.cutoff() {
@@ -171,8 +256,82 @@ impl System {
         self.interactions.globals.push(potential);
     }
 
-    /// Get the pair potential acting between the particles at indexes `i` and `j`.
+    /// Define a group of particles named `name`, containing the particles at
+    /// the given `indexes`. If a group with the same name already exists, it
+    /// is replaced.
+    ///
+    /// Groups refer to particles by their index, and are not updated when
+    /// molecules are added to or removed from the system.
+    ///
+    /// # Panics
+    ///
+    /// If any of the `indexes` is not the index of a particle in the system
+    pub fn define_group(&mut self, name: &str, indexes: &[usize]) {
+        assert!(
+            indexes.iter().all(|&i| i < self.size()),
+            "particle index out of bounds in group '{}'", name
+        );
+        let _ = self.groups.insert(name.into(), indexes.iter().cloned().collect());
+    }
+
+    /// Get the indexes of the particles in the group named `name`, or `None`
+    /// if no such group was defined with
+    /// [`System::define_group`](struct.System.html#method.define_group).
+    pub fn group(&self, name: &str) -> Option<&BTreeSet<usize>> {
+        self.groups.get(name)
+    }
+
+    /// Disable all the pair interactions between particles in the groups
+    /// named `a` and `b`, for example between a frozen wall and a fluid. The
+    /// interactions between particles in the same group are not modified,
+    /// unless `a` and `b` are the same group.
+    ///
+    /// Only the pair potentials are disabled, the coulombic and global
+    /// potentials and the pair potentials tail corrections still use all the
+    /// particles in the system.
+    ///
+    /// # Panics
+    ///
+    /// If any of the groups was not defined with
+    /// [`System::define_group`](struct.System.html#method.define_group)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, Molecule, Particle, UnitCell};
+    /// let mut system = System::with_cell(UnitCell::cubic(20.0));
+    /// for _ in 0..4 {
+    ///     system.add_molecule(Molecule::new(Particle::new("Ar")));
+    /// }
+    ///
+    /// system.define_group("wall", &[0, 1]);
+    /// system.define_group("fluid", &[2, 3]);
+    /// system.disable_group_interactions(("wall", "fluid"));
+    /// ```
+    pub fn disable_group_interactions(&mut self, (a, b): (&str, &str)) {
+        for name in &[a, b] {
+            assert!(self.groups.contains_key(*name), "no group named '{}' in this system", name);
+        }
+        self.disabled_groups.push((a.into(), b.into()));
+    }
+
+    /// Check if the pair interactions between the particles at indexes `i`
+    /// and `j` are disabled by their groups
+    fn are_in_disabled_groups(&self, i: usize, j: usize) -> bool {
+        self.disabled_groups.iter().any(|(a, b)| {
+            let (a, b) = (&self.groups[a], &self.groups[b]);
+            (a.contains(&i) && b.contains(&j)) || (a.contains(&j) && b.contains(&i))
+        })
+    }
+
+    /// Get the pair potential acting between the particles at indexes `i` and
+    /// `j`. This returns `None` if there is no potential for this pair, or if
+    /// the interactions between the groups of these particles are
+    /// [disabled](struct.System.html#method.disable_group_interactions).
     pub fn pair_potential(&self, i: usize, j: usize) -> Option<&PairInteraction> {
+        if self.are_in_disabled_groups(i, j) {
+            return None;
+        }
         let kind_i = self.particles().kind[i];
         let kind_j = self.particles().kind[j];
         return self.interactions.pair((kind_i, kind_j));
@@ -216,6 +375,37 @@ impl System {
         return self.interactions.dihedral((kind_i, kind_j, kind_k, kind_m));
     }
 
+    /// Get the improper dihedral angles potential acting between the
+    /// particles at indexes `i`, `j`, `k` and `m`, where `i` is the central
+    /// particle.
+    pub fn improper_potential(
+        &self,
+        i: usize,
+        j: usize,
+        k: usize,
+        m: usize,
+    ) -> Option<&dyn DihedralPotential> {
+        let kind_i = self.particles().kind[i];
+        let kind_j = self.particles().kind[j];
+        let kind_k = self.particles().kind[k];
+        let kind_m = self.particles().kind[m];
+        return self.interactions.improper((kind_i, kind_j, kind_k, kind_m));
+    }
+
+    /// Get the Urey-Bradley potential acting between the particles at indexes
+    /// `i` and `k`, in the angle `i, j, k`.
+    pub fn urey_bradley_potential(
+        &self,
+        i: usize,
+        j: usize,
+        k: usize,
+    ) -> Option<&dyn BondPotential> {
+        let kind_i = self.particles().kind[i];
+        let kind_j = self.particles().kind[j];
+        let kind_k = self.particles().kind[k];
+        return self.interactions.urey_bradley((kind_i, kind_j, kind_k));
+    }
+
     /// Get the coulombic interaction for the system
     pub fn coulomb_potential(&self) -> Option<&dyn CoulombicPotential> {
         self.interactions.coulomb.as_ref().map(|coulomb| &**coulomb)
@@ -236,19 +426,31 @@ use crate::compute::{KineticEnergy, PotentialEnergy, TotalEnergy};
 use crate::compute::{Pressure, Stress, Virial};
 use crate::compute::{PressureAtTemperature, StressAtTemperature};
 use crate::compute::Compute;
-use crate::compute::Forces;
+use crate::compute::{BondedForces, Forces, NonBondedForces};
 use crate::compute::Temperature;
+use crate::compute::TotalMomentum;
 use crate::compute::Volume;
 
 /// Functions to get physical properties of a system.
 impl System {
-    /// Get the number of degrees of freedom in the system
+    /// Get the number of degrees of freedom in the system, accounting for
+    /// the degrees of freedom removed by the simulation (frozen particles,
+    /// constraints, center-of-mass motion removal, *etc.*) as given by
+    /// `simulated_degrees_of_freedom`.
+    ///
+    /// # Panics
+    ///
+    /// If more degrees of freedom are frozen than there are in the system.
     pub fn degrees_of_freedom(&self) -> usize {
-        match self.simulated_degrees_of_freedom {
-            DegreesOfFreedom::Particles => 3 * self.size(),
-            DegreesOfFreedom::Frozen(frozen) => 3 * self.size() - frozen,
-            DegreesOfFreedom::Molecules => 3 * self.molecules().count(),
-        }
+        let (total, frozen) = match self.simulated_degrees_of_freedom {
+            DegreesOfFreedom::Particles => (3 * self.size(), 0),
+            DegreesOfFreedom::Frozen(frozen) => (3 * self.size(), frozen),
+            DegreesOfFreedom::Molecules => (3 * self.molecules().count(), 0),
+            DegreesOfFreedom::FrozenMolecules(frozen) => (3 * self.molecules().count(), frozen),
+        };
+        return total.checked_sub(frozen).unwrap_or_else(|| panic!(
+            "{} degrees of freedom are frozen, but the system only has {}", frozen, total
+        ));
     }
 
     /// Get the kinetic energy of the system.
@@ -258,12 +460,12 @@ impl System {
 
     /// Get the potential energy of the system.
     pub fn potential_energy(&self) -> f64 {
-        PotentialEnergy.compute(self)
+        self.install(|| PotentialEnergy.compute(self))
     }
 
     /// Get the total energy of the system.
     pub fn total_energy(&self) -> f64 {
-        TotalEnergy.compute(self)
+        self.install(|| TotalEnergy.compute(self))
     }
 
     /// Get the temperature of the system.
@@ -281,37 +483,183 @@ impl System {
 
     /// Get the virial of the system as a tensor
     pub fn virial(&self) -> Matrix3 {
-        Virial.compute(self)
+        self.install(|| Virial.compute(self))
     }
 
     /// Get the pressure of the system from the virial equation, at the system
     /// instantaneous temperature.
     pub fn pressure(&self) -> f64 {
-        match self.external_temperature {
+        self.install(|| match self.external_temperature {
             Some(temperature) => {
                 PressureAtTemperature {
                     temperature: temperature,
                 }.compute(self)
             }
             None => Pressure.compute(self),
-        }
+        })
     }
 
     /// Get the stress tensor of the system from the virial equation.
     pub fn stress(&self) -> Matrix3 {
-        match self.external_temperature {
+        self.install(|| match self.external_temperature {
             Some(temperature) => {
                 StressAtTemperature {
                     temperature: temperature,
                 }.compute(self)
             }
             None => Stress.compute(self),
-        }
+        })
     }
 
     /// Get the forces acting on all the particles in the system
     pub fn forces(&self) -> Vec<Vector3D> {
-        Forces.compute(self)
+        self.install(|| Forces.compute(self))
+    }
+
+    /// Get the forces coming from the bonded interactions (bonds, angles,
+    /// Urey-Bradley terms, dihedral angles and improper dihedral angles)
+    /// acting on all the particles in the system
+    pub fn bonded_forces(&self) -> Vec<Vector3D> {
+        self.install(|| BondedForces.compute(self))
+    }
+
+    /// Get the forces coming from the non-bonded interactions (pairs,
+    /// coulombic and global potentials) acting on all the particles in the
+    /// system
+    pub fn nonbonded_forces(&self) -> Vec<Vector3D> {
+        self.install(|| NonBondedForces.compute(self))
+    }
+}
+
+/// Energy changes for Monte Carlo moves
+impl System {
+    /// Get the change in potential energy if the particle at index `i` moved
+    /// to `new_position`, all the other particles staying in place.
+    ///
+    /// Only the interactions involving the particle `i` are computed: pair
+    /// interactions with all the other particles, using the pair
+    /// restrictions, and the bonds, angles, dihedral angles, improper
+    /// dihedral angles and Urey-Bradley terms containing `i`. Coulombic and
+    /// global potentials are fully recomputed on a copy of the configuration
+    /// where the particle moved: their Monte Carlo cost functions rely on
+    /// internal caches (for example the charge density in Ewald summation)
+    /// which are not guaranteed to match the current configuration.
+    ///
+    /// Without coulombic and global potentials, this function is O(N) in the
+    /// number of particles. With such potentials, it copies the whole
+    /// configuration and computes their energy twice, which is more
+    /// expensive than a full energy evaluation: O(N²) for the real space
+    /// part of Ewald summation. Monte Carlo simulations repeatedly moving
+    /// particles should use [`EnergyCache::move_molecule_cost`] instead, which
+    /// keeps the caches of these potentials up to date.
+    ///
+    /// [`EnergyCache::move_molecule_cost`]: struct.EnergyCache.html#method.move_molecule_cost
+    ///
+    /// # Panics
+    ///
+    /// If `i` is not the index of a particle in the system
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, Molecule, Particle, UnitCell, Vector3D};
+    /// # use lumol_core::energy::{Harmonic, PairInteraction};
+    /// let mut system = System::with_cell(UnitCell::cubic(20.0));
+    /// system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 0.0].into())));
+    /// system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 2.0].into())));
+    /// let harmonic = Harmonic { x0: 2.3, k: 0.1 };
+    /// system.set_pair_potential(("Cl", "Cl"), PairInteraction::new(Box::new(harmonic), 10.0));
+    ///
+    /// // moving the second particle to the equilibrium distance
+    /// let delta = system.energy_delta(1, Vector3D::new(0.0, 0.0, 2.3));
+    /// assert!(f64::abs(delta + 0.5 * 0.1 * 0.3 * 0.3) < 1e-12);
+    /// ```
+    pub fn energy_delta(&self, i: usize, new_position: Vector3D) -> f64 {
+        assert!(i < self.size(), "particle index out of bounds in energy_delta");
+        let evaluator = self.energy_evaluator();
+        let positions = self.particles().position;
+
+        let mut delta = 0.0;
+        for j in (0..self.size()).filter(|&j| j != i) {
+            let path = self.bond_path(i, j);
+            let old_r = self.nearest_image(i, j).norm();
+            let new_r = self.cell.distance(&new_position, &positions[j]);
+            delta += evaluator.pair(path, new_r, i, j) - evaluator.pair(path, old_r, i, j);
+        }
+
+        let molecule_id = self.molecule_id(i);
+        let molecule = self.molecule(molecule_id);
+        let old_positions = &positions[molecule.indexes()];
+        let mut new_positions = old_positions.to_vec();
+        new_positions[i - molecule.start()] = new_position;
+        delta += self.bonded_energy(i, &new_positions) - self.bonded_energy(i, old_positions);
+
+        if self.coulomb_potential().is_some() || !self.global_potentials().is_empty() {
+            let configuration = &self.configuration;
+            let mut moved = configuration.clone();
+            moved.particles_mut().position[i] = new_position;
+            if let Some(coulomb) = self.coulomb_potential() {
+                delta += coulomb.energy(&moved) - coulomb.energy(configuration);
+            }
+            for global in self.global_potentials() {
+                delta += global.energy(&moved) - global.energy(configuration);
+            }
+        }
+
+        return delta;
+    }
+
+    /// Get the energy of the bonded terms containing the particle `i`, using
+    /// `positions` for the particles in the molecule containing `i`.
+    fn bonded_energy(&self, i: usize, positions: &[Vector3D]) -> f64 {
+        let evaluator = self.energy_evaluator();
+        let molecule = self.molecule(self.molecule_id(i));
+        let first = molecule.start();
+        let position = |j: usize| &positions[j - first];
+
+        let mut energy = 0.0;
+        for bond in molecule.bonds() {
+            let (a, b) = (bond.i(), bond.j());
+            if a == i || b == i {
+                let r = self.cell.distance(position(a), position(b));
+                energy += evaluator.bond(r, a, b);
+            }
+        }
+
+        for angle in molecule.angles() {
+            let (a, b, c) = (angle.i(), angle.j(), angle.k());
+            if a == i || b == i || c == i {
+                let theta = self.cell.angle(position(a), position(b), position(c));
+                energy += evaluator.angle(theta, a, b, c);
+            }
+        }
+
+        for angle in molecule.urey_bradleys() {
+            let (a, b, c) = (angle.i(), angle.j(), angle.k());
+            if a == i || c == i {
+                let r = self.cell.distance(position(a), position(c));
+                energy += evaluator.urey_bradley(r, a, b, c);
+            }
+        }
+
+        for dihedral in molecule.dihedrals() {
+            let (a, b, c, d) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+            if a == i || b == i || c == i || d == i {
+                let phi = self.cell.dihedral(position(a), position(b), position(c), position(d));
+                energy += evaluator.dihedral(phi, a, b, c, d);
+            }
+        }
+
+        for improper in molecule.impropers() {
+            let (a, b, c, d) = (improper.i(), improper.j(), improper.k(), improper.m());
+            if a == i || b == i || c == i || d == i {
+                let (ra, rb, rc, rd) = (position(a), position(b), position(c), position(d));
+                let chi = self.cell.out_of_plane(ra, rb, rc, rd);
+                energy += evaluator.improper(chi, a, b, c, d);
+            }
+        }
+
+        return energy;
     }
 }
 
@@ -375,6 +723,26 @@ impl System {
                     );
                 }
             }
+
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                if self.improper_potential(i, j, k, m).is_none() {
+                    let names = self.particles().name;
+                    warn_once!(
+                        "no potential defined for the improper dihedral angle {:?}",
+                        (&names[i], &names[j], &names[k], &names[m])
+                    );
+                }
+            }
+
+            for angle in molecule.urey_bradleys() {
+                if self.urey_bradley_potential(angle.i(), angle.j(), angle.k()).is_none() {
+                    warn_once!(
+                        "no potential defined for the Urey-Bradley term {:?}",
+                        self.sorted_names_angle(angle.i(), angle.j(), angle.k())
+                    );
+                }
+            }
         }
 
         // check the need for a coulombic potential
@@ -446,7 +814,15 @@ impl DerefMut for System {
 
 #[cfg(test)]
 mod tests {
-    use crate::{System, Molecule, Particle, ParticleKind};
+    use std::sync::Arc;
+
+    use crate::{System, Molecule, Particle, ParticleKind, Vector3D};
+    use crate::{Harmonic, LennardJones, PairInteraction, UnitCell, Wolf};
+    use crate::{Ewald, SharedEwald};
+    use crate::compute::{Compute, TotalMomentum};
+    use crate::units;
+
+    use approx::{assert_relative_eq, assert_ulps_eq};
 
     #[test]
     #[should_panic]
@@ -455,6 +831,18 @@ mod tests {
         system.simulated_temperature(Some(-1.0));
     }
 
+    #[test]
+    fn remove_com_motion() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("H")));
+        system.add_molecule(Molecule::new(Particle::new("O")));
+        system.particles_mut().velocity[0] = Vector3D::new(1.0, 2.0, -3.0);
+        system.particles_mut().velocity[1] = Vector3D::new(0.0, 5.0, 0.5);
+
+        system.remove_com_motion();
+        assert_ulps_eq!(TotalMomentum.compute(&system).norm(), 0.0, epsilon = 1e-12);
+    }
+
     #[test]
     fn deref() {
         let mut system = System::new();
@@ -483,6 +871,37 @@ mod tests {
         assert_eq!(system.particles().kind[2], ParticleKind(0));
     }
 
+    #[test]
+    fn kind_and_charge() {
+        let mut system = System::new();
+        let pair = PairInteraction::new(Box::new(Harmonic { k: 1.0, x0: 1.0 }), 5.0);
+        system.set_pair_potential(("Na", "Cl"), pair);
+
+        let mut sodium = Particle::new("Na");
+        sodium.charge = 1.0;
+        let mut chlorine = Particle::new("Cl");
+        chlorine.charge = -1.0;
+
+        let mut molecule = Molecule::new(sodium.clone());
+        molecule.add_particle_bonded_to(0, chlorine.clone());
+        system.add_molecule(molecule);
+        system.add_molecule(Molecule::new(chlorine));
+        system.add_molecule(Molecule::new(sodium));
+
+        // Kinds are shared with the interactions defined before adding the
+        // particles, and between particles with the same name
+        let kinds = system.particles().kind;
+        assert_eq!(kinds[0], ParticleKind(0));
+        assert_eq!(kinds[1], ParticleKind(1));
+        assert_eq!(kinds[2], kinds[1]);
+        assert_eq!(kinds[3], kinds[0]);
+        assert!(system.pair_potential(0, 1).is_some());
+        assert!(system.pair_potential(2, 3).is_some());
+        assert!(system.pair_potential(0, 3).is_none());
+
+        assert_eq!(system.particles().charge, &[1.0, -1.0, -1.0, 1.0]);
+    }
+
     #[test]
     fn composition() {
         let mut system = System::new();
@@ -570,4 +989,149 @@ no potential defined for the dihedral angle ("Ar", "Ar", "He", "He")
             assert!(EXPECTED_WARNINGS.contains(line));
         }
     }
+
+    #[test]
+    #[should_panic]
+    fn undefined_group() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        system.define_group("wall", &[0]);
+        system.disable_group_interactions(("wall", "fluid"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn group_out_of_bounds() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        system.define_group("wall", &[0, 1]);
+    }
+
+    #[test]
+    fn group_interactions() {
+        let positions = [0.0, 1.5, 3.5, 4.2];
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        for &x in &positions {
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(x, 0.0, 0.0))));
+        }
+        let pair = PairInteraction::new(Box::new(Harmonic { k: 1.0, x0: 1.0 }), 8.0);
+        system.set_pair_potential(("Ar", "Ar"), pair);
+
+        let all_forces = system.forces();
+        system.define_group("wall", &[0, 1]);
+        system.define_group("fluid", &[2, 3]);
+        for (force, expected) in system.forces().iter().zip(&all_forces) {
+            assert_ulps_eq!(force, expected, epsilon = 1e-12);
+        }
+
+        system.disable_group_interactions(("wall", "fluid"));
+        assert!(system.pair_potential(0, 1).is_some());
+        assert!(system.pair_potential(2, 3).is_some());
+        assert!(system.pair_potential(1, 2).is_none());
+        assert!(system.pair_potential(3, 0).is_none());
+
+        // Only the intra-group forces remain
+        let forces = system.forces();
+        assert_ulps_eq!(forces[0], Vector3D::new(0.5, 0.0, 0.0));
+        assert_ulps_eq!(forces[1], Vector3D::new(-0.5, 0.0, 0.0));
+        assert_ulps_eq!(forces[2], Vector3D::new(-0.3, 0.0, 0.0), epsilon = 1e-12);
+        assert_ulps_eq!(forces[3], Vector3D::new(0.3, 0.0, 0.0), epsilon = 1e-12);
+
+        let energy = 0.5 * 0.5 * 0.5 + 0.5 * 0.3 * 0.3;
+        assert_ulps_eq!(system.potential_energy(), energy, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn thread_pool() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        for i in 0..50 {
+            let i = i as f64;
+            let position = Vector3D::new(f64::sin(i), f64::cos(2.0 * i), f64::sin(3.0 * i)) * 5.0;
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        }
+        let pair = PairInteraction::new(Box::new(Harmonic { k: 1.0, x0: 1.0 }), 4.0);
+        system.set_pair_potential(("Ar", "Ar"), pair);
+
+        let forces = system.forces();
+        let energy = system.potential_energy();
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        assert!(system.set_thread_pool(Some(Arc::new(pool))).is_none());
+        assert_eq!(system.install(rayon::current_num_threads), 2);
+
+        for (force, expected) in system.forces().iter().zip(&forces) {
+            assert_ulps_eq!(force, expected, epsilon = 1e-12);
+        }
+        assert_ulps_eq!(system.potential_energy(), energy, epsilon = 1e-12);
+
+        assert!(system.set_thread_pool(None).is_some());
+        assert_eq!(system.install(rayon::current_num_threads), rayon::current_num_threads());
+    }
+
+    #[test]
+    fn energy_delta() {
+        let mut system = System::with_cell(UnitCell::cubic(15.0));
+        let particle = |name, x, y, z| Particle::with_position(name, Vector3D::new(x, y, z));
+        let mut molecule = Molecule::new(particle("C", 0.0, 0.0, 0.0));
+        molecule.add_particle_bonded_to(0, particle("C", 1.5, 0.0, 0.0));
+        molecule.add_particle_bonded_to(1, particle("C", 2.0, 1.4, 0.0));
+        molecule.add_particle_bonded_to(2, particle("C", 3.5, 1.6, 0.3));
+        system.add_molecule(molecule);
+        system.add_molecule(Molecule::new(particle("Na", 3.0, 4.0, 0.0)));
+        system.add_molecule(Molecule::new(particle("Cl", 0.0, 4.0, 3.0)));
+
+        system.particles_mut().charge[0] = 0.3;
+        system.particles_mut().charge[3] = -0.3;
+        system.particles_mut().charge[4] = 1.0;
+        system.particles_mut().charge[5] = -1.0;
+
+        let lj = LennardJones {
+            sigma: units::from(3.0, "A").unwrap(),
+            epsilon: units::from(0.5, "kJ/mol").unwrap(),
+        };
+        for &(a, b) in &[("C", "C"), ("C", "Na"), ("C", "Cl"), ("Na", "Cl")] {
+            system.set_pair_potential((a, b), PairInteraction::new(Box::new(lj), 7.0));
+        }
+        system.set_bond_potential(("C", "C"), Box::new(Harmonic { k: 100.0, x0: 1.5 }));
+        system.set_angle_potential(("C", "C", "C"), Box::new(Harmonic { k: 10.0, x0: 2.0 }));
+        system.set_dihedral_potential(("C", "C", "C", "C"), Box::new(Harmonic { k: 5.0, x0: 1.0 }));
+        system.set_coulomb_potential(Box::new(Wolf::new(7.0)));
+
+        // A particle inside a molecule, and a single particle
+        let moves = [(1, Vector3D::new(1.4, -0.2, 0.3)), (4, Vector3D::new(2.0, 4.5, 1.0))];
+        for &(i, new_position) in &moves {
+            let delta = system.energy_delta(i, new_position);
+
+            let mut moved = system.clone();
+            moved.particles_mut().position[i] = new_position;
+            let expected = moved.potential_energy() - system.potential_energy();
+            assert_relative_eq!(delta, expected, max_relative = 1e-9);
+        }
+    }
+
+    #[test]
+    fn energy_delta_ewald() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        let positions = [[0.0, 0.0, 0.0], [2.5, 0.5, 0.0], [0.5, 3.0, 4.0], [5.0, 2.0, 1.0]];
+        for (i, &position) in positions.iter().enumerate() {
+            let (name, charge) = if i % 2 == 0 { ("Na", 1.0) } else { ("Cl", -1.0) };
+            let mut particle = Particle::with_position(name, position.into());
+            particle.charge = charge;
+            system.add_molecule(Molecule::new(particle));
+        }
+        system.set_coulomb_potential(Box::new(SharedEwald::new(Ewald::new(8.0, 7, None))));
+
+        // Fill the caches of the Ewald summation, and then move a particle
+        // without updating them
+        let _ = system.potential_energy();
+        system.particles_mut().position[3] = Vector3D::new(4.0, 1.0, 3.0);
+
+        let new_position = Vector3D::new(1.0, 0.5, 2.0);
+        let delta = system.energy_delta(0, new_position);
+
+        let mut moved = system.clone();
+        moved.particles_mut().position[0] = new_position;
+        let expected = moved.potential_energy() - system.potential_energy();
+        assert_relative_eq!(delta, expected, max_relative = 1e-9);
+    }
 }
@@ -0,0 +1,206 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Parameters for common three-sites water models.
+//!
+//! The [`WaterModel`](struct.WaterModel.html) type contains a template water
+//! molecule with the right geometry and charges, as well as the potentials
+//! for this model. The original models are rigid, the bond and angle
+//! potentials provided here use the force constants of the flexible SPC/Fw
+//! model [1] around the rigid geometry. Use them together with the `Rattle`
+//! constraints from lumol-sim to get back the rigid models.
+//!
+//! The electrostatic interactions are not set by these models, as they depend
+//! on the simulation cell. They should use an intermolecular restriction.
+//!
+//! [1] Y. Wu, H. L. Tepper and G. A. Voth, J. Chem. Phys. 124, 024503 (2006);
+//! doi: 10.1063/1.2136877
+use crate::energy::{Harmonic, LennardJones, NullPotential, PairInteraction};
+use crate::{Molecule, Particle, System, Vector3D};
+use crate::units;
+
+/// A three-sites water model
+#[derive(Clone)]
+pub struct WaterModel {
+    /// Template water molecule, with the oxygen at the origin and the
+    /// molecule in the xy plane
+    pub molecule: Molecule,
+    /// Lennard-Jones potential between oxygen atoms
+    pub lennard_jones: LennardJones,
+    /// Potential for the O-H bonds
+    pub bond: Harmonic,
+    /// Potential for the H-O-H angle
+    pub angle: Harmonic,
+}
+
+impl WaterModel {
+    /// Create a new water model from the O-H `distance` in Angstroms, the
+    /// H-O-H `angle` in degrees, the `charge` of the oxygen in elementary
+    /// charges, and the Lennard-Jones parameters between oxygen atoms:
+    /// `sigma` in Angstroms and `epsilon` in kJ/mol.
+    fn new(distance: f64, angle: f64, charge: f64, sigma: f64, epsilon: f64) -> WaterModel {
+        let distance = units::from(distance, "A").expect("bad unit");
+        let angle = units::from(angle, "deg").expect("bad unit");
+
+        let mut oxygen = Particle::new("O");
+        oxygen.charge = charge;
+        let mut molecule = Molecule::new(oxygen);
+
+        let half_angle = 0.5 * angle;
+        for &sign in &[1.0, -1.0] {
+            let position = Vector3D::new(
+                distance * f64::sin(half_angle),
+                sign * distance * f64::cos(half_angle),
+                0.0,
+            );
+            let mut hydrogen = Particle::with_position("H", position);
+            hydrogen.charge = -0.5 * charge;
+            molecule.add_particle_bonded_to(0, hydrogen);
+        }
+
+        WaterModel {
+            molecule: molecule,
+            lennard_jones: LennardJones {
+                sigma: units::from(sigma, "A").expect("bad unit"),
+                epsilon: units::from(epsilon, "kJ/mol").expect("bad unit"),
+            },
+            bond: Harmonic {
+                k: units::from(4431.53, "kJ/mol/A^2").expect("bad unit"),
+                x0: distance,
+            },
+            angle: Harmonic {
+                k: units::from(317.5656, "kJ/mol/rad^2").expect("bad unit"),
+                x0: angle,
+            },
+        }
+    }
+
+    /// Set the pair, bond and angle potentials of this model in the
+    /// `system`, using the given `cutoff` for the Lennard-Jones interaction.
+    pub fn set_potentials(&self, system: &mut System, cutoff: f64) {
+        let mut lj = PairInteraction::new(Box::new(self.lennard_jones), cutoff);
+        lj.enable_tail_corrections();
+        system.set_pair_potential(("O", "O"), lj);
+        system.set_pair_potential(("O", "H"), PairInteraction::new(Box::new(NullPotential), cutoff));
+        system.set_pair_potential(("H", "H"), PairInteraction::new(Box::new(NullPotential), cutoff));
+
+        system.set_bond_potential(("O", "H"), Box::new(self.bond));
+        system.set_angle_potential(("H", "O", "H"), Box::new(self.angle));
+    }
+}
+
+/// Get the SPC water model.
+///
+/// [1] H. J. C. Berendsen, J. P. M. Postma, W. F. van Gunsteren and J.
+/// Hermans, in Intermolecular Forces, 331 (1981); doi: 10.1007/978-94-015-7658-1_21
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{System, UnitCell};
+/// # use lumol_core::sys::water;
+/// let water = water::spc();
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// system.add_water_molecules(64, &water);
+/// water.set_potentials(&mut system, 9.0);
+///
+/// assert_eq!(system.molecules().count(), 64);
+/// ```
+pub fn spc() -> WaterModel {
+    WaterModel::new(1.0, 109.47, -0.82, 3.166, 0.650)
+}
+
+/// Get the TIP3P water model.
+///
+/// [1] W. L. Jorgensen, J. Chandrasekhar, J. D. Madura, R. W. Impey and M.
+/// L. Klein, J. Chem. Phys. 79, 926 (1983); doi: 10.1063/1.445869
+pub fn tip3p() -> WaterModel {
+    WaterModel::new(0.9572, 104.52, -0.834, 3.15061, 0.6364)
+}
+
+impl System {
+    /// Add `n` water molecules from the `model` to this system, placing
+    /// them on a cubic grid filling the unit cell. All the molecules have
+    /// the same orientation.
+    ///
+    /// # Panics
+    ///
+    /// If the unit cell of the system is infinite.
+    pub fn add_water_molecules(&mut self, n: usize, model: &WaterModel) {
+        assert!(!self.cell.is_infinite(), "can not place water molecules in an infinite cell");
+        let per_side = f64::ceil(f64::cbrt(n as f64)) as usize;
+        let step = 1.0 / per_side as f64;
+
+        let mut added = 0;
+        'outer: for i in 0..per_side {
+            for j in 0..per_side {
+                for k in 0..per_side {
+                    if added == n {
+                        break 'outer;
+                    }
+                    let fractional = Vector3D::new(i as f64 + 0.5, j as f64 + 0.5, k as f64 + 0.5) * step;
+                    let origin = self.cell.cartesian(&fractional);
+
+                    let mut molecule = model.molecule.clone();
+                    for position in molecule.particles_mut().position {
+                        *position += origin;
+                    }
+                    self.add_molecule(molecule);
+                    added += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnitCell;
+    use approx::assert_ulps_eq;
+
+    fn check_geometry(model: &WaterModel, distance: f64, angle: f64) {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(model.molecule.clone());
+
+        assert_eq!(system.size(), 3);
+        assert_eq!(system.molecule(0).bonds().len(), 2);
+        assert_ulps_eq!(system.distance(0, 1), distance, epsilon = 1e-12);
+        assert_ulps_eq!(system.distance(0, 2), distance, epsilon = 1e-12);
+        assert_ulps_eq!(system.angle(1, 0, 2), units::from(angle, "deg").unwrap(), epsilon = 1e-12);
+
+        let charge: f64 = system.particles().charge.iter().sum();
+        assert_eq!(charge, 0.0);
+    }
+
+    #[test]
+    fn geometry() {
+        check_geometry(&spc(), 1.0, 109.47);
+        check_geometry(&tip3p(), 0.9572, 104.52);
+    }
+
+    #[test]
+    fn add_water_molecules() {
+        let model = tip3p();
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_water_molecules(100, &model);
+        model.set_potentials(&mut system, 9.0);
+
+        assert_eq!(system.molecules().count(), 100);
+        assert_eq!(system.size(), 300);
+        assert!(system.molecules().all(|molecule| molecule.hash() == model.molecule.hash()));
+
+        // 5x5x5 grid, with 4 A between oxygen atoms
+        let oxygens = (0..100).map(|i| 3 * i).collect::<Vec<_>>();
+        for &i in &oxygens {
+            for &j in &oxygens {
+                if i != j {
+                    assert!(system.distance(i, j) > 4.0 - 1e-12);
+                }
+            }
+        }
+
+        // All the bonded and non-bonded potentials are defined
+        assert!(system.potential_energy().is_finite());
+    }
+}
@@ -0,0 +1,248 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Reading and writing systems in the extended XYZ format.
+//!
+//! Extended XYZ files are XYZ files containing `key=value` pairs in the
+//! comment line of each frame. The unit cell is stored in the `Lattice` field,
+//! as the nine cartesian components of the three cell vectors, and the
+//! `Properties` field describes the columns of the atomic lines. Only the
+//! names and positions of the particles are written and read, and floating
+//! point values are written with enough digits to be read back exactly.
+use std::io::{self, BufRead, Write};
+
+use crate::{CellShape, Matrix3, Molecule, Particle, System, UnitCell, Vector3D};
+
+/// Description of the atomic lines in the files
+const PROPERTIES: &str = "Properties=species:S:1:pos:R:3";
+
+fn invalid_data<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Get the unit cell from the `Lattice` field in the `comment` line of a
+/// frame, or an infinite cell if there is no such field.
+fn read_lattice(comment: &str) -> io::Result<UnitCell> {
+    let start = match comment.find("Lattice=\"") {
+        Some(start) => start + "Lattice=\"".len(),
+        None => return Ok(UnitCell::infinite()),
+    };
+    let lattice = match comment[start..].find('"') {
+        Some(length) => &comment[start..start + length],
+        None => return Err(invalid_data("missing closing quote in XYZ Lattice field")),
+    };
+
+    let values = lattice.split_whitespace()
+                        .map(|value| value.parse::<f64>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .ok()
+                        .filter(|values| values.len() == 9)
+                        .ok_or_else(|| invalid_data(format!(
+                            "expected 9 numbers in XYZ Lattice field, got '{}'", lattice
+                        )))?;
+
+    // The cell vectors are the columns of the cell matrix
+    let matrix = Matrix3::new([
+        [values[0], values[3], values[6]],
+        [values[1], values[4], values[7]],
+        [values[2], values[5], values[8]],
+    ]);
+    let diagonal = matrix[0][1] == 0.0 && matrix[0][2] == 0.0 && matrix[1][0] == 0.0 &&
+                   matrix[1][2] == 0.0 && matrix[2][0] == 0.0 && matrix[2][1] == 0.0;
+    let shape = if diagonal { CellShape::Orthorhombic } else { CellShape::Triclinic };
+    return Ok(UnitCell::from_matrix(matrix, shape));
+}
+
+/// Read a single atomic line, containing the name and position of a particle
+fn read_particle(line: &str) -> io::Result<Particle> {
+    let mut fields = line.split_whitespace();
+    let name = fields.next().ok_or_else(|| invalid_data("missing atom in XYZ file"))?;
+    let mut position = Vector3D::zero();
+    for k in 0..3 {
+        position[k] = fields.next()
+                            .and_then(|value| value.parse().ok())
+                            .ok_or_else(|| invalid_data(format!(
+                                "invalid position in XYZ atomic line '{}'", line.trim()
+                            )))?;
+    }
+    return Ok(Particle::with_position(name, position));
+}
+
+impl System {
+    /// Write this system as a single frame of an extended XYZ file to `file`.
+    /// The unit cell is written in the `Lattice` field of the comment line,
+    /// except for infinite cells. Multiple frames can be written to the same
+    /// file to create a trajectory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, Molecule, Particle, UnitCell, Vector3D};
+    /// let mut system = System::with_cell(UnitCell::cubic(10.0));
+    /// let position = Vector3D::new(1.0, 2.0, 3.0);
+    /// system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+    ///
+    /// let mut output = Vec::new();
+    /// system.write_xyz(&mut output).unwrap();
+    /// let output = String::from_utf8(output).unwrap();
+    ///
+    /// let mut lines = output.lines();
+    /// assert_eq!(lines.next(), Some("1"));
+    /// assert!(lines.next().unwrap().starts_with("Lattice=\"10.0 0.0 0.0 0.0 10.0 0.0"));
+    /// assert_eq!(lines.next(), Some("Ar 1.0 2.0 3.0"));
+    /// ```
+    pub fn write_xyz<W: Write>(&self, file: &mut W) -> io::Result<()> {
+        writeln!(file, "{}", self.size())?;
+        if self.cell.is_infinite() {
+            writeln!(file, "{}", PROPERTIES)?;
+        } else {
+            let matrix = self.cell.matrix();
+            writeln!(
+                file, "Lattice=\"{:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}\" {}",
+                matrix[0][0], matrix[1][0], matrix[2][0],
+                matrix[0][1], matrix[1][1], matrix[2][1],
+                matrix[0][2], matrix[1][2], matrix[2][2],
+                PROPERTIES,
+            )?;
+        }
+
+        for (name, position) in self.particles().name.iter().zip(self.particles().position) {
+            writeln!(file, "{} {:?} {:?} {:?}", name, position[0], position[1], position[2])?;
+        }
+        return Ok(());
+    }
+
+    /// Read a single frame of an extended XYZ file from `file`, or `None` if
+    /// the end of the file is reached before the start of the frame.
+    ///
+    /// The unit cell is read from the `Lattice` field in the comment line, and
+    /// is infinite if this field is missing. Each particle is placed in its
+    /// own molecule, and the velocities are set to zero. An error is returned
+    /// if the file does not contain the expected number of atoms, or if an
+    /// atomic line or the `Lattice` field is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, UnitCell, Vector3D};
+    /// let file = "2
+    /// Lattice=\"10.0 0.0 0.0 0.0 10.0 0.0 0.0 0.0 10.0\"
+    /// Ar 1.0 2.0 3.0
+    /// Kr 0.0 0.0 0.5
+    /// ";
+    ///
+    /// let system = System::read_xyz(&mut file.as_bytes()).unwrap().unwrap();
+    /// assert_eq!(system.size(), 2);
+    /// assert_eq!(system.cell, UnitCell::cubic(10.0));
+    /// assert_eq!(system.particles().name[1], "Kr");
+    /// assert_eq!(system.particles().position[1], Vector3D::new(0.0, 0.0, 0.5));
+    /// ```
+    pub fn read_xyz<R: BufRead>(file: &mut R) -> io::Result<Option<System>> {
+        let mut line = String::new();
+        if file.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let natoms = line.trim().parse::<usize>().map_err(|_| {
+            invalid_data(format!("invalid atom count '{}' in XYZ file", line.trim()))
+        })?;
+
+        line.clear();
+        if file.read_line(&mut line)? == 0 {
+            return Err(invalid_data("missing comment line in XYZ file"));
+        }
+        let mut system = System::with_cell(read_lattice(&line)?);
+
+        for i in 0..natoms {
+            line.clear();
+            if file.read_line(&mut line)? == 0 {
+                return Err(invalid_data(format!(
+                    "expected {} atoms in XYZ frame, but the file ended after {}", natoms, i
+                )));
+            }
+            system.add_molecule(Molecule::new(read_particle(&line)?));
+        }
+
+        return Ok(Some(system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{System, Molecule, Particle, UnitCell, Vector3D};
+
+    fn testing_system(cell: UnitCell) -> System {
+        let mut system = System::with_cell(cell);
+        let positions = [
+            ("Ar", Vector3D::new(1.0, 2.0, 3.0)),
+            ("Kr", Vector3D::new(-1.5, 1.0 / 3.0, 8.0)),
+            ("Ar", Vector3D::new(4.0, 5.0, 1e-12)),
+        ];
+        for &(name, position) in &positions {
+            system.add_molecule(Molecule::new(Particle::with_position(name, position)));
+        }
+        return system;
+    }
+
+    fn check_same(read: &System, system: &System) {
+        assert_eq!(read.cell, system.cell);
+        assert_eq!(read.size(), system.size());
+        assert_eq!(read.particles().name, system.particles().name);
+        assert_eq!(read.particles().kind, system.particles().kind);
+        assert_eq!(read.particles().position, system.particles().position);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut first = testing_system(UnitCell::ortho(10.0, 11.0, 12.5));
+        let second = testing_system(UnitCell::triclinic(10.0, 11.0, 12.5, 90.0, 80.0, 100.0));
+        let third = testing_system(UnitCell::infinite());
+
+        let mut output = Vec::new();
+        first.write_xyz(&mut output).unwrap();
+        second.write_xyz(&mut output).unwrap();
+        third.write_xyz(&mut output).unwrap();
+
+        let mut file = &output[..];
+        let read = System::read_xyz(&mut file).unwrap().unwrap();
+        check_same(&read, &first);
+
+        let read = System::read_xyz(&mut file).unwrap().unwrap();
+        check_same(&read, &second);
+
+        let read = System::read_xyz(&mut file).unwrap().unwrap();
+        check_same(&read, &third);
+        assert!(read.cell.is_infinite());
+
+        assert!(System::read_xyz(&mut file).unwrap().is_none());
+
+        // Velocities are not part of XYZ files
+        first.particles_mut().velocity[0] = Vector3D::new(1.0, 2.0, 3.0);
+        let mut output = Vec::new();
+        first.write_xyz(&mut output).unwrap();
+        let read = System::read_xyz(&mut &output[..]).unwrap().unwrap();
+        assert_eq!(read.particles().velocity[0], Vector3D::zero());
+    }
+
+    #[test]
+    fn errors() {
+        let read = |content: &str| System::read_xyz(&mut content.as_bytes());
+
+        let error = read("3\n\nAr 0 0 0\nAr 1 0 0\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "expected 3 atoms in XYZ frame, but the file ended after 2"
+        );
+        let error = read("three\n\nAr 0 0 0\n").err().unwrap();
+        assert_eq!(error.to_string(), "invalid atom count 'three' in XYZ file");
+        assert!(read("1\n").is_err());
+        assert!(read("1\n\nAr 0 0\n").is_err());
+        assert!(read("1\n\nAr 0 0 zero\n").is_err());
+
+        let error = read("1\nLattice=\"10 0 0 0 10 0 0 0\"\nAr 0 0 0\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "expected 9 numbers in XYZ Lattice field, got '10 0 0 0 10 0 0 0'"
+        );
+        assert!(read("1\nLattice=\"10 0 0 0 10 0 0 0 10\nAr 0 0 0\n").is_err());
+    }
+}
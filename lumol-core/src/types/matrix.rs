@@ -69,6 +69,9 @@ use crate::Vector3D;
 ///     [0.0, 0.0, 4.0]
 /// ]));
 /// ```
+///
+/// When the `serde` feature is enabled, `Matrix3` is serialized as a flat
+/// array of nine numbers, in row-major order.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Matrix3([[f64; 3]; 3]);
 
@@ -208,10 +211,39 @@ impl Matrix3 {
     /// # Panics
     ///
     /// If the matrix is not invertible, *i.e.* if the matrix determinant
-    /// equals zero.
+    /// equals zero. Use [`Matrix3::try_inverse`] to handle this case.
+    ///
+    /// [`Matrix3::try_inverse`]: struct.Matrix3.html#method.try_inverse
     pub fn inverse(&self) -> Matrix3 {
+        self.try_inverse().expect("The matrix is not inversible!")
+    }
+
+    /// Computes the inverse of a matrix, or returns `None` if the matrix is
+    /// not invertible, *i.e.* if the matrix determinant equals zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::Matrix3;
+    /// let matrix = Matrix3::new([
+    ///     [4.0, 0.0, 0.0],
+    ///     [0.0, 1.0, 0.0],
+    ///     [0.0, 0.0, 7.0]
+    /// ]);
+    /// assert_eq!(matrix.try_inverse(), Some(matrix.inverse()));
+    ///
+    /// let singular = Matrix3::new([
+    ///     [1.0, 2.0, 3.0],
+    ///     [4.0, 5.0, 6.0],
+    ///     [5.0, 7.0, 9.0]
+    /// ]);
+    /// assert_eq!(singular.try_inverse(), None);
+    /// ```
+    pub fn try_inverse(&self) -> Option<Matrix3> {
         let determinant = self.determinant();
-        assert!(determinant.abs() > 1e-30, "The matrix is not inversible!");
+        if determinant.abs() <= 1e-30 {
+            return None;
+        }
         let invdet = 1.0 / determinant;
         let mut res = Matrix3::zero();
         res[0][0] = (self[1][1] * self[2][2] - self[2][1] * self[1][2]) * invdet;
@@ -223,7 +255,60 @@ impl Matrix3 {
         res[2][0] = (self[1][0] * self[2][1] - self[2][0] * self[1][1]) * invdet;
         res[2][1] = (self[2][0] * self[0][1] - self[0][0] * self[2][1]) * invdet;
         res[2][2] = (self[0][0] * self[1][1] - self[1][0] * self[0][1]) * invdet;
-        return res;
+        return Some(res);
+    }
+
+    /// Solve the linear system $M x = b$ for $x$, where $M$ is this matrix,
+    /// using a LU decomposition with partial pivoting. This returns `None`
+    /// if the matrix is (numerically) singular.
+    ///
+    /// This is more accurate and faster than computing the inverse of the
+    /// matrix when solving a single system.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::{Matrix3, Vector3D};
+    /// let matrix = Matrix3::new([
+    ///     [2.0, 0.0, 0.0],
+    ///     [0.0, 4.0, 0.0],
+    ///     [0.0, 0.0, 8.0]
+    /// ]);
+    /// let b = Vector3D::new(1.0, 1.0, 1.0);
+    /// assert_eq!(matrix.solve(&b), Some(Vector3D::new(0.5, 0.25, 0.125)));
+    /// ```
+    pub fn solve(&self, b: &Vector3D) -> Option<Vector3D> {
+        let mut lu = *self;
+        let mut x = *b;
+        let scale = self.iter().flat_map(|row| row.iter()).fold(0.0, |max, &value| f64::max(max, value.abs()));
+
+        for k in 0..3 {
+            // Use the row with the largest value in column k as pivot
+            let pivot = (k..3).max_by(|&i, &j| {
+                lu[i][k].abs().partial_cmp(&lu[j][k].abs()).expect("got NaN in Matrix3::solve")
+            }).expect("empty range");
+            if lu[pivot][k].abs() <= 1e-12 * scale {
+                return None;
+            }
+            lu.swap(k, pivot);
+            x.swap(k, pivot);
+
+            for i in (k + 1)..3 {
+                let factor = lu[i][k] / lu[k][k];
+                for j in k..3 {
+                    lu[i][j] -= factor * lu[k][j];
+                }
+                x[i] -= factor * x[k];
+            }
+        }
+
+        for k in (0..3).rev() {
+            for j in (k + 1)..3 {
+                x[k] -= lu[k][j] * x[j];
+            }
+            x[k] /= lu[k][k];
+        }
+        return Some(x);
     }
 
     /// Computes the [determinant][Wiki] of a matrix
@@ -297,6 +382,85 @@ impl Matrix3 {
             self[0][2] * self[0][2] + self[1][2] * self[1][2] + self[2][2] * self[2][2]
         )
     }
+
+    /// Compute the eigenvalues and eigenvectors of a symmetric matrix, using
+    /// the cyclic [Jacobi eigenvalue algorithm][Wiki]. The eigenvalues are
+    /// sorted in increasing order, and the eigenvectors are normalized. The
+    /// eigenvectors form an orthonormal basis, even for degenerate
+    /// eigenvalues.
+    ///
+    /// Only the upper triangle of the matrix is used, the result is
+    /// meaningless if the matrix is not symmetric.
+    ///
+    /// [Wiki]: https://en.wikipedia.org/wiki/Jacobi_eigenvalue_algorithm
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::{Matrix3, Vector3D};
+    /// let matrix = Matrix3::new([
+    ///     [2.0, 1.0, 0.0],
+    ///     [1.0, 2.0, 0.0],
+    ///     [0.0, 0.0, 5.0]
+    /// ]);
+    ///
+    /// let (values, vectors) = matrix.symmetric_eigen();
+    /// assert!(f64::abs(values[0] - 1.0) < 1e-12);
+    /// assert!(f64::abs(values[1] - 3.0) < 1e-12);
+    /// assert!(f64::abs(values[2] - 5.0) < 1e-12);
+    ///
+    /// for i in 0..3 {
+    ///     let residual = matrix * vectors[i] - values[i] * vectors[i];
+    ///     assert!(residual.norm() < 1e-12);
+    /// }
+    /// ```
+    pub fn symmetric_eigen(&self) -> ([f64; 3], [Vector3D; 3]) {
+        let mut matrix = *self;
+        for i in 0..3 {
+            for j in 0..i {
+                matrix[i][j] = matrix[j][i];
+            }
+        }
+
+        let scale = matrix.norm() * matrix.norm();
+        let mut vectors = Matrix3::one();
+        for _ in 0..50 {
+            let off_diagonal = matrix[0][1] * matrix[0][1] + matrix[0][2] * matrix[0][2] + matrix[1][2] * matrix[1][2];
+            if off_diagonal <= 1e-32 * scale {
+                break;
+            }
+
+            for &(p, q) in &[(0, 1), (0, 2), (1, 2)] {
+                if matrix[p][q] == 0.0 {
+                    continue;
+                }
+
+                // Rotation in the (p, q) plane cancelling matrix[p][q]
+                let theta = (matrix[q][q] - matrix[p][p]) / (2.0 * matrix[p][q]);
+                let t = f64::signum(theta) / (f64::abs(theta) + f64::sqrt(theta * theta + 1.0));
+                let cos = 1.0 / f64::sqrt(t * t + 1.0);
+                let sin = t * cos;
+
+                let mut rotation = Matrix3::one();
+                rotation[p][p] = cos;
+                rotation[q][q] = cos;
+                rotation[p][q] = sin;
+                rotation[q][p] = -sin;
+
+                matrix = rotation.transposed() * matrix * rotation;
+                matrix[p][q] = 0.0;
+                matrix[q][p] = 0.0;
+                vectors = vectors * rotation;
+            }
+        }
+
+        let mut order = [0, 1, 2];
+        order.sort_by(|&i, &j| matrix[i][i].partial_cmp(&matrix[j][j]).expect("got NaN in eigenvalues"));
+
+        let values = [matrix[order[0]][order[0]], matrix[order[1]][order[1]], matrix[order[2]][order[2]]];
+        let column = |i: usize| Vector3D::new(vectors[0][i], vectors[1][i], vectors[2][i]);
+        return (values, [column(order[0]), column(order[1]), column(order[2])]);
+    }
 }
 
 impl_arithmetic!(
@@ -499,6 +663,31 @@ impl From<[[f64; 3]; 3]> for Matrix3 {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Matrix3 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let m = &self.0;
+        let flat = [
+            m[0][0], m[0][1], m[0][2],
+            m[1][0], m[1][1], m[1][2],
+            m[2][0], m[2][1], m[2][2],
+        ];
+        flat.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Matrix3 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Matrix3, D::Error> {
+        let f = <[f64; 9]>::deserialize(deserializer)?;
+        Ok(Matrix3::new([
+            [f[0], f[1], f[2]],
+            [f[3], f[4], f[5]],
+            [f[6], f[7], f[8]],
+        ]))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -706,6 +895,49 @@ mod tests {
         ]);
         assert!(a.determinant() != 0.0);
         assert_eq!(one, a * a.inverse());
+
+        let b = Matrix3::new([
+            [2.0, -1.0, 0.5],
+            [0.3, 4.0, 1.0],
+            [-1.5, 2.0, 3.0],
+        ]);
+        assert_ulps_eq!(b * b.try_inverse().unwrap(), one, epsilon = 1e-14);
+        assert_ulps_eq!(b.try_inverse().unwrap() * b, one, epsilon = 1e-14);
+
+        let singular = Matrix3::new([
+            [1.0, 2.0, 3.0],
+            [2.0, 4.0, 6.0],
+            [1.0, 3.0, 8.0],
+        ]);
+        assert_eq!(singular.try_inverse(), None);
+        assert_eq!(Matrix3::zero().try_inverse(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The matrix is not inversible!")]
+    fn inverse_singular() {
+        let _ = Matrix3::zero().inverse();
+    }
+
+    #[test]
+    fn solve() {
+        let a = Matrix3::new([
+            [0.0, 2.0, 3.0],
+            [2.0, 5.0, 3.0],
+            [1.0, -3.0, 8.0],
+        ]);
+        let x = Vector3D::new(1.5, -2.0, 0.25);
+        let solution = a.solve(&(a * x)).unwrap();
+        assert_ulps_eq!(solution, x, epsilon = 1e-14);
+        assert_ulps_eq!(solution, a.inverse() * (a * x), epsilon = 1e-14);
+
+        let singular = Matrix3::new([
+            [1.0, 2.0, 3.0],
+            [4.0, 5.0, 6.0],
+            [7.0, 8.0, 9.0],
+        ]);
+        assert_eq!(singular.solve(&Vector3D::new(1.0, 2.0, 3.0)), None);
+        assert_eq!(Matrix3::zero().solve(&Vector3D::new(1.0, 2.0, 3.0)), None);
     }
 
     #[test]
@@ -782,4 +1014,82 @@ mod tests {
         assert_eq!(Matrix3::zero().norm(), 0.0);
         assert_eq!(Matrix3::one().norm(), f64::sqrt(3.0));
     }
+
+    /// Check that `values` and `vectors` are the eigen-decomposition of the
+    /// symmetric `matrix`
+    fn check_eigen(matrix: &Matrix3, values: &[f64; 3], vectors: &[Vector3D; 3]) {
+        assert!(values[0] <= values[1] && values[1] <= values[2]);
+        for i in 0..3 {
+            assert_ulps_eq!(vectors[i].norm(), 1.0, epsilon = 1e-12);
+            assert_ulps_eq!(matrix * vectors[i], values[i] * vectors[i], epsilon = 1e-12);
+            for j in (i + 1)..3 {
+                assert!(f64::abs(vectors[i] * vectors[j]) < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_eigen() {
+        let matrix = Matrix3::new([
+            [3.0, 0.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 2.0]
+        ]);
+        let (values, vectors) = matrix.symmetric_eigen();
+        assert_eq!(values, [-1.0, 2.0, 3.0]);
+        assert_eq!(vectors, [
+            Vector3D::new(0.0, 1.0, 0.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+            Vector3D::new(1.0, 0.0, 0.0),
+        ]);
+
+        // Rotated tensor with known principal values and directions
+        let rotation = Matrix3::rotation(&Vector3D::new(1.0, -2.0, 0.5), 0.7);
+        let diagonal = Matrix3::new([
+            [1.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0],
+            [0.0, 0.0, 5.0]
+        ]);
+        let matrix = rotation * diagonal * rotation.transposed();
+        let (values, vectors) = matrix.symmetric_eigen();
+        assert_ulps_eq!(values[0], 1.0, epsilon = 1e-12);
+        assert_ulps_eq!(values[1], 2.0, epsilon = 1e-12);
+        assert_ulps_eq!(values[2], 5.0, epsilon = 1e-12);
+        check_eigen(&matrix, &values, &vectors);
+        for i in 0..3 {
+            let expected = Vector3D::new(rotation[0][i], rotation[1][i], rotation[2][i]);
+            assert_ulps_eq!(f64::abs(vectors[i] * expected), 1.0, epsilon = 1e-12);
+        }
+
+        // Degenerate eigenvalues
+        let diagonal = Matrix3::new([
+            [4.0, 0.0, 0.0],
+            [0.0, 4.0, 0.0],
+            [0.0, 0.0, -2.0]
+        ]);
+        let matrix = rotation * diagonal * rotation.transposed();
+        let (values, vectors) = matrix.symmetric_eigen();
+        assert_ulps_eq!(values[0], -2.0, epsilon = 1e-12);
+        assert_ulps_eq!(values[1], 4.0, epsilon = 1e-12);
+        assert_ulps_eq!(values[2], 4.0, epsilon = 1e-12);
+        check_eigen(&matrix, &values, &vectors);
+
+        let (values, _) = Matrix3::zero().symmetric_eigen();
+        assert_eq!(values, [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let matrix = Matrix3::new([
+            [1.0, 2.0, 4.0],
+            [0.0, 1.0, 3.0],
+            [-2.0, 2.5, 1.0]
+        ]);
+
+        let json = serde_json::to_string(&matrix).unwrap();
+        assert_eq!(json, "[1.0,2.0,4.0,0.0,1.0,3.0,-2.0,2.5,1.0]");
+        assert_eq!(serde_json::from_str::<Matrix3>(&json).unwrap(), matrix);
+        assert!(serde_json::from_str::<Matrix3>("[1.0,2.0]").is_err());
+    }
 }
@@ -0,0 +1,352 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Quaternion type, used to represent rotations
+use std::f64;
+use std::ops::{Mul, MulAssign};
+
+use crate::{Matrix3, Vector3D};
+
+/// A quaternion $q = w + x \mathbf i + y \mathbf j + z \mathbf k$, with double
+/// precision components.
+///
+/// Unit quaternions represent rotations in 3 dimensions, and are used to
+/// store the orientation of rigid bodies. Multiplying two unit quaternions
+/// composes the corresponding rotations:
+///
+/// ```
+/// # use lumol_core::types::{Quaternion, Vector3D};
+/// # use std::f64::consts::PI;
+/// let a = Quaternion::from_axis_angle(&Vector3D::new(0.0, 0.0, 1.0), PI / 2.0);
+/// let b = Quaternion::from_axis_angle(&Vector3D::new(1.0, 0.0, 0.0), PI / 2.0);
+///
+/// // Rotate first with b, then with a
+/// let rotated = (a * b).rotate(&Vector3D::new(0.0, 1.0, 0.0));
+/// assert!((rotated - Vector3D::new(0.0, 0.0, 1.0)).norm() < 1e-12);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl Quaternion {
+    /// Create a new `Quaternion` from its components.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::Quaternion;
+    /// let q = Quaternion::new(1.0, 2.0, 3.0, 4.0);
+    /// assert_eq!(q.w(), 1.0);
+    /// assert_eq!(q.x(), 2.0);
+    /// ```
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion { w: w, x: x, y: y, z: z }
+    }
+
+    /// Create the unit quaternion corresponding to the identity rotation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::{Quaternion, Vector3D};
+    /// let q = Quaternion::one();
+    /// let v = Vector3D::new(1.0, 2.0, 3.0);
+    /// assert_eq!(q.rotate(&v), v);
+    /// ```
+    pub fn one() -> Quaternion {
+        Quaternion::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Create the unit quaternion corresponding to a rotation of `angle`
+    /// radians around the `axis`. The axis does not need to be normalized.
+    ///
+    /// # Panics
+    ///
+    /// If the `axis` is the null vector.
+    pub fn from_axis_angle(axis: &Vector3D, angle: f64) -> Quaternion {
+        let norm = axis.norm();
+        assert!(norm > 0.0, "the rotation axis can not be the null vector");
+        let axis = axis * (f64::sin(0.5 * angle) / norm);
+        Quaternion::new(f64::cos(0.5 * angle), axis[0], axis[1], axis[2])
+    }
+
+    /// Create the unit quaternion corresponding to the given `rotation`
+    /// matrix, which must be orthogonal with a determinant of 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::{Quaternion, Vector3D};
+    /// let q = Quaternion::from_axis_angle(&Vector3D::new(1.0, 2.0, 3.0), 0.3);
+    /// let converted = Quaternion::from_rotation_matrix(&q.rotation_matrix());
+    /// assert!((q.w() - converted.w()).abs() < 1e-12);
+    /// ```
+    pub fn from_rotation_matrix(rotation: &Matrix3) -> Quaternion {
+        let m = rotation;
+        let trace = m.trace();
+        // Use the biggest diagonal element to get the best numerical
+        // accuracy, following Shepperd's method.
+        let q = if trace > 0.0 {
+            let s = 2.0 * f64::sqrt(1.0 + trace);
+            Quaternion::new(
+                0.25 * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * f64::sqrt(1.0 + m[0][0] - m[1][1] - m[2][2]);
+            Quaternion::new(
+                (m[2][1] - m[1][2]) / s,
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * f64::sqrt(1.0 + m[1][1] - m[0][0] - m[2][2]);
+            Quaternion::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = 2.0 * f64::sqrt(1.0 + m[2][2] - m[0][0] - m[1][1]);
+            Quaternion::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+            )
+        };
+        return q.normalized();
+    }
+
+    /// Get the real part of the quaternion
+    #[inline]
+    pub fn w(&self) -> f64 {
+        self.w
+    }
+
+    /// Get the first imaginary component of the quaternion
+    #[inline]
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    /// Get the second imaginary component of the quaternion
+    #[inline]
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    /// Get the third imaginary component of the quaternion
+    #[inline]
+    pub fn z(&self) -> f64 {
+        self.z
+    }
+
+    /// Get the imaginary part of the quaternion, as a vector
+    #[inline]
+    pub fn vector(&self) -> Vector3D {
+        Vector3D::new(self.x, self.y, self.z)
+    }
+
+    /// Get the square of the norm of the quaternion
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::Quaternion;
+    /// let q = Quaternion::new(1.0, 2.0, 3.0, 4.0);
+    /// assert_eq!(q.norm2(), 30.0);
+    /// ```
+    #[inline]
+    pub fn norm2(&self) -> f64 {
+        self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z
+    }
+
+    /// Get the norm of the quaternion
+    #[inline]
+    pub fn norm(&self) -> f64 {
+        f64::sqrt(self.norm2())
+    }
+
+    /// Get the unit quaternion with the same direction as this one
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::Quaternion;
+    /// let q = Quaternion::new(0.0, 3.0, 0.0, 4.0).normalized();
+    /// assert_eq!(q, Quaternion::new(0.0, 0.6, 0.0, 0.8));
+    /// ```
+    #[inline]
+    pub fn normalized(&self) -> Quaternion {
+        let norm = self.norm();
+        Quaternion::new(self.w / norm, self.x / norm, self.y / norm, self.z / norm)
+    }
+
+    /// Get the conjugate of the quaternion. For unit quaternions, this is the
+    /// inverse rotation.
+    #[inline]
+    pub fn conj(&self) -> Quaternion {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Rotate the `vector` with the rotation represented by this quaternion,
+    /// which must be a unit quaternion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::types::{Quaternion, Vector3D};
+    /// # use std::f64::consts::PI;
+    /// let q = Quaternion::from_axis_angle(&Vector3D::new(0.0, 0.0, 1.0), PI / 2.0);
+    /// let rotated = q.rotate(&Vector3D::new(1.0, 0.0, 0.0));
+    /// assert!((rotated - Vector3D::new(0.0, 1.0, 0.0)).norm() < 1e-12);
+    /// ```
+    pub fn rotate(&self, vector: &Vector3D) -> Vector3D {
+        // v' = v + 2 w (u x v) + 2 u x (u x v), with u the vector part
+        let u = self.vector();
+        let t = 2.0 * (u ^ vector);
+        return vector + self.w * t + (u ^ t);
+    }
+
+    /// Get the rotation matrix corresponding to this quaternion, which must
+    /// be a unit quaternion.
+    pub fn rotation_matrix(&self) -> Matrix3 {
+        let (w, x, y, z) = (self.w, self.x, self.y, self.z);
+        Matrix3::new([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+            [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+            [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+        ])
+    }
+}
+
+/// Hamilton product of quaternions
+impl Mul<Quaternion> for Quaternion {
+    type Output = Quaternion;
+    #[inline]
+    fn mul(self, other: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        )
+    }
+}
+
+impl MulAssign<Quaternion> for Quaternion {
+    #[inline]
+    fn mul_assign(&mut self, other: Quaternion) {
+        *self = *self * other;
+    }
+}
+
+impl Mul<f64> for Quaternion {
+    type Output = Quaternion;
+    #[inline]
+    fn mul(self, other: f64) -> Quaternion {
+        Quaternion::new(self.w * other, self.x * other, self.y * other, self.z * other)
+    }
+}
+
+impl Mul<Quaternion> for f64 {
+    type Output = Quaternion;
+    #[inline]
+    fn mul(self, other: Quaternion) -> Quaternion {
+        other * self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_ulps_eq;
+
+    fn assert_matrix_eq(a: &Matrix3, b: &Matrix3) {
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_ulps_eq!(a[i][j], b[i][j], epsilon = 1e-14);
+            }
+        }
+    }
+
+    fn rotations() -> Vec<Quaternion> {
+        vec![
+            Quaternion::one(),
+            Quaternion::from_axis_angle(&Vector3D::new(1.0, 2.0, 3.0), 0.3),
+            Quaternion::from_axis_angle(&Vector3D::new(0.0, 0.0, 1.0), f64::consts::PI),
+            Quaternion::from_axis_angle(&Vector3D::new(1.0, 0.0, 0.0), 3.0),
+            Quaternion::from_axis_angle(&Vector3D::new(0.0, -1.0, 0.1), 2.9),
+            Quaternion::from_axis_angle(&Vector3D::new(0.2, 0.1, -5.0), -2.5),
+            Quaternion::new(0.1, -0.4, 0.7, 0.3).normalized(),
+        ]
+    }
+
+    #[test]
+    fn multiplication() {
+        let i = Quaternion::new(0.0, 1.0, 0.0, 0.0);
+        let j = Quaternion::new(0.0, 0.0, 1.0, 0.0);
+        let k = Quaternion::new(0.0, 0.0, 0.0, 1.0);
+        assert_eq!(i * j, k);
+        assert_eq!(j * k, i);
+        assert_eq!(k * i, j);
+        assert_eq!(i * i, -1.0 * Quaternion::one());
+        assert_eq!(i * j * k, -1.0 * Quaternion::one());
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        for q in rotations() {
+            let matrix = q.rotation_matrix();
+            assert_ulps_eq!(matrix.determinant(), 1.0, epsilon = 1e-14);
+
+            // q and -q represent the same rotation
+            let converted = Quaternion::from_rotation_matrix(&matrix);
+            let sign = if converted.w() * q.w() + converted.vector() * q.vector() < 0.0 { -1.0 } else { 1.0 };
+            let converted = sign * converted;
+            assert_ulps_eq!(converted.w(), q.w(), epsilon = 1e-14);
+            assert_ulps_eq!(converted.x(), q.x(), epsilon = 1e-14);
+            assert_ulps_eq!(converted.y(), q.y(), epsilon = 1e-14);
+            assert_ulps_eq!(converted.z(), q.z(), epsilon = 1e-14);
+
+            assert_matrix_eq(&converted.rotation_matrix(), &matrix);
+        }
+    }
+
+    #[test]
+    fn rotate() {
+        let vector = Vector3D::new(0.3, -2.0, 1.5);
+        for q in rotations() {
+            let expected = q.rotation_matrix() * vector;
+            let rotated = q.rotate(&vector);
+            for i in 0..3 {
+                assert_ulps_eq!(rotated[i], expected[i], epsilon = 1e-14);
+            }
+            assert_ulps_eq!(rotated.norm(), vector.norm(), epsilon = 1e-14);
+
+            let back = q.conj().rotate(&rotated);
+            for i in 0..3 {
+                assert_ulps_eq!(back[i], vector[i], epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn composition() {
+        for a in rotations() {
+            for b in rotations() {
+                let product = a.rotation_matrix() * b.rotation_matrix();
+                assert_matrix_eq(&(a * b).rotation_matrix(), &product);
+            }
+        }
+    }
+}
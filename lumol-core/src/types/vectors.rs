@@ -55,7 +55,23 @@ use crate::Matrix3;
 /// let a = u * v;
 /// assert_eq!(a, 3.0);
 /// ```
+///
+/// When the `serde` feature is enabled, `Vector3D` can be serialized and
+/// deserialized as an array of three numbers:
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// # use lumol_core::types::Vector3D;
+/// let u = Vector3D::new(1.0, 2.5, -3.0);
+/// let json = serde_json::to_string(&u).unwrap();
+/// assert_eq!(json, "[1.0,2.5,-3.0]");
+///
+/// let v: Vector3D = serde_json::from_str(&json).unwrap();
+/// assert_eq!(u, v);
+/// # }
+/// ```
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector3D([f64; 3]);
 
 impl Vector3D {
@@ -116,6 +132,22 @@ impl Vector3D {
         f64::sqrt(self.norm2())
     }
 
+    /// Add `factor * other` to this vector, in place. This is the same as
+    /// `*self += factor * other`, but can use fused multiply-add operations
+    /// when the `simd` feature is enabled.
+    ///
+    /// # Examples
+    /// ```
+    /// # use lumol_core::types::Vector3D;
+    /// let mut vec = Vector3D::new(1.0, 0.0, -4.0);
+    /// vec.add_scaled(2.0, &Vector3D::new(1.0, 1.0, 1.0));
+    /// assert_eq!(vec, Vector3D::new(3.0, 2.0, -2.0));
+    /// ```
+    #[inline]
+    pub fn add_scaled(&mut self, factor: f64, other: &Vector3D) {
+        backend::add_scaled(&mut self.0, factor, &other.0);
+    }
+
     /// Normalize a `Vector3D`.
     /// # Examples
     /// ```
@@ -186,6 +218,50 @@ impl Vector3D {
     }
 }
 
+/// Scalar implementation of the most used vector operations
+#[cfg_attr(feature = "simd", allow(dead_code))]
+mod scalar {
+    #[inline]
+    pub fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+    }
+
+    #[inline]
+    pub fn add_scaled(a: &mut [f64; 3], factor: f64, b: &[f64; 3]) {
+        a[0] += factor * b[0];
+        a[1] += factor * b[1];
+        a[2] += factor * b[2];
+    }
+}
+
+/// SIMD implementation of the most used vector operations, using 4-wide
+/// vectors with a zero padding component.
+#[cfg(feature = "simd")]
+mod simd {
+    use wide::f64x4;
+
+    #[inline]
+    fn load(a: &[f64; 3]) -> f64x4 {
+        f64x4::from([a[0], a[1], a[2], 0.0])
+    }
+
+    #[inline]
+    pub fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+        (load(a) * load(b)).reduce_add()
+    }
+
+    #[inline]
+    pub fn add_scaled(a: &mut [f64; 3], factor: f64, b: &[f64; 3]) {
+        let result = load(b).mul_add(f64x4::splat(factor), load(a)).to_array();
+        a.copy_from_slice(&result[..3]);
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+use self::scalar as backend;
+#[cfg(feature = "simd")]
+use self::simd as backend;
+
 impl_arithmetic!(
     Vector3D, Vector3D, Add, add, Vector3D,
     self, other,
@@ -214,7 +290,7 @@ impl_inplace_arithmetic!(
 impl_arithmetic!(
     Vector3D, Vector3D, Mul, mul, f64,
     self, other,
-    self[0] * other[0] + self[1] * other[1] + self[2] * other[2]
+    backend::dot(&self, &other)
 );
 
 // Cross product
@@ -570,4 +646,39 @@ mod tests {
         assert_eq!(Vector3D::new(4.0e89, 0.0, f64::INFINITY).max(), f64::INFINITY);
         assert_eq!(Vector3D::new(f64::MAX, 0.0, f64::INFINITY).max(), f64::INFINITY);
     }
+
+    #[test]
+    fn add_scaled() {
+        let mut a = Vector3D::new(1.0, 0.0, -4.0);
+        a.add_scaled(-0.5, &Vector3D::new(2.0, 4.0, 1.0));
+        assert_eq!(a, Vector3D::new(0.0, -2.0, -4.5));
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn simd_matches_scalar() {
+        use super::{scalar, simd};
+        use approx::assert_relative_eq;
+
+        // Deterministic batch of pseudo-random vectors
+        let vectors = (0..1000).map(|i| {
+            let i = i as f64;
+            Vector3D::new(f64::sin(i) * 100.0, f64::cos(3.0 * i) * 10.0, f64::sin(7.0 * i + 0.3))
+        }).collect::<Vec<_>>();
+
+        for pair in vectors.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            let scale = a.norm() * b.norm();
+            assert_relative_eq!(simd::dot(a, b), scalar::dot(a, b), epsilon = 1e-14 * scale);
+
+            let factor = a[1];
+            let mut expected = **a;
+            let mut actual = **a;
+            scalar::add_scaled(&mut expected, factor, b);
+            simd::add_scaled(&mut actual, factor, b);
+            for k in 0..3 {
+                assert_relative_eq!(actual[k], expected[k], epsilon = 1e-14 * (a.norm() + f64::abs(factor) * b.norm()));
+            }
+        }
+    }
 }
@@ -14,11 +14,17 @@
 //!
 //! Other units are derived from these primitives units. For examples, the
 //! internal unit for energy is 1e-4 kJ/mol.
+//!
+//! Units can be combined using multiplication (`*`), division (`/`),
+//! integer powers (`^`) and parentheses. Divisions are left-associative, so
+//! `kJ/mol/A^2` and `kJ/(mol*A^2)` are the same unit. Powers can be negative,
+//! as in `kJ*mol^-1*A^-2`.
 #![allow(clippy::unreadable_literal)]
 
 use std::error::Error;
 use std::fmt;
 use std::num;
+use std::ops::{Div, Mul};
 use std::collections::BTreeMap;
 use std::f64::consts::PI;
 
@@ -30,58 +36,74 @@ use crate::consts::{BOHR_RADIUS, AVOGADRO_NUMBER};
 const U_IN_KG: f64 = 1.660538782e-27;
 
 lazy_static!{
-    /// A map of conversion factors from various units to lumol internal units
-    pub static ref CONVERSION_FACTORS: BTreeMap<&'static str, f64> = {
+    /// A map of units to their conversion factor to lumol internal units and
+    /// their dimension
+    static ref UNITS: BTreeMap<&'static str, (f64, Dimension)> = {
+        let length = Dimension::LENGTH;
+        let time = Dimension::TIME;
+        let mass = Dimension::MASS;
+        let temperature = Dimension::TEMPERATURE;
+        let angle = Dimension::ANGLE;
+        let energy = Dimension::ENERGY;
+        let force = energy / length;
+        let pressure = energy / length.powi(3);
+
         let mut map = BTreeMap::new();
         // Distances units.
-        assert!(map.insert("A", 1.0).is_none());
-        assert!(map.insert("nm", 10.0).is_none());
-        assert!(map.insert("pm", 1e-2).is_none());
-        assert!(map.insert("fm", 1e-5).is_none());
-        assert!(map.insert("m", 1e10).is_none());
-        assert!(map.insert("bohr", BOHR_RADIUS).is_none());
+        assert!(map.insert("A", (1.0, length)).is_none());
+        assert!(map.insert("nm", (10.0, length)).is_none());
+        assert!(map.insert("cm", (1e8, length)).is_none());
+        assert!(map.insert("pm", (1e-2, length)).is_none());
+        assert!(map.insert("fm", (1e-5, length)).is_none());
+        assert!(map.insert("m", (1e10, length)).is_none());
+        assert!(map.insert("bohr", (BOHR_RADIUS, length)).is_none());
 
         // Time units.
-        assert!(map.insert("fs", 1.0).is_none());
-        assert!(map.insert("ps", 1e3).is_none());
-        assert!(map.insert("ns", 1e6).is_none());
+        assert!(map.insert("fs", (1.0, time)).is_none());
+        assert!(map.insert("ps", (1e3, time)).is_none());
+        assert!(map.insert("ns", (1e6, time)).is_none());
 
         // Mass units.
-        assert!(map.insert("u", 1.0).is_none());
-        assert!(map.insert("Da", 1.0).is_none());
-        assert!(map.insert("kDa", 1.0).is_none());
-        assert!(map.insert("g", 1e-3 / U_IN_KG).is_none());
-        assert!(map.insert("kg", 1.0 / U_IN_KG).is_none());
+        assert!(map.insert("u", (1.0, mass)).is_none());
+        assert!(map.insert("Da", (1.0, mass)).is_none());
+        assert!(map.insert("kDa", (1.0, mass)).is_none());
+        assert!(map.insert("g", (1e-3 / U_IN_KG, mass)).is_none());
+        assert!(map.insert("kg", (1.0 / U_IN_KG, mass)).is_none());
 
         // Temperature units.
-        assert!(map.insert("K", 1.0).is_none());
+        assert!(map.insert("K", (1.0, temperature)).is_none());
         // Quantity of matter units.
-        assert!(map.insert("mol", AVOGADRO_NUMBER).is_none());
+        assert!(map.insert("mol", (AVOGADRO_NUMBER, Dimension::NONE)).is_none());
 
         // Angle units.
-        assert!(map.insert("rad", 1.0).is_none());
-        assert!(map.insert("deg", PI / 180.0).is_none());
+        assert!(map.insert("rad", (1.0, angle)).is_none());
+        assert!(map.insert("deg", (PI / 180.0, angle)).is_none());
 
         // Energy units.
-        assert!(map.insert("J", 1e-10 / U_IN_KG).is_none());
-        assert!(map.insert("kJ", 1e-7 / U_IN_KG).is_none());
-        assert!(map.insert("kcal", 4.184 * 1e-7 / U_IN_KG).is_none());
-        assert!(map.insert("eV", 1.60217653e-19 * 1e-10 / U_IN_KG).is_none());
-        assert!(map.insert("H", 4.35974417e-18 * 1e-10 / U_IN_KG).is_none());
-        assert!(map.insert("Ry", 4.35974417e-18 / 2.0 * 1e-10 / U_IN_KG).is_none());
+        assert!(map.insert("J", (1e-10 / U_IN_KG, energy)).is_none());
+        assert!(map.insert("kJ", (1e-7 / U_IN_KG, energy)).is_none());
+        assert!(map.insert("kcal", (4.184 * 1e-7 / U_IN_KG, energy)).is_none());
+        assert!(map.insert("eV", (1.60217653e-19 * 1e-10 / U_IN_KG, energy)).is_none());
+        assert!(map.insert("H", (4.35974417e-18 * 1e-10 / U_IN_KG, energy)).is_none());
+        assert!(map.insert("Ry", (4.35974417e-18 / 2.0 * 1e-10 / U_IN_KG, energy)).is_none());
 
         // Force unit.
-        assert!(map.insert("N", 1e-20 / U_IN_KG).is_none());
+        assert!(map.insert("N", (1e-20 / U_IN_KG, force)).is_none());
 
         // Pressure units.
-        assert!(map.insert("Pa", 1e-40 / U_IN_KG).is_none());
-        assert!(map.insert("kPa", 1e-37 / U_IN_KG).is_none());
-        assert!(map.insert("MPa", 1e-34 / U_IN_KG).is_none());
-        assert!(map.insert("bar", 1e-35 / U_IN_KG).is_none());
-        assert!(map.insert("atm", 101325.0 * 1e-40 / U_IN_KG).is_none());
+        assert!(map.insert("Pa", (1e-40 / U_IN_KG, pressure)).is_none());
+        assert!(map.insert("kPa", (1e-37 / U_IN_KG, pressure)).is_none());
+        assert!(map.insert("MPa", (1e-34 / U_IN_KG, pressure)).is_none());
+        assert!(map.insert("bar", (1e-35 / U_IN_KG, pressure)).is_none());
+        assert!(map.insert("atm", (101325.0 * 1e-40 / U_IN_KG, pressure)).is_none());
 
         return map;
     };
+
+    /// A map of conversion factors from various units to lumol internal units
+    pub static ref CONVERSION_FACTORS: BTreeMap<&'static str, f64> = {
+        UNITS.iter().map(|(&unit, &(factor, _))| (unit, factor)).collect()
+    };
 }
 
 /// Possible error causes when parsing an unit string.
@@ -98,6 +120,13 @@ pub enum ParseError {
         /// The unit that created this error
         unit: String,
     },
+    /// The dimension of an unit is not the expected one
+    DimensionMismatch {
+        /// The expected dimension
+        expected: Dimension,
+        /// The actual dimension of the unit
+        got: Dimension,
+    },
     /// Any other error
     MalformedExpr(String),
 }
@@ -121,6 +150,9 @@ impl fmt::Display for ParseError {
             ParseError::Value(ref err) => err.fmt(f),
             ParseError::ParenthesesMismatch => write!(f, "Parentheses are not equilibrated."),
             ParseError::NotFound { ref unit } => write!(f, "Unit '{}' not found.", unit),
+            ParseError::DimensionMismatch { expected, got } => write!(
+                f, "Expected an unit with dimension '{}', got an unit with dimension '{}'.", expected, got
+            ),
             ParseError::MalformedExpr(ref err) => write!(f, "Malformed expression: {}", err),
         }
     }
@@ -133,11 +165,118 @@ impl Error for ParseError {
             ParseError::Value(ref err) => err.description(),
             ParseError::ParenthesesMismatch => "Parentheses are not equilibrated.",
             ParseError::NotFound { .. } => "Unit not found.",
+            ParseError::DimensionMismatch { .. } => "Unit with the wrong dimension.",
             ParseError::MalformedExpr(..) => "Malformed expression",
         }
     }
 }
 
+/// Dimension of a physical quantity, as the exponents of the base
+/// dimensions.
+///
+/// The base dimensions correspond to the internal units: length, time, mass,
+/// temperature and angle. Quantities of matter are numbers of particles, so
+/// `mol` is dimensionless and `kJ/mol` is an energy, like `eV`.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::units::{self, Dimension};
+/// let spring_constant = Dimension::ENERGY / Dimension::LENGTH.powi(2);
+/// assert_eq!(units::dimension_of("kJ/mol/A^2").unwrap(), spring_constant);
+/// assert_eq!(units::dimension_of("kcal/(mol*nm^2)").unwrap(), spring_constant);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Dimension {
+    /// Exponent of the length dimension
+    pub length: i32,
+    /// Exponent of the time dimension
+    pub time: i32,
+    /// Exponent of the mass dimension
+    pub mass: i32,
+    /// Exponent of the temperature dimension
+    pub temperature: i32,
+    /// Exponent of the angle dimension
+    pub angle: i32,
+}
+
+impl Dimension {
+    /// Dimension of dimensionless quantities
+    pub const NONE: Dimension = Dimension { length: 0, time: 0, mass: 0, temperature: 0, angle: 0 };
+    /// Length dimension
+    pub const LENGTH: Dimension = Dimension { length: 1, ..Dimension::NONE };
+    /// Time dimension
+    pub const TIME: Dimension = Dimension { time: 1, ..Dimension::NONE };
+    /// Mass dimension
+    pub const MASS: Dimension = Dimension { mass: 1, ..Dimension::NONE };
+    /// Temperature dimension
+    pub const TEMPERATURE: Dimension = Dimension { temperature: 1, ..Dimension::NONE };
+    /// Angle dimension
+    pub const ANGLE: Dimension = Dimension { angle: 1, ..Dimension::NONE };
+    /// Energy dimension, mass times length squared divided by time squared
+    pub const ENERGY: Dimension = Dimension { length: 2, time: -2, mass: 1, ..Dimension::NONE };
+
+    /// Get this dimension to the power `n`
+    pub fn powi(self, n: i32) -> Dimension {
+        Dimension {
+            length: n * self.length,
+            time: n * self.time,
+            mass: n * self.mass,
+            temperature: n * self.temperature,
+            angle: n * self.angle,
+        }
+    }
+}
+
+impl Mul for Dimension {
+    type Output = Dimension;
+    fn mul(self, other: Dimension) -> Dimension {
+        Dimension {
+            length: self.length + other.length,
+            time: self.time + other.time,
+            mass: self.mass + other.mass,
+            temperature: self.temperature + other.temperature,
+            angle: self.angle + other.angle,
+        }
+    }
+}
+
+impl Div for Dimension {
+    type Output = Dimension;
+    fn div(self, other: Dimension) -> Dimension {
+        self * other.powi(-1)
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Dimension::NONE {
+            return write!(f, "dimensionless");
+        }
+
+        let exponents = [
+            ("mass", self.mass),
+            ("length", self.length),
+            ("time", self.time),
+            ("temperature", self.temperature),
+            ("angle", self.angle),
+        ];
+        let mut first = true;
+        for &(name, exponent) in exponents.iter().filter(|&&(_, exponent)| exponent != 0) {
+            if !first {
+                write!(f, " ")?;
+            }
+            first = false;
+            if exponent == 1 {
+                write!(f, "{}", name)?;
+            } else {
+                write!(f, "{}^{}", name, exponent)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Possible tokens in unit strings
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Token {
@@ -264,8 +403,8 @@ fn shunting_yard(tokens: Vec<Token>) -> Result<Vec<Token>, ParseError> {
 /// Possible members in unit expressions
 #[derive(Debug, PartialEq)]
 enum UnitExpr {
-    /// A single value
-    Val(f64),
+    /// A single value, with its dimension
+    Val(f64, Dimension),
     /// Multiplication of left-hand side by right-hand side
     Mul(Box<UnitExpr>, Box<UnitExpr>),
     /// Division of left-hand side by right-hand side
@@ -278,13 +417,23 @@ impl UnitExpr {
     /// Recursively evaluate an unit expression
     fn eval(&self) -> f64 {
         match *self {
-            UnitExpr::Val(v) => v,
+            UnitExpr::Val(v, _) => v,
             UnitExpr::Mul(ref lhs, ref rhs) => lhs.eval() * rhs.eval(),
             UnitExpr::Div(ref lhs, ref rhs) => lhs.eval() / rhs.eval(),
             UnitExpr::Pow(ref expr, pow) => expr.eval().powi(pow),
         }
     }
 
+    /// Recursively compute the dimension of an unit expression
+    fn dimension(&self) -> Dimension {
+        match *self {
+            UnitExpr::Val(_, dimension) => dimension,
+            UnitExpr::Mul(ref lhs, ref rhs) => lhs.dimension() * rhs.dimension(),
+            UnitExpr::Div(ref lhs, ref rhs) => lhs.dimension() / rhs.dimension(),
+            UnitExpr::Pow(ref expr, pow) => expr.dimension().powi(pow),
+        }
+    }
+
     /// Parse a string, and generate the corresponding unit expression
     fn parse(unit: &str) -> Result<UnitExpr, ParseError> {
         let tokens = tokenize(unit);
@@ -301,33 +450,37 @@ impl UnitExpr {
     }
 }
 
+/// Add context to an `error` in the unit at the `side` of an `operator`.
+/// Unknown units are reported directly, since their error message is
+/// already clear enough.
+fn with_context(error: ParseError, side: &str, operator: &str) -> ParseError {
+    match error {
+        ParseError::NotFound { .. } => error,
+        error => ParseError::MalformedExpr(
+            format!("Error in unit at the {} of '{}': {}", side, operator, error)
+        ),
+    }
+}
+
 /// Read and pop (recursively) a single expression from the `stream`.
 /// The `stream` must be in reverse polish notation.
 fn read_expr(stream: &mut Vec<Token>) -> Result<UnitExpr, ParseError> {
     if let Some(token) = stream.pop() {
         match token {
             Token::Value(unit) => {
-                match CONVERSION_FACTORS.get(&*unit) {
-                    Some(&value) => Ok(UnitExpr::Val(value)),
+                match UNITS.get(&*unit) {
+                    Some(&(value, dimension)) => Ok(UnitExpr::Val(value, dimension)),
                     None => Err(ParseError::NotFound { unit: unit }),
                 }
             }
             Token::Mul => {
-                let rhs = read_expr(stream).map_err(|err| {
-                    ParseError::MalformedExpr(format!("Error in unit at the right of '*': {}", err))
-                })?;
-                let lhs = read_expr(stream).map_err(|err| {
-                    ParseError::MalformedExpr(format!("Error in unit at the left of '*': {}", err))
-                })?;
+                let rhs = read_expr(stream).map_err(|err| with_context(err, "right", "*"))?;
+                let lhs = read_expr(stream).map_err(|err| with_context(err, "left", "*"))?;
                 Ok(UnitExpr::Mul(Box::new(lhs), Box::new(rhs)))
             }
             Token::Div => {
-                let rhs = read_expr(stream).map_err(|err| {
-                    ParseError::MalformedExpr(format!("Error in unit at the right of '/': {}", err))
-                })?;
-                let lhs = read_expr(stream).map_err(|err| {
-                    ParseError::MalformedExpr(format!("Error in unit at the left of '/': {}", err))
-                })?;
+                let rhs = read_expr(stream).map_err(|err| with_context(err, "right", "/"))?;
+                let lhs = read_expr(stream).map_err(|err| with_context(err, "left", "/"))?;
                 Ok(UnitExpr::Div(Box::new(lhs), Box::new(rhs)))
             }
             Token::Pow => {
@@ -348,9 +501,7 @@ fn read_expr(stream: &mut Vec<Token>) -> Result<UnitExpr, ParseError> {
                         )
                     }
                 };
-                let expr = read_expr(stream).map_err(|err| {
-                    ParseError::MalformedExpr(format!("Error in unit at the left of '*': {}", err))
-                })?;
+                let expr = read_expr(stream).map_err(|err| with_context(err, "left", "^"))?;
                 Ok(UnitExpr::Pow(Box::new(expr), pow))
             }
             Token::LParen | Token::RParen => {
@@ -385,7 +536,7 @@ pub fn from_str(value: &str) -> Result<f64, ParseError> {
     let splitted = value.split_whitespace().collect::<Vec<&str>>();
     let unit = splitted[1..].join(" ");
     let unit = if unit.is_empty() {
-        UnitExpr::Val(1.0)
+        UnitExpr::Val(1.0, Dimension::NONE)
     } else {
         UnitExpr::parse(&unit)?
     };
@@ -405,6 +556,64 @@ pub fn to(value: f64, unit: &str) -> Result<f64, ParseError> {
     return Ok(value / unit.eval());
 }
 
+/// Get the dimension of the `unit`.
+///
+/// ```
+/// use lumol_core::units::{self, Dimension};
+/// assert_eq!(units::dimension_of("kJ/mol").unwrap(), Dimension::ENERGY);
+/// assert_eq!(units::dimension_of("bohr").unwrap(), Dimension::LENGTH);
+/// assert_eq!(units::dimension_of("K").unwrap(), Dimension::TEMPERATURE);
+/// ```
+pub fn dimension_of(unit: &str) -> Result<Dimension, ParseError> {
+    let unit = UnitExpr::parse(unit)?;
+    return Ok(unit.dimension());
+}
+
+/// Convert the numeric value `val` from the unit `unit` to the internal unit,
+/// checking that the unit has the `expected` dimension.
+///
+/// ```
+/// use lumol_core::units::{self, Dimension};
+/// let k = units::from_checked(300.0, "kJ/mol/A^2", Dimension::ENERGY / Dimension::LENGTH.powi(2));
+/// assert!(k.is_ok());
+///
+/// let k = units::from_checked(300.0, "kJ/mol/deg^2", Dimension::ENERGY / Dimension::LENGTH.powi(2));
+/// assert!(k.is_err());
+/// ```
+pub fn from_checked(value: f64, unit: &str, expected: Dimension) -> Result<f64, ParseError> {
+    let unit = UnitExpr::parse(unit)?;
+    let dimension = unit.dimension();
+    if dimension != expected {
+        return Err(ParseError::DimensionMismatch {
+            expected: expected,
+            got: dimension,
+        });
+    }
+    return Ok(unit.eval() * value);
+}
+
+/// Convert the numeric value `val` from the unit `from` to the unit `to`,
+/// checking that both units have the same dimension.
+///
+/// ```
+/// use lumol_core::units;
+/// let pressure = units::convert(1.0, "atm", "bar").unwrap();
+/// assert!(f64::abs(pressure - 1.01325) < 1e-12);
+///
+/// assert!(units::convert(1.0, "kJ/mol", "K").is_err());
+/// ```
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64, ParseError> {
+    let from = UnitExpr::parse(from)?;
+    let to = UnitExpr::parse(to)?;
+    if from.dimension() != to.dimension() {
+        return Err(ParseError::DimensionMismatch {
+            expected: to.dimension(),
+            got: from.dimension(),
+        });
+    }
+    return Ok(value * from.eval() / to.eval());
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -454,8 +663,8 @@ mod test {
 
     #[test]
     fn eval() {
-        assert_eq!(UnitExpr::parse("A").unwrap(), UnitExpr::Val(1.0));
-        assert_eq!(UnitExpr::parse("nm").unwrap(), UnitExpr::Val(10.0));
+        assert_eq!(UnitExpr::parse("A").unwrap(), UnitExpr::Val(1.0, Dimension::LENGTH));
+        assert_eq!(UnitExpr::parse("nm").unwrap(), UnitExpr::Val(10.0, Dimension::LENGTH));
 
         assert_eq!(UnitExpr::parse("bohr/fs").unwrap().eval(), 0.52917720859);
         assert_eq!(UnitExpr::parse("(Ry / rad^-3   )").unwrap().eval(), 0.1312749878912494);
@@ -466,6 +675,48 @@ mod test {
         assert_ulps_eq!(UnitExpr::parse("kcal/mol/A^2").unwrap().eval(), 4.184e-4, epsilon = 1e-9);
     }
 
+    #[test]
+    fn compound_units() {
+        // Each compound unit should be equal to the flat version
+        let units = [
+            ("kJ/(mol*A^2)", "kJ/mol/A^2"),
+            ("kJ / ( mol * A ^ 2 )", "kJ/mol/A^2"),
+            ("kJ*mol^-1*A^-2", "kJ/mol/A^2"),
+            ("g/(mol*cm^3)", "g/mol/cm^3"),
+            ("kcal/(mol*deg^2)", "kcal/mol/deg^2"),
+            ("(kJ/mol)/A", "kJ/mol/A"),
+            ("kJ/(mol/A)", "kJ*A/mol"),
+            ("(kJ/mol/A)^2", "kJ^2/mol^2/A^2"),
+            ("bar/(m*fs^2)", "bar/m/fs^2"),
+            ("eV/(A*(u/fs^2))", "eV*fs^2/A/u"),
+            ("(nm^3)^-1", "nm^-3"),
+            ("A^(-2)", "A^-2"),
+            ("ps^-1", "rad/ps"),
+        ];
+
+        for &(compound, flat) in &units {
+            let expected = UnitExpr::parse(flat).unwrap().eval();
+            let value = UnitExpr::parse(compound).unwrap().eval();
+            assert_ulps_eq!(value, expected, max_ulps = 4);
+        }
+
+        assert_eq!(UnitExpr::parse("cm").unwrap().eval(), 1e8);
+        assert_ulps_eq!(UnitExpr::parse("nm^-2").unwrap().eval(), 0.01);
+    }
+
+    #[test]
+    fn unknown_units() {
+        for &unit in &["mool", "kJ/mool", "kJ/(mool*A^2)", "(mool/A)^2", "A*mool^-1"] {
+            match UnitExpr::parse(unit) {
+                Err(ParseError::NotFound { unit }) => assert_eq!(unit, "mool"),
+                other => panic!("expected a NotFound error for {}, got {:?}", unit, other),
+            }
+        }
+
+        let error = from(1.0, "kJ/(mol*cm^3*mool)").unwrap_err();
+        assert_eq!(error.to_string(), "Unit 'mool' not found.");
+    }
+
     #[test]
     fn parsing_errrors() {
         assert!(UnitExpr::parse("m^4-8").is_err());
@@ -474,6 +725,49 @@ mod test {
         assert!(UnitExpr::parse("HJK").is_err());
     }
 
+    #[test]
+    fn dimensions() {
+        let energy = dimension_of("kJ/mol").unwrap();
+        assert_eq!(energy, Dimension::ENERGY);
+        assert_ne!(energy, dimension_of("K").unwrap());
+        assert_eq!(energy, dimension_of("eV").unwrap());
+        assert_eq!(energy, dimension_of("u*A^2/fs^2").unwrap());
+        assert_eq!(dimension_of("mol").unwrap(), Dimension::NONE);
+
+        let spring = Dimension::ENERGY / (Dimension::LENGTH * Dimension::LENGTH);
+        assert_eq!(dimension_of("kJ/mol/A^2").unwrap(), spring);
+        assert_eq!(dimension_of("kJ/(mol*nm^2)").unwrap(), spring);
+        assert_ne!(dimension_of("kJ/mol/deg^2").unwrap(), spring);
+        assert_eq!(dimension_of("N/m").unwrap(), spring);
+        assert_eq!(dimension_of("bar*A^3").unwrap(), Dimension::ENERGY);
+
+        assert_eq!(spring.to_string(), "mass time^-2");
+        assert_eq!(Dimension::NONE.to_string(), "dimensionless");
+        assert_eq!(dimension_of("kJ/mol/deg^2").unwrap().to_string(), "mass length^2 time^-2 angle^-2");
+
+        assert!(dimension_of("kJ/mool").is_err());
+    }
+
+    #[test]
+    fn checked_conversions() {
+        let spring = Dimension::ENERGY / Dimension::LENGTH.powi(2);
+        assert_eq!(from_checked(2.0, "kJ/mol/A^2", spring).unwrap(), from(2.0, "kJ/mol/A^2").unwrap());
+        match from_checked(2.0, "kJ/mol/A", spring) {
+            Err(ParseError::DimensionMismatch { expected, got }) => {
+                assert_eq!(expected, spring);
+                assert_eq!(got, Dimension::ENERGY / Dimension::LENGTH);
+            }
+            other => panic!("expected a dimension mismatch, got {:?}", other),
+        }
+
+        assert_ulps_eq!(convert(2.0, "nm", "A").unwrap(), 20.0, epsilon = 1e-12);
+        assert_ulps_eq!(convert(1.0, "kcal/mol", "kJ/mol").unwrap(), 4.184, epsilon = 1e-12);
+        assert_ulps_eq!(convert(25.0, "kJ/mol", "kJ/mol").unwrap(), 25.0, epsilon = 1e-12);
+        assert!(convert(1.0, "kJ/mol", "K").is_err());
+        assert!(convert(1.0, "bar", "kJ/mol").is_err());
+        assert!(convert(1.0, "A", "fs").is_err());
+    }
+
     #[test]
     fn unit_from_str() {
         assert_eq!(from_str("10.0 A").unwrap(), 10.0);
@@ -491,4 +785,22 @@ mod test {
         assert_eq!(to(25.0, "bar").unwrap(), 4.1513469550000005e9);
         assert_eq!(to(25.0, "kJ/mol").unwrap(), 249999.99982494753);
     }
+
+    #[test]
+    fn round_trip() {
+        let units = [
+            "A", "nm", "bohr", "fs", "ps", "u", "g/mol", "K", "deg", "kJ/mol",
+            "kcal/mol", "eV", "bar", "atm", "kJ/(mol*A^2)", "(kcal/mol/A)^2",
+        ];
+        for &unit in &units {
+            for &value in &[1.0, -3.5, 42.0, 1e-7, 6.02e23] {
+                let internal = from(value, unit).unwrap();
+                assert_ulps_eq!(to(internal, unit).unwrap(), value, max_ulps = 4);
+            }
+        }
+
+        assert!(to(25.0, "kJ/mool").is_err());
+        assert!(to(25.0, "kJ/(mol").is_err());
+        assert!(to(25.0, "bar^x").is_err());
+    }
 }
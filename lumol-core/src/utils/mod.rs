@@ -9,6 +9,9 @@ mod macros;
 mod thread_vec;
 pub use self::thread_vec::ThreadLocalVec;
 
+mod reduce;
+pub use self::reduce::{each_i_reduce, each_i_fold_reduce};
+
 #[cfg(test)]
 mod xyz;
 #[cfg(test)]
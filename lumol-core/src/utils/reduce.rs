@@ -0,0 +1,153 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+/// Number of consecutive values reduced serially at the leaves of the tree
+const LEAF_SIZE: usize = 16;
+
+/// Maximal number of blocks used by `each_i_fold_reduce`
+const MAX_FOLD_BLOCKS: usize = 64;
+
+/// Compute `map(i)` in parallel for all `i` in `0..size`, and combine the
+/// values with `reduce`.
+///
+/// The range is split in blocks of consecutive indexes, and the values in
+/// each block are combined starting from a new `identity()` value. The
+/// `identity` function is then called once per block (and once for an empty
+/// range), and must return a neutral element for `reduce`, such as zero for
+/// a sum.
+///
+/// Contrary to rayon's `reduce`, the values are always combined in the same
+/// order: the range is recursively split in two halves independently of the
+/// number of threads, and the two halves are then combined. With floating
+/// point values, the result is bit-identical whatever the number of threads.
+pub fn each_i_reduce<T, I, M, R>(size: usize, identity: I, map: M, reduce: R) -> T
+where
+    T: Send,
+    I: Fn() -> T + Sync,
+    M: Fn(usize) -> T + Sync,
+    R: Fn(T, T) -> T + Sync,
+{
+    let fold = |accumulated: T, i: usize| reduce(accumulated, map(i));
+    return reduce_range(0, size, LEAF_SIZE, &identity, &fold, &reduce);
+}
+
+/// Call `fold(&mut accumulated, i)` in parallel for all `i` in `0..size`, and
+/// combine the accumulated values with `reduce`.
+///
+/// This works like [`each_i_reduce`], for accumulators which are expensive to
+/// create, such as a vector of forces acting on all the particles. The values
+/// are combined in the same order whatever the number of threads, and the
+/// range is split in at most 64 blocks: `identity` is called at most 64
+/// times.
+///
+/// [`each_i_reduce`]: fn.each_i_reduce.html
+pub fn each_i_fold_reduce<T, I, F, R>(size: usize, identity: I, fold: F, reduce: R) -> T
+where
+    T: Send,
+    I: Fn() -> T + Sync,
+    F: Fn(&mut T, usize) + Sync,
+    R: Fn(T, T) -> T + Sync,
+{
+    // Splitting in two halves until the blocks are smaller than `leaf_size`
+    // creates at most `MAX_FOLD_BLOCKS` blocks.
+    let leaf_size = usize::max(LEAF_SIZE, (size + MAX_FOLD_BLOCKS - 1) / MAX_FOLD_BLOCKS);
+    let fold_by_value = |mut accumulated: T, i: usize| {
+        fold(&mut accumulated, i);
+        accumulated
+    };
+    return reduce_range(0, size, leaf_size, &identity, &fold_by_value, &reduce);
+}
+
+fn reduce_range<T, I, F, R>(
+    start: usize,
+    end: usize,
+    leaf_size: usize,
+    identity: &I,
+    fold: &F,
+    reduce: &R,
+) -> T
+where
+    T: Send,
+    I: Fn() -> T + Sync,
+    F: Fn(T, usize) -> T + Sync,
+    R: Fn(T, T) -> T + Sync,
+{
+    if end - start <= leaf_size {
+        return (start..end).fold(identity(), fold);
+    }
+
+    let middle = start + (end - start) / 2;
+    let (first, second) = rayon::join(
+        || reduce_range(start, middle, leaf_size, identity, fold, reduce),
+        || reduce_range(middle, end, leaf_size, identity, fold, reduce),
+    );
+    return reduce(first, second);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn thread_count() {
+        // Values spanning many orders of magnitude, so that the result of the
+        // sum depends on the order of the additions
+        let value = |i: usize| f64::sin(i as f64) * 10f64.powi((i % 30) as i32 - 15);
+        let sum_with_threads = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| each_i_reduce(10_000, || 0.0, value, |a, b| a + b))
+        };
+
+        let serial = sum_with_threads(1);
+        let parallel = sum_with_threads(8);
+        assert_eq!(serial.to_bits(), parallel.to_bits());
+
+        let expected = (0..10_000).map(value).sum::<f64>();
+        assert!(f64::abs(serial - expected) < 1e-12 * f64::abs(expected));
+    }
+
+    #[test]
+    fn identity() {
+        assert_eq!(each_i_reduce(0, || 3, |i| i, |a, b| a + b), 3);
+        assert_eq!(each_i_reduce(100, || 0, |i| i, |a, b| a + b), 4950);
+
+        // The identity is called once per block of consecutive indexes: 100
+        // values are split in 8 blocks of 12 or 13 values
+        let calls = AtomicUsize::new(0);
+        let identity = || {
+            let _ = calls.fetch_add(1, Ordering::SeqCst);
+            0
+        };
+        assert_eq!(each_i_reduce(100, identity, |i| i, |a, b| a + b), 4950);
+        assert_eq!(calls.load(Ordering::SeqCst), 8);
+    }
+
+    #[test]
+    fn fold() {
+        let calls = AtomicUsize::new(0);
+        let identity = || {
+            let _ = calls.fetch_add(1, Ordering::SeqCst);
+            vec![0; 10]
+        };
+        let histogram = each_i_fold_reduce(100_000, identity, |histogram, i| {
+            histogram[i % 10] += 1;
+        }, |mut a, b| {
+            for (a, b) in a.iter_mut().zip(b) {
+                *a += b;
+            }
+            a
+        });
+        assert_eq!(histogram, vec![10_000; 10]);
+        assert!(calls.load(Ordering::SeqCst) <= 64);
+
+        // Small ranges use the same blocks as each_i_reduce
+        let _ = calls.swap(0, Ordering::SeqCst);
+        let sum = each_i_fold_reduce(100, || {
+            let _ = calls.fetch_add(1, Ordering::SeqCst);
+            0
+        }, |sum, i| *sum += i, |a, b| a + b);
+        assert_eq!(sum, 4950);
+        assert_eq!(calls.load(Ordering::SeqCst), 8);
+    }
+}
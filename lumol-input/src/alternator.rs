@@ -48,4 +48,8 @@ impl<T: Control> Control for Alternator<T> {
             self.as_mut().control(system)
         }
     }
+
+    fn removed_degrees_of_freedom(&self, system: &System) -> usize {
+        self.as_ref().removed_degrees_of_freedom(system)
+    }
 }
@@ -2,13 +2,14 @@
 // Copyright (C) Lumol's contributors — BSD license
 use toml::value::{Table, Value};
 
+use std::collections::HashMap;
+
 use lumol_core::System;
 use lumol_core::units;
 
 use lumol_core::energy::{BondPotential, PairInteraction, PairPotential};
-use lumol_core::energy::{BornMayerHuggins, Buckingham, Gaussian, Morse};
-use lumol_core::energy::{Harmonic, LennardJones, NullPotential, Mie};
-use lumol_core::energy::TableComputation;
+use lumol_core::energy::{Harmonic, Morse, NullPotential};
+use lumol_core::energy::{TableComputation, potential_from_name};
 
 use super::read_restriction;
 use crate::{Error, InteractionsInput, FromToml, FromTomlWithData};
@@ -105,14 +106,23 @@ impl InteractionsInput {
                     PairInteraction::new(potential, cutoff)
                 }
                 Value::Table(ref table) => {
-                    let shifted = table.get("shifted").ok_or(
-                        Error::from("'cutoff' table can only contain 'shifted' key")
-                    )?;
-                    let cutoff = shifted.as_str().ok_or(
-                        Error::from("'cutoff.shifted' value must be a string")
-                    )?;
-                    let cutoff = units::from_str(cutoff)?;
-                    PairInteraction::shifted(potential, cutoff)
+                    if let Some(shifted) = table.get("shifted") {
+                        let cutoff = shifted.as_str().ok_or(
+                            Error::from("'cutoff.shifted' value must be a string")
+                        )?;
+                        let cutoff = units::from_str(cutoff)?;
+                        PairInteraction::shifted(potential, cutoff)
+                    } else if let Some(shifted) = table.get("shifted_force") {
+                        let cutoff = shifted.as_str().ok_or(
+                            Error::from("'cutoff.shifted_force' value must be a string")
+                        )?;
+                        let cutoff = units::from_str(cutoff)?;
+                        PairInteraction::shifted_force(potential, cutoff)
+                    } else {
+                        return Err(Error::from(
+                            "'cutoff' table can only contain 'shifted' or 'shifted_force' key"
+                        ));
+                    }
                 }
                 _ => return Err(Error::from("'cutoff' must be a string or a table")),
             };
@@ -172,18 +182,52 @@ impl InteractionsInput {
     }
 }
 
+/// Keys in the pair potentials tables which are not parameters of the
+/// potential itself
+const INTERACTION_KEYS: &[&str] = &[
+    "type", "cutoff", "computation", "tail_correction", "restriction"
+];
+
+/// Parameters of the pair potentials given as numbers without units. All the
+/// other parameters are given as strings with units.
+const DIMENSIONLESS_PARAMETERS: &[&str] = &["n", "m", "lambda", "alpha"];
+
+/// Get the name of the pair potential with the given `typ`, to be used in
+/// error messages
+fn pair_potential_name(typ: &str) -> &'static str {
+    match typ {
+        "null" => "null potential",
+        "harmonic" => "harmonic potential",
+        "lj" | "lennard-jones" => "Lennard-Jones potential",
+        "soft-core-lj" => "soft-core Lennard-Jones potential",
+        "buckingham" => "Buckingham potential",
+        "born" | "born-mayer-huggins" => "Born-Mayer-Huggins potential",
+        "morse" => "Morse potential",
+        "gaussian" => "Gaussian potential",
+        "mie" => "Mie potential",
+        _ => "pair potential",
+    }
+}
+
 fn read_pair_potential(table: &Table) -> Result<Box<dyn PairPotential>, Error> {
-    match extract::typ(table, "pair potential")? {
-        "null" => Ok(Box::new(NullPotential::from_toml(table)?)),
-        "harmonic" => Ok(Box::new(Harmonic::from_toml(table)?)),
-        "lj" => Ok(Box::new(LennardJones::from_toml(table)?)),
-        "buckingham" => Ok(Box::new(Buckingham::from_toml(table)?)),
-        "born" => Ok(Box::new(BornMayerHuggins::from_toml(table)?)),
-        "morse" => Ok(Box::new(Morse::from_toml(table)?)),
-        "gaussian" => Ok(Box::new(Gaussian::from_toml(table)?)),
-        "mie" => Ok(Box::new(Mie::from_toml(table)?)),
-        other => Err(Error::from(format!("unknown potential type '{}'", other))),
+    let typ = extract::typ(table, "pair potential")?;
+    let context = pair_potential_name(typ);
+
+    let mut parameters = HashMap::new();
+    for key in table.keys() {
+        if INTERACTION_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+
+        let value = if DIMENSIONLESS_PARAMETERS.contains(&key.as_str()) {
+            extract::number(key, table, context)?
+        } else {
+            units::from_str(extract::str(key, table, context)?)?
+        };
+        let _ = parameters.insert(key.clone(), value);
     }
+
+    return potential_from_name(typ, &parameters).map_err(Error::from);
 }
 
 fn read_bond_potential(table: &Table) -> Result<Box<dyn BondPotential>, Error> {
@@ -3,7 +3,6 @@
 
 //! Convert TOML values to Lumol types.
 use toml::value::Table;
-use log::warn;
 
 use lumol_core::units;
 use lumol_core::energy::*;
@@ -29,37 +28,6 @@ impl FromToml for Harmonic {
     }
 }
 
-impl FromToml for LennardJones {
-    fn from_toml(table: &Table) -> Result<LennardJones, Error> {
-        let sigma = extract::str("sigma", table, "Lennard-Jones potential")?;
-        let epsilon = extract::str("epsilon", table, "Lennard-Jones potential")?;
-        Ok(LennardJones {
-            sigma: units::from_str(sigma)?,
-            epsilon: units::from_str(epsilon)?,
-        })
-    }
-}
-
-impl FromToml for Mie {
-    fn from_toml(table: &Table) -> Result<Mie, Error> {
-        let sigma = extract::str("sigma", table, "Mie potential")?;
-        let epsilon = extract::str("epsilon", table, "Mie potential")?;
-        let m = extract::number("m", table, "Mie potential")?;
-        let n = extract::number("n", table, "Mie potential")?;
-
-        if m < 3.0 {
-            warn!("'m' is smaller than 3. Tail corrections for Mie potential are set to zero.");
-        };
-
-        Ok(Mie::new(
-                units::from_str(sigma)?,
-                units::from_str(epsilon)?,
-                n as f64,
-                m as f64)
-        )
-    }
-}
-
 impl FromToml for CosineHarmonic {
     fn from_toml(table: &Table) -> Result<CosineHarmonic, Error> {
         let k = extract::str("k", table, "cosine harmonic potential")?;
@@ -81,35 +49,21 @@ impl FromToml for Torsion {
     }
 }
 
-impl FromToml for Buckingham {
-    fn from_toml(table: &Table) -> Result<Buckingham, Error> {
-        let a = extract::str("A", table, "Buckingham potential")?;
-        let c = extract::str("C", table, "Buckingham potential")?;
-        let rho = extract::str("rho", table, "Buckingham potential")?;
-
-        Ok(Buckingham {
-            a: units::from_str(a)?,
-            c: units::from_str(c)?,
-            rho: units::from_str(rho)?,
-        })
-    }
-}
-
-impl FromToml for BornMayerHuggins {
-    fn from_toml(table: &Table) -> Result<BornMayerHuggins, Error> {
-        let a = extract::str("A", table, "Born-Mayer-Huggins potential")?;
-        let c = extract::str("C", table, "Born-Mayer-Huggins potential")?;
-        let d = extract::str("D", table, "Born-Mayer-Huggins potential")?;
-        let rho = extract::str("rho", table, "Born-Mayer-Huggins potential")?;
-        let sigma = extract::str("sigma", table, "Born-Mayer-Huggins potential")?;
+impl FromToml for CosineDihedral {
+    fn from_toml(table: &Table) -> Result<CosineDihedral, Error> {
+        let terms = extract::slice("terms", table, "cosine series potential")?;
+        let terms = terms.iter().map(|term| {
+            let term = term.as_table().ok_or(
+                Error::from("'terms' must be an array of tables in cosine series potential")
+            )?;
+            Torsion::from_toml(term)
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        if terms.is_empty() {
+            return Err(Error::from("'terms' can not be empty in cosine series potential"));
+        }
 
-        Ok(BornMayerHuggins {
-            a: units::from_str(a)?,
-            c: units::from_str(c)?,
-            d: units::from_str(d)?,
-            sigma: units::from_str(sigma)?,
-            rho: units::from_str(rho)?,
-        })
+        Ok(CosineDihedral::new(terms))
     }
 }
 
@@ -126,19 +80,6 @@ impl FromToml for Morse {
     }
 }
 
-impl FromToml for Gaussian {
-    fn from_toml(table: &Table) -> Result<Gaussian, Error> {
-        let a = units::from_str(extract::str("A", table, "Gaussian potential")?)?;
-        let b = units::from_str(extract::str("B", table, "Gaussian potential")?)?;
-
-        if b <= 0.0 {
-            Err(Error::from("'B' parameter has to be positive in Gaussian potential"))
-        } else {
-            Ok(Gaussian::new(a, b))
-        }
-    }
-}
-
 impl FromTomlWithData for TableComputation {
     type Data = Box<dyn PairPotential>;
 
@@ -156,7 +97,13 @@ impl FromTomlWithData for TableComputation {
 impl FromToml for Wolf {
     fn from_toml(table: &Table) -> Result<Wolf, Error> {
         let cutoff = extract::str("cutoff", table, "Wolf coulombic potential")?;
-        Ok(Wolf::new(units::from_str(cutoff)?))
+        let cutoff = units::from_str(cutoff)?;
+        if table.contains_key("alpha") {
+            let alpha = extract::str("alpha", table, "Wolf coulombic potential")?;
+            Ok(Wolf::with_alpha(cutoff, units::from_str(alpha)?))
+        } else {
+            Ok(Wolf::new(cutoff))
+        }
     }
 }
 
@@ -41,6 +41,8 @@ impl FromTomlWithData for MonteCarlo {
                 "Translate" => Box::new(Translate::from_toml(mc_move, root.clone())?),
                 "Rotate" => Box::new(Rotate::from_toml(mc_move, root.clone())?),
                 "Resize" => Box::new(Resize::from_toml(mc_move, root.clone())?),
+                "Insert" => Box::new(Insert::from_toml(mc_move, root.clone())?),
+                "Delete" => Box::new(Delete::from_toml(mc_move, root.clone())?),
                 other => return Err(Error::from(format!("unknown Monte Carlo move '{}'", other))),
             };
 
@@ -118,3 +120,29 @@ impl FromTomlWithData for Resize {
         Ok(Resize::new(pressure, delta))
     }
 }
+
+impl FromTomlWithData for Insert {
+    type Data = PathBuf;
+    fn from_toml(config: &Table, root: PathBuf) -> Result<Insert, Error> {
+        let chemical_potential = extract::str("chemical_potential", config, "Insert move")?;
+        let chemical_potential = units::from_str(chemical_potential)?;
+
+        let molfile = extract::str("molecule", config, "Insert move")?;
+        let molfile = get_input_path(root, molfile);
+        let molecule = read_molecule(molfile)?;
+        Ok(Insert::new(molecule, chemical_potential))
+    }
+}
+
+impl FromTomlWithData for Delete {
+    type Data = PathBuf;
+    fn from_toml(config: &Table, root: PathBuf) -> Result<Delete, Error> {
+        let chemical_potential = extract::str("chemical_potential", config, "Delete move")?;
+        let chemical_potential = units::from_str(chemical_potential)?;
+
+        let molfile = extract::str("molecule", config, "Delete move")?;
+        let molfile = get_input_path(root, molfile);
+        let hash = read_molecule(molfile)?.as_ref().hash();
+        Ok(Delete::new(hash, chemical_potential))
+    }
+}
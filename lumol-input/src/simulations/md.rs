@@ -28,6 +28,9 @@ impl FromToml for MolecularDynamics {
                 "AnisoBerendsenBarostat" => {
                     Box::new(AnisoBerendsenBarostat::from_toml(integrator, timestep)?)
                 }
+                "ParrinelloRahman" => {
+                    Box::new(ParrinelloRahman::from_toml(integrator, timestep)?)
+                }
                 "Verlet" => Box::new(Verlet::from_toml(integrator, timestep)?),
                 "VelocityVerlet" => Box::new(VelocityVerlet::from_toml(integrator, timestep)?),
                 "LeapFrog" => Box::new(LeapFrog::from_toml(integrator, timestep)?),
@@ -122,6 +125,19 @@ impl FromTomlWithData for AnisoBerendsenBarostat {
     }
 }
 
+impl FromTomlWithData for ParrinelloRahman {
+    type Data = f64;
+    fn from_toml(config: &Table, timestep: f64) -> Result<ParrinelloRahman, Error> {
+        let pressure = extract::str("pressure", config, "Parrinello-Rahman barostat")?;
+        let pressure = units::from_str(pressure)?;
+        let tau = extract::number("timestep", config, "Parrinello-Rahman barostat")?;
+        let compressibility = units::from_str(
+            extract::str("compressibility", config, "Parrinello-Rahman barostat")?
+        )?;
+        Ok(ParrinelloRahman::hydrostatic(timestep, pressure, tau, compressibility))
+    }
+}
+
 impl FromToml for BerendsenThermostat {
     fn from_toml(config: &Table) -> Result<BerendsenThermostat, Error> {
         let temperature = extract::str("temperature", config, "Berendsen thermostat")?;
@@ -2,7 +2,7 @@
 // Copyright (C) Lumol's contributors — BSD license
 use toml::value::{Table, Value};
 
-use lumol_core::{System, UnitCell, TrajectoryBuilder};
+use lumol_core::{Lattice, System, SystemBuilder, UnitCell, TrajectoryBuilder};
 use lumol_sim::{BoltzmannVelocities, InitVelocities};
 use lumol_core::units;
 
@@ -17,6 +17,22 @@ impl Input {
     pub fn read_system(&self) -> Result<System, Error> {
         let config = self.system_table()?;
 
+        let mut system = if config.get("lattice").is_some() {
+            self.read_lattice()?
+        } else {
+            self.read_file()?
+        };
+
+        self.read_potentials(&mut system)?;
+        self.init_velocities(&mut system)?;
+
+        Ok(system)
+    }
+
+    /// Read the initial configuration of the system from a file
+    fn read_file(&self) -> Result<System, Error> {
+        let config = self.system_table()?;
+
         let file = extract::str("file", config, "system")?;
         let file = get_input_path(&self.path, file);
         let mut trajectory = TrajectoryBuilder::new().open(file)?;
@@ -41,15 +57,12 @@ impl Input {
             false
         };
 
-        let mut system = if guess_bonds {
+        let system = if guess_bonds {
             trajectory.read_guess_bonds()?
         } else {
             trajectory.read()?
         };
 
-        self.read_potentials(&mut system)?;
-        self.init_velocities(&mut system)?;
-
         if !with_cell && system.cell.is_infinite() {
             warn!(
                 "No unit cell in the system, using an infinite unit cell.\n\
@@ -61,6 +74,61 @@ impl Input {
         Ok(system)
     }
 
+    /// Build the initial configuration of the system from a crystal lattice
+    fn read_lattice(&self) -> Result<System, Error> {
+        let config = self.system_table()?;
+        for &key in &["file", "topology", "guess_bonds"] {
+            if config.get(key).is_some() {
+                return Err(Error::from(format!(
+                    "'{}' can not be used with 'lattice' in system", key
+                )));
+            }
+        }
+
+        let table = extract::table("lattice", config, "system")?;
+        let name = extract::str("name", table, "lattice")?;
+        let lattice = match extract::typ(table, "lattice")? {
+            "sc" => Lattice::sc(name),
+            "bcc" => Lattice::bcc(name),
+            "fcc" => Lattice::fcc(name),
+            other => return Err(Error::from(format!("unknown lattice type '{}'", other))),
+        };
+
+        let constant = extract::str("constant", table, "lattice")?;
+        let constant = units::from_str(constant)?;
+        if constant <= 0.0 {
+            return Err(Error::from("'constant' must be positive in lattice"));
+        }
+
+        let repetitions = extract::slice("repetitions", table, "lattice")?;
+        if repetitions.len() != 3 {
+            return Err(Error::from("'repetitions' array must have a size of 3 in lattice"));
+        }
+        let repetitions = repetitions.iter().map(|value| {
+            match value.as_integer() {
+                Some(n) if n > 0 => Ok(n as usize),
+                _ => Err(Error::from("values must be positive integers in 'repetitions' array")),
+            }
+        }).collect::<Result<Vec<_>, _>>()?;
+        let (nx, ny, nz) = (repetitions[0], repetitions[1], repetitions[2]);
+
+        // Use the cell containing all the repetitions of the lattice by default
+        let cell = match self.read_cell()? {
+            Some(cell) => cell,
+            None => UnitCell::ortho(
+                constant * nx as f64,
+                constant * ny as f64,
+                constant * nz as f64,
+            ),
+        };
+
+        let system = SystemBuilder::new()
+            .cell(cell)
+            .add_lattice(lattice, constant, nx, ny, nz)
+            .build();
+        Ok(system)
+    }
+
     fn system_table(&self) -> Result<&Table, Error> {
         let systems = extract::slice("systems", &self.config, "input file")?;
 
@@ -0,0 +1,58 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Check that a complete simulation can be created from an input file
+//! without any configuration file, using a crystal lattice.
+use std::path::PathBuf;
+
+use lumol_core::energy::{LennardJones, Potential};
+use lumol_core::units;
+use lumol_input::Input;
+
+static INPUT: &str = r#"
+[input]
+version = 1
+
+[[systems]]
+lattice = {type = "fcc", name = "Ar", constant = "5.26 A", repetitions = [3, 3, 4]}
+
+[systems.potentials.global]
+cutoff = "7.5 A"
+
+[systems.potentials.pairs]
+Ar-Ar = {type = "lj", sigma = "3.4 A", epsilon = "1 kJ/mol"}
+
+[[simulations]]
+nsteps = 42
+[simulations.propagator]
+type = "MolecularDynamics"
+timestep = "1 fs"
+"#;
+
+#[test]
+fn lattice() {
+    let input = Input::from_str(PathBuf::from("lattice.toml"), INPUT).unwrap();
+    let config = input.read().unwrap();
+    assert_eq!(config.nsteps, 42);
+
+    let system = config.system;
+    assert_eq!(system.size(), 4 * 3 * 3 * 4);
+    assert!(system.particles().name.iter().all(|name| name == "Ar"));
+
+    let lengths = system.cell.lengths();
+    assert!(f64::abs(lengths[0] - 3.0 * 5.26) < 1e-12);
+    assert!(f64::abs(lengths[1] - 3.0 * 5.26) < 1e-12);
+    assert!(f64::abs(lengths[2] - 4.0 * 5.26) < 1e-12);
+
+    let lj = LennardJones {
+        sigma: 3.4,
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    let potential = system.pair_potential(0, 1).expect("missing Ar-Ar potential");
+    assert_eq!(potential.cutoff(), 7.5);
+    for &r in &[3.0, 3.72, 5.0, 7.0] {
+        assert_eq!(potential.energy(r), lj.energy(r));
+        assert_eq!(potential.force(r), lj.force(r));
+    }
+    assert_eq!(potential.energy(8.0), 0.0);
+}
@@ -0,0 +1,238 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Saving the internal state of simulation algorithms in checkpoints
+use std::collections::BTreeMap;
+
+use rand::RngCore;
+use rand_xorshift::XorShiftRng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A random number generator which state can be saved in checkpoints.
+///
+/// All the random number generators used by propagators must implement this
+/// trait, for restarted simulations to use the same random numbers as the
+/// simulation which created the checkpoint.
+pub trait CheckpointRng: RngCore {
+    /// Get the current state of this generator, without modifying it
+    fn state(&self) -> Value;
+    /// Set the state of this generator to a `state` created by
+    /// `CheckpointRng::state`, or return an error message if the state is
+    /// invalid.
+    fn set_state(&mut self, state: &Value) -> Result<(), String>;
+}
+
+impl CheckpointRng for XorShiftRng {
+    fn state(&self) -> Value {
+        serde_json::to_value(self).expect("failed to serialize the random number generator")
+    }
+
+    fn set_state(&mut self, state: &Value) -> Result<(), String> {
+        *self = XorShiftRng::deserialize(state).map_err(|error| {
+            format!("invalid random number generator in checkpoint: {}", error)
+        })?;
+        Ok(())
+    }
+}
+
+/// Internal state of the algorithms in a simulation (random number
+/// generators, thermostat variables, Monte Carlo moves amplitudes, *etc.*),
+/// saved in checkpoints to restart simulations exactly.
+///
+/// The state is a set of values associated with names. The values can be of
+/// any serializable type, and are stored as JSON.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_sim::CheckpointState;
+/// let mut state = CheckpointState::new();
+/// state.set("amplitude", &0.3);
+/// state.set("counters", &[3, 4]);
+///
+/// assert_eq!(state.get::<f64>("amplitude").unwrap(), 0.3);
+/// assert_eq!(state.get::<Vec<u64>>("counters").unwrap(), vec![3, 4]);
+/// assert!(state.get::<f64>("missing").is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CheckpointState {
+    values: BTreeMap<String, Value>,
+}
+
+impl CheckpointState {
+    /// Create a new empty `CheckpointState`
+    pub fn new() -> CheckpointState {
+        CheckpointState {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Check if this state contains any value
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Set the `value` associated with `name`, replacing any previous value.
+    /// Floating point values are saved with enough digits to be read back
+    /// exactly.
+    ///
+    /// # Panics
+    ///
+    /// If `name` is empty or contains whitespace, or if `value` can not be
+    /// serialized.
+    pub fn set<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) {
+        let value = serde_json::to_value(value).expect("failed to serialize checkpoint value");
+        self.insert(name, value);
+    }
+
+    /// Get the value associated with `name`, or an error message if it is
+    /// missing or can not be converted to `T`.
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Result<T, String> {
+        let value = self.value(name)?;
+        return T::deserialize(value).map_err(|error| {
+            format!("invalid value for '{}' in checkpoint: {}", name, error)
+        });
+    }
+
+    /// Save the state of the random number generator `rng` under `name`. The
+    /// generator is not modified.
+    ///
+    /// # Panics
+    ///
+    /// If `name` is empty or contains whitespace.
+    pub fn set_rng(&mut self, name: &str, rng: &dyn CheckpointRng) {
+        self.insert(name, rng.state());
+    }
+
+    /// Restore the state of the random number generator `rng` from the one
+    /// saved under `name` by [`set_rng`].
+    ///
+    /// [`set_rng`]: struct.CheckpointState.html#method.set_rng
+    pub fn load_rng(&self, name: &str, rng: &mut dyn CheckpointRng) -> Result<(), String> {
+        return rng.set_state(self.value(name)?);
+    }
+
+    /// Add all the values in `other` to this state, adding `prefix` and a dot
+    /// in front of their names.
+    pub fn extend_with_prefix(&mut self, prefix: &str, other: CheckpointState) {
+        for (name, value) in other.values {
+            self.insert(&format!("{}.{}", prefix, name), value);
+        }
+    }
+
+    /// Get a new state containing all the values in this state with a name
+    /// starting with `prefix` and a dot, with this prefix removed.
+    pub fn with_prefix(&self, prefix: &str) -> CheckpointState {
+        let prefix = format!("{}.", prefix);
+        let mut state = CheckpointState::new();
+        for (name, value) in &self.values {
+            if name.starts_with(&prefix) {
+                state.insert(&name[prefix.len()..], value.clone());
+            }
+        }
+        return state;
+    }
+
+    fn insert(&mut self, name: &str, value: Value) {
+        assert!(
+            !name.is_empty() && !name.contains(char::is_whitespace),
+            "invalid name '{}' in checkpoint state", name
+        );
+        let _ = self.values.insert(name.into(), value);
+    }
+
+    fn value(&self, name: &str) -> Result<&Value, String> {
+        match self.values.get(name) {
+            Some(value) => Ok(value),
+            None => Err(format!("missing '{}' in checkpoint", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use lumol_core::Matrix3;
+
+    #[test]
+    fn values() {
+        let mut state = CheckpointState::new();
+        assert!(state.is_empty());
+
+        state.set("x", &[0.1, 1.0 / 3.0, -2e-300]);
+        assert_eq!(state.get::<Vec<f64>>("x").unwrap(), vec![0.1, 1.0 / 3.0, -2e-300]);
+        assert!(state.get::<f64>("x").is_err());
+
+        state.set("n", &42_u64);
+        assert_eq!(state.get::<u64>("n").unwrap(), 42);
+        assert_eq!(state.get::<u64>("m").err().unwrap(), "missing 'm' in checkpoint");
+
+        state.set("s", "foo");
+        assert!(state.get::<u64>("s").is_err());
+
+        let matrix = Matrix3::new([[1.0, 2.0, 1.0 / 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, -9e-12]]);
+        state.set("matrix", &matrix);
+        assert_eq!(state.get::<Matrix3>("matrix").unwrap(), matrix);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut state = CheckpointState::new();
+        state.set("x", &(0.1 + 0.2));
+        state.set("inner.values", &[1e-310, 6.02214076e23]);
+
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: CheckpointState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, state);
+        assert_eq!(restored.get::<f64>("x").unwrap(), 0.1 + 0.2);
+    }
+
+    #[test]
+    fn prefix() {
+        let mut inner = CheckpointState::new();
+        inner.set("a", &1.0);
+        inner.set("b", &2.0);
+
+        let mut state = CheckpointState::new();
+        state.set("a", &3.0);
+        state.extend_with_prefix("inner", inner.clone());
+        assert_eq!(state.get::<f64>("inner.a").unwrap(), 1.0);
+        assert_eq!(state.get::<f64>("a").unwrap(), 3.0);
+
+        assert_eq!(state.with_prefix("inner"), inner);
+        assert!(state.with_prefix("other").is_empty());
+    }
+
+    #[test]
+    fn rng() {
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        let _ = rng.next_u64();
+
+        let mut state = CheckpointState::new();
+        state.set_rng("rng", &rng);
+
+        // Saving the state does not change the generator
+        let mut copy = XorShiftRng::seed_from_u64(42);
+        let _ = copy.next_u64();
+        let mut restored = XorShiftRng::seed_from_u64(0);
+        state.load_rng("rng", &mut restored).unwrap();
+        for _ in 0..10 {
+            let value = rng.next_u64();
+            assert_eq!(copy.next_u64(), value);
+            assert_eq!(restored.next_u64(), value);
+        }
+
+        state.set("rng", &[1, 2, 3]);
+        assert!(state.load_rng("rng", &mut restored).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid name 'a b' in checkpoint state")]
+    fn invalid_name() {
+        CheckpointState::new().set("a b", &1.0);
+    }
+}
@@ -26,6 +26,9 @@
 #![doc(test(attr(deny(warnings))))]
 #![doc(test(attr(allow(unused_variables))))]
 
+mod checkpoint;
+pub use self::checkpoint::{CheckpointRng, CheckpointState};
+
 mod propagator;
 pub use self::propagator::Propagator;
 pub use self::propagator::TemperatureStrategy;
@@ -39,7 +42,10 @@ mod simulations;
 pub use self::mc::MonteCarlo;
 pub use self::md::MolecularDynamics;
 pub use self::min::Minimization;
-pub use self::simulations::Simulation;
+pub use self::simulations::{Progress, Simulation};
+
+mod rng;
+pub use self::rng::Rng;
 
 mod velocities;
-pub use self::velocities::{InitVelocities, BoltzmannVelocities, UniformVelocities};
+pub use self::velocities::{InitVelocities, BoltzmannVelocities, UniformVelocities, ZeroVelocities};
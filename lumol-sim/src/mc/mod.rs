@@ -8,3 +8,4 @@ pub use self::monte_carlo::{MonteCarlo, MonteCarloBuilder};
 mod moves;
 pub use self::moves::{MCDegreeOfFreedom, MCMove};
 pub use self::moves::{Resize, Rotate, Translate};
+pub use self::moves::{Insert, Delete};
@@ -4,7 +4,7 @@
 //! Metropolis Monte Carlo propagator implementation
 use std::ops::{Deref, DerefMut};
 
-use rand::{self, Rng, SeedableRng};
+use rand::{Rng, SeedableRng};
 
 use log::{warn, info, trace};
 
@@ -12,6 +12,7 @@ use lumol_core::consts::K_BOLTZMANN;
 use lumol_core::{DegreesOfFreedom, EnergyCache, System};
 
 use crate::propagator::{Propagator, TemperatureStrategy};
+use crate::{CheckpointRng, CheckpointState};
 use super::{MCDegreeOfFreedom, MCMove};
 
 /// This struct keeps a move and some statistics on the move (number of times
@@ -129,7 +130,7 @@ impl DerefMut for Move {
 pub struct MonteCarlo {
     /// Random number generator for the simulation. All random state will be
     /// taken from this.
-    rng: Box<dyn rand::RngCore>,
+    rng: Box<dyn CheckpointRng>,
     /// Boltzmann factor: beta = 1/(kB * T)
     beta: f64,
     /// List of possible Monte Carlo moves
@@ -145,7 +146,7 @@ pub struct MonteCarlo {
 
 /// Builder for `MonteCarlo` struct
 pub struct MonteCarloBuilder {
-    rng: Box<dyn rand::RngCore>,
+    rng: Box<dyn CheckpointRng>,
     beta: f64,
     moves: Vec<Move>,
     frequencies: Vec<f64>,
@@ -163,7 +164,7 @@ impl MonteCarloBuilder {
 
     /// Create a Monte Carlo propagator at temperature `T`, using the `rng`
     /// random number generator.
-    pub fn from_rng(temperature: f64, rng: Box<dyn rand::RngCore>) -> MonteCarloBuilder {
+    pub fn from_rng(temperature: f64, rng: Box<dyn CheckpointRng>) -> MonteCarloBuilder {
         assert!(temperature >= 0.0, "Monte Carlo temperature must be positive");
         MonteCarloBuilder {
             beta: 1.0 / (K_BOLTZMANN * temperature),
@@ -340,6 +341,38 @@ impl Propagator for MonteCarlo {
             );
         }
     }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set_rng("rng", &*self.rng);
+        state.set("moves", &self.moves.len());
+        for (i, mc_move) in self.moves.iter().enumerate() {
+            let mut move_state = mc_move.mcmove.save_checkpoint();
+            move_state.set("counters", &[
+                mc_move.total_attempted, mc_move.total_accepted,
+                mc_move.attempted, mc_move.accepted,
+            ]);
+            state.extend_with_prefix(&format!("move{}", i), move_state);
+        }
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        if state.get::<usize>("moves")? != self.moves.len() {
+            return Err("the number of Monte Carlo moves does not match the checkpoint".into());
+        }
+        state.load_rng("rng", &mut *self.rng)?;
+        for (i, mc_move) in self.moves.iter_mut().enumerate() {
+            let move_state = state.with_prefix(&format!("move{}", i));
+            let counters = move_state.get::<[u64; 4]>("counters")?;
+            mc_move.total_attempted = counters[0];
+            mc_move.total_accepted = counters[1];
+            mc_move.attempted = counters[2];
+            mc_move.accepted = counters[3];
+            mc_move.mcmove.load_checkpoint(&move_state)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
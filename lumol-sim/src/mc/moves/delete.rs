@@ -0,0 +1,105 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use rand::RngCore;
+
+use std::collections::BTreeSet;
+use std::f64;
+use std::usize;
+
+use super::{MCDegreeOfFreedom, MCMove};
+use super::{select_molecule, thermal_wavelength};
+
+use lumol_core::{EnergyCache, System, MoleculeHash};
+
+/// Monte Carlo move for removing a molecule from the system, in the
+/// grand-canonical ensemble.
+///
+/// A random molecule of the given type is removed from the system. The move is
+/// accepted with the probability
+///
+/// $$ P = \min\left(1, \frac{\Lambda^3 N}{V} e^{-\beta (\mu + \Delta U)}\right) $$
+///
+/// where $V$ is the volume of the system, $N$ the number of molecules of
+/// this type before the deletion, $\Lambda$ the thermal de Broglie
+/// wavelength of the molecule, and $\mu$ the chemical potential. This move
+/// should be used together with an [`Insert`](struct.Insert.html) move with
+/// the same chemical potential.
+pub struct Delete {
+    /// Hash of the molecules to delete
+    hash: MoleculeHash,
+    /// Chemical potential of the molecule
+    chemical_potential: f64,
+    /// Index of the molecule to delete
+    molid: usize,
+    /// Total mass of the molecule to delete
+    mass: f64,
+    /// Number of molecules of this type before the deletion
+    count: usize,
+}
+
+impl Delete {
+    /// Create a new `Delete` move, removing molecules with the given `hash`
+    /// from the system, using the given `chemical_potential`.
+    pub fn new(hash: MoleculeHash, chemical_potential: f64) -> Delete {
+        Delete {
+            hash: hash,
+            chemical_potential: chemical_potential,
+            molid: usize::max_value(),
+            mass: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl MCMove for Delete {
+    fn describe(&self) -> &str {
+        "molecular deletion"
+    }
+
+    fn degrees_of_freedom(&self) -> MCDegreeOfFreedom {
+        let mut all = BTreeSet::new();
+        let _ = all.insert(self.hash);
+        MCDegreeOfFreedom::Molecules(all)
+    }
+
+    fn setup(&mut self, system: &System) {
+        if system.cell.is_infinite() {
+            panic!("Cannot use `Delete` move with infinite simulation cell.")
+        }
+    }
+
+    fn prepare(&mut self, system: &mut System, rng: &mut dyn RngCore) -> bool {
+        // An empty system is a perfectly valid state in the grand-canonical
+        // ensemble, so there is no need to warn here.
+        if let Some(id) = select_molecule(system, Some(self.hash), rng) {
+            self.molid = id;
+        } else {
+            return false;
+        }
+
+        let hash = self.hash;
+        self.count = system.molecules().filter(|m| m.hash() == hash).count();
+        self.mass = system.molecule(self.molid).particles().mass.iter().sum();
+        return true;
+    }
+
+    fn cost(&self, system: &System, beta: f64, cache: &mut EnergyCache) -> f64 {
+        let lambda = thermal_wavelength(self.mass, beta);
+        let delta = cache.remove_molecule_cost(system, self.molid);
+        let ratio = lambda.powi(3) * self.count as f64 / system.volume();
+        return beta * (delta + self.chemical_potential) - f64::ln(ratio);
+    }
+
+    fn apply(&mut self, system: &mut System) {
+        system.remove_molecule(self.molid);
+    }
+
+    fn restore(&mut self, _: &mut System) {
+        // Nothing to do
+    }
+
+    fn update_amplitude(&mut self, _: Option<f64>) {
+        // Nothing to do
+    }
+}
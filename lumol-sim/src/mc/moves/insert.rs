@@ -0,0 +1,172 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use rand::{Rng, RngCore};
+
+use std::collections::BTreeSet;
+use std::f64;
+use std::usize;
+
+use super::{MCDegreeOfFreedom, MCMove};
+use super::thermal_wavelength;
+
+use lumol_core::{EnergyCache, System, Molecule, MoleculeHash, Matrix3, Quaternion, Vector3D};
+
+/// Monte Carlo move for inserting a molecule in the system, in the
+/// grand-canonical ensemble.
+///
+/// The new molecule is a copy of a template molecule, with a random position
+/// and orientation. The move is accepted with the probability
+///
+/// $$ P = \min\left(1, \frac{V}{\Lambda^3 (N + 1)} e^{\beta (\mu - \Delta U)}\right) $$
+///
+/// where $V$ is the volume of the system, $N$ the number of molecules of
+/// this type before the insertion, $\Lambda$ the thermal de Broglie
+/// wavelength of the molecule, and $\mu$ the chemical potential. This move
+/// should be used together with a [`Delete`](struct.Delete.html) move with
+/// the same chemical potential.
+///
+/// The energy difference $\Delta U$ includes the intramolecular energy of the
+/// new molecule, which should then also be included in the chemical
+/// potential.
+pub struct Insert {
+    /// Template of the molecule to insert, centered on its center-of-mass
+    molecule: Molecule,
+    /// Hash of the molecule to insert
+    hash: MoleculeHash,
+    /// Total mass of the molecule to insert
+    mass: f64,
+    /// Chemical potential of the molecule
+    chemical_potential: f64,
+    /// Index of the inserted molecule
+    molid: usize,
+    /// Number of molecules of this type after the insertion
+    count: usize,
+}
+
+impl Insert {
+    /// Create a new `Insert` move, adding copies of `molecule` in the system,
+    /// using the given `chemical_potential`.
+    pub fn new(mut molecule: Molecule, chemical_potential: f64) -> Insert {
+        let com = molecule.center_of_mass();
+        for position in molecule.particles_mut().position {
+            *position -= com;
+        }
+        let mass: f64 = molecule.particles().mass.iter().sum();
+        assert!(mass > 0.0, "the molecule must have a positive mass in Insert move");
+
+        Insert {
+            hash: molecule.hash(),
+            molecule: molecule,
+            mass: mass,
+            chemical_potential: chemical_potential,
+            molid: usize::max_value(),
+            count: 0,
+        }
+    }
+}
+
+impl MCMove for Insert {
+    fn describe(&self) -> &str {
+        "molecular insertion"
+    }
+
+    fn degrees_of_freedom(&self) -> MCDegreeOfFreedom {
+        let mut all = BTreeSet::new();
+        let _ = all.insert(self.hash);
+        MCDegreeOfFreedom::Molecules(all)
+    }
+
+    fn setup(&mut self, system: &System) {
+        if system.cell.is_infinite() {
+            panic!("Cannot use `Insert` move with infinite simulation cell.")
+        }
+    }
+
+    fn prepare(&mut self, system: &mut System, rng: &mut dyn RngCore) -> bool {
+        let mut molecule = self.molecule.clone();
+        if molecule.size() > 1 {
+            let rotation = random_rotation(rng);
+            for position in molecule.particles_mut().position {
+                *position = rotation * *position;
+            }
+        }
+
+        let fractional = Vector3D::new(rng.gen(), rng.gen(), rng.gen());
+        let com = system.cell.cartesian(&fractional);
+        for position in molecule.particles_mut().position {
+            *position += com;
+        }
+
+        let hash = self.hash;
+        self.count = system.molecules().filter(|m| m.hash() == hash).count() + 1;
+
+        // The molecule is directly added to the system, and removed in
+        // `restore` if the move is rejected.
+        system.add_molecule(molecule);
+        self.molid = system.molecules().count() - 1;
+        return true;
+    }
+
+    fn cost(&self, system: &System, beta: f64, cache: &mut EnergyCache) -> f64 {
+        let lambda = thermal_wavelength(self.mass, beta);
+        let delta = cache.insert_molecule_cost(system, self.molid);
+        let ratio = system.volume() / (lambda.powi(3) * self.count as f64);
+        return beta * (delta - self.chemical_potential) - f64::ln(ratio);
+    }
+
+    fn apply(&mut self, _: &mut System) {
+        // Nothing to do, the molecule is already in the system
+    }
+
+    fn restore(&mut self, system: &mut System) {
+        system.remove_molecule(self.molid);
+    }
+
+    fn update_amplitude(&mut self, _: Option<f64>) {
+        // Nothing to do
+    }
+}
+
+/// Generate a uniformly distributed random rotation matrix, using random unit
+/// quaternions.
+///
+/// [1] Shoemake, Graphics Gems III, 124 (1992); doi: 10.1016/B978-0-08-050755-2.50036-1
+fn random_rotation(rng: &mut dyn RngCore) -> Matrix3 {
+    let u1: f64 = rng.gen();
+    let u2: f64 = rng.gen();
+    let u3: f64 = rng.gen();
+
+    let two_pi = 2.0 * f64::consts::PI;
+    let x = f64::sqrt(1.0 - u1) * f64::sin(two_pi * u2);
+    let y = f64::sqrt(1.0 - u1) * f64::cos(two_pi * u2);
+    let z = f64::sqrt(u1) * f64::sin(two_pi * u3);
+    let w = f64::sqrt(u1) * f64::cos(two_pi * u3);
+
+    Quaternion::new(w, x, y, z).rotation_matrix()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+    use approx::assert_ulps_eq;
+
+    #[test]
+    fn rotation() {
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let rotation = random_rotation(&mut rng);
+            // Rotations are orthogonal with unit determinant
+            let product = rotation * rotation.transposed();
+            let one = Matrix3::one();
+            for i in 0..3 {
+                for j in 0..3 {
+                    assert_ulps_eq!(product[i][j], one[i][j], epsilon = 1e-12);
+                }
+            }
+            assert_ulps_eq!(rotation.determinant(), 1.0, epsilon = 1e-12);
+        }
+    }
+}
@@ -5,12 +5,17 @@
 //!
 //! For example, NVT Monte Carlo will use the `Translate` move only for particles
 //! and add the `Rotate` moves for molecules. NPT Monte Carlo will add the
-//! `VolumeResize` move.
+//! `VolumeResize` move, and grand-canonical Monte Carlo the `Insert` and
+//! `Delete` moves.
 //!
 //! In all this module, beta refers to the Boltzmann factor 1/(kB T)
 use rand::{RngCore, Rng, seq::SliceRandom};
 use std::collections::BTreeSet;
+use std::f64::consts::PI;
 use lumol_core::{EnergyCache, System, MoleculeHash};
+use lumol_core::consts::PLANCK_CONSTANT;
+
+use crate::CheckpointState;
 
 /// Possible degrees of freedom simulated by a given Monte Carlo move
 #[derive(Clone, PartialEq, Debug)]
@@ -81,6 +86,19 @@ pub trait MCMove {
 
     /// Update the sample range for displacements.
     fn update_amplitude(&mut self, scaling_factor: Option<f64>);
+
+    /// Save the internal state of the move (for example the amplitude of
+    /// displacements), to restart a simulation from a checkpoint. The default
+    /// is to save nothing.
+    fn save_checkpoint(&self) -> CheckpointState {
+        CheckpointState::new()
+    }
+
+    /// Restore the internal state of the move from the `state` created by
+    /// `save_checkpoint`. The default is to restore nothing.
+    fn load_checkpoint(&mut self, _: &CheckpointState) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 /// Select a random molecule in the system using `rng` as random number
@@ -109,6 +127,12 @@ fn select_molecule(system: &System, hash: Option<MoleculeHash>, rng: &mut dyn Rn
     }
 }
 
+/// Get the thermal de Broglie wavelength of a molecule with total mass `mass`
+/// at the inverse temperature `beta`.
+fn thermal_wavelength(mass: f64, beta: f64) -> f64 {
+    PLANCK_CONSTANT * f64::sqrt(beta / (2.0 * PI * mass))
+}
+
 mod translate;
 pub use self::translate::Translate;
 
@@ -117,3 +141,9 @@ pub use self::rotate::Rotate;
 
 mod resize;
 pub use self::resize::Resize;
+
+mod insert;
+pub use self::insert::Insert;
+
+mod delete;
+pub use self::delete::Delete;
@@ -8,6 +8,7 @@ use std::f64;
 use std::mem;
 
 use super::{MCDegreeOfFreedom, MCMove};
+use crate::CheckpointState;
 
 use lumol_core::{Configuration, EnergyCache, System, Matrix3};
 
@@ -126,4 +127,16 @@ impl MCMove for Resize {
             self.range = Uniform::new(-self.delta, self.delta);
         }
     }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set("delta", &self.delta);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.delta = state.get("delta")?;
+        self.range = Uniform::new(-self.delta, self.delta);
+        Ok(())
+    }
 }
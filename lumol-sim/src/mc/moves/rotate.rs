@@ -14,6 +14,7 @@ use soa_derive::soa_zip;
 
 use super::{MCDegreeOfFreedom, MCMove};
 use super::select_molecule;
+use crate::CheckpointState;
 
 use lumol_core::{EnergyCache, System, MoleculeHash, Matrix3, Vector3D};
 
@@ -111,6 +112,18 @@ impl MCMove for Rotate {
             }
         }
     }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set("theta", &self.theta);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.theta = state.get("theta")?;
+        self.range = Uniform::new(-self.theta, self.theta);
+        Ok(())
+    }
 }
 
 /// Rotate the particles at `positions` with the center-of-mass position
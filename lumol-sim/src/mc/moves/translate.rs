@@ -15,6 +15,7 @@ use soa_derive::soa_zip;
 
 use super::{MCDegreeOfFreedom, MCMove};
 use super::select_molecule;
+use crate::CheckpointState;
 
 use lumol_core::{EnergyCache, System, MoleculeHash, Vector3D};
 
@@ -145,4 +146,16 @@ impl MCMove for Translate {
             self.range = Uniform::new(-self.delta, self.delta);
         };
     }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set("delta", &self.delta);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.delta = state.get("delta")?;
+        self.range = Uniform::new(-self.delta, self.delta);
+        Ok(())
+    }
 }
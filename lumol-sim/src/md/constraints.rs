@@ -0,0 +1,180 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::collections::BTreeMap;
+
+use log::warn;
+
+use lumol_core::{System, Vector3D};
+
+/// A single bond length constraint
+#[derive(Clone, Debug)]
+struct Constraint {
+    /// First particle in the bond
+    i: usize,
+    /// Second particle in the bond
+    j: usize,
+    /// Squared target bond length
+    length2: f64,
+}
+
+/// RATTLE algorithm for rigid bonds.
+///
+/// The RATTLE algorithm keeps some bonds at a fixed length during a
+/// velocity-Verlet integration. After the position update, positions and
+/// velocities are iteratively corrected until all the constrained bonds have
+/// their target length; and after the velocity update the velocities are
+/// corrected to remove any component along the constrained bonds.
+///
+/// The constrained bonds are all the bonds in the system between particles
+/// for which a target length was given with
+/// [`Rattle::set_bond_length`](struct.Rattle.html#method.set_bond_length).
+///
+/// [1] H. C. Andersen, J. Comput. Phys. 52, 24 (1983);
+/// doi: 10.1016/0021-9991(83)90014-1
+#[derive(Clone, Debug)]
+pub struct Rattle {
+    /// Target bond lengths, indexed by the sorted pair of particle names
+    lengths: BTreeMap<(String, String), f64>,
+    /// Relative tolerance on the bond lengths
+    tolerance: f64,
+    /// Maximal number of iterations
+    max_iterations: usize,
+    /// All the constraints in the system, created in `setup`
+    constraints: Vec<Constraint>,
+}
+
+impl Rattle {
+    /// Create a new `Rattle` constraint solver, without any constrained bond.
+    pub fn new() -> Rattle {
+        Rattle {
+            lengths: BTreeMap::new(),
+            tolerance: 1e-10,
+            max_iterations: 1000,
+            constraints: Vec::new(),
+        }
+    }
+
+    /// Constrain all the bonds between particles named `i` and `j` to have
+    /// the given `length`.
+    pub fn set_bond_length(&mut self, (i, j): (&str, &str), length: f64) {
+        assert!(length > 0.0, "constrained bond length must be positive");
+        let _ = self.lengths.insert(sorted_names(i, j), length);
+    }
+
+    /// Set the relative tolerance on the bond lengths. The default value is
+    /// `1e-10`.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        assert!(tolerance > 0.0, "RATTLE tolerance must be positive");
+        self.tolerance = tolerance;
+    }
+
+    /// Get the number of constrained bonds in the `system`
+    pub fn count(&self, system: &System) -> usize {
+        let names = system.particles().name;
+        let mut count = 0;
+        for molecule in system.molecules() {
+            for bond in molecule.bonds() {
+                if self.lengths.contains_key(&sorted_names(&names[bond.i()], &names[bond.j()])) {
+                    count += 1;
+                }
+            }
+        }
+        return count;
+    }
+
+    /// Find all the constrained bonds in the `system`.
+    pub fn setup(&mut self, system: &System) {
+        let names = system.particles().name;
+        self.constraints.clear();
+        for molecule in system.molecules() {
+            for bond in molecule.bonds() {
+                let (i, j) = (bond.i(), bond.j());
+                if let Some(&length) = self.lengths.get(&sorted_names(&names[i], &names[j])) {
+                    self.constraints.push(Constraint {
+                        i: i,
+                        j: j,
+                        length2: length * length,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Correct the positions and velocities in the `system` after a position
+    /// update with timestep `dt`, such that all the constraints are
+    /// satisfied. The `previous` positions must satisfy the constraints.
+    pub fn constrain_positions(&self, system: &mut System, previous: &[Vector3D], dt: f64) {
+        let cell = system.cell;
+        for _ in 0..self.max_iterations {
+            let mut converged = true;
+            let particles = system.particles_mut();
+            for constraint in &self.constraints {
+                let (i, j) = (constraint.i, constraint.j);
+                let mut rij = particles.position[i] - particles.position[j];
+                cell.vector_image(&mut rij);
+                let delta = constraint.length2 - rij.norm2();
+                if f64::abs(delta) <= 2.0 * self.tolerance * constraint.length2 {
+                    continue;
+                }
+                converged = false;
+
+                let mut old_rij = previous[i] - previous[j];
+                cell.vector_image(&mut old_rij);
+                let (inv_mi, inv_mj) = (1.0 / particles.mass[i], 1.0 / particles.mass[j]);
+                let g = delta / (2.0 * (rij * old_rij) * (inv_mi + inv_mj));
+
+                let correction = g * old_rij;
+                particles.position[i] += inv_mi * correction;
+                particles.position[j] -= inv_mj * correction;
+                particles.velocity[i] += inv_mi / dt * correction;
+                particles.velocity[j] -= inv_mj / dt * correction;
+            }
+
+            if converged {
+                return;
+            }
+        }
+        warn!("RATTLE did not converge for positions in {} iterations", self.max_iterations);
+    }
+
+    /// Correct the velocities in the `system` to remove any component along
+    /// the constrained bonds.
+    pub fn constrain_velocities(&self, system: &mut System) {
+        let cell = system.cell;
+        for _ in 0..self.max_iterations {
+            let mut converged = true;
+            let particles = system.particles_mut();
+            for constraint in &self.constraints {
+                let (i, j) = (constraint.i, constraint.j);
+                let mut rij = particles.position[i] - particles.position[j];
+                cell.vector_image(&mut rij);
+                let vij = particles.velocity[i] - particles.velocity[j];
+                let dot = rij * vij;
+                if f64::abs(dot) <= self.tolerance * f64::sqrt(constraint.length2) * vij.norm() {
+                    continue;
+                }
+                converged = false;
+
+                let (inv_mi, inv_mj) = (1.0 / particles.mass[i], 1.0 / particles.mass[j]);
+                let k = dot / (constraint.length2 * (inv_mi + inv_mj));
+                particles.velocity[i] -= inv_mi * k * rij;
+                particles.velocity[j] += inv_mj * k * rij;
+            }
+
+            if converged {
+                return;
+            }
+        }
+        warn!("RATTLE did not converge for velocities in {} iterations", self.max_iterations);
+    }
+}
+
+/// Get the pair of names `(i, j)` in a canonical order
+fn sorted_names(i: &str, j: &str) -> (String, String) {
+    if i < j {
+        (i.into(), j.into())
+    } else {
+        (j.into(), i.into())
+    }
+}
@@ -21,6 +21,13 @@ pub trait Control {
 
     /// Function called once at the end of the simulation.
     fn finish(&mut self, _: &System) {}
+
+    /// Get the number of degrees of freedom removed from the `system` by this
+    /// control. This is used to compute the temperature of the system. The
+    /// default is to not remove any degree of freedom.
+    fn removed_degrees_of_freedom(&self, _: &System) -> usize {
+        0
+    }
 }
 
 /// Remove global translation from the system
@@ -39,6 +46,14 @@ impl Control for RemoveTranslation {
             *velocity -= com_velocity;
         }
     }
+
+    fn removed_degrees_of_freedom(&self, system: &System) -> usize {
+        if system.size() == 0 {
+            0
+        } else {
+            3
+        }
+    }
 }
 
 /// Remove global rotation from the system
@@ -70,6 +85,33 @@ impl Control for RemoveRotation {
             *velocity -= (position - com) ^ angular;
         }
     }
+
+    /// The global rotation has no degree of freedom for a single particle,
+    /// two degrees of freedom if all the particles are on a line, and three
+    /// otherwise.
+    fn removed_degrees_of_freedom(&self, system: &System) -> usize {
+        let positions = system.particles().position;
+        let first = match positions.first() {
+            Some(&first) => first,
+            None => return 0,
+        };
+
+        // Direction of the line containing all the particles seen so far
+        let mut axis = None;
+        for position in &positions[1..] {
+            let delta = position - first;
+            match axis {
+                None if delta.norm() > 1e-6 => axis = Some(delta.normalized()),
+                Some(axis) if (delta ^ axis).norm() > 1e-6 => return 3,
+                _ => {}
+            }
+        }
+
+        match axis {
+            Some(_) => 2,
+            None => 0,
+        }
+    }
 }
 
 /// Rewrap all molecules' centers of mass to lie within the unit cell.
@@ -119,6 +161,25 @@ mod tests {
         assert_eq!(system.particles().velocity[1], Vector3D::new(0.0, 0.0, 1.0));
     }
 
+    #[test]
+    fn removed_degrees_of_freedom() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        assert_eq!(RemoveTranslation.removed_degrees_of_freedom(&system), 0);
+        assert_eq!(RemoveRotation.removed_degrees_of_freedom(&system), 0);
+
+        system.add_molecule(Molecule::new(Particle::with_position("Ag", [0.0, 0.0, 0.0].into())));
+        assert_eq!(RemoveTranslation.removed_degrees_of_freedom(&system), 3);
+        assert_eq!(RemoveRotation.removed_degrees_of_freedom(&system), 0);
+
+        system.add_molecule(Molecule::new(Particle::with_position("Ag", [1.0, 1.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ag", [3.0, 3.0, 0.0].into())));
+        assert_eq!(RemoveTranslation.removed_degrees_of_freedom(&system), 3);
+        assert_eq!(RemoveRotation.removed_degrees_of_freedom(&system), 2);
+
+        system.add_molecule(Molecule::new(Particle::with_position("Ag", [3.0, 0.0, 0.0].into())));
+        assert_eq!(RemoveRotation.removed_degrees_of_freedom(&system), 3);
+    }
+
     #[test]
     fn rewrap() {
         let mut system = System::with_cell(UnitCell::cubic(10.0));
@@ -2,7 +2,11 @@
 // Copyright (C) Lumol's contributors — BSD license
 use soa_derive::soa_zip;
 
-use lumol_core::{System, Matrix3, Vector3D};
+use lumol_core::{System, DegreesOfFreedom, Matrix3, Vector3D};
+use lumol_core::{CellShape, UnitCell};
+
+use super::Rattle;
+use crate::CheckpointState;
 
 /// The `Integrator` trait define integrator interface for molecular dynamics.
 /// An integrator is an algorithm responsible for propagating the equations of
@@ -14,16 +18,67 @@ pub trait Integrator {
     /// Integrate the equations of motion. This is called at every step of the
     /// simulation.
     fn integrate(&mut self, system: &mut System);
+    /// Get the number of degrees of freedom simulated by this integrator.
+    /// The default is to simulate all the particles.
+    fn degrees_of_freedom(&self, _: &System) -> DegreesOfFreedom {
+        DegreesOfFreedom::Particles
+    }
+    /// Get the internal variables of the integrator, as pairs of name and
+    /// value. These are reported alongside the other properties by the
+    /// outputs. The default is to report nothing.
+    fn state(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+    /// Set the particles which should not move during the simulation. The
+    /// `frozen` mask contains one value for each particle in the system, and
+    /// is `true` for frozen particles. The integrator must keep the
+    /// velocities and accelerations of these particles at zero. This function
+    /// is called before `setup`.
+    ///
+    /// The default implementation panics, for integrators which do not
+    /// support frozen particles.
+    fn set_frozen(&mut self, _: Vec<bool>) {
+        panic!("this integrator does not support frozen particles");
+    }
+    /// Save the internal state of the integrator which is not recomputed in
+    /// `setup`, to restart a simulation from a checkpoint. The default is to
+    /// save nothing.
+    fn save_checkpoint(&self) -> CheckpointState {
+        CheckpointState::new()
+    }
+    /// Restore the internal state of the integrator from the `state` created
+    /// by `save_checkpoint`. The default is to restore nothing.
+    fn load_checkpoint(&mut self, _: &CheckpointState) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Set the `values` associated with the `frozen` particles to zero
+fn zero_frozen(frozen: &[bool], values: &mut [Vector3D]) {
+    for (value, &frozen) in values.iter_mut().zip(frozen) {
+        if frozen {
+            *value = Vector3D::zero();
+        }
+    }
 }
 
 /// Velocity-Verlet integrator.
 ///
-/// This integrator is time-reversible and symplectic (volume preserving).
+/// This integrator is time-reversible and symplectic (volume preserving). It
+/// can keep some bonds at a fixed length using the [`Rattle`] algorithm.
+///
+/// [`Rattle`]: struct.Rattle.html
 pub struct VelocityVerlet {
     /// Timestep for the integrator
     timestep: f64,
     /// Storing the accelerations
     accelerations: Vec<Vector3D>,
+    /// Optional bond constraints
+    constraints: Option<Rattle>,
+    /// Positions at the beginning of the step, used by the constraints
+    previous: Vec<Vector3D>,
+    /// Mask of the frozen particles
+    frozen: Vec<bool>,
 }
 
 impl VelocityVerlet {
@@ -32,17 +87,44 @@ impl VelocityVerlet {
         VelocityVerlet {
             timestep: timestep,
             accelerations: Vec::new(),
+            constraints: None,
+            previous: Vec::new(),
+            frozen: Vec::new(),
         }
     }
+
+    /// Create a new integrator with a timestep of `timestep`, keeping the
+    /// bonds fixed according to the `constraints`.
+    pub fn with_constraints(timestep: f64, constraints: Rattle) -> VelocityVerlet {
+        let mut integrator = VelocityVerlet::new(timestep);
+        integrator.constraints = Some(constraints);
+        return integrator;
+    }
 }
 
 impl Integrator for VelocityVerlet {
     fn setup(&mut self, system: &System) {
         self.accelerations = vec![Vector3D::zero(); system.size()];
+        let forces = system.forces();
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.accelerations, forces
+        ) {
+            *acceleration = force / mass;
+        }
+        zero_frozen(&self.frozen, &mut self.accelerations);
+
+        if let Some(ref mut constraints) = self.constraints {
+            constraints.setup(system);
+        }
     }
 
     fn integrate(&mut self, system: &mut System) {
         let dt = self.timestep;
+        if self.constraints.is_some() {
+            self.previous.clear();
+            self.previous.extend_from_slice(system.particles().position);
+        }
+        zero_frozen(&self.frozen, system.particles_mut().velocity);
 
         // Update velocities at t + ∆t/2 and positions at t + ∆t
         for (position, velocity, acceleration) in soa_zip!(
@@ -52,6 +134,10 @@ impl Integrator for VelocityVerlet {
             *position += velocity * dt;
         }
 
+        if let Some(ref constraints) = self.constraints {
+            constraints.constrain_positions(system, &self.previous, dt);
+        }
+
         let forces = system.forces();
         // Update accelerations at t + ∆t
         for (&mass, acceleration, force) in soa_zip!(
@@ -59,6 +145,7 @@ impl Integrator for VelocityVerlet {
         ) {
             *acceleration = force / mass;
         }
+        zero_frozen(&self.frozen, &mut self.accelerations);
 
         // Update velocities at t + ∆t
         for (velocity, acceleration) in soa_zip!(
@@ -66,6 +153,21 @@ impl Integrator for VelocityVerlet {
         ) {
             *velocity += 0.5 * dt * acceleration;
         }
+
+        if let Some(ref constraints) = self.constraints {
+            constraints.constrain_velocities(system);
+        }
+    }
+
+    fn degrees_of_freedom(&self, system: &System) -> DegreesOfFreedom {
+        match self.constraints {
+            Some(ref constraints) => DegreesOfFreedom::Frozen(constraints.count(system)),
+            None => DegreesOfFreedom::Particles,
+        }
+    }
+
+    fn set_frozen(&mut self, frozen: Vec<bool>) {
+        self.frozen = frozen;
     }
 }
 
@@ -77,6 +179,8 @@ pub struct Verlet {
     timestep: f64,
     /// Previous positions
     prevpos: Vec<Vector3D>,
+    /// Mask of the frozen particles
+    frozen: Vec<bool>,
 }
 
 impl Verlet {
@@ -85,6 +189,7 @@ impl Verlet {
         Verlet {
             timestep: timestep,
             prevpos: Vec::new(),
+            frozen: Vec::new(),
         }
     }
 }
@@ -100,10 +205,21 @@ impl Integrator for Verlet {
         ) {
             *prevpos = position - velocity * dt;
         }
+
+        // Frozen particles did not move during the previous step
+        for ((prevpos, position), &frozen) in self.prevpos.iter_mut()
+            .zip(system.particles().position)
+            .zip(&self.frozen)
+        {
+            if frozen {
+                *prevpos = *position;
+            }
+        }
     }
 
     fn integrate(&mut self, system: &mut System) {
-        let forces = system.forces();
+        let mut forces = system.forces();
+        zero_frozen(&self.frozen, &mut forces);
         let dt = self.timestep;
         let dt2 = dt * dt;
 
@@ -120,6 +236,10 @@ impl Integrator for Verlet {
             *prevpos = tmp;
         }
     }
+
+    fn set_frozen(&mut self, frozen: Vec<bool>) {
+        self.frozen = frozen;
+    }
 }
 
 /// Leap-frog integrator.
@@ -130,6 +250,8 @@ pub struct LeapFrog {
     timestep: f64,
     /// Storing the accelerations
     accelerations: Vec<Vector3D>,
+    /// Mask of the frozen particles
+    frozen: Vec<bool>,
 }
 
 impl LeapFrog {
@@ -138,6 +260,7 @@ impl LeapFrog {
         LeapFrog {
             timestep: timestep,
             accelerations: Vec::new(),
+            frozen: Vec::new(),
         }
     }
 }
@@ -145,11 +268,19 @@ impl LeapFrog {
 impl Integrator for LeapFrog {
     fn setup(&mut self, system: &System) {
         self.accelerations = vec![Vector3D::zero(); system.size()];
+        let mut forces = system.forces();
+        zero_frozen(&self.frozen, &mut forces);
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.accelerations, &forces
+        ) {
+            *acceleration = force / mass;
+        }
     }
 
     fn integrate(&mut self, system: &mut System) {
         let dt = self.timestep;
         let dt2 = dt * dt;
+        zero_frozen(&self.frozen, system.particles_mut().velocity);
 
         for (position, velocity, acceleration) in soa_zip!(
             system.particles_mut(), [mut position, velocity], &self.accelerations
@@ -157,7 +288,8 @@ impl Integrator for LeapFrog {
             *position += velocity * dt + 0.5 * acceleration * dt2;
         }
 
-        let forces = system.forces();
+        let mut forces = system.forces();
+        zero_frozen(&self.frozen, &mut forces);
         for (velocity, &mass, acceleration, force) in soa_zip!(
             system.particles_mut(), [mut velocity, mass], &mut self.accelerations, &forces
         ) {
@@ -166,6 +298,126 @@ impl Integrator for LeapFrog {
             *acceleration = new_acceleration;
         }
     }
+
+    fn set_frozen(&mut self, frozen: Vec<bool>) {
+        self.frozen = frozen;
+    }
+}
+
+/// Multiple timestep integrator, using the reversible reference system
+/// propagator algorithm (r-RESPA) [1].
+///
+/// The fast bonded forces (bonds, angles, Urey-Bradley terms, dihedral and
+/// improper dihedral angles) are integrated with a small inner timestep,
+/// while the slow non-bonded forces (pairs, coulombic and global potentials)
+/// are only computed once every outer timestep. This reduces the number of
+/// non-bonded forces computations for systems with stiff bonds. Both levels
+/// use a velocity-Verlet scheme, and this integrator is time-reversible and
+/// symplectic.
+///
+/// [1] Tuckerman et al. J. Chem. Phys. 97, 1990 (1992); doi: 10.1063/1.463137
+pub struct Respa {
+    /// Outer timestep for the integrator, used for the non-bonded forces
+    timestep: f64,
+    /// Number of inner steps for the bonded forces in each outer step
+    inner_steps: usize,
+    /// Storing the accelerations from the bonded forces
+    bonded_accelerations: Vec<Vector3D>,
+    /// Storing the accelerations from the non-bonded forces
+    nonbonded_accelerations: Vec<Vector3D>,
+    /// Mask of the frozen particles
+    frozen: Vec<bool>,
+}
+
+impl Respa {
+    /// Create a new RESPA integrator with an outer timestep of `timestep`,
+    /// integrating the bonded forces with `inner_steps` inner steps of
+    /// `timestep / inner_steps`.
+    ///
+    /// # Panics
+    ///
+    /// If `inner_steps` is zero
+    pub fn new(timestep: f64, inner_steps: usize) -> Respa {
+        assert!(inner_steps > 0, "RESPA integrator needs at least one inner step");
+        Respa {
+            timestep: timestep,
+            inner_steps: inner_steps,
+            bonded_accelerations: Vec::new(),
+            nonbonded_accelerations: Vec::new(),
+            frozen: Vec::new(),
+        }
+    }
+}
+
+impl Integrator for Respa {
+    fn setup(&mut self, system: &System) {
+        self.bonded_accelerations = vec![Vector3D::zero(); system.size()];
+        self.nonbonded_accelerations = vec![Vector3D::zero(); system.size()];
+
+        let mut forces = system.bonded_forces();
+        zero_frozen(&self.frozen, &mut forces);
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.bonded_accelerations, &forces
+        ) {
+            *acceleration = force / mass;
+        }
+
+        let mut forces = system.nonbonded_forces();
+        zero_frozen(&self.frozen, &mut forces);
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.nonbonded_accelerations, &forces
+        ) {
+            *acceleration = force / mass;
+        }
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        let dt = self.timestep;
+        let inner_dt = dt / self.inner_steps as f64;
+        zero_frozen(&self.frozen, system.particles_mut().velocity);
+
+        // Update velocities at t + ∆t/2 with the non-bonded forces
+        for (velocity, acceleration) in soa_zip!(
+            system.particles_mut(), [mut velocity], &self.nonbonded_accelerations
+        ) {
+            *velocity += 0.5 * dt * acceleration;
+        }
+
+        for _ in 0..self.inner_steps {
+            // Update velocities at t + δt/2 and positions at t + δt
+            for (position, velocity, acceleration) in soa_zip!(
+                system.particles_mut(), [mut position, mut velocity], &self.bonded_accelerations
+            ) {
+                *velocity += 0.5 * inner_dt * acceleration;
+                *position += velocity * inner_dt;
+            }
+
+            let mut forces = system.bonded_forces();
+            zero_frozen(&self.frozen, &mut forces);
+            let accelerations = &mut self.bonded_accelerations;
+            // Update bonded accelerations and velocities at t + δt
+            for (velocity, &mass, acceleration, force) in soa_zip!(
+                system.particles_mut(), [mut velocity, mass], accelerations, &forces
+            ) {
+                *acceleration = force / mass;
+                *velocity += 0.5 * inner_dt * (*acceleration);
+            }
+        }
+
+        let mut forces = system.nonbonded_forces();
+        zero_frozen(&self.frozen, &mut forces);
+        // Update non-bonded accelerations and velocities at t + ∆t
+        for (velocity, &mass, acceleration, force) in soa_zip!(
+            system.particles_mut(), [mut velocity, mass], &mut self.nonbonded_accelerations, &forces
+        ) {
+            *acceleration = force / mass;
+            *velocity += 0.5 * dt * (*acceleration);
+        }
+    }
+
+    fn set_frozen(&mut self, frozen: Vec<bool>) {
+        self.frozen = frozen;
+    }
 }
 
 /// This is needed for the `BerendsenBarostat` implementation. The value comes
@@ -201,11 +453,23 @@ impl BerendsenBarostat {
             eta: 1.0,
         }
     }
+
+    /// Get the scaling factor applied to the cell lengths and the positions
+    /// during the next step.
+    pub fn scale_factor(&self) -> f64 {
+        self.eta
+    }
 }
 
 impl Integrator for BerendsenBarostat {
     fn setup(&mut self, system: &System) {
         self.accelerations = vec![Vector3D::zero(); system.size()];
+        let forces = system.forces();
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.accelerations, forces
+        ) {
+            *acceleration = force / mass;
+        }
     }
 
     fn integrate(&mut self, system: &mut System) {
@@ -246,6 +510,21 @@ impl Integrator for BerendsenBarostat {
             *velocity += 0.5 * dt * acceleration;
         }
     }
+
+    fn state(&self) -> Vec<(&'static str, f64)> {
+        vec![("barostat_scale", self.eta)]
+    }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set("eta", &self.eta);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.eta = state.get("eta")?;
+        Ok(())
+    }
 }
 
 /// Anisotropic Berendsen barostat integrator based on velocity-Verlet.
@@ -289,6 +568,12 @@ impl AnisoBerendsenBarostat {
 impl Integrator for AnisoBerendsenBarostat {
     fn setup(&mut self, system: &System) {
         self.accelerations = vec![Vector3D::zero(); system.size()];
+        let forces = system.forces();
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.accelerations, forces
+        ) {
+            *acceleration = force / mass;
+        }
     }
 
     fn integrate(&mut self, system: &mut System) {
@@ -337,4 +622,341 @@ impl Integrator for AnisoBerendsenBarostat {
             *velocity += 0.5 * dt * acceleration;
         }
     }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set("eta", &self.eta);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.eta = state.get("eta")?;
+        Ok(())
+    }
+}
+
+/// Parrinello-Rahman barostat integrator based on velocity-Verlet.
+///
+/// Contrary to the Berendsen barostats, this barostat uses a second order
+/// equation of motion for the unit cell, allowing full fluctuations of all the
+/// cell parameters. The cell deformation rate $\dot{\underline{\epsilon}}$
+/// evolves according to
+///
+/// $$ \ddot{\underline{\epsilon}} = \frac{\beta}{\tau^2}
+///    (\underline{\sigma} - \underline{\sigma}_0) $$
+///
+/// where $\beta$ is the isothermal compressibility of the system, $\tau$ the
+/// barostat time scale, $\underline{\sigma}$ the instantaneous stress and
+/// $\underline{\sigma}_0$ the target stress. The particles positions and
+/// velocities are coupled to the cell deformation rate. This barostat should
+/// be used with a thermostat to produce an NPT ensemble.
+///
+/// This integrator is **neither** time-reversible nor symplectic.
+///
+/// [1] Parrinello & Rahman, J. Appl. Phys. 52, 7182 (1981); doi: 10.1063/1.328693
+pub struct ParrinelloRahman {
+    /// Timestep for the integrator
+    timestep: f64,
+    /// Target stress matrix for the barostat
+    stress: Matrix3,
+    /// Barostat time scale, expressed in units of the timestep
+    tau: f64,
+    /// Isothermal compressibility of the system, in inverse internal units of
+    /// pressure
+    compressibility: f64,
+    /// Storing the accelerations
+    accelerations: Vec<Vector3D>,
+    /// Deformation rate of the unit cell
+    strain_rate: Matrix3,
+    /// Instantaneous stress at the current step
+    current_stress: Matrix3,
+}
+
+impl ParrinelloRahman {
+    /// Create a new Parrinello-Rahman barostat with an integration timestep of
+    /// `timestep`, and a target stress matrix of `stress`, the barostat time
+    /// scale `tau` and the isothermal `compressibility` of the system.
+    ///
+    /// The compressibility is expressed in inverse internal units of pressure,
+    /// and together with `tau` it sets the inertia of the cell. An estimate of
+    /// the compressibility is enough, for example water has a compressibility
+    /// of about 4.6e-5 bar^-1.
+    ///
+    /// # Panics
+    ///
+    /// If `tau` or `compressibility` are not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::Matrix3;
+    /// # use lumol_core::units;
+    /// # use lumol_sim::md::ParrinelloRahman;
+    /// let compressibility = units::from(4.6e-5, "bar^-1").unwrap();
+    /// let stress = units::from(100.0, "bar").unwrap() * Matrix3::one();
+    /// let barostat = ParrinelloRahman::new(1.0, stress, 1000.0, compressibility);
+    /// ```
+    pub fn new(timestep: f64, stress: Matrix3, tau: f64, compressibility: f64) -> ParrinelloRahman {
+        assert!(tau > 0.0, "The timestep must be positive in Parrinello-Rahman barostat.");
+        assert!(
+            compressibility > 0.0,
+            "The compressibility must be positive in Parrinello-Rahman barostat."
+        );
+        ParrinelloRahman {
+            timestep: timestep,
+            stress: stress,
+            tau: tau,
+            compressibility: compressibility,
+            accelerations: Vec::new(),
+            strain_rate: Matrix3::zero(),
+            current_stress: Matrix3::zero(),
+        }
+    }
+
+    /// Create a new Parrinello-Rahman barostat with an integration timestep
+    /// of `timestep`, using an hydrostatic stress matrix corresponding to the
+    /// pressure `pressure`, the barostat time scale `tau` and the isothermal
+    /// `compressibility` of the system, in inverse internal units of pressure.
+    pub fn hydrostatic(
+        timestep: f64,
+        pressure: f64,
+        tau: f64,
+        compressibility: f64,
+    ) -> ParrinelloRahman {
+        ParrinelloRahman::new(timestep, pressure * Matrix3::one(), tau, compressibility)
+    }
+
+    /// Get the current deformation rate of the unit cell
+    pub fn strain_rate(&self) -> Matrix3 {
+        self.strain_rate
+    }
+
+    /// Update the cell deformation rate for half a timestep
+    fn update_strain_rate(&mut self) {
+        let tau = self.tau * self.timestep;
+        let factor = 0.5 * self.timestep * self.compressibility / (tau * tau);
+        self.strain_rate += factor * (self.current_stress - self.stress);
+
+        // Prevent rotations of the cell by keeping the matrix symmetric
+        for i in 0..3 {
+            for j in 0..i {
+                self.strain_rate[i][j] = 0.5 * (self.strain_rate[i][j] + self.strain_rate[j][i]);
+                self.strain_rate[j][i] = self.strain_rate[i][j];
+            }
+        }
+    }
+}
+
+impl Integrator for ParrinelloRahman {
+    fn setup(&mut self, system: &System) {
+        self.accelerations = vec![Vector3D::zero(); system.size()];
+        let forces = system.forces();
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.accelerations, forces
+        ) {
+            *acceleration = force / mass;
+        }
+        self.current_stress = system.stress();
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        let dt = self.timestep;
+
+        // Update the cell deformation rate at t + ∆t/2
+        self.update_strain_rate();
+        let strain_rate = self.strain_rate;
+        let scaling = Matrix3::one() + dt * strain_rate;
+
+        // Update velocities at t + ∆t/2 and positions at t + ∆t
+        for (position, velocity, acceleration) in soa_zip!(
+            system.particles_mut(), [mut position, mut velocity], &self.accelerations
+        ) {
+            *velocity += 0.5 * dt * (acceleration - strain_rate * (*velocity));
+            *position = scaling * (*position);
+            *position += velocity * dt;
+        }
+
+        system.cell.scale_mut(scaling);
+
+        if let Some(maximum_cutoff) = system.maximum_cutoff() {
+            if system.cell.lengths().iter().any(|&d| 0.5 * d <= maximum_cutoff) {
+                panic!(
+                    "Tried to decrease the cell size in Parrinello-Rahman \
+                     barostat but the new size is smaller than the interactions \
+                     cut off radius. You can try to increase the cell size or \
+                     the number of particles."
+                );
+            }
+        };
+
+        let forces = system.forces();
+        // Update accelerations at t + ∆t and velocities at t + ∆t
+        for (velocity, &mass, acceleration, force) in soa_zip!(
+            system.particles_mut(), [mut velocity, mass], &mut self.accelerations, &forces
+        ) {
+            *acceleration = force / mass;
+            *velocity += 0.5 * dt * (*acceleration - strain_rate * (*velocity));
+        }
+
+        // Update the cell deformation rate at t + ∆t
+        self.current_stress = system.stress();
+        self.update_strain_rate();
+    }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set("strain_rate", &self.strain_rate);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.strain_rate = state.get("strain_rate")?;
+        Ok(())
+    }
+}
+
+/// Non-equilibrium SLLOD integrator, driving a homogeneous flow in the system.
+///
+/// The equations of motion for the particles are
+///
+/// $$ \dot{\vec r}_i = \vec v_i + \underline{\nabla u} \cdot \vec r_i $$
+///
+/// $$ \dot{\vec v}_i = \frac{\vec F_i}{m_i} - \underline{\nabla u} \cdot \vec v_i $$
+///
+/// where $\underline{\nabla u}$ is the imposed velocity gradient. The
+/// velocities stored in the system are the *peculiar* (thermal) velocities,
+/// relative to the streaming velocity $\underline{\nabla u} \cdot \vec r$,
+/// so that thermostats and temperature computations only act on the thermal
+/// motion. The unit cell is deformed along with the flow, which is equivalent
+/// to Lees-Edwards boundary conditions in the case of a simple shear [1].
+/// When a cell vector becomes tilted by more than half of another cell
+/// vector, it is replaced by the equivalent vector of the same periodic
+/// lattice with the opposite tilt, and the molecules are wrapped back in the
+/// new cell. This keeps the cell close to orthorhombic, so that the minimum
+/// image convention stays valid for long simulations.
+///
+/// These equations are integrated with a velocity-Verlet scheme. This
+/// integrator should be used with a thermostat to remove the heat produced by
+/// the flow. The shear viscosity can then be computed from the average stress
+/// as $\eta = - \langle \sigma_{xy} \rangle / \dot\gamma$.
+///
+/// [1] Evans & Morriss, Statistical Mechanics of Nonequilibrium Liquids,
+/// Cambridge University Press (2008); doi: 10.1017/CBO9780511535307
+pub struct Sllod {
+    /// Timestep for the integrator
+    timestep: f64,
+    /// Imposed velocity gradient
+    velocity_gradient: Matrix3,
+    /// Storing the accelerations
+    accelerations: Vec<Vector3D>,
+}
+
+impl Sllod {
+    /// Create a new SLLOD integrator with an integration timestep of
+    /// `timestep`, imposing the given `velocity_gradient` in the system. The
+    /// streaming velocity at position $\vec r$ is
+    /// `velocity_gradient * r`.
+    pub fn new(timestep: f64, velocity_gradient: Matrix3) -> Sllod {
+        Sllod {
+            timestep: timestep,
+            velocity_gradient: velocity_gradient,
+            accelerations: Vec::new(),
+        }
+    }
+
+    /// Create a new SLLOD integrator with an integration timestep of
+    /// `timestep`, imposing a simple shear flow with the given `shear_rate`.
+    /// The flow is along the x axis, and the velocity gradient along the y
+    /// axis.
+    pub fn shear(timestep: f64, shear_rate: f64) -> Sllod {
+        let mut velocity_gradient = Matrix3::zero();
+        velocity_gradient[0][1] = shear_rate;
+        Sllod::new(timestep, velocity_gradient)
+    }
+
+    /// Get the streaming velocity imposed by this integrator at the given
+    /// `position`. The velocity of a particle in the laboratory frame is the
+    /// sum of its peculiar velocity and of the streaming velocity.
+    pub fn streaming_velocity(&self, position: &Vector3D) -> Vector3D {
+        self.velocity_gradient * position
+    }
+}
+
+impl Integrator for Sllod {
+    fn setup(&mut self, system: &System) {
+        self.accelerations = vec![Vector3D::zero(); system.size()];
+        let forces = system.forces();
+        for (&mass, acceleration, force) in soa_zip!(
+            system.particles(), [mass], &mut self.accelerations, forces
+        ) {
+            *acceleration = force / mass;
+        }
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        let dt = self.timestep;
+        let gradient = self.velocity_gradient;
+        let deformation = Matrix3::one() + dt * gradient;
+
+        // Update velocities at t + ∆t/2 and positions at t + ∆t
+        for (position, velocity, acceleration) in soa_zip!(
+            system.particles_mut(), [mut position, mut velocity], &self.accelerations
+        ) {
+            *velocity += 0.5 * dt * (acceleration - gradient * (*velocity));
+            *position = deformation * (*position);
+            *position += velocity * dt;
+        }
+
+        // Deform the cell along with the flow. Orthorhombic cells only use
+        // the diagonal of the cell matrix, and need to be converted to
+        // triclinic cells first.
+        if system.cell.shape() == CellShape::Orthorhombic {
+            let lengths = system.cell.lengths();
+            system.cell = UnitCell::triclinic(lengths[0], lengths[1], lengths[2], 90.0, 90.0, 90.0);
+        }
+        if !system.cell.is_infinite() {
+            system.cell = system.cell.scale(deformation);
+            if lees_edwards_flip(&mut system.cell) {
+                let cell = system.cell;
+                for mut molecule in system.molecules_mut() {
+                    molecule.wrap(&cell);
+                }
+            }
+        }
+
+        let forces = system.forces();
+        // Update accelerations at t + ∆t and velocities at t + ∆t
+        for (velocity, &mass, acceleration, force) in soa_zip!(
+            system.particles_mut(), [mut velocity, mass], &mut self.accelerations, &forces
+        ) {
+            *acceleration = force / mass;
+            *velocity += 0.5 * dt * (*acceleration - gradient * (*velocity));
+        }
+    }
+}
+
+/// Flip the vectors of a sheared triclinic `cell` to keep it close to
+/// orthorhombic. When a cell vector is tilted by more than half of the
+/// length of a previous cell vector along the same direction, this previous
+/// vector is subtracted from it as many times as needed. This generates the
+/// same periodic lattice. Returns `true` if the cell was modified.
+fn lees_edwards_flip(cell: &mut UnitCell) -> bool {
+    let mut flipped = false;
+    // Tilt of the cell vector in `column`, in the direction given by `row`,
+    // relative to the cell vector in `reference`. `c` is corrected relative
+    // to `b` before being corrected relative to `a`, since changing `c` by
+    // `b` also changes its x component.
+    for &(row, column, reference) in &[(0, 1, 0), (1, 2, 1), (0, 2, 0)] {
+        let matrix = cell.matrix();
+        let shift = f64::round(matrix[row][column] / matrix[row][reference]);
+        if shift != 0.0 {
+            // Multiplying the cell matrix on the right by this matrix
+            // subtracts `shift` times the reference vector from the vector
+            let mut flip = Matrix3::one();
+            flip[reference][column] = -shift;
+            cell.scale_mut(flip);
+            flipped = true;
+        }
+    }
+    return flipped;
 }
@@ -20,10 +20,16 @@
 //! - [`VelocityVerlet`](struct.VelocityVerlet.html): simple velocity-Verlet
 //!   integrator;
 //! - [`LeapFrog`](struct.LeapFrog.html): Leap-Frog integrator;
+//! - [`Respa`](struct.Respa.html): multiple timestep integrator, using a
+//!   smaller timestep for the bonded forces;
 //! - [`BerendsenBarostat`](struct.BerendsenBarostat.html): isotropic Berendsen
 //!   barostat coupled to a velocity-Verlet integrator;
 //! - [`AnisoBerendsenBarostat`](struct.AnisoBerendsenBarostat.html) anisotropic
-//!   Berendsen barostat coupled to a velocity-Verlet integrator
+//!   Berendsen barostat coupled to a velocity-Verlet integrator;
+//! - [`ParrinelloRahman`](struct.ParrinelloRahman.html) anisotropic
+//!   Parrinello-Rahman barostat coupled to a velocity-Verlet integrator;
+//! - [`Sllod`](struct.Sllod.html): non-equilibrium SLLOD integrator, imposing
+//!   a shear flow in the system.
 //!
 //! # Themostats
 //!
@@ -50,6 +56,15 @@
 //!   rotational momentum of a system;
 //! - [`Rewrap`](struct.Rewrap.html): wrap all atoms from a system inside the
 //!   unit cell;
+//!
+//! # Constraints
+//!
+//! The [`Rattle`](struct.Rattle.html) algorithm keeps some bonds at a fixed
+//! length when used with the [`VelocityVerlet`](struct.VelocityVerlet.html)
+//! integrator.
+
+mod constraints;
+pub use self::constraints::Rattle;
 
 mod integrators;
 pub use self::integrators::Integrator;
@@ -57,6 +72,9 @@ pub use self::integrators::Integrator;
 pub use self::integrators::AnisoBerendsenBarostat;
 pub use self::integrators::BerendsenBarostat;
 pub use self::integrators::LeapFrog;
+pub use self::integrators::Respa;
+pub use self::integrators::ParrinelloRahman;
+pub use self::integrators::Sllod;
 pub use self::integrators::VelocityVerlet;
 pub use self::integrators::Verlet;
 
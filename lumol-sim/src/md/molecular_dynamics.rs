@@ -2,10 +2,11 @@
 // Copyright (C) Lumol's contributors — BSD license
 
 use crate::propagator::{Propagator, TemperatureStrategy};
-use lumol_core::{System, DegreesOfFreedom};
+use crate::CheckpointState;
+use lumol_core::{System, DegreesOfFreedom, Vector3D};
 
 use super::{Control, Integrator, Thermostat};
-use super::VelocityVerlet;
+use super::{Rattle, VelocityVerlet};
 
 /// Molecular Dynamics propagator for the simulation.
 pub struct MolecularDynamics {
@@ -15,6 +16,8 @@ pub struct MolecularDynamics {
     thermostat: Option<Box<dyn Thermostat>>,
     /// Control algorithms in the simulation.
     controls: Vec<Box<dyn Control>>,
+    /// Indexes of the frozen particles, sorted and without duplicates
+    frozen: Vec<usize>,
 }
 
 impl MolecularDynamics {
@@ -24,6 +27,13 @@ impl MolecularDynamics {
         MolecularDynamics::from_integrator(Box::new(VelocityVerlet::new(dt)))
     }
 
+    /// Create a new `MolecularDynamics` propagator using a `VelocityVerlet`
+    /// integrator, keeping some bonds at a fixed length with the
+    /// `constraints`.
+    pub fn with_constraints(dt: f64, constraints: Rattle) -> MolecularDynamics {
+        MolecularDynamics::from_integrator(Box::new(VelocityVerlet::with_constraints(dt, constraints)))
+    }
+
     /// Create a new `MolecularDynamics` propagator using the specified
     /// `integrator`.
     pub fn from_integrator(integrator: Box<dyn Integrator>) -> MolecularDynamics {
@@ -31,6 +41,7 @@ impl MolecularDynamics {
             integrator: integrator,
             thermostat: None,
             controls: Vec::new(),
+            frozen: Vec::new(),
         }
     }
 
@@ -43,6 +54,30 @@ impl MolecularDynamics {
     pub fn set_thermostat(&mut self, thermostat: Box<dyn Thermostat>) {
         self.thermostat = Some(thermostat);
     }
+
+    /// Freeze the particles at the given `indexes`. The integrator keeps the
+    /// velocities and accelerations of frozen particles at zero, so that they
+    /// stay at the position they have at the beginning of the simulation,
+    /// while still interacting with the other particles. This can be used to
+    /// create fixed walls or substrates.
+    ///
+    /// Frozen particles are only supported by some integrators, and the
+    /// simulation setup will panic with integrators changing the unit cell,
+    /// such as barostats.
+    pub fn freeze(&mut self, indexes: &[usize]) {
+        self.frozen.extend_from_slice(indexes);
+        self.frozen.sort_unstable();
+        self.frozen.dedup();
+    }
+
+    /// Set the velocity of the frozen particles to zero, after thermostats
+    /// and controls modified it.
+    fn stop_frozen(&self, system: &mut System) {
+        let velocities = system.particles_mut().velocity;
+        for &i in &self.frozen {
+            velocities[i] = Vector3D::zero();
+        }
+    }
 }
 
 impl Propagator for MolecularDynamics {
@@ -50,13 +85,36 @@ impl Propagator for MolecularDynamics {
         TemperatureStrategy::Velocities
     }
 
-    fn degrees_of_freedom(&self, _: &System) -> DegreesOfFreedom {
-        // default to particles for now. change this if/when constrains are
-        // implemented
-        DegreesOfFreedom::Particles
+    fn degrees_of_freedom(&self, system: &System) -> DegreesOfFreedom {
+        let removed = self.controls.iter()
+            .map(|control| control.removed_degrees_of_freedom(system))
+            .sum::<usize>();
+        let frozen = 3 * self.frozen.len() + removed;
+        match self.integrator.degrees_of_freedom(system) {
+            DegreesOfFreedom::Particles if frozen != 0 => DegreesOfFreedom::Frozen(frozen),
+            DegreesOfFreedom::Frozen(n) => DegreesOfFreedom::Frozen(n + frozen),
+            DegreesOfFreedom::Molecules if frozen != 0 => DegreesOfFreedom::FrozenMolecules(frozen),
+            DegreesOfFreedom::FrozenMolecules(n) => DegreesOfFreedom::FrozenMolecules(n + frozen),
+            other => other,
+        }
     }
 
     fn setup(&mut self, system: &System) {
+        if let Some(&last) = self.frozen.last() {
+            assert!(last < system.size(), "frozen particle index out of bounds");
+            assert!(
+                self.integrator.degrees_of_freedom(system) != DegreesOfFreedom::Molecules,
+                "can not freeze particles when simulating rigid molecules"
+            );
+        }
+        if !self.frozen.is_empty() {
+            let mut mask = vec![false; system.size()];
+            for &i in &self.frozen {
+                mask[i] = true;
+            }
+            self.integrator.set_frozen(mask);
+        }
+
         self.integrator.setup(system);
         for control in &mut self.controls {
             control.setup(system);
@@ -73,6 +131,9 @@ impl Propagator for MolecularDynamics {
         for control in &mut self.controls {
             control.control(system);
         }
+
+        // Thermostats and controls can give a velocity to frozen particles
+        self.stop_frozen(system);
     }
 
     fn finish(&mut self, system: &System) {
@@ -80,4 +141,29 @@ impl Propagator for MolecularDynamics {
             control.finish(system);
         }
     }
+
+    fn state(&self) -> Vec<(&'static str, f64)> {
+        let mut state = self.integrator.state();
+        if let Some(ref thermostat) = self.thermostat {
+            state.extend(thermostat.state());
+        }
+        return state;
+    }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.extend_with_prefix("integrator", self.integrator.save_checkpoint());
+        if let Some(ref thermostat) = self.thermostat {
+            state.extend_with_prefix("thermostat", thermostat.save_checkpoint());
+        }
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.integrator.load_checkpoint(&state.with_prefix("integrator"))?;
+        if let Some(ref mut thermostat) = self.thermostat {
+            thermostat.load_checkpoint(&state.with_prefix("thermostat"))?;
+        }
+        Ok(())
+    }
 }
@@ -4,10 +4,11 @@
 use lumol_core::System;
 use lumol_core::consts::K_BOLTZMANN;
 
-use rand::{self, SeedableRng};
+use rand::SeedableRng;
 use rand_distr::{Distribution, Normal, Gamma};
 
 use crate::velocities;
+use crate::{CheckpointRng, CheckpointState};
 
 /// Trait for thermostat algorithms some parameters in a system during a simulation.
 pub trait Thermostat {
@@ -21,6 +22,25 @@ pub trait Thermostat {
 
     /// Function called once at the end of the simulation.
     fn finish(&mut self, _: &System) {}
+
+    /// Get the internal variables of the thermostat, as pairs of name and
+    /// value. These are reported alongside the other properties by the
+    /// outputs. The default is to report nothing.
+    fn state(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+
+    /// Save the internal state of the thermostat, to restart a simulation
+    /// from a checkpoint. The default is to save nothing.
+    fn save_checkpoint(&self) -> CheckpointState {
+        CheckpointState::new()
+    }
+
+    /// Restore the internal state of the thermostat from the `state` created
+    /// by `save_checkpoint`. The default is to restore nothing.
+    fn load_checkpoint(&mut self, _: &CheckpointState) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 
@@ -94,6 +114,8 @@ pub struct BerendsenThermostat {
     /// Timestep of the thermostat, expressed as a multiplicative factor of the
     /// integrator timestep.
     tau: f64,
+    /// Scaling factor applied to the velocities in the last step
+    scale_factor: f64,
 }
 
 impl BerendsenThermostat {
@@ -105,8 +127,15 @@ impl BerendsenThermostat {
         BerendsenThermostat {
             temperature: temperature,
             tau: tau,
+            scale_factor: 1.0,
         }
     }
+
+    /// Get the scaling factor applied to the velocities during the last
+    /// step. This is 1 before the first step.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
 }
 
 impl Thermostat for BerendsenThermostat {
@@ -116,6 +145,22 @@ impl Thermostat for BerendsenThermostat {
         for velocity in system.particles_mut().velocity {
             *velocity *= factor;
         }
+        self.scale_factor = factor;
+    }
+
+    fn state(&self) -> Vec<(&'static str, f64)> {
+        vec![("thermostat_scale", self.scale_factor)]
+    }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set("scale_factor", &self.scale_factor);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        self.scale_factor = state.get("scale_factor")?;
+        Ok(())
     }
 }
 
@@ -137,9 +182,11 @@ pub struct CSVRThermostat {
     /// integrator timestep.
     tau: f64,
     /// Random number generator for the stochatsic propagation of kinetic energy
-    rng: Box<dyn rand::RngCore>,
+    rng: Box<dyn CheckpointRng>,
     /// normal (i.e. gaussian) distribution
     normal: Normal<f64>,
+    /// Scaling factor applied to the velocities in the last step
+    scale_factor: f64,
 }
 
 impl CSVRThermostat {
@@ -156,7 +203,7 @@ impl CSVRThermostat {
     /// Create a new `CSVRThermostat` enforcing the given `temperature`, with a
     /// timestep of `tau` times the integrator timestep, using the given `rng`
     /// when generating random noise.
-    pub fn from_rng(temperature: f64, tau: f64, rng: Box<dyn rand::RngCore>) -> CSVRThermostat {
+    pub fn from_rng(temperature: f64, tau: f64, rng: Box<dyn CheckpointRng>) -> CSVRThermostat {
         assert!(temperature >= 0.0, "The temperature must be positive in thermostats.");
         assert!(tau >= 1.0, "The timestep must be larger than 1 in CSVR thermostat.");
         CSVRThermostat {
@@ -164,9 +211,16 @@ impl CSVRThermostat {
             tau: tau,
             rng: rng,
             normal: Normal::new(0.0, 1.0).expect("bad normal distribution"),
+            scale_factor: 1.0,
         }
     }
 
+    /// Get the scaling factor applied to the velocities during the last
+    /// step. This is 1 before the first step.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
     /// Get the sum of n independent gaussian noises squared, i.e. the Wiener
     /// noise in equation 4 of Bussi2007.
     ///
@@ -207,6 +261,24 @@ impl Thermostat for CSVRThermostat {
         for velocity in system.particles_mut().velocity {
             *velocity *= alpha;
         }
+        self.scale_factor = alpha;
+    }
+
+    fn state(&self) -> Vec<(&'static str, f64)> {
+        vec![("thermostat_scale", self.scale_factor)]
+    }
+
+    fn save_checkpoint(&self) -> CheckpointState {
+        let mut state = CheckpointState::new();
+        state.set_rng("rng", &*self.rng);
+        state.set("scale_factor", &self.scale_factor);
+        return state;
+    }
+
+    fn load_checkpoint(&mut self, state: &CheckpointState) -> Result<(), String> {
+        state.load_rng("rng", &mut *self.rng)?;
+        self.scale_factor = state.get("scale_factor")?;
+        Ok(())
     }
 }
 
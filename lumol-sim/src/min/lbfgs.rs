@@ -0,0 +1,242 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::collections::VecDeque;
+use std::f64;
+
+use soa_derive::soa_zip;
+
+use lumol_core::{units, System, DegreesOfFreedom, Vector3D};
+use super::{Minimizer, Tolerance};
+
+/// Limited-memory Broyden–Fletcher–Goldfarb–Shanno minimization algorithm.
+///
+/// This quasi-Newton method builds an approximation of the inverse Hessian
+/// from the positions and forces at the last few steps, and uses it to find a
+/// better descent direction than the forces. The step length along this
+/// direction is found with a backtracking line search. It usually converges
+/// much faster than the [steepest descent](struct.SteepestDescent.html) close
+/// to a minimum.
+///
+/// [1] J. Nocedal, Math. Comput. 35, 773 (1980); doi: 10.2307/2006193
+pub struct LBFGS {
+    /// Number of previous steps used to approximate the inverse Hessian
+    memory: usize,
+    /// Maximal displacement of a single particle in one step
+    max_displacement: f64,
+    /// Differences of positions between successive steps, most recent last
+    positions_diff: VecDeque<Vec<Vector3D>>,
+    /// Differences of energy gradients between successive steps, most recent
+    /// last
+    gradients_diff: VecDeque<Vec<Vector3D>>,
+}
+
+impl LBFGS {
+    /// Create a new `LBFGS` minimizer, using the last 10 steps to approximate
+    /// the inverse Hessian.
+    pub fn new() -> LBFGS {
+        LBFGS::with_memory(10)
+    }
+
+    /// Create a new `LBFGS` minimizer, using the last `memory` steps to
+    /// approximate the inverse Hessian.
+    pub fn with_memory(memory: usize) -> LBFGS {
+        assert!(memory > 0, "L-BFGS memory must be at least 1");
+        LBFGS {
+            memory: memory,
+            max_displacement: units::from(0.2, "A").expect("bad unit"),
+            positions_diff: VecDeque::with_capacity(memory),
+            gradients_diff: VecDeque::with_capacity(memory),
+        }
+    }
+
+    /// Set the maximal displacement of a single particle in one step of the
+    /// minimization. The default value is 0.2 A.
+    pub fn set_max_displacement(&mut self, max_displacement: f64) {
+        assert!(max_displacement > 0.0, "the maximal displacement must be positive");
+        self.max_displacement = max_displacement;
+    }
+
+    /// Forget all the previous steps
+    fn reset(&mut self) {
+        self.positions_diff.clear();
+        self.gradients_diff.clear();
+    }
+
+    /// Get the search direction using the L-BFGS two-loop recursion, from the
+    /// current `forces` (*i.e.* the opposite of the energy gradient).
+    fn direction(&self, forces: &[Vector3D]) -> Vec<Vector3D> {
+        let mut direction = forces.to_vec();
+        let mut alphas = Vec::with_capacity(self.positions_diff.len());
+        for (s, y) in self.positions_diff.iter().zip(&self.gradients_diff).rev() {
+            let rho = 1.0 / dot(y, s);
+            let alpha = rho * dot(s, &direction);
+            for (d, y) in direction.iter_mut().zip(y) {
+                *d -= alpha * y;
+            }
+            alphas.push((rho, alpha));
+        }
+
+        // Scale the initial inverse Hessian with the most recent step
+        if let (Some(s), Some(y)) = (self.positions_diff.back(), self.gradients_diff.back()) {
+            let gamma = dot(s, y) / dot(y, y);
+            for d in &mut direction {
+                *d *= gamma;
+            }
+        }
+
+        let pairs = self.positions_diff.iter().zip(&self.gradients_diff);
+        for ((s, y), &(rho, alpha)) in pairs.zip(alphas.iter().rev()) {
+            let beta = rho * dot(y, &direction);
+            for (d, s) in direction.iter_mut().zip(s) {
+                *d += (alpha - beta) * s;
+            }
+        }
+
+        return direction;
+    }
+}
+
+impl Minimizer for LBFGS {
+    fn setup(&mut self, _: &System) {
+        self.reset();
+    }
+
+    fn degrees_of_freedom(&self, _: &System) -> DegreesOfFreedom {
+        DegreesOfFreedom::Particles
+    }
+
+    fn minimize(&mut self, system: &mut System) -> Tolerance {
+        let initial_positions = system.particles().position.to_vec();
+        let initial_forces = system.forces();
+        let initial_energy = system.potential_energy();
+
+        let mut direction = self.direction(&initial_forces);
+        let mut slope = -dot(&initial_forces, &direction);
+        if slope >= 0.0 {
+            // The approximated inverse Hessian is not positive definite
+            // anymore, restart from the steepest descent direction.
+            self.reset();
+            direction = initial_forces.clone();
+            slope = -dot(&initial_forces, &direction);
+        }
+
+        let largest = direction.iter().map(|d| d.norm()).fold(0.0, f64::max);
+        if largest > self.max_displacement {
+            let factor = self.max_displacement / largest;
+            for d in &mut direction {
+                *d *= factor;
+            }
+            slope *= factor;
+        }
+
+        // Backtracking line search, using the Armijo condition
+        let mut step = 1.0;
+        let mut energy;
+        loop {
+            for (position, initial, d) in soa_zip!(system.particles_mut(), [mut position], &initial_positions, &direction) {
+                *position = initial + step * d;
+            }
+
+            energy = system.potential_energy();
+            if energy <= initial_energy + 1e-4 * step * slope {
+                break;
+            }
+
+            step /= 2.0;
+            if step < 1e-10 {
+                // No lower energy along this direction, go back to the
+                // initial configuration and restart from scratch
+                for (position, initial) in soa_zip!(system.particles_mut(), [mut position], &initial_positions) {
+                    *position = *initial;
+                }
+                self.reset();
+                return Tolerance {
+                    energy: initial_energy,
+                    force2: initial_forces.iter().map(|f| f.norm2()).fold(0.0, f64::max),
+                };
+            }
+        }
+
+        let forces = system.forces();
+        let positions_diff = direction.iter().map(|&d| step * d).collect::<Vec<_>>();
+        let gradients_diff = initial_forces.iter().zip(&forces).map(|(&f0, &f1)| f0 - f1).collect::<Vec<_>>();
+        if dot(&positions_diff, &gradients_diff) > 0.0 {
+            if self.positions_diff.len() == self.memory {
+                let _ = self.positions_diff.pop_front();
+                let _ = self.gradients_diff.pop_front();
+            }
+            self.positions_diff.push_back(positions_diff);
+            self.gradients_diff.push_back(gradients_diff);
+        }
+
+        return Tolerance {
+            energy: energy,
+            force2: forces.iter().map(|f| f.norm2()).fold(0.0, f64::max),
+        };
+    }
+}
+
+/// Get the scalar product of `a` and `b`, considered as vectors with `3N`
+/// components
+fn dot(a: &[Vector3D], b: &[Vector3D]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Harmonic, LennardJones, PairInteraction};
+    use lumol_core::{System, UnitCell, Molecule, Particle};
+
+    use crate::min::Minimization;
+
+    use approx::assert_relative_eq;
+
+    /// Get a tolerance using only the force criterion
+    fn tolerance(force2: f64) -> Tolerance {
+        Tolerance {
+            energy: 0.0,
+            force2: force2,
+        }
+    }
+
+    #[test]
+    fn harmonic_dimer() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.1, 2.0].into())));
+
+        let pair = PairInteraction::new(Box::new(Harmonic { x0: 2.3, k: 0.1 }), 10.0);
+        system.set_pair_potential(("Cl", "Cl"), pair);
+
+        let mut minimization = Minimization::new(Box::new(LBFGS::new()), tolerance(1e-20));
+        let (iterations, force) = minimization.run(&mut system, 100);
+        assert!(minimization.converged());
+        assert!(iterations < 20);
+        assert!(force < 1e-10);
+        assert_relative_eq!(system.distance(0, 1), 2.3, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn lennard_jones_trimer() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [3.6, 0.2, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [1.5, 3.3, 0.4].into())));
+
+        let lj = LennardJones { sigma: 3.4, epsilon: 1.0 };
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 9.0));
+
+        let mut minimization = Minimization::new(Box::new(LBFGS::with_memory(5)), tolerance(1e-12));
+        let (_, force) = minimization.run(&mut system, 200);
+        assert!(minimization.converged());
+        assert!(force < 1e-6);
+
+        // The minimum is an equilateral triangle
+        let r_min = f64::powf(2.0, 1.0 / 6.0) * 3.4;
+        assert_relative_eq!(system.distance(0, 1), r_min, epsilon = 1e-6);
+        assert_relative_eq!(system.distance(0, 2), r_min, epsilon = 1e-6);
+        assert_relative_eq!(system.distance(1, 2), r_min, epsilon = 1e-6);
+    }
+}
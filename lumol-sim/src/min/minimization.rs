@@ -46,6 +46,7 @@ pub struct Minimization {
     minimizer: Box<dyn Minimizer>,
     is_converged: bool,
     last_energy: f64,
+    last_force2: f64,
     tolerance: Tolerance,
 }
 
@@ -57,6 +58,7 @@ impl Minimization {
             minimizer: minimizer,
             is_converged: false,
             last_energy: 0.0,
+            last_force2: f64::NAN,
             tolerance: tolerance,
         }
     }
@@ -65,6 +67,40 @@ impl Minimization {
     pub fn converged(&self) -> bool {
         self.is_converged
     }
+
+    /// Minimize the energy of the `system`, until the minimization converges
+    /// or for at most `max_iterations` iterations. This returns the number of
+    /// iterations performed, and the maximal norm of the atomic force at the
+    /// last iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, Molecule, Particle, UnitCell};
+    /// # use lumol_core::energy::{Harmonic, PairInteraction};
+    /// # use lumol_sim::min::{Minimization, SteepestDescent, Tolerance};
+    /// let mut system = System::with_cell(UnitCell::cubic(20.0));
+    /// system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 0.0].into())));
+    /// system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 2.0].into())));
+    /// let harmonic = Harmonic { x0: 2.3, k: 0.1 };
+    /// system.set_pair_potential(("Cl", "Cl"), PairInteraction::new(Box::new(harmonic), 10.0));
+    ///
+    /// let tolerance = Tolerance { energy: 1e-10, force2: 1e-10 };
+    /// let mut minimization = Minimization::new(Box::new(SteepestDescent::new()), tolerance);
+    /// let (iterations, _) = minimization.run(&mut system, 100);
+    ///
+    /// assert!(minimization.converged());
+    /// assert!(iterations <= 100);
+    /// ```
+    pub fn run(&mut self, system: &mut System, max_iterations: usize) -> (usize, f64) {
+        self.setup(system);
+        let mut iterations = 0;
+        while !self.is_converged && iterations < max_iterations {
+            self.propagate(system);
+            iterations += 1;
+        }
+        return (iterations, f64::sqrt(self.last_force2));
+    }
 }
 
 impl Propagator for Minimization {
@@ -79,6 +115,7 @@ impl Propagator for Minimization {
     fn setup(&mut self, system: &System) {
         self.is_converged = false;
         self.last_energy = system.potential_energy();
+        self.last_force2 = f64::NAN;
         self.minimizer.setup(system);
     }
 
@@ -100,5 +137,6 @@ impl Propagator for Minimization {
         }
 
         self.last_energy = result.energy;
+        self.last_force2 = result.force2;
     }
 }
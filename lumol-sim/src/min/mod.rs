@@ -10,3 +10,6 @@ pub use self::minimization::Tolerance;
 
 mod steepest_descent;
 pub use self::steepest_descent::SteepestDescent;
+
+mod lbfgs;
+pub use self::lbfgs::LBFGS;
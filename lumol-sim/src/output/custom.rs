@@ -1,6 +1,7 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
 
+use std::collections::BTreeMap;
 use std::error;
 use std::fmt;
 use std::fs::File;
@@ -129,7 +130,11 @@ impl FormatArgs {
         })
     }
 
-    fn get_context<'a>(&self, system: &'a System) -> Context<'a> {
+    fn get_context<'a>(
+        &self,
+        system: &'a System,
+        propagator_state: &'a BTreeMap<&'static str, f64>,
+    ) -> Context<'a> {
         let mut context = Context::new();
         context.set_query(move |name| {
             // Get unit conversion factor firsts
@@ -187,7 +192,7 @@ impl FormatArgs {
                         "stress.xy" => Some(system.stress()[0][1]),
                         "stress.xz" => Some(system.stress()[0][2]),
                         "stress.yz" => Some(system.stress()[1][2]),
-                        _ => None,
+                        name => propagator_state.get(name).cloned(),
                     }
                 }
             })
@@ -196,8 +201,12 @@ impl FormatArgs {
         return context;
     }
 
-    fn format(&self, system: &System) -> Result<String, CustomOutputError> {
-        let context = self.get_context(system);
+    fn format(
+        &self,
+        system: &System,
+        propagator_state: &BTreeMap<&'static str, f64>,
+    ) -> Result<String, CustomOutputError> {
+        let context = self.get_context(system, propagator_state);
         let mut output = String::new();
         for &(ref string, ref expr) in &self.args {
             output.push_str(string);
@@ -262,11 +271,15 @@ fn parse_index(input: &str) -> (&str, usize) {
 /// - Unit Cell properties: `cell.a`, `cell.b`, `cell.c` are the unit cell
 ///   vector lengths; `cell.alpha`, `cell.beta` and `cell.gamma` are the unit
 ///   cell angles.
+/// - Propagator state: the internal variables of the propagator, such as
+///   `thermostat_scale` for the velocity scaling factor of Berendsen and CSVR
+///   thermostats or `barostat_scale` for the Berendsen barostat.
 pub struct CustomOutput {
     file: BufWriter<File>,
     path: PathBuf,
     template: String,
     args: FormatArgs,
+    propagator_state: BTreeMap<&'static str, f64>,
 }
 
 impl CustomOutput {
@@ -282,6 +295,7 @@ impl CustomOutput {
             path: filename.as_ref().to_owned(),
             template: template.into(),
             args: FormatArgs::new(template)?,
+            propagator_state: BTreeMap::new(),
         })
     }
 }
@@ -292,8 +306,12 @@ impl Output for CustomOutput {
         writeln_or_log!(self, "# {}", self.template);
     }
 
+    fn set_propagator_state(&mut self, state: &BTreeMap<&'static str, f64>) {
+        self.propagator_state.clone_from(state);
+    }
+
     fn write(&mut self, system: &System) {
-        if let Ok(formatted) = self.args.format(system) {
+        if let Ok(formatted) = self.args.format(system, &self.propagator_state) {
             writeln_or_log!(self, "{}", formatted);
         } else {
             error_once!("Could not evaluate custom output {}", self.template);
@@ -307,7 +325,7 @@ mod tests {
     use super::super::tests::{test_output, testing_system};
 
     fn format(input: &str) -> String {
-        FormatArgs::new(input).unwrap().format(&testing_system()).unwrap()
+        FormatArgs::new(input).unwrap().format(&testing_system(), &BTreeMap::new()).unwrap()
     }
 
     #[test]
@@ -365,6 +383,17 @@ mod tests {
         assert_eq!(format("{step}"), "42");
     }
 
+    #[test]
+    fn propagator_state() {
+        let system = testing_system();
+        let mut state = BTreeMap::new();
+        let _ = state.insert("thermostat_scale", 0.5);
+        let args = FormatArgs::new("{2 * thermostat_scale}").unwrap();
+        assert_eq!(args.format(&system, &state).unwrap(), "1");
+        let args = FormatArgs::new("{barostat_scale}").unwrap();
+        assert!(args.format(&system, &state).is_err());
+    }
+
     #[test]
     fn custom() {
         let template = "p {pressure/bar} t {3 * 5} \tff";
@@ -2,6 +2,7 @@
 // Copyright (C) Lumol's contributors — BSD license
 
 //! Saving properties of a system during a simulation
+use std::collections::BTreeMap;
 
 use lumol_core::System;
 
@@ -13,6 +14,12 @@ pub trait Output {
     /// for some setup of the output if needed.
     fn setup(&mut self, _: &System) {}
 
+    /// Update the internal variables of the propagator (thermostat scaling
+    /// factor, barostat scaling factor, *etc.*) indexed by name. This function
+    /// is called by the simulation before `setup` and before every call to
+    /// `write`. The default is to ignore these variables.
+    fn set_propagator_state(&mut self, _: &BTreeMap<&'static str, f64>) {}
+
     /// Write the output from the system.
     fn write(&mut self, system: &System);
 
@@ -51,3 +58,6 @@ pub use self::properties::PropertiesOutput;
 
 mod trajectory;
 pub use self::trajectory::TrajectoryOutput;
+
+mod xyz;
+pub use self::xyz::XyzOutput;
@@ -1,6 +1,7 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
 
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, BufWriter};
 use std::io::prelude::*;
@@ -19,9 +20,14 @@ use lumol_core::units;
 /// - volume of the unit cell;
 /// - instant temperature;
 /// - instant pressure;
+///
+/// The internal variables of the propagator (for example the scaling factor of
+/// a Berendsen thermostat, see `Output::set_propagator_state`) are written
+/// as additional columns.
 pub struct PropertiesOutput {
     file: BufWriter<File>,
     path: PathBuf,
+    propagator_state: BTreeMap<&'static str, f64>,
 }
 
 impl PropertiesOutput {
@@ -31,28 +37,42 @@ impl PropertiesOutput {
         Ok(PropertiesOutput {
             file: BufWriter::new(File::create(filename.as_ref())?),
             path: filename.as_ref().to_owned(),
+            propagator_state: BTreeMap::new(),
         })
     }
 }
 
 impl Output for PropertiesOutput {
-    fn setup(&mut self, _: &System) {
+    fn setup(&mut self, system: &System) {
+        let mut header = String::from("# Step Volume/A^3 Temperature/K Pressure/bar");
+        for name in self.propagator_state.keys() {
+            header.push(' ');
+            header.push_str(name);
+        }
         writeln_or_log!(self, "# Physical properties of the simulation");
-        writeln_or_log!(self, "# Step Volume/A^3 Temperature/K Pressure/bar");
+        writeln_or_log!(self, "{}", header);
+    }
+
+    fn set_propagator_state(&mut self, state: &BTreeMap<&'static str, f64>) {
+        self.propagator_state.clone_from(state);
     }
 
     fn write(&mut self, system: &System) {
         let volume = units::to(system.volume(), "A^3").expect("bad unit");
         let temperature = units::to(system.temperature(), "K").expect("bad unit");
         let pressure = units::to(system.pressure(), "bar").expect("bad unit");
-        writeln_or_log!(self, "{} {} {} {}", system.step, volume, temperature, pressure);
+        let mut line = format!("{} {} {} {}", system.step, volume, temperature, pressure);
+        for value in self.propagator_state.values() {
+            line.push_str(&format!(" {}", value));
+        }
+        writeln_or_log!(self, "{}", line);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::tests::test_output;
+    use super::super::tests::{test_output, testing_system};
 
     #[test]
     fn properties() {
@@ -64,4 +84,25 @@ mod tests {
             ",
         );
     }
+
+    #[test]
+    fn propagator_state() {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        let system = testing_system();
+        let mut state = BTreeMap::new();
+        let _ = state.insert("thermostat_scale", 0.5);
+        {
+            let mut output = PropertiesOutput::new(tempfile.path()).unwrap();
+            output.set_propagator_state(&state);
+            output.setup(&system);
+            output.write(&system);
+            output.finish(&system);
+        }
+
+        let mut content = String::new();
+        let _ = tempfile.reopen().unwrap().read_to_string(&mut content).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "# Step Volume/A^3 Temperature/K Pressure/bar thermostat_scale");
+        assert!(lines[2].ends_with(" 0.5"));
+    }
 }
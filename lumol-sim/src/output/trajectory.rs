@@ -65,7 +65,8 @@ impl Output for TrajectoryOutput {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::tests::test_output;
+    use super::super::tests::{test_output, testing_system};
+    use lumol_core::Vector3D;
 
     #[test]
     fn cell() {
@@ -78,4 +79,28 @@ mod tests {
             ",
         );
     }
+
+    #[test]
+    fn round_trip() {
+        let tempfile = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
+        let mut system = testing_system();
+        {
+            let mut output = TrajectoryOutput::new(tempfile.path()).unwrap();
+            output.write(&system);
+            system.particles_mut().position[1] = Vector3D::new(1.5, 0.2, -0.7);
+            output.write(&system);
+        }
+
+        let mut trajectory = TrajectoryBuilder::new().open(tempfile.path()).unwrap();
+        let first = trajectory.read().unwrap();
+        assert_eq!(first.size(), 2);
+        assert_eq!(first.particles().name[0], "F");
+        assert_eq!(first.particles().name[1], "F");
+        assert_eq!(first.particles().position[0], Vector3D::new(0.0, 0.0, 0.0));
+        assert_eq!(first.particles().position[1], Vector3D::new(1.3, 0.0, 0.0));
+
+        let second = trajectory.read().unwrap();
+        assert_eq!(second.size(), 2);
+        assert_eq!(second.particles().position[1], Vector3D::new(1.5, 0.2, -0.7));
+    }
 }
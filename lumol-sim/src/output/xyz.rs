@@ -0,0 +1,87 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use log::error;
+
+use super::Output;
+use lumol_core::System;
+
+/// The `XyzOutput` writes the trajectory of the system to a file in the
+/// extended XYZ format, adding one frame to the file every time it is called.
+/// Contrary to the XYZ files written by [`TrajectoryOutput`], these files
+/// contain the unit cell in the `Lattice` field of each frame, and can be read
+/// back with `System::read_xyz`.
+///
+/// [`TrajectoryOutput`]: struct.TrajectoryOutput.html
+pub struct XyzOutput {
+    file: BufWriter<File>,
+    path: PathBuf,
+}
+
+impl XyzOutput {
+    /// Create a new `XyzOutput` writing to `filename`. The file is replaced if
+    /// it already exists.
+    pub fn new<P: AsRef<Path>>(filename: P) -> Result<XyzOutput, io::Error> {
+        Ok(XyzOutput {
+            file: BufWriter::new(File::create(filename.as_ref())?),
+            path: filename.as_ref().to_owned(),
+        })
+    }
+}
+
+impl Output for XyzOutput {
+    fn write(&mut self, system: &System) {
+        if let Err(err) = system.write_xyz(&mut self.file) {
+            error!("could not write to file '{}': {}", self.path.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{test_output, testing_system};
+    use lumol_core::{UnitCell, Vector3D};
+    use std::io::BufReader;
+
+    #[test]
+    fn xyz() {
+        test_output(
+            |path| Box::new(XyzOutput::new(path).unwrap()),
+            "2
+            Lattice=\"10.0 0.0 0.0 0.0 10.0 0.0 0.0 0.0 10.0\" Properties=species:S:1:pos:R:3
+            F 0.0 0.0 0.0
+            F 1.3 0.0 0.0
+            ",
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        let mut system = testing_system();
+        {
+            let mut output = XyzOutput::new(tempfile.path()).unwrap();
+            output.write(&system);
+            system.particles_mut().position[1] = Vector3D::new(1.5, 0.2, -0.7);
+            system.cell = UnitCell::triclinic(10.0, 11.0, 12.5, 90.0, 80.0, 100.0);
+            output.write(&system);
+        }
+
+        let mut file = BufReader::new(tempfile.reopen().unwrap());
+        let first = System::read_xyz(&mut file).unwrap().unwrap();
+        assert_eq!(first.size(), 2);
+        assert_eq!(first.cell, testing_system().cell);
+        assert_eq!(first.particles().name, testing_system().particles().name);
+        assert_eq!(first.particles().position, testing_system().particles().position);
+
+        let second = System::read_xyz(&mut file).unwrap().unwrap();
+        assert_eq!(second.cell, system.cell);
+        assert_eq!(second.particles().position[1], Vector3D::new(1.5, 0.2, -0.7));
+        assert!(System::read_xyz(&mut file).unwrap().is_none());
+    }
+}
@@ -4,6 +4,8 @@
 //! A propagator is responsible for updating the system during a simulation
 use lumol_core::{System, DegreesOfFreedom};
 
+use crate::CheckpointState;
+
 /// Possible temperature computation strategies. Different propagators needs
 /// different ways to compute the temperature: Monte Carlo temperature is a
 /// constant of the simulation, whereas for molecular dynamics we use the
@@ -41,4 +43,28 @@ pub trait Propagator {
 
     /// Finish the simulation, and maybe output some information about it
     fn finish(&mut self, _: &System) {}
+
+    /// Get the internal variables of the propagator (thermostat and barostat
+    /// state, *etc.*) as pairs of name and value. This function is called
+    /// after every step, and the values are given to the outputs with
+    /// `Output::set_propagator_state`. The default is to report nothing.
+    fn state(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+
+    /// Save the internal state of the propagator (random number generators,
+    /// thermostat variables, *etc.*) needed to restart the simulation from a
+    /// checkpoint and get the same trajectory. The default is to save
+    /// nothing.
+    fn save_checkpoint(&self) -> CheckpointState {
+        CheckpointState::new()
+    }
+
+    /// Restore the internal state of the propagator from the `state` created
+    /// by `save_checkpoint`, or return an error message if the state is
+    /// invalid. This function is called before `setup`. The default is to
+    /// restore nothing.
+    fn load_checkpoint(&mut self, _: &CheckpointState) -> Result<(), String> {
+        Ok(())
+    }
 }
@@ -0,0 +1,77 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Seedable source of random number generators for reproducible simulations
+use rand::{RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use crate::CheckpointRng;
+
+/// A seedable source of random number generators.
+///
+/// All the stochastic algorithms in a simulation (velocities initialization,
+/// stochastic thermostats, Monte Carlo moves, ...) should use their own
+/// random number generator, so that the random streams do not depend on the
+/// order in which the algorithms are called. An `Rng` is created once from a
+/// single seed, and then used to create the independent generators given to
+/// the different algorithms. The whole simulation is then reproducible from
+/// this single seed.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_sim::{Rng, BoltzmannVelocities};
+/// # use lumol_sim::md::{MolecularDynamics, CSVRThermostat};
+/// # use lumol_sim::mc::MonteCarloBuilder;
+/// let mut rng = Rng::new(129);
+///
+/// let velocities = BoltzmannVelocities::from_rng(300.0, rng.split());
+///
+/// let mut md = MolecularDynamics::new(1.0);
+/// md.set_thermostat(Box::new(CSVRThermostat::from_rng(300.0, 10.0, rng.split())));
+///
+/// let mc = MonteCarloBuilder::from_rng(300.0, rng.split());
+/// ```
+pub struct Rng {
+    seeder: XorShiftRng,
+}
+
+impl Rng {
+    /// Create a new `Rng` using the given `seed`.
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            seeder: XorShiftRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Create a new random number generator, seeded from this `Rng`. The
+    /// sequence of generators returned by successive calls to this function
+    /// only depends on the seed used to create this `Rng`.
+    pub fn split(&mut self) -> Box<dyn CheckpointRng> {
+        return Box::new(XorShiftRng::seed_from_u64(self.seeder.next_u64()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence<R: RngCore + ?Sized>(rng: &mut R) -> Vec<u64> {
+        (0..10).map(|_| rng.next_u64()).collect()
+    }
+
+    #[test]
+    fn split() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+        let a = sequence(&mut *first.split());
+        let b = sequence(&mut *first.split());
+        assert_ne!(a, b);
+
+        assert_eq!(sequence(&mut *second.split()), a);
+        assert_eq!(sequence(&mut *second.split()), b);
+
+        let mut other = Rng::new(43);
+        assert_ne!(sequence(&mut *other.split()), a);
+    }
+}
@@ -1,11 +1,28 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use log::{info, warn};
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
 
-use lumol_core::{System, DegreesOfFreedom, Vector3D};
+use lumol_core::{System, SystemCheckpoint, DegreesOfFreedom, Vector3D};
 
 use crate::output::Output;
 use crate::propagator::{Propagator, TemperatureStrategy};
+use crate::CheckpointState;
+
+/// Content of the checkpoint files written by `Simulation::save_checkpoint`
+#[derive(Serialize, Deserialize)]
+struct SimulationCheckpoint {
+    system: SystemCheckpoint,
+    propagator: CheckpointState,
+}
 
 /// Writing an output at a given frequency
 struct OutputFrequency {
@@ -37,6 +54,10 @@ impl Output for OutputFrequency {
         self.output.setup(system);
     }
 
+    fn set_propagator_state(&mut self, state: &BTreeMap<&'static str, f64>) {
+        self.output.set_propagator_state(state);
+    }
+
     fn write(&mut self, system: &System) {
         if system.step % self.frequency == 0 {
             self.output.write(system);
@@ -48,12 +69,44 @@ impl Output for OutputFrequency {
     }
 }
 
+/// Progress of a running simulation, as given to the progress callback set
+/// with [`Simulation::set_progress_callback`].
+///
+/// [`Simulation::set_progress_callback`]: struct.Simulation.html#method.set_progress_callback
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// Number of steps completed in the current run
+    pub step: usize,
+    /// Total number of steps in the current run, or `None` when running
+    /// until a condition is met with [`Simulation::run_until`]
+    ///
+    /// [`Simulation::run_until`]: struct.Simulation.html#method.run_until
+    pub nsteps: Option<usize>,
+    /// Average number of steps per second since the start of the run
+    pub steps_per_second: f64,
+    /// Time elapsed since the start of the run
+    pub elapsed: Duration,
+    /// Estimated time remaining before the end of the run, or `None` if the
+    /// total number of steps is not known
+    pub remaining: Option<Duration>,
+}
+
+/// Calling a progress callback at a given frequency
+struct ProgressFrequency {
+    /// The callback to use
+    callback: Box<dyn FnMut(&Progress)>,
+    /// The callback is called every time this number of steps is completed
+    frequency: u64,
+}
+
 /// The Simulation struct holds all the needed algorithms for running the
 /// simulation. It should be use together with a `System` to perform the
 /// simulation.
 pub struct Simulation {
     propagator: Box<dyn Propagator>,
     outputs: Vec<OutputFrequency>,
+    progress: Option<ProgressFrequency>,
+    thread_pool: Option<Arc<ThreadPool>>,
 }
 
 impl Simulation {
@@ -62,11 +115,59 @@ impl Simulation {
         Simulation {
             propagator: propagator,
             outputs: Vec::new(),
+            progress: None,
+            thread_pool: None,
         }
     }
 
     /// Run the simulation on System for `nsteps` steps.
     pub fn run(&mut self, system: &mut System, nsteps: usize) {
+        let _ = self.run_steps(system, Some(nsteps), |_, _| false);
+    }
+
+    /// Run the simulation on System until the `predicate` returns `true`, and
+    /// return the number of steps performed.
+    ///
+    /// The `predicate` is called with the system and the number of steps
+    /// already performed in this run, before every step. It can check for
+    /// the convergence of some property (temperature, maximal force, *etc.*)
+    /// and should also include a cap on the number of steps if convergence
+    /// is not guaranteed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, Molecule, Particle, UnitCell};
+    /// # use lumol_core::energy::{Harmonic, PairInteraction};
+    /// # use lumol_sim::Simulation;
+    /// # use lumol_sim::md::MolecularDynamics;
+    /// let mut system = System::with_cell(UnitCell::cubic(20.0));
+    /// system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 0.0].into())));
+    /// system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 2.0].into())));
+    /// let harmonic = Harmonic { x0: 2.3, k: 0.1 };
+    /// system.set_pair_potential(("Cl", "Cl"), PairInteraction::new(Box::new(harmonic), 10.0));
+    ///
+    /// let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+    /// // Run until the molecules are further than the equilibrium distance,
+    /// // or for at most 1000 steps.
+    /// let steps = simulation.run_until(&mut system, |system, step| {
+    ///     system.distance(0, 1) > 2.3 || step >= 1000
+    /// });
+    /// assert!(steps < 1000);
+    /// ```
+    pub fn run_until<F>(&mut self, system: &mut System, predicate: F) -> usize
+    where
+        F: Fn(&System, usize) -> bool,
+    {
+        return self.run_steps(system, None, predicate);
+    }
+
+    /// Run the simulation for at most `nsteps` steps, stopping earlier if
+    /// `stop` returns `true`. This returns the number of steps performed.
+    fn run_steps<F>(&mut self, system: &mut System, nsteps: Option<usize>, stop: F) -> usize
+    where
+        F: Fn(&System, usize) -> bool,
+    {
         match self.propagator.temperature_strategy() {
             TemperatureStrategy::External(temperature) => {
                 system.simulated_temperature(Some(temperature))
@@ -80,22 +181,182 @@ impl Simulation {
             DegreesOfFreedom::Molecules => info!("All molecules are treated as rigid bodies"),
             DegreesOfFreedom::Particles => info!("All particles are allowed to move in this simulation"),
             DegreesOfFreedom::Frozen(n) => info!("{} degrees of freedom are frozen", n),
+            DegreesOfFreedom::FrozenMolecules(n) => {
+                info!("All molecules are treated as rigid bodies");
+                info!("{} degrees of freedom are frozen", n);
+            }
         }
 
+        let previous_pool = match self.thread_pool {
+            Some(ref pool) => Some(system.set_thread_pool(Some(Arc::clone(pool)))),
+            None => None,
+        };
+
         system.check();
         self.setup(system);
-        for i in 0..nsteps {
+        let start = Instant::now();
+        let mut i = 0;
+        while nsteps.map_or(true, |nsteps| i < nsteps) && !stop(system, i) {
             self.propagator.propagate(system);
             system.step += 1;
+            let state = self.propagator_state();
             for output in &mut self.outputs {
+                output.set_propagator_state(&state);
                 output.write(system);
             }
 
+            if let Some(ref mut progress) = self.progress {
+                let step = i + 1;
+                if step as u64 % progress.frequency == 0 {
+                    let elapsed = start.elapsed();
+                    let seconds = elapsed.as_secs_f64();
+                    let remaining = nsteps.map(|nsteps| {
+                        Duration::from_secs_f64(seconds * (nsteps - step) as f64 / step as f64)
+                    });
+                    (progress.callback)(&Progress {
+                        step: step,
+                        nsteps: nsteps,
+                        steps_per_second: step as f64 / seconds,
+                        elapsed: elapsed,
+                        remaining: remaining,
+                    });
+                }
+            }
+
             if i % 10_000 == 0 {
                 self.sanity_check(system);
             }
+            i += 1;
         }
         self.finish(system);
+
+        if let Some(previous) = previous_pool {
+            let _ = system.set_thread_pool(previous);
+        }
+        return i;
+    }
+
+    /// Set a `callback` reporting the progress of the simulation, called
+    /// every time `frequency` steps are completed during a run. This replaces
+    /// any previously set callback.
+    ///
+    /// # Panics
+    ///
+    /// If `frequency` is zero
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_sim::{Simulation, Progress};
+    /// # use lumol_sim::md::MolecularDynamics;
+    /// let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+    /// simulation.set_progress_callback(1000, Box::new(|progress: &Progress| {
+    ///     if let (Some(nsteps), Some(remaining)) = (progress.nsteps, progress.remaining) {
+    ///         println!(
+    ///             "step {}/{}, {:.1} steps/s, {} s remaining",
+    ///             progress.step, nsteps, progress.steps_per_second, remaining.as_secs()
+    ///         );
+    ///     }
+    /// }));
+    /// ```
+    pub fn set_progress_callback(&mut self, frequency: u64, callback: Box<dyn FnMut(&Progress)>) {
+        assert!(frequency > 0, "the frequency of the progress callback must be positive");
+        self.progress = Some(ProgressFrequency {
+            callback: callback,
+            frequency: frequency,
+        });
+    }
+
+    /// Use a dedicated pool of `threads` threads for the parallel computations
+    /// in this simulation, instead of the global rayon thread pool. This is
+    /// useful to limit the parallelism when running multiple simulations at
+    /// the same time.
+    ///
+    /// By default, the thread pool of the system is used, which is the global
+    /// rayon thread pool unless set with `System::set_thread_pool`. The
+    /// number of threads in the global pool can be set with the
+    /// `RAYON_NUM_THREADS` environment variable.
+    ///
+    /// # Panics
+    ///
+    /// If `threads` is zero, or if the thread pool can not be created
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_sim::Simulation;
+    /// # use lumol_sim::md::MolecularDynamics;
+    /// let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+    /// simulation.set_threads(2);
+    /// ```
+    pub fn set_threads(&mut self, threads: usize) {
+        assert!(threads > 0, "the number of threads must be positive");
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to create the thread pool");
+        self.thread_pool = Some(Arc::new(pool));
+    }
+
+    /// Save the `system` and the internal state of the propagator (random
+    /// number generators, thermostat variables, Monte Carlo moves amplitudes,
+    /// *etc.*) in a JSON checkpoint file at `path`. The file is replaced if it
+    /// already exists.
+    ///
+    /// Restarting a simulation from this checkpoint with `load_checkpoint`
+    /// gives the same trajectory as continuing this simulation. Saving the
+    /// checkpoint does not modify the simulation.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::System;
+    /// # use lumol_sim::Simulation;
+    /// # use lumol_sim::md::MolecularDynamics;
+    /// let mut system = System::new();
+    /// let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+    /// simulation.run(&mut system, 1000);
+    /// simulation.save_checkpoint(&system, "checkpoint.json").unwrap();
+    /// ```
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, system: &System, path: P) -> io::Result<()> {
+        let checkpoint = SimulationCheckpoint {
+            system: system.checkpoint(),
+            propagator: self.propagator.save_checkpoint(),
+        };
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut file, &checkpoint)?;
+        return file.flush();
+    }
+
+    /// Restore the `system` and the internal state of the propagator from the
+    /// checkpoint file at `path`, created by `save_checkpoint`. The
+    /// interactions of the `system` are kept, and the propagator must be
+    /// configured as in the simulation which created the checkpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::System;
+    /// # use lumol_sim::Simulation;
+    /// # use lumol_sim::md::MolecularDynamics;
+    /// let mut system = System::new();
+    /// // set the interactions here
+    /// let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+    /// simulation.load_checkpoint(&mut system, "checkpoint.json").unwrap();
+    /// simulation.run(&mut system, 1000);
+    /// ```
+    pub fn load_checkpoint<P: AsRef<Path>>(
+        &mut self,
+        system: &mut System,
+        path: P,
+    ) -> io::Result<()> {
+        let file = BufReader::new(File::open(path)?);
+        let checkpoint: SimulationCheckpoint = serde_json::from_reader(file)?;
+        self.propagator.load_checkpoint(&checkpoint.propagator).map_err(|message| {
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        })?;
+        system.restore_checkpoint(checkpoint.system);
+        return Ok(());
     }
 
     /// Add a new `Output` algorithm in the outputs list
@@ -112,11 +373,18 @@ impl Simulation {
 
     fn setup(&mut self, system: &mut System) {
         self.propagator.setup(system);
+        let state = self.propagator_state();
         for output in &mut self.outputs {
+            output.set_propagator_state(&state);
             output.setup(system);
         }
     }
 
+    /// Get the internal variables of the propagator, indexed by name
+    fn propagator_state(&self) -> BTreeMap<&'static str, f64> {
+        self.propagator.state().into_iter().collect()
+    }
+
     fn finish(&mut self, system: &mut System) {
         self.propagator.finish(system);
         for output in &mut self.outputs {
@@ -157,3 +425,244 @@ impl Simulation {
 fn any<F: Fn(f64) -> bool>(vector: &Vector3D, function: F) -> bool {
     function(vector[0]) || function(vector[1]) || function(vector[2])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use lumol_core::{Molecule, Particle, UnitCell};
+    use lumol_core::energy::{Harmonic, PairInteraction, PairPotential, Potential};
+
+    use crate::mc::{MonteCarloBuilder, Translate};
+    use crate::min::{Minimization, SteepestDescent, Tolerance};
+
+    struct DoNothing;
+
+    impl Propagator for DoNothing {
+        fn temperature_strategy(&self) -> TemperatureStrategy {
+            TemperatureStrategy::None
+        }
+
+        fn degrees_of_freedom(&self, _: &System) -> DegreesOfFreedom {
+            DegreesOfFreedom::Particles
+        }
+
+        fn propagate(&mut self, _: &mut System) {}
+    }
+
+    /// Output counting the number of calls to its functions
+    #[derive(Clone, Default)]
+    struct CountingOutput {
+        setup: Rc<Cell<usize>>,
+        write: Rc<Cell<usize>>,
+        finish: Rc<Cell<usize>>,
+    }
+
+    impl Output for CountingOutput {
+        fn setup(&mut self, _: &System) {
+            self.setup.set(self.setup.get() + 1);
+        }
+
+        fn write(&mut self, _: &System) {
+            self.write.set(self.write.get() + 1);
+        }
+
+        fn finish(&mut self, _: &System) {
+            self.finish.set(self.finish.get() + 1);
+        }
+    }
+
+    #[test]
+    fn outputs_frequency() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+
+        let every_step = CountingOutput::default();
+        let every_seven = CountingOutput::default();
+
+        let mut simulation = Simulation::new(Box::new(DoNothing));
+        simulation.add_output(Box::new(every_step.clone()));
+        simulation.add_output_with_frequency(Box::new(every_seven.clone()), 7);
+        simulation.run(&mut system, 100);
+
+        assert_eq!(every_step.setup.get(), 1);
+        assert_eq!(every_step.write.get(), 100);
+        assert_eq!(every_step.finish.get(), 1);
+
+        assert_eq!(every_seven.setup.get(), 1);
+        assert_eq!(every_seven.write.get(), 100 / 7);
+        assert_eq!(every_seven.finish.get(), 1);
+    }
+
+    #[test]
+    fn run_until() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+
+        let output = CountingOutput::default();
+        let mut simulation = Simulation::new(Box::new(DoNothing));
+        simulation.add_output(Box::new(output.clone()));
+        let steps = simulation.run_until(&mut system, |_, step| step == 42);
+
+        assert_eq!(steps, 42);
+        assert_eq!(system.step, 42);
+        assert_eq!(output.setup.get(), 1);
+        assert_eq!(output.write.get(), 42);
+        assert_eq!(output.finish.get(), 1);
+
+        // The predicate is checked before the first step
+        let steps = simulation.run_until(&mut system, |_, _| true);
+        assert_eq!(steps, 0);
+        assert_eq!(system.step, 42);
+    }
+
+    #[test]
+    fn run_until_converged() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", [0.0, 0.0, 2.0].into())));
+        let harmonic = Harmonic { x0: 2.3, k: 0.1 };
+        system.set_pair_potential(("Cl", "Cl"), PairInteraction::new(Box::new(harmonic), 10.0));
+
+        // Use a very strict tolerance for the minimization itself, and rely on
+        // the predicate to stop the run
+        let tolerance = Tolerance { energy: 0.0, force2: 0.0 };
+        let minimization = Minimization::new(Box::new(SteepestDescent::new()), tolerance);
+        let mut simulation = Simulation::new(Box::new(minimization));
+
+        let max_force = 1e-5;
+        let steps = simulation.run_until(&mut system, |system, step| {
+            let forces = system.forces();
+            forces.iter().all(|force| force.norm() < max_force) || step >= 10_000
+        });
+
+        assert!(steps < 10_000);
+        assert!(system.forces().iter().all(|force| force.norm() < max_force));
+        assert!(f64::abs(system.distance(0, 1) - 2.3) < 1e-3);
+    }
+
+    /// Pair potential recording the number of threads used to compute it
+    #[derive(Clone)]
+    struct ThreadsPotential(Arc<AtomicUsize>);
+
+    impl Potential for ThreadsPotential {
+        fn energy(&self, _: f64) -> f64 {
+            self.0.store(rayon::current_num_threads(), Ordering::SeqCst);
+            0.0
+        }
+
+        fn force(&self, _: f64) -> f64 {
+            0.0
+        }
+    }
+
+    impl PairPotential for ThreadsPotential {
+        fn tail_energy(&self, _: f64) -> f64 {
+            0.0
+        }
+
+        fn tail_virial(&self, _: f64) -> f64 {
+            0.0
+        }
+    }
+
+    /// Propagator computing the potential energy of the system at every step
+    struct ComputeEnergy;
+
+    impl Propagator for ComputeEnergy {
+        fn temperature_strategy(&self) -> TemperatureStrategy {
+            TemperatureStrategy::None
+        }
+
+        fn degrees_of_freedom(&self, _: &System) -> DegreesOfFreedom {
+            DegreesOfFreedom::Particles
+        }
+
+        fn propagate(&mut self, system: &mut System) {
+            let _ = system.potential_energy();
+        }
+    }
+
+    #[test]
+    fn threads() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 2.0].into())));
+        let threads_used = Arc::new(AtomicUsize::new(0));
+        let potential = ThreadsPotential(Arc::clone(&threads_used));
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(potential), 10.0));
+
+        // Use a different number of threads than the global thread pool
+        let threads = rayon::current_num_threads() + 1;
+        let mut simulation = Simulation::new(Box::new(ComputeEnergy));
+        simulation.set_threads(threads);
+        simulation.run(&mut system, 10);
+        assert_eq!(threads_used.load(Ordering::SeqCst), threads);
+
+        // The thread pool of the system is restored after the run
+        assert!(system.set_thread_pool(None).is_none());
+        let _ = system.potential_energy();
+        assert_eq!(threads_used.load(Ordering::SeqCst), rayon::current_num_threads());
+    }
+
+    #[test]
+    fn threads_monte_carlo() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 2.0].into())));
+        let threads_used = Arc::new(AtomicUsize::new(0));
+        let potential = ThreadsPotential(Arc::clone(&threads_used));
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(potential), 10.0));
+
+        let mut builder = MonteCarloBuilder::new(300.0);
+        builder.add(Box::new(Translate::new(0.5, None)), 1.0, None);
+        let threads = rayon::current_num_threads() + 1;
+        let mut simulation = Simulation::new(Box::new(builder.finish()));
+        simulation.set_threads(threads);
+        // The last energy evaluation is the cost of the last Monte Carlo move
+        simulation.run(&mut system, 10);
+        assert_eq!(threads_used.load(Ordering::SeqCst), threads);
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of threads must be positive")]
+    fn zero_threads() {
+        let mut simulation = Simulation::new(Box::new(DoNothing));
+        simulation.set_threads(0);
+    }
+
+    #[test]
+    fn progress_callback() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let mut simulation = Simulation::new(Box::new(DoNothing));
+        let callback_reports = Rc::clone(&reports);
+        simulation.set_progress_callback(7, Box::new(move |progress: &Progress| {
+            callback_reports.borrow_mut().push(progress.clone());
+        }));
+        simulation.run(&mut system, 100);
+
+        let reports = reports.borrow();
+        assert_eq!(reports.len(), 100 / 7);
+        for (i, progress) in reports.iter().enumerate() {
+            assert_eq!(progress.step, 7 * (i + 1));
+            assert_eq!(progress.nsteps, Some(100));
+            assert!(progress.remaining.is_some());
+            assert!(progress.steps_per_second > 0.0);
+        }
+        assert!(reports.windows(2).all(|pair| pair[0].elapsed <= pair[1].elapsed));
+    }
+
+    #[test]
+    #[should_panic(expected = "the frequency of the progress callback must be positive")]
+    fn progress_callback_zero_frequency() {
+        let mut simulation = Simulation::new(Box::new(DoNothing));
+        simulation.set_progress_callback(0, Box::new(|_: &Progress| {}));
+    }
+}
@@ -4,12 +4,13 @@
 //! This module provides some ways to initialize the velocities in a `System`
 use rand_xorshift::XorShiftRng;
 use rand::SeedableRng;
-use rand_distr::{Normal, Uniform, Distribution};
+use rand_distr::{Normal, UnitSphere, Distribution};
 
 use lumol_core::consts::K_BOLTZMANN;
 use lumol_core::{System, Vector3D};
 
 use crate::md::{Control, RemoveRotation, RemoveTranslation};
+use crate::CheckpointRng;
 
 /// Scale all velocities in the `System` such that the `system` temperature
 /// is `temperature`.
@@ -30,26 +31,45 @@ pub trait InitVelocities {
 }
 
 /// Initialize the velocities from a Boltzmann distribution.
+///
+/// By default, the center-of-mass velocity is removed after sampling the
+/// velocities, to prevent the system from drifting during the simulation.
 pub struct BoltzmannVelocities {
     temperature: f64,
     dist: Normal<f64>,
-    rng: XorShiftRng,
+    rng: Box<dyn CheckpointRng>,
+    remove_com: bool,
 }
 
 impl BoltzmannVelocities {
     /// Create a new `BoltzmannVelocities` at the given `temperature`.
     pub fn new(temperature: f64) -> BoltzmannVelocities {
+        let rng = Box::new(XorShiftRng::from_seed([
+            0xeb, 0xa8, 0xe4, 0x29, 0xca, 0x60, 0x44, 0xb0,
+            0xd3, 0x77, 0xc6, 0xa0, 0x21, 0x71, 0x37, 0xf7,
+        ]));
+        return BoltzmannVelocities::from_rng(temperature, rng);
+    }
+
+    /// Create a new `BoltzmannVelocities` at the given `temperature`, using
+    /// the `rng` random number generator.
+    pub fn from_rng(temperature: f64, rng: Box<dyn CheckpointRng>) -> BoltzmannVelocities {
         let dist = Normal::new(0.0, f64::sqrt(K_BOLTZMANN * temperature))
                           .expect("bad normal distribution");
         BoltzmannVelocities {
             temperature: temperature,
             dist: dist,
-            rng: XorShiftRng::from_seed([
-                0xeb, 0xa8, 0xe4, 0x29, 0xca, 0x60, 0x44, 0xb0,
-                0xd3, 0x77, 0xc6, 0xa0, 0x21, 0x71, 0x37, 0xf7,
-            ]),
+            rng: rng,
+            remove_com: true,
         }
     }
+
+    /// Set whether the center-of-mass velocity should be removed after
+    /// sampling the velocities. The velocities are always rescaled afterward
+    /// to match the target temperature.
+    pub fn remove_com(&mut self, remove: bool) {
+        self.remove_com = remove;
+    }
 }
 
 impl InitVelocities for BoltzmannVelocities {
@@ -61,7 +81,9 @@ impl InitVelocities for BoltzmannVelocities {
             let z = f64::sqrt(m_inv) * self.dist.sample(&mut self.rng);
             *particle.velocity = Vector3D::new(x, y, z);
         }
-        RemoveTranslation.control(system);
+        if self.remove_com {
+            RemoveTranslation.control(system);
+        }
         RemoveRotation.control(system);
         scale(system, self.temperature);
     }
@@ -78,28 +100,35 @@ impl InitVelocities for BoltzmannVelocities {
         let seed = [
             b1, 0xa8, b2, 0x29, b3, 0x60, b4, 0xb0, b5, 0x77, b6, 0xa0, b7, 0x71, b8, 0xf7,
         ];
-        self.rng = XorShiftRng::from_seed(seed);
+        self.rng = Box::new(XorShiftRng::from_seed(seed));
     }
 }
 
-/// Initialize the velocities from an uniform distribution.
+/// Initialize the velocities with the same kinetic energy for all particles.
+///
+/// All particles get the speed corresponding to the target temperature
+/// ($v = \sqrt{3 k_B T / m}$), in a random direction.
 pub struct UniformVelocities {
     temperature: f64,
-    dist: Uniform<f64>,
-    rng: XorShiftRng,
+    rng: Box<dyn CheckpointRng>,
 }
 
 impl UniformVelocities {
     /// Create a new `UniformVelocities` at the given `temperature`.
     pub fn new(temperature: f64) -> UniformVelocities {
-        let factor = f64::sqrt(3.0 * K_BOLTZMANN * temperature);
+        let rng = Box::new(XorShiftRng::from_seed([
+            0xeb, 0xa8, 0xe4, 0x29, 0xca, 0x60, 0x44, 0xb0,
+            0xd3, 0x77, 0xc6, 0xa0, 0x21, 0x71, 0x37, 0xf7,
+        ]));
+        return UniformVelocities::from_rng(temperature, rng);
+    }
+
+    /// Create a new `UniformVelocities` at the given `temperature`, using the
+    /// `rng` random number generator.
+    pub fn from_rng(temperature: f64, rng: Box<dyn CheckpointRng>) -> UniformVelocities {
         UniformVelocities {
             temperature: temperature,
-            dist: Uniform::new(-factor, factor),
-            rng: XorShiftRng::from_seed([
-                0xeb, 0xa8, 0xe4, 0x29, 0xca, 0x60, 0x44, 0xb0,
-                0xd3, 0x77, 0xc6, 0xa0, 0x21, 0x71, 0x37, 0xf7,
-            ]),
+            rng: rng,
         }
     }
 }
@@ -107,12 +136,9 @@ impl UniformVelocities {
 impl InitVelocities for UniformVelocities {
     fn init(&mut self, system: &mut System) {
         for particle in system.particles_mut() {
-            let m_inv = 1.0 / (*particle.mass);
-            *particle.velocity = f64::sqrt(m_inv) * Vector3D::new(
-                self.dist.sample(&mut self.rng),
-                self.dist.sample(&mut self.rng),
-                self.dist.sample(&mut self.rng),
-            );
+            let speed = f64::sqrt(3.0 * K_BOLTZMANN * self.temperature / *particle.mass);
+            let direction: Vector3D = UnitSphere.sample(&mut self.rng).into();
+            *particle.velocity = speed * direction;
         }
         RemoveTranslation.control(system);
         RemoveRotation.control(system);
@@ -131,7 +157,22 @@ impl InitVelocities for UniformVelocities {
         let seed = [
             b1, 0xa8, b2, 0x29, b3, 0x60, b4, 0xb0, b5, 0x77, b6, 0xa0, b7, 0x71, b8, 0xf7,
         ];
-        self.rng = XorShiftRng::from_seed(seed);
+        self.rng = Box::new(XorShiftRng::from_seed(seed));
+    }
+}
+
+/// Set all the velocities to zero, for simulations starting at rest.
+pub struct ZeroVelocities;
+
+impl InitVelocities for ZeroVelocities {
+    fn init(&mut self, system: &mut System) {
+        for velocity in system.particles_mut().velocity {
+            *velocity = Vector3D::zero();
+        }
+    }
+
+    fn seed(&mut self, _: u64) {
+        // Nothing to do, there is no randomness here
     }
 }
 
@@ -180,6 +221,31 @@ mod test {
         assert_ulps_eq!(global_translation(&system), 0.0);
     }
 
+    fn total_momentum(system: &System) -> f64 {
+        let mut momentum = Vector3D::zero();
+        for (&mass, velocity) in soa_zip!(system.particles(), [mass, velocity]) {
+            momentum += velocity * mass;
+        }
+        return momentum.norm();
+    }
+
+    #[test]
+    fn boltzmann_remove_com() {
+        let mut system = testing_system();
+        let mut velocities = BoltzmannVelocities::new(300.0);
+        velocities.seed(1234);
+        velocities.init(&mut system);
+        assert!(total_momentum(&system) < 1e-12);
+        assert_ulps_eq!(system.temperature(), 300.0, epsilon = 1e-9);
+
+        let mut velocities = BoltzmannVelocities::new(300.0);
+        velocities.seed(1234);
+        velocities.remove_com(false);
+        velocities.init(&mut system);
+        assert!(total_momentum(&system) > 1e-6);
+        assert_ulps_eq!(system.temperature(), 300.0, epsilon = 1e-9);
+    }
+
     #[test]
     fn init_uniform() {
         let mut system = testing_system();
@@ -191,6 +257,35 @@ mod test {
         assert_ulps_eq!(global_translation(&system), 0.0);
     }
 
+    #[test]
+    fn uniform_reproducible() {
+        let mut system = testing_system();
+        let mut velocities = UniformVelocities::new(300.0);
+        velocities.seed(42);
+        velocities.init(&mut system);
+        let first = system.particles().velocity.to_vec();
+
+        velocities.seed(42);
+        velocities.init(&mut system);
+        assert_eq!(system.particles().velocity, &first[..]);
+
+        velocities.seed(43);
+        velocities.init(&mut system);
+        assert_ne!(system.particles().velocity, &first[..]);
+    }
+
+    #[test]
+    fn init_zero() {
+        let mut system = testing_system();
+        let mut velocities = UniformVelocities::new(300.0);
+        velocities.init(&mut system);
+        assert!(system.kinetic_energy() > 0.0);
+
+        ZeroVelocities.init(&mut system);
+        assert_eq!(system.kinetic_energy(), 0.0);
+        assert!(system.particles().velocity.iter().all(|&v| v == Vector3D::zero()));
+    }
+
     #[test]
     fn scaling_keeps_global_velocity() {
         let mut system = System::with_cell(UnitCell::cubic(10.0));
@@ -0,0 +1,81 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell};
+use lumol_core::energy::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::md::{Integrator, ParrinelloRahman};
+
+use approx::assert_relative_eq;
+
+/// Compressibility of the system used in these tests, in internal units. This
+/// is the value used by the Berendsen barostats, and it is large enough for
+/// the cell to relax in a few hundred steps.
+const COMPRESSIBILITY: f64 = 7372.0;
+
+// A compressed simple cubic crystal of argon
+fn testing_system() -> System {
+    let spacing = 3.4;
+    let mut system = System::with_cell(UnitCell::cubic(4.0 * spacing));
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                let position = Vector3D::new(i as f64, j as f64, k as f64) * spacing;
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+            }
+        }
+    }
+
+    let lj = LennardJones {
+        sigma: units::from(3.405, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 6.5));
+    return system;
+}
+
+#[test]
+fn parrinello_rahman_expands_compressed_system() {
+    let mut system = testing_system();
+    let initial_volume = system.volume();
+    let initial_pressure = system.pressure();
+    let target = units::from(1.0, "bar").unwrap();
+    assert!(initial_pressure > 1000.0 * target);
+
+    let mut barostat = ParrinelloRahman::hydrostatic(1.0, target, 200.0, COMPRESSIBILITY);
+    barostat.setup(&system);
+    for _ in 0..100 {
+        barostat.integrate(&mut system);
+    }
+
+    // The system expands, reducing the pressure
+    assert!(system.volume() > 1.05 * initial_volume);
+    assert!(system.pressure() < initial_pressure);
+
+    // The cell stays cubic, since the stress is isotropic
+    let lengths = system.cell.lengths();
+    assert_relative_eq!(lengths[0], lengths[1], max_relative = 1e-9);
+    assert_relative_eq!(lengths[0], lengths[2], max_relative = 1e-9);
+    assert_relative_eq!(system.cell.alpha(), 90.0, max_relative = 1e-9);
+    assert_relative_eq!(system.cell.beta(), 90.0, max_relative = 1e-9);
+    assert_relative_eq!(system.cell.gamma(), 90.0, max_relative = 1e-9);
+}
+
+#[test]
+fn parrinello_rahman_equilibrium() {
+    // Without external stress and internal stress, nothing moves
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(1.0, 2.0, 3.0))));
+    let initial_volume = system.volume();
+
+    let mut barostat = ParrinelloRahman::hydrostatic(1.0, 0.0, 100.0, COMPRESSIBILITY);
+    barostat.setup(&system);
+    for _ in 0..100 {
+        barostat.integrate(&mut system);
+    }
+
+    assert_eq!(barostat.strain_rate(), lumol_core::Matrix3::zero());
+    assert_eq!(system.volume(), initial_volume);
+    assert_eq!(system.particles().position[0], Vector3D::new(1.0, 2.0, 3.0));
+}
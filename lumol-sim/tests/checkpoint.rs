@@ -0,0 +1,163 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell};
+use lumol_core::energy::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::{MolecularDynamics, Rng, Simulation};
+use lumol_sim::md::CSVRThermostat;
+use lumol_sim::mc::{MonteCarloBuilder, Translate};
+use lumol_sim::output::Output;
+
+// A small argon crystal, with some initial velocities
+fn testing_system() -> System {
+    let spacing = 3.8;
+    let mut system = System::with_cell(UnitCell::cubic(4.0 * spacing));
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                let position = Vector3D::new(i as f64, j as f64, k as f64) * spacing;
+                let mut particle = Particle::with_position("Ar", position);
+                particle.velocity = Vector3D::new(
+                    f64::sin(position[0] + 2.0 * position[1]),
+                    f64::cos(position[1] - position[2]),
+                    f64::sin(3.0 * position[2] - position[0]),
+                ) * 1e-3;
+                system.add_molecule(Molecule::new(particle));
+            }
+        }
+    }
+
+    let lj = LennardJones {
+        sigma: units::from(3.405, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 7.0));
+    return system;
+}
+
+/// Output recording the internal variables of the propagator at every step
+struct StateOutput {
+    states: Rc<RefCell<Vec<BTreeMap<&'static str, f64>>>>,
+    current: BTreeMap<&'static str, f64>,
+}
+
+impl StateOutput {
+    fn new(states: &Rc<RefCell<Vec<BTreeMap<&'static str, f64>>>>) -> Box<StateOutput> {
+        Box::new(StateOutput {
+            states: Rc::clone(states),
+            current: BTreeMap::new(),
+        })
+    }
+}
+
+impl Output for StateOutput {
+    fn set_propagator_state(&mut self, state: &BTreeMap<&'static str, f64>) {
+        self.current.clone_from(state);
+    }
+
+    fn write(&mut self, _: &System) {
+        self.states.borrow_mut().push(self.current.clone());
+    }
+}
+
+/// Final system and propagator states of a simulation
+struct Trajectory {
+    system: System,
+    states: Vec<BTreeMap<&'static str, f64>>,
+}
+
+impl Trajectory {
+    fn check_same(&self, other: &Trajectory) {
+        assert_eq!(self.system.step, other.system.step);
+        assert_eq!(self.system.cell, other.system.cell);
+        assert_eq!(self.system.particles().position, other.system.particles().position);
+        assert_eq!(self.system.particles().velocity, other.system.particles().velocity);
+        assert_eq!(self.states, other.states);
+    }
+}
+
+// Run `simulation` for `steps` steps, recording the propagator state at
+// every step
+fn run(simulation: &mut Simulation, mut system: System, steps: usize) -> Trajectory {
+    let states = Rc::new(RefCell::new(Vec::new()));
+    simulation.add_output(StateOutput::new(&states));
+    simulation.run(&mut system, steps);
+    let states = states.borrow().clone();
+    return Trajectory {
+        system: system,
+        states: states,
+    };
+}
+
+// Run a simulation for 200 steps without interruption. Then run another
+// simulation for 100 steps, save a checkpoint, and restart a new simulation
+// from this checkpoint for 100 more steps. Check that the restarted
+// simulation gives exactly the same trajectory and propagator state as the
+// uninterrupted one, and that saving the checkpoint did not change the
+// simulation which saved it.
+fn check_restart<F: Fn() -> Simulation>(create_simulation: F) {
+    let file = tempfile::NamedTempFile::new().unwrap();
+
+    let mut reference = run(&mut create_simulation(), testing_system(), 200);
+    let _ = reference.states.drain(..100);
+
+    let mut simulation = create_simulation();
+    let mut system = testing_system();
+    simulation.run(&mut system, 100);
+    simulation.save_checkpoint(&system, file.path()).unwrap();
+    let continued = run(&mut simulation, system, 100);
+    continued.check_same(&reference);
+
+    // The interactions are not part of the checkpoint
+    let mut restarted = testing_system();
+    let mut simulation = create_simulation();
+    simulation.load_checkpoint(&mut restarted, file.path()).unwrap();
+    assert_eq!(restarted.step, 100);
+    let restarted = run(&mut simulation, restarted, 100);
+    restarted.check_same(&reference);
+}
+
+#[test]
+fn molecular_dynamics() {
+    check_restart(|| {
+        let mut rng = Rng::new(42);
+        let mut md = MolecularDynamics::new(units::from(1.0, "fs").unwrap());
+        md.set_thermostat(Box::new(CSVRThermostat::from_rng(300.0, 10.0, rng.split())));
+        let mut simulation = Simulation::new(Box::new(md));
+        // Use a single thread to get reproducible sums in the forces
+        simulation.set_threads(1);
+        return simulation;
+    });
+}
+
+#[test]
+fn monte_carlo() {
+    check_restart(|| {
+        let mut rng = Rng::new(42);
+        let mut builder = MonteCarloBuilder::from_rng(300.0, rng.split());
+        builder.add(Box::new(Translate::new(units::from(0.5, "A").unwrap(), None)), 1.0, 0.5);
+        let mut mc = builder.finish();
+        // Update the amplitude of the moves during the simulation
+        mc.set_amplitude_update_frequency(30);
+        let mut simulation = Simulation::new(Box::new(mc));
+        simulation.set_threads(1);
+        return simulation;
+    });
+}
+
+#[test]
+fn missing_propagator_state() {
+    let system = testing_system();
+    let file = tempfile::NamedTempFile::new().unwrap();
+    system.save_checkpoint(file.path()).unwrap();
+
+    let mut restarted = testing_system();
+    let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+    assert!(simulation.load_checkpoint(&mut restarted, file.path()).is_err());
+}
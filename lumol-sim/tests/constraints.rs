@@ -0,0 +1,136 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell, DegreesOfFreedom};
+use lumol_core::energy::Harmonic;
+use lumol_core::units;
+use lumol_core::consts::K_BOLTZMANN;
+
+use approx::assert_ulps_eq;
+
+use lumol_sim::Propagator;
+use lumol_sim::md::{MolecularDynamics, Rattle, RemoveRotation, RemoveTranslation};
+
+// A rotating and vibrating N2 molecule
+fn diatomic() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    let mut molecule = Molecule::new(Particle::with_position("N", Vector3D::new(0.0, 0.0, 0.0)));
+    molecule.add_particle_bonded_to(0, Particle::with_position("N", Vector3D::new(1.1, 0.0, 0.0)));
+    system.add_molecule(molecule);
+
+    system.particles_mut().velocity[0] = Vector3D::new(0.01, 0.005, 0.0);
+    system.particles_mut().velocity[1] = Vector3D::new(-0.01, -0.005, 0.002);
+
+    // The bond potential tries to stretch the bond, the constraint must win
+    system.set_bond_potential(("N", "N"), Box::new(Harmonic {
+        k: units::from(1000.0, "kJ/mol/A^2").unwrap(),
+        x0: units::from(1.3, "A").unwrap(),
+    }));
+    return system;
+}
+
+#[test]
+fn constrained_diatomic() {
+    let mut system = diatomic();
+    let mut rattle = Rattle::new();
+    rattle.set_bond_length(("N", "N"), 1.1);
+
+    let mut md = MolecularDynamics::with_constraints(units::from(1.0, "fs").unwrap(), rattle);
+    assert_eq!(md.degrees_of_freedom(&system), DegreesOfFreedom::Frozen(1));
+    md.setup(&system);
+
+    let mut moved = 0.0;
+    for _ in 0..20_000 {
+        let previous = system.particles().position[1];
+        md.propagate(&mut system);
+        moved += (system.particles().position[1] - previous).norm();
+
+        assert!(f64::abs(system.distance(0, 1) - 1.1) < 1e-8);
+        // No relative velocity along the bond
+        let rij = system.nearest_image(0, 1);
+        let vij = system.particles().velocity[0] - system.particles().velocity[1];
+        assert!(f64::abs(rij * vij) < 1e-8 * vij.norm());
+    }
+
+    // Check that the molecule actually moved during the simulation
+    assert!(moved > 10.0);
+}
+
+#[test]
+fn unconstrained_diatomic() {
+    let mut system = diatomic();
+    let mut md = MolecularDynamics::new(units::from(1.0, "fs").unwrap());
+    assert_eq!(md.degrees_of_freedom(&system), DegreesOfFreedom::Particles);
+    md.setup(&system);
+
+    let mut max = 0.0;
+    for _ in 0..1000 {
+        md.propagate(&mut system);
+        max = f64::max(max, system.distance(0, 1));
+    }
+    assert!(max > 1.2);
+}
+
+#[test]
+fn degrees_of_freedom() {
+    let mut system = diatomic();
+    let kinetic = system.kinetic_energy();
+    let timestep = units::from(1.0, "fs").unwrap();
+
+    let mut check = |md: MolecularDynamics, expected: usize| {
+        system.simulated_degrees_of_freedom = md.degrees_of_freedom(&system);
+        assert_eq!(system.degrees_of_freedom(), expected);
+        let temperature = 2.0 * kinetic / (expected as f64 * K_BOLTZMANN);
+        assert_ulps_eq!(system.temperature(), temperature);
+    };
+
+    // Free system
+    check(MolecularDynamics::new(timestep), 6);
+
+    // Removing the center-of-mass motion
+    let mut md = MolecularDynamics::new(timestep);
+    md.add_control(Box::new(RemoveTranslation));
+    check(md, 3);
+
+    // Constraining the bond
+    let mut rattle = Rattle::new();
+    rattle.set_bond_length(("N", "N"), 1.1);
+    check(MolecularDynamics::with_constraints(timestep, rattle), 5);
+
+    // Both constraints and center-of-mass motion removal
+    let mut rattle = Rattle::new();
+    rattle.set_bond_length(("N", "N"), 1.1);
+    let mut md = MolecularDynamics::with_constraints(timestep, rattle);
+    md.add_control(Box::new(RemoveTranslation));
+    check(md, 2);
+}
+
+#[test]
+fn center_of_mass_degrees_of_freedom() {
+    let timestep = units::from(1.0, "fs").unwrap();
+    let count = |system: &mut System, md: MolecularDynamics| {
+        system.simulated_degrees_of_freedom = md.degrees_of_freedom(system);
+        return system.degrees_of_freedom();
+    };
+    let remove_motion = |mut md: MolecularDynamics| {
+        md.add_control(Box::new(RemoveTranslation));
+        md.add_control(Box::new(RemoveRotation));
+        return md;
+    };
+
+    // A single atom has no rotational degree of freedom
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::zero())));
+    assert_eq!(count(&mut system, remove_motion(MolecularDynamics::new(timestep))), 0);
+
+    // A linear molecule only has two rotational degrees of freedom, leaving
+    // the bond vibration
+    let mut system = diatomic();
+    assert_eq!(count(&mut system, remove_motion(MolecularDynamics::new(timestep))), 1);
+
+    // And nothing is left if the bond is constrained
+    let mut rattle = Rattle::new();
+    rattle.set_bond_length(("N", "N"), 1.1);
+    let md = remove_motion(MolecularDynamics::with_constraints(timestep, rattle));
+    assert_eq!(count(&mut system, md), 0);
+}
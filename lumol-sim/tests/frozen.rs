@@ -0,0 +1,93 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell, DegreesOfFreedom};
+use lumol_core::energy::{Harmonic, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::Propagator;
+use lumol_sim::md::{MolecularDynamics, LeapFrog};
+
+// Two particles interacting with an harmonic potential
+fn testing_system() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(10.0));
+    system.add_molecule(Molecule::new(Particle::with_position("He", Vector3D::new(0.0, 0.0, 0.0))));
+    system.add_molecule(Molecule::new(Particle::with_position("He", Vector3D::new(1.3, 0.0, 0.0))));
+
+    let potential = Harmonic {
+        k: units::from(300.0, "kJ/mol/A^2").unwrap(),
+        x0: units::from(1.2, "A").unwrap(),
+    };
+    system.set_pair_potential(("He", "He"), PairInteraction::new(Box::new(potential), 5.0));
+    return system;
+}
+
+/// Harmonic force acting on the second particle, when the first one stays
+/// at the origin
+fn reference_force(x: f64) -> f64 {
+    let k = units::from(300.0, "kJ/mol/A^2").unwrap();
+    let x0 = units::from(1.2, "A").unwrap();
+    return -k * (x - x0);
+}
+
+/// Check that the first particle does not move, and that the second one
+/// follows the `reference` trajectory. The `reference` function takes the
+/// mass and the current position, velocity and acceleration of the second
+/// particle, and update them for one step.
+fn check_frozen<F>(md: &mut MolecularDynamics, reference: F)
+    where F: Fn(f64, &mut f64, &mut f64, &mut f64)
+{
+    let mut system = testing_system();
+    let mass = system.particles().mass[1];
+
+    md.freeze(&[0]);
+    assert_eq!(md.degrees_of_freedom(&system), DegreesOfFreedom::Frozen(3));
+    md.setup(&system);
+
+    // The integrators compute the initial accelerations in setup
+    let (mut x, mut v, mut a) = (1.3, 0.0, reference_force(1.3) / mass);
+    let mut min_distance: f64 = 1.3;
+    for _ in 0..2000 {
+        md.propagate(&mut system);
+        reference(mass, &mut x, &mut v, &mut a);
+
+        let particles = system.particles();
+        assert_eq!(particles.position[0], Vector3D::zero());
+        assert_eq!(particles.velocity[0], Vector3D::zero());
+
+        // The second particle feels the force from a fixed particle
+        assert!(f64::abs(particles.position[1][0] - x) < 1e-12);
+        assert!(f64::abs(particles.velocity[1][0] - v) < 1e-12);
+        assert_eq!(particles.position[1][1], 0.0);
+        assert_eq!(particles.position[1][2], 0.0);
+
+        min_distance = min_distance.min(system.distance(0, 1));
+    }
+
+    // The mobile particle oscillates around the equilibrium distance
+    assert!(min_distance < 1.15);
+}
+
+#[test]
+fn frozen_velocity_verlet() {
+    let dt = units::from(0.5, "fs").unwrap();
+    let mut md = MolecularDynamics::new(dt);
+    check_frozen(&mut md, |mass, x, v, a| {
+        *v += 0.5 * dt * (*a);
+        *x += (*v) * dt;
+        *a = reference_force(*x) / mass;
+        *v += 0.5 * dt * (*a);
+    });
+}
+
+#[test]
+fn frozen_leap_frog() {
+    let dt = units::from(0.5, "fs").unwrap();
+    let mut md = MolecularDynamics::from_integrator(Box::new(LeapFrog::new(dt)));
+    check_frozen(&mut md, |mass, x, v, a| {
+        *x += (*v) * dt + 0.5 * (*a) * dt * dt;
+        let new_acceleration = reference_force(*x) / mass;
+        *v += 0.5 * ((*a) + new_acceleration) * dt;
+        *a = new_acceleration;
+    });
+}
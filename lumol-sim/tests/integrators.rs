@@ -0,0 +1,134 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell};
+use lumol_core::energy::{Harmonic, LennardJones, PairInteraction, PairRestriction};
+use lumol_core::units;
+
+use lumol_sim::md::{Integrator, VelocityVerlet, Verlet, LeapFrog, Respa};
+
+// Two particles interacting with an harmonic potential
+fn testing_system() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(10.0));
+    system.add_molecule(Molecule::new(Particle::with_position("He", Vector3D::new(0.0, 0.0, 0.0))));
+    system.add_molecule(Molecule::new(Particle::with_position("He", Vector3D::new(1.3, 0.0, 0.0))));
+
+    let potential = Harmonic {
+        k: units::from(300.0, "kJ/mol/A^2").unwrap(),
+        x0: units::from(1.2, "A").unwrap(),
+    };
+    system.set_pair_potential(("He", "He"), PairInteraction::new(Box::new(potential), 5.0));
+    return system;
+}
+
+/// Run 10000 steps with the given integrator, and return the maximal
+/// relative deviation of the total energy from its initial value.
+fn max_energy_deviation(integrator: &mut dyn Integrator) -> f64 {
+    let mut system = testing_system();
+    let initial = system.total_energy();
+
+    integrator.setup(&system);
+    let mut deviation: f64 = 0.0;
+    let mut min_distance: f64 = 1.3;
+    for _ in 0..10_000 {
+        integrator.integrate(&mut system);
+        let energy = system.total_energy();
+        deviation = deviation.max(f64::abs((energy - initial) / initial));
+        min_distance = min_distance.min(system.distance(0, 1));
+    }
+
+    // Make sure the particles actually oscillated around the equilibrium
+    assert!(min_distance < 1.15);
+
+    return deviation;
+}
+
+#[test]
+fn velocity_verlet_energy_conservation() {
+    let mut integrator = VelocityVerlet::new(units::from(0.5, "fs").unwrap());
+    assert!(max_energy_deviation(&mut integrator) < 5e-3);
+}
+
+#[test]
+fn verlet_energy_conservation() {
+    let mut integrator = Verlet::new(units::from(0.5, "fs").unwrap());
+    assert!(max_energy_deviation(&mut integrator) < 5e-3);
+}
+
+#[test]
+fn leap_frog_energy_conservation() {
+    let mut integrator = LeapFrog::new(units::from(0.5, "fs").unwrap());
+    assert!(max_energy_deviation(&mut integrator) < 5e-3);
+}
+
+// Two N2 molecules with a stiff bond, interacting with a Lennard-Jones
+// potential
+fn molecular_system() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    let nitrogen = |x, y| Particle::with_position("N", Vector3D::new(x, y, 0.0));
+
+    let mut molecule = Molecule::new(nitrogen(0.0, 0.0));
+    molecule.add_particle_bonded_to(0, nitrogen(1.2, 0.0));
+    system.add_molecule(molecule);
+
+    let mut molecule = Molecule::new(nitrogen(0.0, 3.5));
+    molecule.add_particle_bonded_to(0, nitrogen(1.0, 3.5));
+    system.add_molecule(molecule);
+
+    system.set_bond_potential(("N", "N"), Box::new(Harmonic {
+        k: units::from(2000.0, "kJ/mol/A^2").unwrap(),
+        x0: units::from(1.1, "A").unwrap(),
+    }));
+
+    let lj = LennardJones {
+        sigma: units::from(3.3, "A").unwrap(),
+        epsilon: units::from(0.3, "kJ/mol").unwrap(),
+    };
+    let mut interaction = PairInteraction::new(Box::new(lj), 8.0);
+    interaction.set_restriction(PairRestriction::InterMolecular);
+    system.set_pair_potential(("N", "N"), interaction);
+    return system;
+}
+
+/// Run the given integrator for 1 ps on the molecular system, and return the
+/// maximal relative deviation of the total energy from its initial value.
+fn max_molecular_energy_deviation(integrator: &mut dyn Integrator, timestep: f64) -> f64 {
+    let mut system = molecular_system();
+    let initial = system.total_energy();
+
+    integrator.setup(&system);
+    let mut deviation: f64 = 0.0;
+    let steps = (units::from(1000.0, "fs").unwrap() / timestep) as usize;
+    for _ in 0..steps {
+        integrator.integrate(&mut system);
+        let energy = system.total_energy();
+        deviation = deviation.max(f64::abs((energy - initial) / initial));
+    }
+    return deviation;
+}
+
+#[test]
+fn respa_energy_conservation() {
+    let small = units::from(0.5, "fs").unwrap();
+    let large = units::from(2.0, "fs").unwrap();
+
+    let mut verlet = VelocityVerlet::new(small);
+    let verlet_small = max_molecular_energy_deviation(&mut verlet, small);
+    assert!(verlet_small < 1e-2);
+
+    let mut verlet = VelocityVerlet::new(large);
+    let verlet_large = max_molecular_energy_deviation(&mut verlet, large);
+
+    // RESPA with the same inner timestep for the bonds conserves the energy
+    // much better than velocity-Verlet with the large timestep
+    let mut respa = Respa::new(large, 4);
+    let respa = max_molecular_energy_deviation(&mut respa, large);
+    assert!(respa < 1e-2);
+    assert!(respa < 0.5 * verlet_large);
+}
+
+#[test]
+#[should_panic(expected = "RESPA integrator needs at least one inner step")]
+fn respa_no_inner_steps() {
+    let _ = Respa::new(1.0, 0);
+}
@@ -0,0 +1,144 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use std::f64::consts::PI;
+
+use lumol_core::{Particle, Molecule, System, UnitCell, EnergyCache};
+use lumol_core::{Lattice, SystemBuilder};
+use lumol_core::energy::{LennardJones, PairInteraction};
+use lumol_core::consts::{K_BOLTZMANN, PLANCK_CONSTANT};
+use lumol_core::units;
+
+use lumol_sim::Propagator;
+use lumol_sim::mc::{MCMove, MonteCarloBuilder, Translate, Insert, Delete};
+
+// A simple cubic crystal of argon, close to the Lennard-Jones minimum
+fn argon_crystal() -> System {
+    let spacing = 3.8;
+    SystemBuilder::new()
+        .cell(UnitCell::cubic(4.0 * spacing))
+        .add_lattice(Lattice::sc("Ar"), spacing, 4, 4, 4)
+        .build()
+}
+
+/// Run `steps` Metropolis steps with a `Translate` move of amplitude `delta`
+/// at the temperature `temperature`, and return the acceptance ratio.
+fn translate_acceptance(system: &mut System, delta: f64, temperature: f64, steps: usize) -> f64 {
+    let mut rng = XorShiftRng::seed_from_u64(129);
+    let beta = 1.0 / (K_BOLTZMANN * temperature);
+
+    let mut translate = Translate::new(delta, None);
+    let mut cache = EnergyCache::new();
+    translate.setup(system);
+    cache.init(system);
+
+    let mut accepted = 0;
+    for _ in 0..steps {
+        assert!(translate.prepare(system, &mut rng));
+        let cost = translate.cost(system, beta, &mut cache);
+        if cost <= 0.0 || rng.gen::<f64>() < f64::exp(-cost) {
+            translate.apply(system);
+            cache.update(system);
+            accepted += 1;
+        } else {
+            translate.restore(system);
+        }
+    }
+
+    return accepted as f64 / steps as f64;
+}
+
+#[test]
+fn ideal_gas_translations() {
+    // Without interactions, every translation leaves the energy unchanged
+    // and is accepted, whatever the amplitude of the move.
+    let temperature = 300.0;
+    for &delta in &[0.1, 1.0, 10.0] {
+        let mut system = argon_crystal();
+        let acceptance = translate_acceptance(&mut system, delta, temperature, 500);
+        assert_eq!(acceptance, 1.0);
+        assert_eq!(system.potential_energy(), 0.0);
+
+        // The pressure is given by the ideal gas law: P V = N k_B T
+        let natoms = 4 * 4 * 4;
+        assert_eq!(system.size(), natoms);
+        let volume = f64::powi(4.0 * 3.8, 3);
+        assert!(f64::abs(system.volume() - volume) < 1e-9);
+
+        system.simulated_temperature(Some(temperature));
+        let expected = natoms as f64 * K_BOLTZMANN * temperature / volume;
+        assert!(f64::abs(system.pressure() - expected) / expected < 1e-9);
+        let pressure = units::to(system.pressure(), "bar").unwrap();
+        // 64 argon atoms in (15.2 A)^3 at 300 K
+        assert!(f64::abs(pressure - 754.84) < 0.01);
+    }
+}
+
+#[test]
+fn translations_acceptance() {
+    let mut system = argon_crystal();
+    let lj = LennardJones {
+        sigma: units::from(3.405, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 7.0));
+
+    // Larger displacements lead to larger energy changes, and are accepted
+    // less often.
+    let mut acceptances = Vec::new();
+    for &delta in &[0.1, 0.5, 2.0] {
+        let mut system = system.clone();
+        acceptances.push(translate_acceptance(&mut system, delta, 120.0, 2000));
+    }
+
+    assert!(acceptances[0] > 0.9);
+    assert!(acceptances[0] > acceptances[1]);
+    assert!(acceptances[1] > acceptances[2]);
+    assert!(acceptances[2] < 0.5);
+}
+
+#[test]
+fn grand_canonical_ideal_gas() {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    let temperature = 300.0;
+    let argon = Molecule::new(Particle::new("Ar"));
+    let hash = argon.hash();
+
+    // Use a chemical potential giving on average 20 molecules in the system:
+    // <N> = exp(beta mu) V / Lambda^3
+    let expected = 20.0;
+    let beta = 1.0 / (K_BOLTZMANN * temperature);
+    let mass = argon.particles().mass[0];
+    let lambda = PLANCK_CONSTANT * f64::sqrt(beta / (2.0 * PI * mass));
+    let chemical_potential = f64::ln(expected * lambda.powi(3) / system.volume()) / beta;
+
+    let rng = Box::new(XorShiftRng::seed_from_u64(42));
+    let mut builder = MonteCarloBuilder::from_rng(temperature, rng);
+    builder.add(Box::new(Insert::new(argon, chemical_potential)), 1.0, None);
+    builder.add(Box::new(Delete::new(hash, chemical_potential)), 1.0, None);
+    let mut mc = builder.finish();
+    mc.setup(&system);
+
+    // Equilibration
+    for _ in 0..1_000 {
+        mc.propagate(&mut system);
+    }
+
+    let mut total = 0;
+    let steps = 5_000;
+    for _ in 0..steps {
+        mc.propagate(&mut system);
+        total += system.molecules().count();
+    }
+
+    // The number of molecules follows a Poisson distribution, with a standard
+    // deviation of sqrt(<N>) ~ 4.5. The number of molecules changes by at
+    // most one at each step, and it takes around 2 <N> steps to decorrelate,
+    // leaving about 60 independent samples. The standard error on the
+    // average is then around 0.6, and we allow for four times this error.
+    let average = total as f64 / steps as f64;
+    assert!(f64::abs(average - expected) < 2.5);
+}
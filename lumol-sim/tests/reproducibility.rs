@@ -0,0 +1,55 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{System, UnitCell, Lattice, SystemBuilder, Vector3D};
+use lumol_core::energy::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::{Rng, Simulation, BoltzmannVelocities, InitVelocities};
+use lumol_sim::md::{MolecularDynamics, CSVRThermostat};
+use lumol_sim::mc::{MonteCarloBuilder, Translate};
+
+// A simple cubic crystal of argon, close to the Lennard-Jones minimum
+fn argon_crystal() -> System {
+    let spacing = 3.8;
+    let mut system = SystemBuilder::new()
+        .cell(UnitCell::cubic(4.0 * spacing))
+        .add_lattice(Lattice::sc("Ar"), spacing, 4, 4, 4)
+        .build();
+
+    let lj = LennardJones {
+        sigma: units::from(3.405, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 7.0));
+    return system;
+}
+
+/// Run a thermostated molecular dynamics simulation followed by a Monte
+/// Carlo simulation, with all the random number generators created from
+/// `seed`, and return the final positions.
+fn run(seed: u64) -> Vec<Vector3D> {
+    let mut rng = Rng::new(seed);
+    let mut system = argon_crystal();
+
+    let mut velocities = BoltzmannVelocities::from_rng(100.0, rng.split());
+    velocities.init(&mut system);
+
+    let mut md = MolecularDynamics::new(units::from(1.0, "fs").unwrap());
+    md.set_thermostat(Box::new(CSVRThermostat::from_rng(100.0, 10.0, rng.split())));
+    let mut simulation = Simulation::new(Box::new(md));
+    simulation.run(&mut system, 50);
+
+    let mut mc = MonteCarloBuilder::from_rng(100.0, rng.split());
+    mc.add(Box::new(Translate::new(0.5, None)), 1.0, None);
+    let mut simulation = Simulation::new(Box::new(mc.finish()));
+    simulation.run(&mut system, 200);
+
+    return system.particles().position.to_vec();
+}
+
+#[test]
+fn same_seed() {
+    assert_eq!(run(129), run(129));
+    assert_ne!(run(129), run(130));
+}
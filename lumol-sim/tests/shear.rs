@@ -0,0 +1,186 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell, CellShape};
+use lumol_core::energy::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::md::{Integrator, Sllod};
+
+use approx::assert_relative_eq;
+
+fn set_argon_potential(system: &mut System, cutoff: f64) {
+    let lj = LennardJones {
+        sigma: units::from(3.405, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), cutoff));
+}
+
+#[test]
+fn sllod_streamlines() {
+    // Non-interacting particles follow the streamlines of the imposed flow
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    for &y in &[-5.0, 0.0, 5.0] {
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, y, 0.0))));
+    }
+    set_argon_potential(&mut system, 4.0);
+
+    let shear_rate = units::from(1e-3, "fs^-1").unwrap();
+    let mut sllod = Sllod::shear(1.0, shear_rate);
+    sllod.setup(&system);
+    for _ in 0..100 {
+        sllod.integrate(&mut system);
+    }
+
+    for (position, velocity) in system.particles().position.iter().zip(system.particles().velocity) {
+        assert_eq!(*velocity, Vector3D::zero());
+        assert_relative_eq!(position[0], shear_rate * position[1] * 100.0, epsilon = 1e-12);
+        assert_eq!(position[2], 0.0);
+
+        let lab_velocity = velocity + sllod.streaming_velocity(position);
+        assert_relative_eq!(lab_velocity[0], shear_rate * position[1], epsilon = 1e-12);
+    }
+
+    // The cell is sheared with the flow
+    assert_eq!(system.cell.shape(), CellShape::Triclinic);
+    assert_relative_eq!(system.volume(), 20.0 * 20.0 * 20.0, max_relative = 1e-12);
+    let cell = system.cell.matrix();
+    assert_relative_eq!(cell[0][1], shear_rate * 20.0 * 100.0, epsilon = 1e-9);
+    assert_relative_eq!(cell[1][1], 20.0, epsilon = 1e-12);
+}
+
+#[test]
+fn sllod_velocity_profile() {
+    // A slightly perturbed simple cubic crystal of argon
+    let spacing = 3.6;
+    let mut system = System::with_cell(UnitCell::cubic(4.0 * spacing));
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                let n = (16 * i + 4 * j + k) as f64;
+                let delta = 0.1 * Vector3D::new(f64::sin(n), f64::cos(2.0 * n), f64::sin(3.0 * n));
+                let position = Vector3D::new(i as f64, j as f64, k as f64) * spacing + delta;
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+            }
+        }
+    }
+    set_argon_potential(&mut system, 6.5);
+
+    let shear_rate = units::from(5e-3, "fs^-1").unwrap();
+    let mut sllod = Sllod::shear(1.0, shear_rate);
+    sllod.setup(&system);
+    for _ in 0..50 {
+        sllod.integrate(&mut system);
+    }
+
+    // The total peculiar momentum stays null
+    let mut momentum = Vector3D::zero();
+    for (&mass, velocity) in system.particles().mass.iter().zip(system.particles().velocity) {
+        momentum += mass * velocity;
+    }
+    assert!(momentum.norm() < 1e-9);
+
+    // The laboratory frame velocity along x is a linear function of y, with
+    // a slope given by the shear rate
+    let size = system.size() as f64;
+    let particles = system.particles();
+    let mut lab_velocities = Vec::new();
+    for (position, velocity) in particles.position.iter().zip(particles.velocity) {
+        lab_velocities.push((velocity + sllod.streaming_velocity(position))[0]);
+    }
+    let mean_y = particles.position.iter().map(|position| position[1]).sum::<f64>() / size;
+    let mean_velocity = lab_velocities.iter().sum::<f64>() / size;
+
+    let mut covariance = 0.0;
+    let mut variance = 0.0;
+    for (position, velocity) in particles.position.iter().zip(&lab_velocities) {
+        covariance += (position[1] - mean_y) * (velocity - mean_velocity);
+        variance += (position[1] - mean_y) * (position[1] - mean_y);
+    }
+    assert_relative_eq!(covariance / variance, shear_rate, max_relative = 0.02);
+
+    // Binned velocity profile, using slabs along y centered on the lattice
+    // planes of the crystal. The average laboratory frame velocity along x in
+    // each slab is the imposed streaming velocity at the slab center.
+    let mut counts = [0; 4];
+    let mut velocities = [0.0; 4];
+    for (position, velocity) in particles.position.iter().zip(&lab_velocities) {
+        let bin = f64::floor(position[1] / spacing + 0.5) as usize;
+        counts[bin] += 1;
+        velocities[bin] += velocity;
+    }
+    for (bin, (&count, &velocity)) in counts.iter().zip(&velocities).enumerate() {
+        assert_eq!(count, 16);
+        let center = bin as f64 * spacing;
+        let velocity = velocity / count as f64;
+        assert_relative_eq!(velocity, shear_rate * center, epsilon = 0.05 * shear_rate * spacing);
+    }
+}
+
+#[test]
+fn sllod_lees_edwards() {
+    // Particles in layers too far apart to interact, sheared up to a strain of 1.2
+    let length = 18.0;
+    let mut system = System::with_cell(UnitCell::cubic(length));
+    for i in 0..3 {
+        for j in 0..3 {
+            for k in 0..3 {
+                let position = Vector3D::new(i as f64, j as f64, k as f64) * 6.0;
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+            }
+        }
+    }
+    set_argon_potential(&mut system, 4.0);
+
+    let shear_rate = units::from(1e-3, "fs^-1").unwrap();
+    let mut sllod = Sllod::shear(1.0, shear_rate);
+    sllod.setup(&system);
+
+    let mut flips = 0;
+    let mut previous_tilt = 0.0;
+    for _ in 0..1200 {
+        sllod.integrate(&mut system);
+
+        let cell = system.cell.matrix();
+        let tilt = cell[0][1];
+        assert!(f64::abs(tilt) <= 0.5 * length + 1e-9);
+        if tilt < previous_tilt {
+            flips += 1;
+        }
+        previous_tilt = tilt;
+
+        assert_relative_eq!(cell[0][0], length, epsilon = 1e-12);
+        assert_relative_eq!(cell[1][1], length, epsilon = 1e-12);
+        assert_relative_eq!(system.volume(), length * length * length, max_relative = 1e-12);
+    }
+    // The tilt was flipped once, when the strain went through 0.5
+    assert_eq!(flips, 1);
+    assert!(previous_tilt > 0.0);
+
+    for velocity in system.particles().velocity {
+        assert_eq!(*velocity, Vector3D::zero());
+    }
+
+    // The minimum image convention gives the shortest distance between
+    // the particles
+    for i in 0..system.size() {
+        for j in (i + 1)..system.size() {
+            let mut shortest = system.particles().position[i] - system.particles().position[j];
+            system.cell.minimum_image(&mut shortest);
+            if shortest.norm() < 0.4 * length {
+                assert!((system.nearest_image(i, j) - shortest).norm() < 1e-9);
+            }
+        }
+    }
+
+    // The particles still follow the streamlines, up to a lattice vector
+    let cell = system.cell;
+    for (i, position) in system.particles().position.iter().enumerate() {
+        let initial = Vector3D::new((i / 9) as f64, ((i / 3) % 3) as f64, (i % 3) as f64) * 6.0;
+        let expected = initial + Vector3D::new(shear_rate * initial[1] * 1200.0, 0.0, 0.0);
+        let mut delta = position - expected;
+        cell.minimum_image(&mut delta);
+        assert!(delta.norm() < 1e-9);
+    }
+}
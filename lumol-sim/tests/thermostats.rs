@@ -4,12 +4,17 @@
 use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell};
 use lumol_core::consts::K_BOLTZMANN;
 
-use lumol_sim::{BoltzmannVelocities, InitVelocities};
-use lumol_sim::md::{Integrator, VelocityVerlet};
+use lumol_sim::{BoltzmannVelocities, InitVelocities, Simulation};
+use lumol_sim::md::{Integrator, MolecularDynamics, VelocityVerlet};
+use lumol_sim::output::Output;
 use lumol_sim::md::{Thermostat, RescaleThermostat, BerendsenThermostat, CSVRThermostat};
 
 use approx::{assert_ulps_eq, assert_relative_eq};
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
 // An ideal gas system
 fn testing_system() -> System {
     let mut system = System::with_cell(UnitCell::cubic(20.0));
@@ -75,6 +80,71 @@ fn berendsen_thermostat() {
 }
 
 
+#[test]
+fn berendsen_thermostat_relaxation() {
+    // Start from a hot system
+    let mut system = testing_system();
+    let mut velocities = BoltzmannVelocities::new(600.0);
+    velocities.init(&mut system);
+
+    let mut thermostat = BerendsenThermostat::new(300.0, 10.0);
+    thermostat.setup(&system);
+
+    // Without interactions, the temperature relaxes exponentially to the
+    // target temperature, with a characteristic time of tau steps.
+    let mut integrator = VelocityVerlet::new(1.0);
+    integrator.setup(&system);
+    for step in 1..=100 {
+        integrator.integrate(&mut system);
+        thermostat.apply(&mut system);
+
+        let expected = 300.0 + 300.0 * f64::powi(1.0 - 1.0 / 10.0, step);
+        assert_relative_eq!(system.temperature(), expected, max_relative = 1e-9);
+        assert!(thermostat.scale_factor() < 1.0);
+    }
+    assert!(system.temperature() < 301.0);
+}
+
+/// Output recording the thermostat scaling factor at every step
+struct ScaleFactorOutput {
+    scale_factors: Rc<RefCell<Vec<f64>>>,
+    current: f64,
+}
+
+impl Output for ScaleFactorOutput {
+    fn set_propagator_state(&mut self, state: &BTreeMap<&'static str, f64>) {
+        self.current = state["thermostat_scale"];
+    }
+
+    fn write(&mut self, _: &System) {
+        self.scale_factors.borrow_mut().push(self.current);
+    }
+}
+
+#[test]
+fn berendsen_thermostat_state() {
+    let mut system = testing_system();
+
+    let mut md = MolecularDynamics::new(1.0);
+    md.set_thermostat(Box::new(BerendsenThermostat::new(300.0, 10.0)));
+    let mut simulation = Simulation::new(Box::new(md));
+
+    let scale_factors = Rc::new(RefCell::new(Vec::new()));
+    simulation.add_output(Box::new(ScaleFactorOutput {
+        scale_factors: Rc::clone(&scale_factors),
+        current: 0.0,
+    }));
+    simulation.run(&mut system, 100);
+
+    // The system is already at the target temperature, so the thermostat
+    // should barely touch the velocities
+    let scale_factors = scale_factors.borrow();
+    assert_eq!(scale_factors.len(), 100);
+    for &factor in scale_factors.iter() {
+        assert_relative_eq!(factor, 1.0, epsilon = 1e-6);
+    }
+}
+
 #[test]
 fn csvr_thermostat() {
     let mut system = testing_system();